@@ -0,0 +1,260 @@
+//! Little goals for using the viewer, unlocked as quotes come up and kept in
+//! `state.toml` with the date they were reached.
+
+use crate::state::State;
+use crate::{AnimeQuote, Palette};
+use chrono::{DateTime, Local, Timelike};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Clear, Paragraph, Wrap};
+use std::collections::HashSet;
+
+pub(crate) struct Achievement {
+    /// What the state file records it under.
+    pub(crate) id: &'static str,
+    pub(crate) title: &'static str,
+    description: &'static str,
+    goal: Goal,
+}
+
+enum Goal {
+    /// Different quotes viewed.
+    QuotesViewed(u64),
+    /// Different anime among the quotes viewed.
+    AnimeViewed(u64),
+    /// Different anime among the favorite quotes.
+    FavoriteAnime(u64),
+    /// Days in a row with at least one quote viewed.
+    Streak(u32),
+    /// Every quote in the collection viewed at least once.
+    WholeCollection,
+    /// A quote viewed between midnight and 4 a.m.
+    LateNight,
+}
+
+pub(crate) const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "first-quote",
+        title: "First words",
+        description: "View your first quote",
+        goal: Goal::QuotesViewed(1),
+    },
+    Achievement {
+        id: "viewed-100",
+        title: "Well read",
+        description: "View 100 different quotes",
+        goal: Goal::QuotesViewed(100),
+    },
+    Achievement {
+        id: "anime-10",
+        title: "Seasonal lineup",
+        description: "View quotes from 10 different anime",
+        goal: Goal::AnimeViewed(10),
+    },
+    Achievement {
+        id: "favorites-10",
+        title: "Eclectic taste",
+        description: "Favorite quotes from 10 different anime",
+        goal: Goal::FavoriteAnime(10),
+    },
+    Achievement {
+        id: "streak-7",
+        title: "Weekly ritual",
+        description: "View quotes 7 days in a row",
+        goal: Goal::Streak(7),
+    },
+    Achievement {
+        id: "streak-30",
+        title: "Long-runner",
+        description: "View quotes 30 days in a row",
+        goal: Goal::Streak(30),
+    },
+    Achievement {
+        id: "whole-collection",
+        title: "Completionist",
+        description: "View every quote in the collection",
+        goal: Goal::WholeCollection,
+    },
+    Achievement {
+        id: "night-owl",
+        title: "Night owl",
+        description: "View a quote between midnight and 4 a.m.",
+        goal: Goal::LateNight,
+    },
+];
+
+impl Achievement {
+    /// How far along the goal is, and where it ends.
+    fn progress(&self, state: &State, quotes: &[AnimeQuote], now: DateTime<Local>) -> (u64, u64) {
+        let viewed = state.viewed(quotes);
+        match self.goal {
+            Goal::QuotesViewed(goal) => (viewed.len() as u64, goal),
+            Goal::AnimeViewed(goal) => {
+                let anime: HashSet<&str> = viewed
+                    .iter()
+                    .map(|&index| quotes[index].anime.as_str())
+                    .collect();
+                (anime.len() as u64, goal)
+            }
+            Goal::FavoriteAnime(goal) => {
+                let anime: HashSet<&str> = quotes
+                    .iter()
+                    .filter(|quote| state.is_favorite(quote))
+                    .map(|quote| quote.anime.as_str())
+                    .collect();
+                (anime.len() as u64, goal)
+            }
+            Goal::Streak(goal) => {
+                let (_, longest) = state.streaks(now.date_naive());
+                (u64::from(longest), u64::from(goal))
+            }
+            Goal::WholeCollection => (viewed.len() as u64, quotes.len().max(1) as u64),
+            Goal::LateNight => (u64::from(now.hour() < 4), 1),
+        }
+    }
+}
+
+/// Records the achievements reached since the last check and returns them,
+/// for a toast. Saved by the caller.
+pub(crate) fn check(
+    state: &mut State,
+    quotes: &[AnimeQuote],
+    now: DateTime<Local>,
+) -> Vec<&'static Achievement> {
+    let reached: Vec<&'static Achievement> = ACHIEVEMENTS
+        .iter()
+        .filter(|achievement| state.unlocked(achievement.id).is_none())
+        .filter(|achievement| {
+            let (progress, goal) = achievement.progress(state, quotes, now);
+            progress >= goal
+        })
+        .collect();
+    for achievement in &reached {
+        state.unlock(achievement.id, now.date_naive());
+    }
+    reached
+}
+
+pub(crate) enum AchievementsAction {
+    Stay,
+    Close,
+}
+
+/// The achievements screen: everything there is to unlock, with progress.
+pub(crate) struct Achievements {
+    scroll: u16,
+}
+
+impl Achievements {
+    pub(crate) fn new() -> Self {
+        Self { scroll: 0 }
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode) -> AchievementsAction {
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Esc | KeyCode::Char('a') | KeyCode::Char('q') => {
+                return AchievementsAction::Close;
+            }
+            _ => {}
+        }
+        AchievementsAction::Stay
+    }
+
+    pub(crate) fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &State,
+        quotes: &[AnimeQuote],
+        palette: &Palette,
+    ) {
+        let now = Local::now();
+        let count_style = Style::default().fg(palette.count);
+        let unlocked = ACHIEVEMENTS
+            .iter()
+            .filter(|achievement| state.unlocked(achievement.id).is_some())
+            .count();
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("Achievements ({unlocked}/{})", ACHIEVEMENTS.len()),
+                Style::default().fg(palette.anime).bold(),
+            )),
+            Line::from(""),
+        ];
+        for achievement in ACHIEVEMENTS {
+            let (status, title_style) = match state.unlocked(achievement.id) {
+                Some(date) => (
+                    format!("unlocked {date}"),
+                    Style::default().fg(palette.character).bold(),
+                ),
+                None => {
+                    let (progress, goal) = achievement.progress(state, quotes, now);
+                    (format!("{}/{goal}", progress.min(goal)), count_style.bold())
+                }
+            };
+            lines.push(Line::from(vec![
+                Span::styled(achievement.title, title_style),
+                Span::styled(format!("  {status}"), count_style),
+            ]));
+            lines.push(Line::from(Span::styled(
+                achievement.description,
+                Style::default().fg(palette.quote),
+            )));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            "Scroll <Up/Down>  Back <Esc>",
+            Style::default().fg(palette.instructions),
+        )));
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .scroll((self.scroll, 0)),
+            area,
+        );
+    }
+}
+
+/// A small box in the top-right corner of `area` announcing `achievement`.
+pub(crate) fn render_toast(
+    frame: &mut Frame,
+    area: Rect,
+    achievement: &Achievement,
+    palette: &Palette,
+) {
+    let width = (achievement.description.len().max(achievement.title.len()) as u16 + 4)
+        .max(24)
+        .min(area.width);
+    let toast = Rect {
+        x: area.right().saturating_sub(width + 1),
+        y: area.y + 1,
+        width,
+        height: 4.min(area.height),
+    };
+    frame.render_widget(Clear, toast);
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(Span::styled(
+                achievement.title,
+                Style::default().fg(palette.character).bold(),
+            )),
+            Line::from(Span::styled(
+                achievement.description,
+                Style::default().fg(palette.quote),
+            )),
+        ])
+        .centered()
+        .block(
+            Block::bordered()
+                .title(" Achievement unlocked ")
+                .border_style(Style::default().fg(palette.anime)),
+        ),
+        toast,
+    );
+}
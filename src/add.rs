@@ -0,0 +1,431 @@
+//! Adding quotes to the data file: drafts built from flags or pasted text,
+//! appended with `toml_edit` so the rest of the file keeps its layout.
+
+use crate::{AnimeQuote, paths, romaji};
+use color_eyre::eyre::{Result, WrapErr, bail};
+use serde::Deserialize;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A quote being put together; every field may still be missing.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub(crate) struct Draft {
+    #[serde(default)]
+    pub(crate) japanese: Option<String>,
+    #[serde(default)]
+    pub(crate) romaji: Option<String>,
+    #[serde(default)]
+    pub(crate) anime: Option<String>,
+    #[serde(default)]
+    pub(crate) character: Option<String>,
+    #[serde(default)]
+    pub(crate) quote: Option<String>,
+    #[serde(default)]
+    pub(crate) image: Option<String>,
+    #[serde(default)]
+    pub(crate) episode: Option<u32>,
+    /// Where in the episode the line is said, like `12:34`.
+    #[serde(default)]
+    pub(crate) timestamp: Option<String>,
+}
+
+/// Separators between a quote and its attribution, longest first so `--`
+/// isn't read as two hyphens.
+const ATTRIBUTION_SEPARATORS: &[&str] = &["—", "―", "–", " -- ", " - "];
+
+impl Draft {
+    /// Reads pasted text: a JSON object with the data-file field names, or
+    /// free text like `"Quote" — Character, Anime`. Lines in Japanese script
+    /// become `japanese`; of the other lines the last is the translation and
+    /// an earlier one the romaji.
+    pub(crate) fn parse(text: &str) -> Self {
+        let text = text.trim();
+        if let Ok(draft) = serde_json::from_str::<Draft>(text) {
+            return draft.cleaned();
+        }
+
+        let (body, attribution) = ATTRIBUTION_SEPARATORS
+            .iter()
+            .filter_map(|separator| text.rsplit_once(separator))
+            .max_by_key(|(body, _)| body.len())
+            .unwrap_or((text, ""));
+        let (character, anime) = match attribution.rsplit_once(',') {
+            Some((character, anime)) => (character, anime),
+            None => (attribution, ""),
+        };
+
+        let lines: Vec<&str> = body
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let japanese: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|line| is_japanese(line))
+            .collect();
+        let other: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|line| !is_japanese(line))
+            .collect();
+        Self {
+            japanese: Some(japanese.join(" ")),
+            romaji: (other.len() > 1).then(|| other[0].to_string()),
+            anime: Some(anime.to_string()),
+            character: Some(character.to_string()),
+            quote: other.last().map(|line| line.to_string()),
+            ..Self::default()
+        }
+        .cleaned()
+    }
+
+    /// Trims fields, strips quotation marks from the lines, and drops empty
+    /// fields.
+    fn cleaned(self) -> Self {
+        let clean = |value: Option<String>, unquote: bool| {
+            value
+                .map(|value| {
+                    let value = value.trim();
+                    if unquote {
+                        value
+                            .trim_matches(['"', '“', '”', '「', '」', '『', '』'])
+                            .trim()
+                            .to_string()
+                    } else {
+                        value.to_string()
+                    }
+                })
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            japanese: clean(self.japanese, true),
+            romaji: clean(self.romaji, true),
+            anime: clean(self.anime, false),
+            character: clean(self.character, false),
+            quote: clean(self.quote, true),
+            image: clean(self.image, false),
+            episode: self.episode,
+            timestamp: clean(self.timestamp, false),
+        }
+    }
+
+    /// Fields set in `other` replace those here.
+    pub(crate) fn overridden_by(self, other: Draft) -> Self {
+        Self {
+            japanese: other.japanese.or(self.japanese),
+            romaji: other.romaji.or(self.romaji),
+            anime: other.anime.or(self.anime),
+            character: other.character.or(self.character),
+            quote: other.quote.or(self.quote),
+            image: other.image.or(self.image),
+            episode: other.episode.or(self.episode),
+            timestamp: other.timestamp.or(self.timestamp),
+        }
+    }
+
+    /// Required fields that are still empty.
+    pub(crate) fn missing(&self) -> Vec<&'static str> {
+        [
+            ("japanese", &self.japanese),
+            ("anime", &self.anime),
+            ("character", &self.character),
+            ("quote", &self.quote),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    pub(crate) fn to_quote(&self) -> Result<AnimeQuote> {
+        let missing = self.missing();
+        if !missing.is_empty() {
+            bail!("missing {}", missing.join(", "));
+        }
+        Ok(AnimeQuote {
+            japanese: self.japanese.clone().unwrap_or_default(),
+            romaji: self.romaji.clone(),
+            anime: self.anime.clone().unwrap_or_default(),
+            character: self.character.clone().unwrap_or_default(),
+            quote: self.quote.clone().unwrap_or_default(),
+            image: self.image.clone(),
+            episode: self.episode,
+            timestamp: self.timestamp.clone(),
+            ..AnimeQuote::default()
+        })
+    }
+}
+
+/// Extensions the image picker offers.
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+/// Asks for every field on the terminal, offering what `draft` already has
+/// as the default, and returns the completed draft once it is confirmed.
+/// Prompts go to stderr so stdout stays clean for the result line.
+pub(crate) fn prompt(
+    draft: Draft,
+    data_file: &Path,
+    romanization: romaji::Romanization,
+) -> Result<Draft> {
+    let base = data_file.parent().unwrap_or(Path::new(""));
+    let mut input = io::stdin().lock();
+    let japanese = loop {
+        let japanese =
+            ask(&mut input, "Japanese", draft.japanese.as_deref(), true)?.unwrap_or_default();
+        if is_japanese(&japanese) {
+            break japanese;
+        }
+        if confirm(
+            &mut input,
+            "  No Japanese script in that; keep it anyway?",
+            false,
+        )? {
+            break japanese;
+        }
+    };
+    // Only kana can be read without a dictionary, so kanji get no suggestion.
+    let suggested = draft
+        .romaji
+        .clone()
+        .or_else(|| romaji::from_kana(&japanese, romanization));
+    let romaji = ask(&mut input, "Romaji", suggested.as_deref(), false)?;
+    let anime = ask(&mut input, "Anime", draft.anime.as_deref(), true)?;
+    let character = ask(&mut input, "Character", draft.character.as_deref(), true)?;
+    let quote = ask(&mut input, "Translation", draft.quote.as_deref(), true)?;
+
+    let images = image_candidates(base);
+    for (number, image) in images.iter().enumerate() {
+        eprintln!("  {:>3}  {image}", number + 1);
+    }
+    let label = if images.is_empty() {
+        "Image path"
+    } else {
+        "Image (number or path)"
+    };
+    let mut default = draft.image.clone();
+    let image = loop {
+        let Some(answer) = ask(&mut input, label, default.as_deref(), false)? else {
+            break None;
+        };
+        let chosen = answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| images.get(number.checked_sub(1)?).cloned())
+            .unwrap_or(answer);
+        if Path::new(&paths::resolve(base, &chosen)).is_file() {
+            break Some(chosen);
+        }
+        eprintln!(
+            "  {} does not exist (paths are relative to {})",
+            chosen,
+            data_file.display()
+        );
+        default = None;
+    };
+
+    let draft = Draft {
+        japanese: Some(japanese),
+        romaji,
+        anime,
+        character,
+        quote,
+        image,
+        ..draft
+    };
+    if !confirm(&mut input, "Add this quote?", true)? {
+        bail!("not added");
+    }
+    Ok(draft)
+}
+
+/// Asks on the terminal for just the required fields `draft` lacks, as when
+/// an imported line has only its Japanese or only its translation.
+pub(crate) fn prompt_missing(draft: Draft) -> Result<Draft> {
+    let mut input = io::stdin().lock();
+    let mut ask_missing = |label: &str, value: Option<String>| match value {
+        Some(value) => Ok(Some(value)),
+        None => ask(&mut input, label, None, true),
+    };
+    Ok(Draft {
+        japanese: ask_missing("Japanese", draft.japanese)?,
+        anime: ask_missing("Anime", draft.anime)?,
+        character: ask_missing("Character", draft.character)?,
+        quote: ask_missing("Translation", draft.quote)?,
+        ..draft
+    })
+}
+
+fn read_answer(input: &mut impl BufRead, prompt: &str) -> Result<String> {
+    eprint!("{prompt}");
+    io::stderr().flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        bail!("input ended before the quote was complete");
+    }
+    Ok(line.trim().to_string())
+}
+
+/// Reads a field until it is valid: Enter keeps `default`, and `-` clears
+/// an optional field.
+fn ask(
+    input: &mut impl BufRead,
+    label: &str,
+    default: Option<&str>,
+    required: bool,
+) -> Result<Option<String>> {
+    loop {
+        let prompt = match default {
+            Some(default) if required => format!("{label} [{default}]: "),
+            Some(default) => format!("{label} [{default}] (- for none): "),
+            None if required => format!("{label}: "),
+            None => format!("{label} (optional): "),
+        };
+        let line = read_answer(input, &prompt)?;
+        match (line.as_str(), default) {
+            ("-", _) if !required => return Ok(None),
+            ("", Some(default)) => return Ok(Some(default.to_string())),
+            ("", None) if !required => return Ok(None),
+            ("", None) => eprintln!("  {label} is required"),
+            _ => return Ok(Some(line)),
+        }
+    }
+}
+
+fn confirm(input: &mut impl BufRead, question: &str, default: bool) -> Result<bool> {
+    let choices = if default { "[Y/n]" } else { "[y/N]" };
+    let answer = read_answer(input, &format!("{question} {choices} "))?;
+    Ok(match answer.to_ascii_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+/// Image files next to the data file and one directory down (where
+/// `assets/` usually is), as paths relative to it.
+fn image_candidates(base: &Path) -> Vec<String> {
+    let directory = if base.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        base
+    };
+    let is_image = |path: &Path| {
+        path.is_file()
+            && path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+                })
+    };
+    let entries = |directory: &Path| -> Vec<std::path::PathBuf> {
+        fs::read_dir(directory)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default()
+    };
+    let mut images = Vec::new();
+    for path in entries(directory) {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden || path.ends_with("target") {
+            continue;
+        }
+        if path.is_dir() {
+            images.extend(entries(&path).into_iter().filter(|path| is_image(path)));
+        } else if is_image(&path) {
+            images.push(path);
+        }
+    }
+    let mut images: Vec<String> = images
+        .iter()
+        .filter_map(|path| path.strip_prefix(directory).ok())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    images.sort();
+    images
+}
+
+/// Hiragana, katakana, and CJK ideographs.
+pub(crate) fn is_japanese(text: &str) -> bool {
+    text.chars().any(|ch| {
+        matches!(ch,
+            '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{ff66}'..='\u{ff9f}')
+    })
+}
+
+/// Appends `draft` as a new `[[quotes]]` entry, in the data file's field
+/// order. The draft must be complete.
+pub(crate) fn append(path: &Path, draft: &Draft) -> Result<AnimeQuote> {
+    let mut quote = draft.to_quote()?;
+    let content =
+        fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let mut document: toml_edit::DocumentMut = content
+        .parse()
+        .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+    let mut table = toml_edit::Table::new();
+    table.insert("japanese", toml_edit::value(&quote.japanese));
+    table.insert("anime", toml_edit::value(&quote.anime));
+    if let Some(romaji) = &quote.romaji {
+        table.insert("romaji", toml_edit::value(romaji));
+    }
+    table.insert("character", toml_edit::value(&quote.character));
+    table.insert("quote", toml_edit::value(&quote.quote));
+    if let Some(image) = &quote.image {
+        table.insert("image", toml_edit::value(image));
+    }
+    if let Some(episode) = quote.episode {
+        table.insert("episode", toml_edit::value(i64::from(episode)));
+    }
+    if let Some(timestamp) = &quote.timestamp {
+        table.insert("timestamp", toml_edit::value(timestamp));
+    }
+    let added = chrono::Local::now().format("%Y-%m-%d").to_string();
+    table.insert("added", toml_edit::value(&added));
+    quote.added = Some(added);
+    let quotes = document
+        .entry("quotes")
+        .or_insert_with(|| toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new()));
+    let Some(quotes) = quotes.as_array_of_tables_mut() else {
+        bail!("`quotes` in {} is not an array of tables", path.display());
+    };
+    quotes.push(table);
+    fs::write(path, document.to_string())
+        .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    Ok(quote)
+}
+
+/// Reads the clipboard through the platform's command-line tool:
+/// `pbpaste`, PowerShell's `Get-Clipboard`, or on Linux `wl-paste`, `xclip`,
+/// or `xsel`, whichever is installed.
+pub(crate) fn read_clipboard() -> Result<String> {
+    let candidates: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbpaste", &[])]
+    } else if cfg!(target_os = "windows") {
+        &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])]
+    } else {
+        &[
+            ("wl-paste", &["--no-newline"]),
+            ("xclip", &["-selection", "clipboard", "-o"]),
+            ("xsel", &["--clipboard", "--output"]),
+        ]
+    };
+    for (program, args) in candidates {
+        let Ok(output) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        else {
+            continue;
+        };
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+    let tried: Vec<&str> = candidates.iter().map(|(program, _)| *program).collect();
+    bail!("could not read the clipboard (tried {})", tried.join(", "))
+}
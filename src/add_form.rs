@@ -0,0 +1,187 @@
+//! The add form: shows a [`Draft`], such as one read from the clipboard,
+//! for checking and filling in before it is added to the data file.
+
+use crate::Palette;
+use crate::add::Draft;
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Paragraph, Wrap};
+use unicode_width::UnicodeWidthStr;
+
+const FIELDS: [&str; 8] = [
+    "Japanese",
+    "Romaji",
+    "Anime",
+    "Character",
+    "Quote",
+    "Image",
+    "Episode",
+    "Timestamp",
+];
+/// Widths of the labels and values, so the fields line up when centered.
+const LABEL_WIDTH: usize = 10;
+const VALUE_WIDTH: usize = 40;
+
+pub(crate) enum AddFormAction {
+    Stay,
+    Close,
+    /// Append this draft, which has every required field.
+    Add(Draft),
+}
+
+pub(crate) struct AddForm {
+    /// What is typed in each of [`FIELDS`].
+    values: [String; 8],
+    selected: usize,
+    /// Why the quote could not be added, until the next key.
+    message: Option<String>,
+}
+
+impl AddForm {
+    /// Opens with the fields set to `draft`, on the first one it is missing.
+    pub(crate) fn new(draft: &Draft) -> Self {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        let values = [
+            text(&draft.japanese),
+            text(&draft.romaji),
+            text(&draft.anime),
+            text(&draft.character),
+            text(&draft.quote),
+            text(&draft.image),
+            draft
+                .episode
+                .map(|episode| episode.to_string())
+                .unwrap_or_default(),
+            text(&draft.timestamp),
+        ];
+        let selected = [0, 2, 3, 4]
+            .into_iter()
+            .find(|&field| values[field].trim().is_empty())
+            .unwrap_or(0);
+        Self {
+            values,
+            selected,
+            message: None,
+        }
+    }
+
+    pub(crate) fn report(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode) -> AddFormAction {
+        self.message = None;
+        match code {
+            KeyCode::Up | KeyCode::BackTab => {
+                self.selected = (self.selected + FIELDS.len() - 1) % FIELDS.len();
+            }
+            KeyCode::Down | KeyCode::Tab => self.selected = (self.selected + 1) % FIELDS.len(),
+            KeyCode::Backspace => {
+                self.values[self.selected].pop();
+            }
+            KeyCode::Delete => self.values[self.selected].clear(),
+            KeyCode::Char(ch) => self.values[self.selected].push(ch),
+            KeyCode::Enter => match self.draft() {
+                Ok(draft) => return AddFormAction::Add(draft),
+                Err(message) => self.message = Some(message),
+            },
+            KeyCode::Esc => return AddFormAction::Close,
+            _ => {}
+        }
+        AddFormAction::Stay
+    }
+
+    fn draft(&self) -> Result<Draft, String> {
+        let text = |field: usize| {
+            Some(self.values[field].trim().to_string()).filter(|value| !value.is_empty())
+        };
+        let episode = text(6)
+            .map(|episode| {
+                episode
+                    .parse()
+                    .map_err(|_| "Episode must be a whole number".to_string())
+            })
+            .transpose()?;
+        let draft = Draft {
+            japanese: text(0),
+            romaji: text(1),
+            anime: text(2),
+            character: text(3),
+            quote: text(4),
+            image: text(5),
+            episode,
+            timestamp: text(7),
+        };
+        let missing = draft.missing();
+        if !missing.is_empty() {
+            return Err(format!("Still missing: {}", missing.join(", ")));
+        }
+        Ok(draft)
+    }
+
+    pub(crate) fn render(&self, frame: &mut Frame, area: Rect, palette: &Palette) {
+        let [title_area, fields_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Add quote",
+                Style::default().fg(palette.anime).bold(),
+            )))
+            .centered(),
+            title_area,
+        );
+
+        let count_style = Style::default().fg(palette.count);
+        let mut lines: Vec<Line> = FIELDS
+            .iter()
+            .zip(&self.values)
+            .enumerate()
+            .map(|(field, (label, value))| {
+                let selected = field == self.selected;
+                let label_style = if selected {
+                    Style::default().fg(palette.character).bold()
+                } else {
+                    count_style
+                };
+                // Japanese text is two columns a character.
+                let padding = VALUE_WIDTH.saturating_sub(value.width());
+                Line::from(vec![
+                    Span::styled(format!("{label:>LABEL_WIDTH$}  "), label_style),
+                    Span::styled(value.clone(), Style::default().fg(palette.quote)),
+                    Span::raw(if selected { "▏" } else { " " }),
+                    Span::raw(" ".repeat(padding)),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Japanese, anime, character, and quote are required",
+            count_style,
+        )));
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .centered()
+                .wrap(Wrap { trim: false }),
+            fields_area,
+        );
+
+        let help = match &self.message {
+            Some(message) => Line::from(Span::styled(
+                message.clone(),
+                Style::default().fg(palette.character).bold(),
+            )),
+            None => Line::from(Span::styled(
+                "Field <Up/Down>  Clear field <Delete>  Add <Enter>  Cancel <Esc>",
+                Style::default().fg(palette.instructions),
+            )),
+        };
+        frame.render_widget(Paragraph::new(help).centered(), help_area);
+    }
+}
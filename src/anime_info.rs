@@ -0,0 +1,111 @@
+//! Details about an anime fetched from AniList on request, for the detail
+//! screen, and kept in `anime-info/` in the data directory so each anime is
+//! only looked up once.
+
+use crate::data_dir;
+use crate::export::markdown_anchor;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const ANILIST_URL: &str = "https://graphql.anilist.co";
+const MEDIA_QUERY: &str = "query ($search: String) { Media(search: $search, type: ANIME) { \
+     title { romaji english } format episodes seasonYear averageScore genres status siteUrl } }";
+/// How long a lookup may take before the screen gives up on it.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The fields of an AniList `Media` the detail screen shows.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AnimeInfo {
+    pub(crate) title: Title,
+    pub(crate) format: Option<String>,
+    pub(crate) episodes: Option<u32>,
+    pub(crate) season_year: Option<u32>,
+    /// Out of 100.
+    pub(crate) average_score: Option<u32>,
+    #[serde(default)]
+    pub(crate) genres: Vec<String>,
+    pub(crate) status: Option<String>,
+    pub(crate) site_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Title {
+    pub(crate) romaji: Option<String>,
+    pub(crate) english: Option<String>,
+}
+
+impl AnimeInfo {
+    /// Format, episodes, year, score, and status, for one line.
+    pub(crate) fn summary(&self) -> String {
+        [
+            self.format
+                .as_deref()
+                .map(|format| format.replace('_', " ")),
+            self.episodes.map(|episodes| match episodes {
+                1 => "1 episode".to_string(),
+                episodes => format!("{episodes} episodes"),
+            }),
+            self.season_year.map(|year| year.to_string()),
+            self.average_score.map(|score| format!("score {score}%")),
+            self.status
+                .as_deref()
+                .map(|status| status.replace('_', " ").to_lowercase()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ")
+    }
+}
+
+fn cache_path(anime: &str) -> PathBuf {
+    data_dir()
+        .join("anime-info")
+        .join(format!("{}.json", markdown_anchor(anime)))
+}
+
+/// What an earlier lookup found for `anime`, if it was looked up.
+pub(crate) fn cached(anime: &str) -> Option<AnimeInfo> {
+    let content = fs::read_to_string(cache_path(anime)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Looks `anime` up on AniList and keeps what it found for next time.
+pub(crate) fn fetch(anime: &str) -> Result<AnimeInfo> {
+    let body = serde_json::json!({
+        "query": MEDIA_QUERY,
+        "variables": { "search": anime },
+    });
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .build()
+        .into();
+    let response = agent
+        .post(ANILIST_URL)
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .send(body.to_string())
+        .wrap_err_with(|| format!("failed to look up {anime:?} on AniList"))?
+        .body_mut()
+        .read_to_string()
+        .wrap_err("failed to read the AniList response")?;
+    let mut response: serde_json::Value =
+        serde_json::from_str(&response).wrap_err("AniList sent something other than JSON")?;
+    let media = response["data"]["Media"].take();
+    if media.is_null() {
+        return Err(eyre!("AniList has no anime matching {anime:?}"));
+    }
+    let info: AnimeInfo = serde_json::from_value(media).wrap_err("unexpected AniList response")?;
+
+    let path = cache_path(anime);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&info)?)
+        .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    Ok(info)
+}
@@ -0,0 +1,237 @@
+//! `anki sync`: keeps a deck in a running Anki up to date with the
+//! collection through the AnkiConnect add-on's HTTP API.
+//!
+//! Each note is tagged `anime-quotes::<id>` with its quote's stable ID, so
+//! later runs find the notes they made wherever they were moved, add the
+//! quotes that have none, and rewrite the fields of the ones that changed.
+//! Notes are never deleted; a quote removed from the collection leaves its
+//! note behind.
+
+use crate::AnimeQuote;
+use crate::export::{self, anki_media_name};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const DEFAULT_ANKI_URL: &str = "http://127.0.0.1:8765";
+const DEFAULT_ANKI_DECK: &str = "Anime Quotes";
+const DEFAULT_ANKI_MODEL: &str = "Basic";
+const DEFAULT_FRONT_FIELD: &str = "Front";
+const DEFAULT_BACK_FIELD: &str = "Back";
+/// The AnkiConnect API version the requests are written for.
+const ANKI_CONNECT_VERSION: u32 = 6;
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// The parent of the per-quote ID tags.
+const ID_TAG: &str = "anime-quotes::";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AnkiConfig {
+    /// Where AnkiConnect listens.
+    #[serde(default = "default_anki_url")]
+    url: String,
+    /// The deck new notes go into, created if it doesn't exist.
+    #[serde(default = "default_anki_deck")]
+    deck: String,
+    /// The note type of new notes.
+    #[serde(default = "default_anki_model")]
+    model: String,
+    /// The note type's fields for the Japanese and for the rest.
+    #[serde(default = "default_front_field")]
+    front_field: String,
+    #[serde(default = "default_back_field")]
+    back_field: String,
+}
+
+impl Default for AnkiConfig {
+    fn default() -> Self {
+        Self {
+            url: default_anki_url(),
+            deck: default_anki_deck(),
+            model: default_anki_model(),
+            front_field: default_front_field(),
+            back_field: default_back_field(),
+        }
+    }
+}
+
+fn default_anki_url() -> String {
+    DEFAULT_ANKI_URL.to_string()
+}
+
+fn default_anki_deck() -> String {
+    DEFAULT_ANKI_DECK.to_string()
+}
+
+fn default_anki_model() -> String {
+    DEFAULT_ANKI_MODEL.to_string()
+}
+
+fn default_front_field() -> String {
+    DEFAULT_FRONT_FIELD.to_string()
+}
+
+fn default_back_field() -> String {
+    DEFAULT_BACK_FIELD.to_string()
+}
+
+/// What a sync did.
+#[derive(Debug, Default)]
+pub(crate) struct SyncReport {
+    pub(crate) added: usize,
+    pub(crate) updated: usize,
+    pub(crate) unchanged: usize,
+}
+
+struct Client {
+    agent: ureq::Agent,
+    url: String,
+}
+
+impl Client {
+    /// Calls an AnkiConnect action and returns its result.
+    fn request(&self, action: &str, params: Value) -> Result<Value> {
+        let body = json!({
+            "action": action,
+            "version": ANKI_CONNECT_VERSION,
+            "params": params,
+        });
+        let response = self
+            .agent
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .send(body.to_string())
+            .wrap_err_with(|| {
+                format!(
+                    "failed to reach AnkiConnect at {}; is Anki running with the add-on?",
+                    self.url
+                )
+            })?
+            .body_mut()
+            .read_to_string()
+            .wrap_err("failed to read the AnkiConnect response")?;
+        let mut response: Value = serde_json::from_str(&response)
+            .wrap_err("AnkiConnect sent something other than JSON")?;
+        match response["error"].as_str() {
+            Some(error) => Err(eyre!("AnkiConnect {action}: {error}")),
+            None => Ok(response["result"].take()),
+        }
+    }
+}
+
+/// A note made by an earlier sync, by the ID of its quote.
+struct SyncedNote {
+    id: u64,
+    front: String,
+    back: String,
+}
+
+/// Adds notes for the quotes that have none in Anki and updates the ones
+/// whose note no longer matches, copying their images into Anki's media.
+pub(crate) fn sync(quotes: &[AnimeQuote], config: &AnkiConfig) -> Result<SyncReport> {
+    let client = Client {
+        agent: ureq::Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .build()
+            .into(),
+        url: config.url.clone(),
+    };
+    client.request("createDeck", json!({ "deck": config.deck }))?;
+    let synced = synced_notes(&client, config)?;
+
+    let mut report = SyncReport::default();
+    let mut new_notes = Vec::new();
+    for quote in quotes {
+        let note = export::anki_note(quote);
+        let fields = json!({
+            config.front_field.as_str(): note.front,
+            config.back_field.as_str(): note.back,
+        });
+        match synced.get(&quote.stable_id()) {
+            Some(existing) if existing.front == note.front && existing.back == note.back => {
+                report.unchanged += 1;
+                continue;
+            }
+            Some(existing) => {
+                client.request(
+                    "updateNoteFields",
+                    json!({ "note": { "id": existing.id, "fields": fields } }),
+                )?;
+                report.updated += 1;
+            }
+            None => {
+                new_notes.push(json!({
+                    "deckName": config.deck,
+                    "modelName": config.model,
+                    "fields": fields,
+                    "tags": [note.tag, format!("{ID_TAG}{}", quote.stable_id())],
+                    "options": { "allowDuplicate": true },
+                }));
+                report.added += 1;
+            }
+        }
+        if let Some(image) = &quote.image {
+            store_media(&client, image)?;
+        }
+    }
+    if !new_notes.is_empty() {
+        client.request("addNotes", json!({ "notes": new_notes }))?;
+    }
+    Ok(report)
+}
+
+fn synced_notes(client: &Client, config: &AnkiConfig) -> Result<HashMap<String, SyncedNote>> {
+    let ids = client.request("findNotes", json!({ "query": format!("tag:{ID_TAG}*") }))?;
+    let notes = client.request("notesInfo", json!({ "notes": ids }))?;
+    let field = |note: &Value, name: &str| {
+        note["fields"][name]["value"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    };
+    let mut synced = HashMap::new();
+    for note in notes.as_array().into_iter().flatten() {
+        let (Some(id), Some(tags)) = (note["noteId"].as_u64(), note["tags"].as_array()) else {
+            continue;
+        };
+        let quote_id = tags
+            .iter()
+            .filter_map(Value::as_str)
+            .find_map(|tag| tag.strip_prefix(ID_TAG));
+        if let Some(quote_id) = quote_id {
+            synced.insert(
+                quote_id.to_string(),
+                SyncedNote {
+                    id,
+                    front: field(note, &config.front_field),
+                    back: field(note, &config.back_field),
+                },
+            );
+        }
+    }
+    Ok(synced)
+}
+
+/// Copies `image` into Anki's media folder under the name the note's
+/// `<img>` uses, replacing an older copy. A missing image is only warned
+/// about, since the note is still worth having.
+fn store_media(client: &Client, image: &str) -> Result<()> {
+    let Some(name) = anki_media_name(image) else {
+        return Ok(());
+    };
+    let path = match fs::canonicalize(Path::new(image)) {
+        Ok(path) => path,
+        Err(error) => {
+            eprintln!("failed to find image {image}: {error}, syncing the note without it");
+            return Ok(());
+        }
+    };
+    client.request(
+        "storeMediaFile",
+        json!({ "filename": name, "path": path.to_string_lossy() }),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Decoded images and their encodings for the terminal's graphics protocol.
+//!
+//! Sixel and kitty encoding is the slow part of drawing an image, so each
+//! image keeps what it was last encoded as for the few sizes it was drawn at:
+//! flipping back to a quote, or between the views that show it at different
+//! sizes, reuses the encoding instead of redoing it.
+//!
+//! Decoding happens off the UI thread, so the viewer starts drawing right
+//! away however large the collection is and each image shows up once it is
+//! ready. Only the images drawn lately and those next to the current quote
+//! are decoded, which keeps memory flat for very large collections.
+
+use crate::timings::DecodeTotals;
+use crate::{AnimeQuote, thumbnails};
+use image::DynamicImage;
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui_image::picker::{Picker, ProtocolType};
+use ratatui_image::protocol::Protocol;
+use ratatui_image::{Image, Resize};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Instant;
+
+/// How many encodings of one image are kept, most recently drawn first.
+const ENCODINGS_PER_IMAGE: usize = 3;
+
+/// What an encoding depends on besides the image itself.
+#[derive(PartialEq)]
+struct EncodingKey {
+    width: u16,
+    height: u16,
+    /// The resize strategy's `Debug` text, since `Resize` has no `PartialEq`.
+    resize: String,
+    protocol: ProtocolType,
+}
+
+pub(crate) struct ImageSlot {
+    image: DynamicImage,
+    encodings: Vec<(EncodingKey, Protocol)>,
+}
+
+impl ImageSlot {
+    pub(crate) fn new(image: DynamicImage) -> Self {
+        Self {
+            image,
+            encodings: Vec::new(),
+        }
+    }
+
+    /// The image's size in cells of `picker`'s font.
+    pub(crate) fn cells(&self, picker: &Picker) -> (u16, u16) {
+        let (font_width, font_height) = picker.font_size();
+        (
+            u16::try_from(self.image.width() / u32::from(font_width.max(1))).unwrap_or(u16::MAX),
+            u16::try_from(self.image.height() / u32::from(font_height.max(1))).unwrap_or(u16::MAX),
+        )
+    }
+
+    /// Draws the image in `area`, encoding it with `picker` only when it has
+    /// not been drawn at this size with these settings recently.
+    pub(crate) fn draw(&mut self, frame: &mut Frame, area: Rect, picker: &Picker, resize: &Resize) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let key = EncodingKey {
+            width: area.width,
+            height: area.height,
+            resize: format!("{resize:?}"),
+            protocol: picker.protocol_type(),
+        };
+        let encoding = match self.encodings.iter().position(|(cached, _)| *cached == key) {
+            Some(position) => self.encodings.remove(position),
+            None => match picker.new_protocol(self.image.clone(), area, resize.clone()) {
+                Ok(protocol) => (key, protocol),
+                Err(error) => {
+                    eprintln!("failed to encode image: {error}");
+                    return;
+                }
+            },
+        };
+        self.encodings.insert(0, encoding);
+        self.encodings.truncate(ENCODINGS_PER_IMAGE);
+        frame.render_widget(Image::new(&self.encodings[0].1), area);
+    }
+}
+
+/// Decodes quote images on a background thread as the viewer asks for
+/// them, scaled down to what the terminal window can show.
+pub(crate) struct ImageLoader {
+    requests: Sender<(usize, AnimeQuote)>,
+    decoded: Receiver<(usize, Option<DynamicImage>)>,
+}
+
+impl ImageLoader {
+    /// Starts the thread; it stops once the loader is dropped. With
+    /// `decoding`, the time spent on each image is added to it.
+    pub(crate) fn new(picker: &Picker, decoding: Option<DecodeTotals>) -> Self {
+        let (columns, rows) = crossterm::terminal::size().unwrap_or((200, 60));
+        let (font_width, font_height) = picker.font_size();
+        let largest = (
+            u32::from(columns) * u32::from(font_width),
+            u32::from(rows) * u32::from(font_height),
+        );
+        let (requests, queue) = mpsc::channel::<(usize, AnimeQuote)>();
+        let (sender, decoded) = mpsc::channel();
+        thread::spawn(move || {
+            for (index, quote) in queue {
+                let started = Instant::now();
+                let loaded = thumbnails::load(&quote, largest);
+                if let Some(decoding) = &decoding {
+                    let mut totals = decoding
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    totals.0 += started.elapsed();
+                    totals.1 += 1;
+                }
+                let image = match loaded {
+                    Some(Ok(image)) => Some(image),
+                    Some(Err(error)) => {
+                        eprintln!(
+                            "failed to load image from {}: {error}",
+                            quote.image.as_deref().unwrap_or_default()
+                        );
+                        None
+                    }
+                    None => None,
+                };
+                if sender.send((index, image)).is_err() {
+                    return;
+                }
+            }
+        });
+        Self { requests, decoded }
+    }
+
+    /// Queues the image of `quote`, the one at `index`.
+    pub(crate) fn request(&self, index: usize, quote: &AnimeQuote) {
+        let _ = self.requests.send((index, quote.clone()));
+    }
+
+    /// The images decoded since the last call, with `None` for those that
+    /// did not load.
+    pub(crate) fn decoded(&self) -> Vec<(usize, Option<DynamicImage>)> {
+        self.decoded.try_iter().collect()
+    }
+}
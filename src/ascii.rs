@@ -0,0 +1,100 @@
+use crate::AsciiSettings;
+use crate::selection::{fnv1a, splitmix64};
+use image::{DynamicImage, imageops::FilterType};
+
+/// Converts an image into rows of gradient characters, `columns` wide.
+///
+/// Each output character averages a `detail_x` × `detail_y` block of samples,
+/// and the row count is scaled by `char_aspect` to compensate for terminal
+/// cells being taller than they are wide.
+pub(crate) fn image_to_ascii(
+    image: &DynamicImage,
+    settings: &AsciiSettings,
+    columns: u32,
+) -> Vec<String> {
+    let columns = columns.max(1);
+    let aspect = image.height() as f32 / image.width().max(1) as f32;
+    let rows = ((columns as f32) * aspect * settings.char_aspect)
+        .round()
+        .max(1.0) as u32;
+
+    let samples = image
+        .resize_exact(
+            columns * settings.detail_x,
+            rows * settings.detail_y,
+            FilterType::Triangle,
+        )
+        .to_luma8();
+
+    (0..rows)
+        .map(|row| {
+            (0..columns)
+                .map(|column| {
+                    let mut total = 0u32;
+                    for dy in 0..settings.detail_y {
+                        for dx in 0..settings.detail_x {
+                            let x = column * settings.detail_x + dx;
+                            let y = row * settings.detail_y + dy;
+                            total += samples.get_pixel(x, y)[0] as u32;
+                        }
+                    }
+                    let luminance = total / (settings.detail_x * settings.detail_y);
+                    gradient_char(&settings.gradient, luminance as u8)
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Maps luminance onto the gradient, which runs from densest (dark) to lightest.
+fn gradient_char(gradient: &[char], luminance: u8) -> char {
+    let last = gradient.len().saturating_sub(1);
+    let index = (luminance as usize * last + 127) / 255;
+    gradient.get(index).copied().unwrap_or(' ')
+}
+
+const AVATAR_GRID: usize = 5;
+
+/// Identicon-style stand-in for missing artwork: a horizontally mirrored
+/// 5 × 5 pattern seeded from `seed`, drawn in one glyph from the dense end
+/// of the gradient and stretched to `columns` × `rows`. The same seed always
+/// gives the same avatar.
+pub(crate) fn avatar(
+    seed: &str,
+    settings: &AsciiSettings,
+    columns: usize,
+    rows: usize,
+) -> Vec<String> {
+    let gradient = &settings.gradient;
+    let blank = gradient.last().copied().unwrap_or(' ');
+    let dense = &gradient[..(gradient.len() / 10).max(1)];
+
+    let mut state = fnv1a(seed.as_bytes());
+    let mut next = || {
+        state = splitmix64(state);
+        state
+    };
+    let glyph = dense[next() as usize % dense.len()];
+    let half = AVATAR_GRID.div_ceil(2);
+    let cells: Vec<Vec<char>> = (0..AVATAR_GRID)
+        .map(|_| {
+            let left: Vec<char> = (0..half)
+                .map(|_| if next() & 1 == 0 { blank } else { glyph })
+                .collect();
+            let mut row = left.clone();
+            row.extend(left.iter().rev().skip(AVATAR_GRID % 2));
+            row
+        })
+        .collect();
+
+    (0..rows)
+        .map(|row| {
+            let cells = &cells[row * AVATAR_GRID / rows.max(1)];
+            (0..columns)
+                .map(|column| cells[column * AVATAR_GRID / columns.max(1)])
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,150 @@
+//! `backup` and `restore`: a gzipped tarball holding the collection, the
+//! config, and the data directory (packs and viewer state).
+//!
+//! Archive layout: `anime.toml` and `config.toml` at the top level, the data
+//! file's `quotes.d` files under `quotes.d/`, and the data directory under
+//! `data/`, so a restore can put each part back where
+//! this machine expects it (including files chosen with `--data`/`--config`).
+
+use crate::{config_path, data_path, organize};
+use chrono::Local;
+use color_eyre::eyre::{Result, WrapErr, bail};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+
+const DATA_MEMBER: &str = "anime.toml";
+const CONFIG_MEMBER: &str = "config.toml";
+const DATA_PREFIX: &str = "data";
+const SPLIT_PREFIX: &str = "quotes.d";
+
+/// `anime-quotes-backup-20250101-120000.tar.gz`
+pub(crate) fn default_archive_name() -> PathBuf {
+    PathBuf::from(format!(
+        "anime-quotes-backup-{}.tar.gz",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ))
+}
+
+/// Writes the archive and returns the archived paths, for the summary.
+pub(crate) fn backup(archive: &Path, data_dir: &Path) -> Result<Vec<String>> {
+    let file = File::create(archive)
+        .wrap_err_with(|| format!("failed to create {}", archive.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mut archived = Vec::new();
+    for (path, member) in [(data_path(), DATA_MEMBER), (config_path(), CONFIG_MEMBER)] {
+        if Path::new(path).is_file() {
+            builder
+                .append_path_with_name(path, member)
+                .wrap_err_with(|| format!("failed to archive {path}"))?;
+            archived.push(path.to_string());
+        }
+    }
+    let split = organize::split_dir(Path::new(data_path()));
+    if split.is_dir() {
+        append_dir(&mut builder, &split, Path::new(SPLIT_PREFIX))?;
+        archived.push(format!("{}/", split.display()));
+    }
+    if data_dir.is_dir() {
+        append_dir(&mut builder, data_dir, Path::new(DATA_PREFIX))?;
+        archived.push(format!("{}/", data_dir.display()));
+    }
+    builder.into_inner()?.finish()?;
+    Ok(archived)
+}
+
+/// Adds `dir` recursively under `name`, skipping the sync repository's
+/// `.git` directory, which `sync` can rebuild from the remote.
+fn append_dir(builder: &mut tar::Builder<GzEncoder<File>>, dir: &Path, name: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir).wrap_err_with(|| format!("failed to read {}", dir.display()))? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let path = entry.path();
+        let target = name.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            append_dir(builder, &path, &target)?;
+        } else {
+            builder
+                .append_path_with_name(&path, &target)
+                .wrap_err_with(|| format!("failed to archive {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks `archive`, refusing to replace existing files unless `force` is
+/// set. Returns the restored paths.
+pub(crate) fn restore(archive: &Path, data_dir: &Path, force: bool) -> Result<Vec<PathBuf>> {
+    let mut targets = Vec::new();
+    for entry in open(archive)?.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            targets.push(target_path(&entry.path()?, data_dir)?);
+        }
+    }
+    let existing: Vec<String> = targets
+        .iter()
+        .filter(|target| target.exists())
+        .map(|target| target.display().to_string())
+        .collect();
+    if !force && !existing.is_empty() {
+        bail!(
+            "restoring would overwrite {} (pass --force to replace them)",
+            existing.join(", ")
+        );
+    }
+
+    for entry in open(archive)?.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let target = target_path(&entry.path()?, data_dir)?;
+        if let Some(parent) = target
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        entry
+            .unpack(&target)
+            .wrap_err_with(|| format!("failed to restore {}", target.display()))?;
+    }
+    Ok(targets)
+}
+
+fn open(archive: &Path) -> Result<tar::Archive<GzDecoder<File>>> {
+    let file =
+        File::open(archive).wrap_err_with(|| format!("failed to open {}", archive.display()))?;
+    Ok(tar::Archive::new(GzDecoder::new(file)))
+}
+
+/// Maps an archive member to where it belongs on this machine, rejecting
+/// anything outside the layout (including `..` components).
+fn target_path(member: &Path, data_dir: &Path) -> Result<PathBuf> {
+    if member
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        bail!("unexpected path {} in archive", member.display());
+    }
+    if member == Path::new(DATA_MEMBER) {
+        return Ok(PathBuf::from(data_path()));
+    }
+    if member == Path::new(CONFIG_MEMBER) {
+        return Ok(PathBuf::from(config_path()));
+    }
+    if let Ok(rest) = member.strip_prefix(SPLIT_PREFIX)
+        && !rest.as_os_str().is_empty()
+    {
+        return Ok(organize::split_dir(Path::new(data_path())).join(rest));
+    }
+    match member.strip_prefix(DATA_PREFIX) {
+        Ok(rest) if !rest.as_os_str().is_empty() => Ok(data_dir.join(rest)),
+        _ => bail!("unexpected path {} in archive", member.display()),
+    }
+}
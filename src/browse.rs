@@ -0,0 +1,304 @@
+//! The browse list: every quote on one line each, in the viewer's order, with
+//! marks for changing several at once.
+
+use crate::state::State;
+use crate::{AnimeQuote, Palette};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Rows moved by Page Up and Page Down.
+const PAGE: usize = 10;
+const DEFAULT_EXPORT_PATH: &str = "selection.toml";
+const DEFAULT_PLAYLIST_PATH: &str = "playlist.txt";
+
+/// A change applied to the marked quotes, or the one under the cursor when
+/// none are marked.
+pub(crate) enum Batch {
+    Tag(String),
+    /// Favorites them, or takes them out of the favorites when they all are.
+    Favorite,
+    Delete,
+    /// Writes them to a file, in the format its extension names.
+    Export(PathBuf),
+    /// Moves them to the pack of this name.
+    Move(String),
+    /// Adds them to the end of the queue, or takes them out when they are
+    /// all in it.
+    Queue,
+    /// Writes the queue to a playlist file.
+    SaveQueue(PathBuf),
+}
+
+pub(crate) enum BrowseAction {
+    Stay,
+    Close,
+    /// Show the quote at this index in the viewer.
+    Show(usize),
+    /// Apply the change to the quotes at these indices.
+    Apply(Batch, Vec<usize>),
+}
+
+#[derive(Clone, Copy)]
+enum Prompt {
+    Tag,
+    Export,
+    Move,
+    SaveQueue,
+    ConfirmDelete,
+}
+
+impl Prompt {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Tag => "Tag",
+            Self::Export => "Export to",
+            Self::Move => "Move to pack",
+            Self::SaveQueue => "Save queue to",
+            Self::ConfirmDelete => "Delete",
+        }
+    }
+}
+
+pub(crate) struct Browse {
+    list: ListState,
+    /// Indices into the collection.
+    marked: BTreeSet<usize>,
+    prompt: Option<(Prompt, String)>,
+    /// The outcome of the last change, until the next key.
+    message: Option<String>,
+    /// Indices into the collection of the queued quotes, in queue order.
+    queued: Vec<usize>,
+}
+
+impl Browse {
+    /// Opens with the cursor on `position` in the viewer's order.
+    pub(crate) fn new(position: usize, queued: Vec<usize>) -> Self {
+        Self {
+            list: ListState::default().with_selected(Some(position)),
+            marked: BTreeSet::new(),
+            prompt: None,
+            message: None,
+            queued,
+        }
+    }
+
+    /// Marks the quotes at these indices as queued, after the queue changed.
+    pub(crate) fn set_queued(&mut self, queued: Vec<usize>) {
+        self.queued = queued;
+    }
+
+    /// Shows `message` in place of the key help until the next key.
+    pub(crate) fn report(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    /// Forgets the marks, whose indices no longer mean the same quotes once
+    /// the collection has changed.
+    pub(crate) fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    fn cursor(&self, order: &[usize]) -> Option<usize> {
+        let selected = self.list.selected()?.min(order.len().checked_sub(1)?);
+        order.get(selected).copied()
+    }
+
+    fn targets(&self, order: &[usize]) -> Vec<usize> {
+        if self.marked.is_empty() {
+            self.cursor(order).into_iter().collect()
+        } else {
+            self.marked.iter().copied().collect()
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode, order: &[usize]) -> BrowseAction {
+        self.message = None;
+        if let Some((prompt, input)) = &mut self.prompt {
+            let prompt = *prompt;
+            let batch = match (prompt, code) {
+                (_, KeyCode::Esc) => {
+                    self.prompt = None;
+                    return BrowseAction::Stay;
+                }
+                (Prompt::ConfirmDelete, KeyCode::Char('y' | 'Y')) => Batch::Delete,
+                (Prompt::ConfirmDelete, _) => {
+                    self.prompt = None;
+                    return BrowseAction::Stay;
+                }
+                (_, KeyCode::Char(ch)) => {
+                    input.push(ch);
+                    return BrowseAction::Stay;
+                }
+                (_, KeyCode::Backspace) => {
+                    input.pop();
+                    return BrowseAction::Stay;
+                }
+                (_, KeyCode::Enter) if !input.trim().is_empty() => {
+                    let input = input.trim().to_string();
+                    match prompt {
+                        Prompt::Tag => Batch::Tag(input),
+                        Prompt::Export => Batch::Export(PathBuf::from(input)),
+                        Prompt::SaveQueue => Batch::SaveQueue(PathBuf::from(input)),
+                        _ => Batch::Move(input),
+                    }
+                }
+                _ => return BrowseAction::Stay,
+            };
+            self.prompt = None;
+            return BrowseAction::Apply(batch, self.targets(order));
+        }
+
+        let selected = self.list.selected().unwrap_or(0);
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::PageUp => self.list.select(Some(selected.saturating_sub(PAGE))),
+            KeyCode::PageDown => self.list.select(Some(selected + PAGE)),
+            KeyCode::Home => self.list.select_first(),
+            KeyCode::End => self.list.select_last(),
+            KeyCode::Char(' ') => {
+                if let Some(index) = self.cursor(order) {
+                    if !self.marked.remove(&index) {
+                        self.marked.insert(index);
+                    }
+                    self.list.select_next();
+                }
+            }
+            KeyCode::Char('a') => {
+                if self.marked.len() == order.len() {
+                    self.marked.clear();
+                } else {
+                    self.marked = order.iter().copied().collect();
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(index) = self.cursor(order) {
+                    return BrowseAction::Show(index);
+                }
+            }
+            KeyCode::Char('t') => self.prompt = Some((Prompt::Tag, String::new())),
+            KeyCode::Char('e') => {
+                self.prompt = Some((Prompt::Export, DEFAULT_EXPORT_PATH.to_string()));
+            }
+            KeyCode::Char('m') => self.prompt = Some((Prompt::Move, String::new())),
+            KeyCode::Char('D') | KeyCode::Delete => {
+                self.prompt = Some((Prompt::ConfirmDelete, String::new()));
+            }
+            KeyCode::Char('f') => {
+                return BrowseAction::Apply(Batch::Favorite, self.targets(order));
+            }
+            KeyCode::Char('p') => {
+                return BrowseAction::Apply(Batch::Queue, self.targets(order));
+            }
+            KeyCode::Char('P') => {
+                self.prompt = Some((Prompt::SaveQueue, DEFAULT_PLAYLIST_PATH.to_string()));
+            }
+            KeyCode::Esc | KeyCode::Char('l') | KeyCode::Char('q') => return BrowseAction::Close,
+            _ => {}
+        }
+        BrowseAction::Stay
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        quotes: &[AnimeQuote],
+        order: &[usize],
+        state: &State,
+        palette: &Palette,
+    ) {
+        let [title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        let count_style = Style::default().fg(palette.count);
+        let title = match self.marked.len() {
+            0 => format!("Browse ({} quotes)", order.len()),
+            marked => format!("Browse ({} quotes, {marked} marked)", order.len()),
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                title,
+                Style::default().fg(palette.anime).bold(),
+            )))
+            .centered(),
+            title_area,
+        );
+
+        let help = match (&self.prompt, &self.message) {
+            (Some((Prompt::ConfirmDelete, _)), _) => {
+                let count = self.targets(order).len();
+                Line::from(Span::styled(
+                    format!("Delete {count} quote(s) from the data files? (y/n)"),
+                    Style::default().fg(palette.character).bold(),
+                ))
+            }
+            (Some((prompt, input)), _) => Line::from(vec![
+                Span::styled(
+                    format!("{}: ", prompt.label()),
+                    Style::default().fg(palette.character).bold(),
+                ),
+                Span::raw(input.clone()),
+                Span::raw("▏"),
+                Span::styled("  <Enter> apply  <Esc> cancel", count_style),
+            ]),
+            (None, Some(message)) => Line::from(Span::styled(message.clone(), count_style)),
+            (None, None) => Line::from(Span::styled(
+                "Mark <Space>  All <A>  Tag <T>  Fav <F>  Export <E>  Move <M>  \
+                 Delete <Shift+D>  Queue <P>  Save queue <Shift+P>  Back <Esc>",
+                Style::default().fg(palette.instructions),
+            )),
+        };
+        frame.render_widget(Paragraph::new(help).centered(), help_area);
+
+        let items: Vec<ListItem> = order
+            .iter()
+            .enumerate()
+            .map(|(position, &index)| {
+                let quote = &quotes[index];
+                let mark = if self.marked.contains(&index) {
+                    "● "
+                } else {
+                    "  "
+                };
+                let favorite = if state.is_favorite(quote) {
+                    "★ "
+                } else {
+                    "  "
+                };
+                let queued = match self.queued.iter().position(|&queued| queued == index) {
+                    Some(place) => format!("▸{:<3}", place + 1),
+                    None => "    ".to_string(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(mark, Style::default().fg(palette.character)),
+                    Span::styled(format!("{:>4} ", position + 1), count_style),
+                    Span::styled(favorite, Style::default().fg(palette.anime)),
+                    Span::styled(queued, count_style),
+                    Span::styled(
+                        quote.character.clone(),
+                        Style::default().fg(palette.character).bold(),
+                    ),
+                    Span::raw(" — "),
+                    Span::styled(quote.anime.clone(), Style::default().fg(palette.anime)),
+                    Span::raw("  "),
+                    Span::styled(
+                        format!("\"{}\"", quote.quote),
+                        Style::default().fg(palette.quote).italic(),
+                    ),
+                ]))
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+    }
+}
@@ -0,0 +1,155 @@
+//! The calendar screen: the quote of the day for every past day, so an
+//! earlier pick ("last Tuesday's quote") is easy to find again.
+
+use crate::schedule::ScheduleConfig;
+use crate::state::State;
+use crate::{AnimeQuote, Palette};
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Paragraph, Wrap};
+
+const WEEKDAYS: &str = " Mo  Tu  We  Th  Fr  Sa  Su ";
+
+pub(crate) enum CalendarAction {
+    Stay,
+    Close,
+    /// Show the quote at this index in the viewer.
+    Show(usize),
+}
+
+pub(crate) struct Calendar {
+    selected: NaiveDate,
+}
+
+impl Calendar {
+    pub(crate) fn new() -> Self {
+        Self {
+            selected: Local::now().date_naive(),
+        }
+    }
+
+    pub(crate) fn handle_key(
+        &mut self,
+        code: KeyCode,
+        state: &State,
+        quotes: &[AnimeQuote],
+        schedule: &ScheduleConfig,
+    ) -> CalendarAction {
+        let selected = self.selected;
+        let moved = match code {
+            KeyCode::Left => selected.checked_sub_days(Days::new(1)),
+            KeyCode::Right => selected.checked_add_days(Days::new(1)),
+            KeyCode::Up => selected.checked_sub_days(Days::new(7)),
+            KeyCode::Down => selected.checked_add_days(Days::new(7)),
+            KeyCode::PageUp | KeyCode::Char('[') => selected.checked_sub_months(Months::new(1)),
+            KeyCode::PageDown | KeyCode::Char(']') => selected.checked_add_months(Months::new(1)),
+            KeyCode::Char('t') => Some(Local::now().date_naive()),
+            KeyCode::Enter => {
+                return match state.daily_pick(quotes, selected, schedule) {
+                    Some((index, _)) => CalendarAction::Show(index),
+                    None => CalendarAction::Stay,
+                };
+            }
+            KeyCode::Esc | KeyCode::Char('d') | KeyCode::Char('q') => return CalendarAction::Close,
+            _ => None,
+        };
+        if let Some(date) = moved {
+            // Future picks aren't history yet.
+            self.selected = date.min(Local::now().date_naive());
+        }
+        CalendarAction::Stay
+    }
+
+    pub(crate) fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &State,
+        quotes: &[AnimeQuote],
+        schedule: &ScheduleConfig,
+        palette: &Palette,
+    ) {
+        let today = Local::now().date_naive();
+        let count_style = Style::default().fg(palette.count);
+        let mut lines = vec![
+            Line::from(Span::styled(
+                self.selected.format("%B %Y").to_string(),
+                Style::default().fg(palette.anime).bold(),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(WEEKDAYS, count_style)),
+        ];
+
+        let first = self.selected.with_day(1).unwrap_or(self.selected);
+        let mut week = vec![Span::raw("    "); first.weekday().num_days_from_monday() as usize];
+        let mut day = Some(first);
+        while let Some(date) = day.filter(|date| date.month() == first.month()) {
+            let style = if date == self.selected {
+                Style::default().reversed().bold()
+            } else if date > today {
+                count_style.dim()
+            } else if matches!(state.daily_pick(quotes, date, schedule), Some((_, true))) {
+                Style::default().fg(palette.character).bold()
+            } else {
+                Style::default()
+            };
+            week.push(Span::styled(format!(" {:>2} ", date.day()), style));
+            if week.len() == 7 {
+                lines.push(Line::from(std::mem::take(&mut week)));
+            }
+            day = date.succ_opt();
+        }
+        if !week.is_empty() {
+            week.resize(7, Span::raw("    "));
+            lines.push(Line::from(week));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            self.selected.format("%A, %B %-d, %Y").to_string(),
+            Style::default().bold(),
+        )));
+        match state.daily_pick(quotes, self.selected, schedule) {
+            Some((index, recorded)) => {
+                let quote = &quotes[index];
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        quote.anime.clone(),
+                        Style::default().fg(palette.anime).bold(),
+                    ),
+                    Span::raw(" — "),
+                    Span::styled(
+                        quote.character.clone(),
+                        Style::default().fg(palette.character).bold(),
+                    ),
+                ]));
+                lines.push(Line::from(Span::styled(
+                    format!("\"{}\"", quote.quote),
+                    Style::default().fg(palette.quote).italic(),
+                )));
+                if !recorded {
+                    lines.push(Line::from(Span::styled(
+                        "(not recorded; computed from the current collection)",
+                        count_style,
+                    )));
+                }
+            }
+            None => lines.push(Line::from(Span::styled("No quotes loaded", count_style))),
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Day <Arrows>  Month <[ ]>  Today <T>  Show <Enter>  Back <Esc>",
+            Style::default().fg(palette.instructions),
+        )));
+
+        let paragraph = Paragraph::new(Text::from(lines))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,430 @@
+use crate::{AnimeQuote, color_to_rgb, parse_color};
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use image::{Rgba, RgbaImage, imageops};
+use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut, text_size};
+use imageproc::rect::Rect;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_CARD_TEMPLATE: &str = "landscape";
+const DEFAULT_CARD_OUTPUT_DIR: &str = ".";
+const MIN_TEXT_SCALE: f32 = 0.5;
+
+/// Fonts probed when `card.font` is not set; all of them can render Japanese.
+const FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/noto/NotoSansJP-Regular.ttf",
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+];
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CardConfig {
+    #[serde(default)]
+    font: Option<String>,
+    #[serde(default)]
+    font_index: u32,
+    #[serde(default = "default_card_template")]
+    template: String,
+    #[serde(default = "default_card_output_dir")]
+    output_dir: String,
+    #[serde(default)]
+    templates: HashMap<String, CardTemplate>,
+}
+
+impl Default for CardConfig {
+    fn default() -> Self {
+        Self {
+            font: None,
+            font_index: 0,
+            template: default_card_template(),
+            output_dir: default_card_output_dir(),
+            templates: HashMap::new(),
+        }
+    }
+}
+
+fn default_card_template() -> String {
+    DEFAULT_CARD_TEMPLATE.to_string()
+}
+
+fn default_card_output_dir() -> String {
+    DEFAULT_CARD_OUTPUT_DIR.to_string()
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum CardLayout {
+    #[default]
+    ImageLeft,
+    ImageTop,
+    TextOnly,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+struct CardTemplate {
+    width: u32,
+    height: u32,
+    padding: u32,
+    layout: CardLayout,
+    background: String,
+    text: String,
+    accent: String,
+    muted: String,
+    japanese_size: f32,
+    romaji_size: f32,
+    quote_size: f32,
+    attribution_size: f32,
+}
+
+impl Default for CardTemplate {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 630,
+            padding: 48,
+            layout: CardLayout::ImageLeft,
+            background: "#1e1e2e".to_string(),
+            text: "#f5f5f5".to_string(),
+            accent: "#f9e2af".to_string(),
+            muted: "#a6adc8".to_string(),
+            japanese_size: 52.0,
+            romaji_size: 26.0,
+            quote_size: 34.0,
+            attribution_size: 26.0,
+        }
+    }
+}
+
+impl CardTemplate {
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "landscape" => Some(Self::default()),
+            "square" => Some(Self {
+                width: 1080,
+                height: 1080,
+                layout: CardLayout::ImageTop,
+                ..Self::default()
+            }),
+            "minimal" => Some(Self {
+                layout: CardLayout::TextOnly,
+                background: "#fafafa".to_string(),
+                text: "#202020".to_string(),
+                accent: "#c0392b".to_string(),
+                muted: "#606060".to_string(),
+                japanese_size: 64.0,
+                quote_size: 38.0,
+                ..Self::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl CardConfig {
+    fn template(&self, name: Option<&str>) -> Result<CardTemplate> {
+        let name = name.unwrap_or(&self.template);
+        self.templates
+            .get(name)
+            .cloned()
+            .or_else(|| CardTemplate::builtin(name))
+            .ok_or_else(|| eyre!("unknown card template {name:?}"))
+    }
+
+    fn load_font(&self) -> Result<FontVec> {
+        let path = match &self.font {
+            Some(path) => PathBuf::from(path),
+            None => FALLBACK_FONTS
+                .iter()
+                .map(PathBuf::from)
+                .find(|path| path.exists())
+                .ok_or_else(|| {
+                    eyre!("no CJK font found; set `font` in the [card] section of config.toml")
+                })?,
+        };
+        let data =
+            fs::read(&path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        FontVec::try_from_vec_and_index(data, self.font_index)
+            .map_err(|error| eyre!("failed to parse font {}: {error}", path.display()))
+    }
+
+    /// Path used by the in-app export key for the quote at `index` (0-based).
+    pub(crate) fn default_output(&self, index: usize) -> PathBuf {
+        Path::new(&self.output_dir).join(format!("card-{}.png", index + 1))
+    }
+}
+
+struct CardColors {
+    background: Rgba<u8>,
+    text: Rgba<u8>,
+    accent: Rgba<u8>,
+    muted: Rgba<u8>,
+}
+
+impl CardColors {
+    fn from_template(template: &CardTemplate) -> Self {
+        Self {
+            background: rgba_or(&template.background, [30, 30, 46]),
+            text: rgba_or(&template.text, [245, 245, 245]),
+            accent: rgba_or(&template.accent, [249, 226, 175]),
+            muted: rgba_or(&template.muted, [166, 173, 200]),
+        }
+    }
+}
+
+fn rgba_or(value: &str, default: [u8; 3]) -> Rgba<u8> {
+    let [r, g, b] = parse_color(value).map(color_to_rgb).unwrap_or(default);
+    Rgba([r, g, b, 255])
+}
+
+struct TextBlock {
+    lines: Vec<String>,
+    scale: PxScale,
+    color: Rgba<u8>,
+    gap_after: f32,
+}
+
+/// Renders `quote` as a share-ready PNG at `output`.
+pub(crate) fn export_card(
+    quote: &AnimeQuote,
+    config: &CardConfig,
+    template_name: Option<&str>,
+    output: &Path,
+) -> Result<()> {
+    let template = config.template(template_name)?;
+    let font = config.load_font()?;
+    let colors = CardColors::from_template(&template);
+    let card = render_card(quote, &template, &colors, &font);
+    if let Some(parent) = output
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    card.save(output)
+        .wrap_err_with(|| format!("failed to write {}", output.display()))
+}
+
+fn render_card(
+    quote: &AnimeQuote,
+    template: &CardTemplate,
+    colors: &CardColors,
+    font: &FontVec,
+) -> RgbaImage {
+    let width = template.width.max(1);
+    let height = template.height.max(1);
+    let padding = template.padding.min(width / 4).min(height / 4);
+    let mut canvas = RgbaImage::from_pixel(width, height, colors.background);
+
+    let artwork = quote
+        .load_image()
+        .filter(|_| template.layout != CardLayout::TextOnly)
+        .and_then(|image| match image {
+            Ok(image) => Some(image),
+            Err(error) => {
+                eprintln!(
+                    "failed to load image from {}: {error}",
+                    quote.image.as_deref().unwrap_or_default()
+                );
+                None
+            }
+        });
+
+    let inner =
+        Rect::at(padding as i32, padding as i32).of_size(width - padding * 2, height - padding * 2);
+    let text_area = match (&artwork, template.layout) {
+        (Some(artwork), CardLayout::ImageLeft) => {
+            let image_width = inner.width() * 2 / 5;
+            let image_area =
+                Rect::at(inner.left(), inner.top()).of_size(image_width, inner.height());
+            draw_artwork(&mut canvas, artwork, image_area);
+            let offset = image_width + padding;
+            Rect::at(inner.left() + offset as i32, inner.top())
+                .of_size(inner.width().saturating_sub(offset).max(1), inner.height())
+        }
+        (Some(artwork), CardLayout::ImageTop) => {
+            let image_height = inner.height() / 2;
+            let image_area =
+                Rect::at(inner.left(), inner.top()).of_size(inner.width(), image_height);
+            draw_artwork(&mut canvas, artwork, image_area);
+            let offset = image_height + padding / 2;
+            Rect::at(inner.left(), inner.top() + offset as i32)
+                .of_size(inner.width(), inner.height().saturating_sub(offset).max(1))
+        }
+        _ => inner,
+    };
+
+    draw_text_blocks(&mut canvas, quote, template, colors, font, text_area);
+    canvas
+}
+
+fn draw_artwork(canvas: &mut RgbaImage, artwork: &image::DynamicImage, area: Rect) {
+    let fitted = artwork
+        .resize(
+            area.width(),
+            area.height(),
+            imageops::FilterType::CatmullRom,
+        )
+        .to_rgba8();
+    let x = area.left() as i64 + (area.width() as i64 - fitted.width() as i64) / 2;
+    let y = area.top() as i64 + (area.height() as i64 - fitted.height() as i64) / 2;
+    imageops::overlay(canvas, &fitted, x, y);
+}
+
+fn draw_text_blocks(
+    canvas: &mut RgbaImage,
+    quote: &AnimeQuote,
+    template: &CardTemplate,
+    colors: &CardColors,
+    font: &FontVec,
+    area: Rect,
+) {
+    // Shrink every font size together until the whole block fits the text area.
+    let mut factor = 1.0;
+    let (blocks, total_height) = loop {
+        let blocks = layout_blocks(quote, template, colors, font, area.width(), factor);
+        let total_height = blocks_height(&blocks, font);
+        if total_height <= area.height() as f32 || factor <= MIN_TEXT_SCALE {
+            break (blocks, total_height);
+        }
+        factor *= 0.9;
+    };
+
+    let mut y = area.top() as f32 + ((area.height() as f32 - total_height) / 2.0).max(0.0);
+    for block in &blocks {
+        let line_height = font.as_scaled(block.scale).height();
+        for line in &block.lines {
+            draw_text_mut(
+                canvas,
+                block.color,
+                area.left(),
+                y.round() as i32,
+                block.scale,
+                font,
+                line,
+            );
+            y += line_height;
+        }
+        if block.gap_after > 0.0 {
+            y += block.gap_after;
+        }
+    }
+
+    // A short accent rule to the left of the text anchors the composition.
+    let rule_height = total_height.min(area.height() as f32).max(1.0) as u32;
+    let rule_top = area.top() + ((area.height().saturating_sub(rule_height)) / 2) as i32;
+    if area.left() >= 16 {
+        draw_filled_rect_mut(
+            canvas,
+            Rect::at(area.left() - 16, rule_top).of_size(4, rule_height),
+            colors.accent,
+        );
+    }
+}
+
+fn layout_blocks(
+    quote: &AnimeQuote,
+    template: &CardTemplate,
+    colors: &CardColors,
+    font: &FontVec,
+    max_width: u32,
+    factor: f32,
+) -> Vec<TextBlock> {
+    let block = |text: &str, size: f32, color: Rgba<u8>, gap_after: f32| {
+        let scale = PxScale::from((size * factor).max(1.0));
+        TextBlock {
+            lines: wrap_text(font, scale, text, max_width),
+            scale,
+            color,
+            gap_after: gap_after * factor,
+        }
+    };
+
+    let mut blocks = vec![block(
+        &quote.japanese,
+        template.japanese_size,
+        colors.accent,
+        8.0,
+    )];
+    if let Some(romaji) = &quote.romaji {
+        blocks.push(block(romaji, template.romaji_size, colors.muted, 24.0));
+    } else if let Some(last) = blocks.last_mut() {
+        last.gap_after = 24.0 * factor;
+    }
+    blocks.push(block(
+        &format!("“{}”", quote.quote),
+        template.quote_size,
+        colors.text,
+        24.0,
+    ));
+    blocks.push(block(
+        &format!("— {}, {}", quote.character, quote.anime),
+        template.attribution_size,
+        colors.muted,
+        0.0,
+    ));
+    blocks
+}
+
+fn blocks_height(blocks: &[TextBlock], font: &FontVec) -> f32 {
+    blocks
+        .iter()
+        .map(|block| {
+            font.as_scaled(block.scale).height() * block.lines.len() as f32 + block.gap_after
+        })
+        .sum()
+}
+
+/// Greedy line wrapping that breaks between words and between individual
+/// CJK characters, since Japanese text has no spaces to break on.
+fn wrap_text(font: &FontVec, scale: PxScale, text: &str, max_width: u32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for token in wrap_tokens(text) {
+        let candidate = format!("{current}{token}");
+        if current.trim().is_empty() || text_size(scale, font, candidate.trim_end()).0 <= max_width
+        {
+            current = candidate;
+        } else {
+            lines.push(current.trim_end().to_string());
+            current = token.trim_start().to_string();
+        }
+    }
+    if !current.trim().is_empty() {
+        lines.push(current.trim_end().to_string());
+    }
+    lines
+}
+
+fn wrap_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            current.push(ch);
+            tokens.push(std::mem::take(&mut current));
+        } else if is_cjk(ch) {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            tokens.push(ch.to_string());
+        } else {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch as u32, 0x3000..=0x9FFF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF)
+}
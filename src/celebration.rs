@@ -0,0 +1,133 @@
+//! A short shower of sakura petals or confetti over the viewer when an
+//! achievement unlocks or a streak reaches a milestone.
+
+use crate::selection::splitmix64;
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use std::time::{Duration, Instant};
+
+/// How long the shower lasts; any key ends it sooner.
+const DURATION: Duration = Duration::from_millis(3500);
+
+const SAKURA_GLYPHS: &[char] = &['✿', '❀', '*', '•'];
+const SAKURA_COLORS: &[Color] = &[
+    Color::Rgb(255, 183, 197),
+    Color::Rgb(255, 209, 220),
+    Color::Rgb(244, 143, 177),
+    Color::White,
+];
+const CONFETTI_GLYPHS: &[char] = &['▪', '▴', '◆', '•', '■'];
+const CONFETTI_COLORS: &[Color] = &[
+    Color::Red,
+    Color::Yellow,
+    Color::Green,
+    Color::Cyan,
+    Color::Magenta,
+    Color::LightBlue,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CelebrationStyle {
+    Sakura,
+    Confetti,
+    Off,
+}
+
+impl CelebrationStyle {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "sakura" => Some(Self::Sakura),
+            "confetti" => Some(Self::Confetti),
+            "off" | "none" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+struct Particle {
+    /// Starting column, as a fraction of the width.
+    x: f32,
+    /// Rows above the top edge it starts at.
+    delay: f32,
+    /// Rows per second.
+    fall: f32,
+    /// Columns of side-to-side sway.
+    sway: f32,
+    phase: f32,
+    glyph: char,
+    color: Color,
+}
+
+pub(crate) struct Celebration {
+    started: Instant,
+    style: CelebrationStyle,
+    particles: Vec<Particle>,
+}
+
+impl Celebration {
+    /// `width` sets how many particles fall; `None` for
+    /// [`CelebrationStyle::Off`].
+    pub(crate) fn start(style: CelebrationStyle, width: u16) -> Option<Self> {
+        let (glyphs, colors) = match style {
+            CelebrationStyle::Sakura => (SAKURA_GLYPHS, SAKURA_COLORS),
+            CelebrationStyle::Confetti => (CONFETTI_GLYPHS, CONFETTI_COLORS),
+            CelebrationStyle::Off => return None,
+        };
+        let seed = rand::random::<u64>();
+        let unit = |index: usize, salt: u64| {
+            (splitmix64(seed ^ ((index as u64) << 8) ^ salt) % 10_000) as f32 / 10_000.0
+        };
+        let particles = (0..usize::from(width / 2).max(8))
+            .map(|index| Particle {
+                x: unit(index, 1),
+                delay: unit(index, 2) * 12.0,
+                fall: 6.0 + unit(index, 3) * 8.0,
+                sway: match style {
+                    CelebrationStyle::Sakura => 1.0 + unit(index, 4) * 3.0,
+                    _ => unit(index, 4),
+                },
+                phase: unit(index, 5) * std::f32::consts::TAU,
+                glyph: glyphs[splitmix64(seed ^ index as u64) as usize % glyphs.len()],
+                color: colors
+                    [splitmix64(seed.rotate_left(17) ^ index as u64) as usize % colors.len()],
+            })
+            .collect();
+        Some(Self {
+            started: Instant::now(),
+            style,
+            particles,
+        })
+    }
+
+    pub(crate) fn is_over(&self) -> bool {
+        self.started.elapsed() >= DURATION
+    }
+
+    /// Draws the particles where they are by now, over what is on screen.
+    pub(crate) fn render(&self, frame: &mut Frame, area: Rect) {
+        let elapsed = self.started.elapsed().as_secs_f32();
+        let buffer = frame.buffer_mut();
+        for particle in &self.particles {
+            let row = elapsed * particle.fall - particle.delay;
+            if row < 0.0 || row >= f32::from(area.height) {
+                continue;
+            }
+            // Petals drift slowly; confetti flutters faster.
+            let speed = if self.style == CelebrationStyle::Sakura {
+                1.5
+            } else {
+                6.0
+            };
+            let column = particle.x * f32::from(area.width.saturating_sub(1))
+                + particle.sway * (elapsed * speed + particle.phase).sin();
+            if column < 0.0 || column >= f32::from(area.width) {
+                continue;
+            }
+            let position = (area.x + column as u16, area.y + row as u16);
+            if let Some(cell) = buffer.cell_mut(position) {
+                cell.set_char(particle.glyph).set_fg(particle.color);
+            }
+        }
+    }
+}
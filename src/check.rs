@@ -0,0 +1,250 @@
+//! `check`: finds the quotes whose artwork is broken, before the viewer
+//! shows a blank space where it should be.
+//!
+//! Each image is decoded in full, so a file cut short by an interrupted
+//! download is caught as well as one that is missing or empty.
+//!
+//! With `--remote`, the web links of the quotes (images given as URLs, the
+//! anime's `url`, and `source_url`) are requested too, a few at a time,
+//! and the ones that are gone or don't answer are reported.
+
+use crate::AnimeQuote;
+use crate::lint::Severity;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Images smaller than this on either side are most likely placeholders or
+/// broken exports.
+const MIN_SIDE: u32 = 16;
+/// Images larger than this on either side take more memory to decode than
+/// any terminal can make use of.
+const MAX_SIDE: u32 = 16_384;
+
+/// How long a remote link has to answer.
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Something wrong with the quote at `index`.
+pub(crate) struct Problem {
+    pub(crate) index: usize,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+}
+
+/// The problems with the images of `quotes`. Quotes that share an image get
+/// the same file's problems, each reported for every quote.
+pub(crate) fn images(quotes: &[AnimeQuote]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    // Decoding is the slow part, so shared images are read once.
+    let mut checked: HashMap<&str, Result<ImageFacts, String>> = HashMap::new();
+    for (index, quote) in quotes.iter().enumerate() {
+        // Links are left to `remote`.
+        let Some(path) = quote.image.as_deref().filter(|image| !is_url(image)) else {
+            continue;
+        };
+        let facts = checked.entry(path).or_insert_with(|| inspect(path));
+        let mut report = |message: String| {
+            problems.push(Problem {
+                index,
+                severity: Severity::Error,
+                message,
+            })
+        };
+        let facts = match facts {
+            Ok(facts) => facts,
+            Err(message) => {
+                report(format!("{path}: {message}"));
+                continue;
+            }
+        };
+        let (width, height) = facts.size;
+        if width.min(height) < MIN_SIDE || width.max(height) > MAX_SIDE {
+            report(format!(
+                "{path}: {width}x{height} is outside {MIN_SIDE} to {MAX_SIDE} pixels a side"
+            ));
+        }
+        if let Some(crop) = quote.crop
+            && (crop.width == 0
+                || crop.height == 0
+                || crop.x.saturating_add(crop.width) > width
+                || crop.y.saturating_add(crop.height) > height)
+        {
+            report(format!(
+                "{path}: the crop {}x{} at {},{} doesn't fit in the {width}x{height} image",
+                crop.width, crop.height, crop.x, crop.y
+            ));
+        }
+        if let Some(expected) = &quote.image_sha256
+            && !expected.trim().eq_ignore_ascii_case(&facts.sha256)
+        {
+            report(format!(
+                "{path}: SHA-256 is {}, not the recorded {}",
+                facts.sha256,
+                expected.trim()
+            ));
+        }
+    }
+    problems
+}
+
+/// The problems with the web links of `quotes`, requesting up to `jobs`
+/// at once. Each link is requested once however many quotes share it.
+pub(crate) fn remote(quotes: &[AnimeQuote], jobs: usize) -> Vec<Problem> {
+    let mut uses: HashMap<&str, Vec<(usize, &str)>> = HashMap::new();
+    for (index, quote) in quotes.iter().enumerate() {
+        let links = [
+            ("image", &quote.image),
+            ("url", &quote.url),
+            ("source_url", &quote.source_url),
+        ];
+        for (field, link) in links {
+            if let Some(link) = link.as_deref().filter(|link| is_url(link)) {
+                uses.entry(link).or_default().push((index, field));
+            }
+        }
+    }
+    let links: Vec<&str> = uses.keys().copied().collect();
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(REMOTE_TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .into();
+    let next = AtomicUsize::new(0);
+    let broken = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, links.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(&link) = links.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(message) = request(&agent, link) {
+                        broken
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner())
+                            .push((link, message));
+                    }
+                }
+            });
+        }
+    });
+    let mut problems: Vec<Problem> = broken
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .into_iter()
+        .flat_map(|(link, message)| {
+            uses[link].iter().map(move |(index, field)| Problem {
+                index: *index,
+                severity: Severity::Error,
+                message: format!("{field} {link}: {message}"),
+            })
+        })
+        .collect();
+    problems.sort_by(|a, b| (a.index, &a.message).cmp(&(b.index, &b.message)));
+    problems
+}
+
+/// Asks for `link` and says what went wrong when it isn't there. Servers
+/// that refuse `HEAD` are asked again with `GET`.
+fn request(agent: &ureq::Agent, link: &str) -> Result<(), String> {
+    let describe = |error: ureq::Error| match error {
+        ureq::Error::Timeout(_) => format!("no answer within {}s", REMOTE_TIMEOUT.as_secs()),
+        error => error.to_string(),
+    };
+    let mut status = agent.head(link).call().map_err(describe)?.status();
+    if matches!(status.as_u16(), 405 | 501) {
+        status = agent.get(link).call().map_err(describe)?.status();
+    }
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {status}"))
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    value.contains("://")
+}
+
+struct ImageFacts {
+    size: (u32, u32),
+    sha256: String,
+}
+
+/// Reads and decodes the image at `path`, or says why it can't be.
+fn inspect(path: &str) -> Result<ImageFacts, String> {
+    let bytes = fs::read(path).map_err(|error| format!("failed to read: {error}"))?;
+    if bytes.is_empty() {
+        return Err("the file is empty".to_string());
+    }
+    let image =
+        image::load_from_memory(&bytes).map_err(|error| format!("doesn't decode: {error}"))?;
+    Ok(ImageFacts {
+        size: (image.width(), image.height()),
+        sha256: sha256_hex(&bytes),
+    })
+}
+
+/// The SHA-256 of `bytes` in lowercase hex, as `sha256sum` prints it.
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    #[rustfmt::skip]
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+    ];
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(bytes.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (word, chunk) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (word, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(add);
+        }
+    }
+    state.iter().map(|word| format!("{word:08x}")).collect()
+}
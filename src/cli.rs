@@ -0,0 +1,1377 @@
+use crate::control::{self, ControlCommand};
+use crate::query::Query;
+use crate::template::Template;
+use crate::{AnimeData, AnimeQuote, ConfigRoot, config_path, data_dir, data_path};
+use crate::{
+    add, anki, backup, card, check, export, filter, graphics, images, lint, merge, organize,
+    output, packs, recent, schedule, server, site, state, stats, subtitles, sync,
+};
+use chrono::Local;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use rand::Rng;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use std::time::Duration;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "anime-quotes",
+    version,
+    about = "Browse anime quotes in your terminal"
+)]
+pub(crate) struct Cli {
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+    /// Quotes file to use instead of ./anime.toml
+    #[arg(long, global = true, env = "ANIME_QUOTES_DATA", value_name = "PATH")]
+    pub(crate) data: Option<PathBuf>,
+    /// Config file to use instead of ./config.toml
+    #[arg(long, global = true, env = "ANIME_QUOTES_CONFIG", value_name = "PATH")]
+    pub(crate) config: Option<PathBuf>,
+    /// Run the viewer as an unattended slideshow (see [kiosk] in config.toml)
+    #[arg(long)]
+    pub(crate) kiosk: bool,
+    /// Step through only the quotes listed in this playlist file, in order
+    #[arg(long, value_name = "PATH")]
+    pub(crate) playlist: Option<PathBuf>,
+    /// Print how long each step of starting the viewer took when it exits
+    #[arg(long)]
+    pub(crate) timings: bool,
+    /// Print the quotes last shown in the viewer and exit; short for `recent`
+    #[arg(long, conflicts_with_all = ["kiosk", "playlist", "timings"])]
+    pub(crate) recent: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Render a quote as a share-ready PNG card
+    Card(CardArgs),
+    /// Export the collection to other formats
+    Export(ExportArgs),
+    /// Print a random quote and exit
+    Random(RandomArgs),
+    /// Print the quote of the day and exit
+    Daily(RandomArgs),
+    /// Print the quotes containing some text, with their numbers and IDs
+    Grep(GrepArgs),
+    /// Summarize the collection: counts, missing fields, extremes
+    Stats(StatsArgs),
+    /// Greeting for login shells: a quote with the character's portrait beside it
+    Greet(GreetArgs),
+    /// Serve the collection as a JSON REST API
+    Serve(ServeArgs),
+    /// Generate a static website for the collection
+    Site(SiteArgs),
+    /// Control a running viewer through its socket
+    Ctl(CtlArgs),
+    /// Install, list, and remove community quote packs
+    Pack(PackArgs),
+    /// Keep a deck in a running Anki in step with the collection
+    Anki(AnkiConnectArgs),
+    /// Commit the data directory and exchange it with the [sync] git remote
+    Sync,
+    /// Archive the collection, config, and data directory
+    Backup(BackupArgs),
+    /// Unpack an archive made by `backup`
+    Restore(RestoreArgs),
+    /// Print a shell completion script
+    Completions(CompletionsArgs),
+    /// Append a quote to the data file
+    Add(AddArgs),
+    /// Turn lines of other files into quotes
+    Import(ImportArgs),
+    /// Look after the collection's image files
+    Images(ImagesArgs),
+    /// Report the quotes whose images are missing, broken, or changed
+    Check(CheckArgs),
+    /// List the quotes last shown in the viewer, newest first
+    Recent(RecentArgs),
+    /// Combine data files into one, settling duplicates and conflicts
+    Merge(MergeArgs),
+    /// Rewrite data files in the canonical style, keeping comments
+    Fmt(FmtArgs),
+    /// Group the data file's quotes by anime or character
+    Organize(OrganizeArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CheckArgs {
+    /// Also request every web link (image URLs, `url`, and `source_url`)
+    /// and report the ones that fail
+    #[arg(long)]
+    remote: bool,
+    /// How many links to request at once
+    #[arg(long, default_value_t = 8)]
+    jobs: usize,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct RecentArgs {
+    /// How many quotes to list
+    #[arg(long, short = 'n', default_value_t = 10)]
+    limit: usize,
+    #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+    format: StatsFormat,
+}
+
+impl Default for RecentArgs {
+    /// What `--recent` lists: the same as `recent` without options.
+    fn default() -> Self {
+        Self {
+            limit: 10,
+            format: StatsFormat::Table,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct MergeArgs {
+    /// The data files, earliest first
+    #[arg(required = true, num_args = 2..)]
+    files: Vec<PathBuf>,
+    /// Where to write the merged collection instead of standard output
+    #[arg(long, short)]
+    out: Option<PathBuf>,
+    /// How to settle conflicts; asks by default when run in a terminal,
+    /// otherwise keeps the earlier file's version
+    #[arg(long, value_enum)]
+    on_conflict: Option<OnConflict>,
+    /// How alike two lines from the same anime must be, from 0 to 1, to count
+    /// as duplicates
+    #[arg(long, default_value_t = 0.8)]
+    threshold: f64,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct FmtArgs {
+    /// The files to format; the data file by default
+    files: Vec<PathBuf>,
+    /// List the files that aren't formatted instead of rewriting them, and
+    /// exit with status 1 if there are any
+    #[arg(long)]
+    check: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct OrganizeArgs {
+    #[arg(long, value_enum, default_value_t = OrganizeBy::Anime)]
+    by: OrganizeBy,
+    /// Move the quotes into a file per anime in `quotes.d/` next to the
+    /// data file
+    #[arg(long)]
+    split: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OrganizeBy {
+    Anime,
+    Character,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OnConflict {
+    Ask,
+    /// Keep the version from the file listed first
+    First,
+    /// Keep the version from the file listed last
+    Last,
+    /// Keep both near-duplicates; same-ID conflicts keep the first
+    Both,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct AddArgs {
+    /// Start from the clipboard: JSON with the data-file fields, or text like
+    /// "Quote" — Character, Anime
+    #[arg(long)]
+    from_clipboard: bool,
+    /// The line in Japanese
+    #[arg(long)]
+    japanese: Option<String>,
+    #[arg(long)]
+    romaji: Option<String>,
+    #[arg(long)]
+    anime: Option<String>,
+    #[arg(long)]
+    character: Option<String>,
+    /// The translation
+    #[arg(long)]
+    quote: Option<String>,
+    /// Artwork path, relative to the data file
+    #[arg(long)]
+    image: Option<String>,
+    /// Never prompt; fail if a required field is missing
+    #[arg(long)]
+    no_input: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ImportArgs {
+    #[command(subcommand)]
+    source: ImportSource,
+}
+
+#[derive(Debug, Subcommand)]
+enum ImportSource {
+    /// Pick lines of an .srt or .ass subtitle file, keeping their timestamps
+    Subs(SubsArgs),
+}
+
+#[derive(Debug, Args)]
+struct SubsArgs {
+    file: PathBuf,
+    #[arg(long)]
+    anime: Option<String>,
+    /// Who says the lines; otherwise the speaker an .ass file names, if any
+    #[arg(long)]
+    character: Option<String>,
+    #[arg(long)]
+    episode: Option<u32>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ImagesArgs {
+    #[command(subcommand)]
+    action: ImagesAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum ImagesAction {
+    /// Shrink oversized images and convert them, updating their paths
+    Optimize {
+        /// The longest side an image may have, in pixels
+        #[arg(long, default_value_t = 1600)]
+        max_dim: u32,
+        /// What to convert images to; only JPEG and PNG can be written, not
+        /// WebP
+        #[arg(long, value_enum, default_value_t = ImageFormatArg::Keep)]
+        format: ImageFormatArg,
+        /// JPEG quality, from 1 to 100
+        #[arg(long, default_value_t = 85)]
+        quality: u8,
+        /// List the images that would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write the viewer's thumbnails ahead of time
+    Pregen {
+        /// The window width to make thumbnails for, in pixels (this
+        /// terminal's by default)
+        #[arg(long)]
+        width: Option<u32>,
+        /// The window height, in pixels
+        #[arg(long)]
+        height: Option<u32>,
+    },
+    /// List image files that look the same under different paths
+    Duplicates {
+        /// How many of the 64 bits of two images' hashes may differ for
+        /// them to count as the same
+        #[arg(long, default_value_t = 2)]
+        threshold: u32,
+        /// Point every quote of a set at the copy listed first
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ImageFormatArg {
+    /// Each image's own format
+    Keep,
+    Jpeg,
+    Png,
+    /// Not supported: refused with an explanation
+    Webp,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CompletionsArgs {
+    shell: clap_complete::Shell,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct BackupArgs {
+    /// Archive to write (defaults to a timestamped name in the current directory)
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct RestoreArgs {
+    archive: PathBuf,
+    /// Replace files that already exist
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct PackArgs {
+    #[command(subcommand)]
+    action: PackAction,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct AnkiConnectArgs {
+    #[command(subcommand)]
+    action: AnkiAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum AnkiAction {
+    /// Add and update notes through AnkiConnect (see [anki] in config.toml)
+    Sync {
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum PackAction {
+    /// Download a pack by URL or by its name in the pack index
+    Install { source: String },
+    /// List installed packs with their precedence
+    List {
+        /// List the packs offered by the index instead
+        #[arg(long)]
+        available: bool,
+    },
+    /// Uninstall a pack
+    Remove { name: String },
+    /// Report pack quotes hidden by duplicates with higher precedence
+    Shadowed,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CtlArgs {
+    #[command(subcommand)]
+    action: CtlAction,
+    /// Socket of the viewer to control
+    #[arg(long, global = true)]
+    socket: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Subcommand)]
+enum CtlAction {
+    /// Show the next quote
+    Next,
+    /// Show the previous quote
+    Prev,
+    /// Jump to a random quote
+    Random,
+    /// Jump to a quote by its counter number
+    Goto { index: usize },
+    /// Print the displayed quote as JSON
+    ShowJson,
+}
+
+impl From<CtlAction> for ControlCommand {
+    fn from(action: CtlAction) -> Self {
+        match action {
+            CtlAction::Next => Self::Next,
+            CtlAction::Prev => Self::Previous,
+            CtlAction::Random => Self::Random,
+            CtlAction::Goto { index } => Self::Goto(index),
+            CtlAction::ShowJson => Self::ShowJson,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SiteArgs {
+    /// Output directory
+    #[arg(long, default_value = "site")]
+    out: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ServeArgs {
+    /// Address to bind
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+    /// Port to listen on
+    #[arg(long, short, default_value_t = 8080)]
+    port: u16,
+    /// Seconds between quotes pushed on `/stream`
+    #[arg(long, default_value_t = 30)]
+    stream_interval: u64,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct RandomArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    format: OutputFormat,
+    /// Maximum width of the speech bubble text (bubble format)
+    #[arg(long, default_value_t = output::DEFAULT_BUBBLE_WIDTH)]
+    width: usize,
+    /// Truncate the status-bar text to this many columns (waybar and tmux formats)
+    #[arg(long)]
+    max_length: Option<usize>,
+    /// Format with a template such as '{{quote}} — {{character}} ({{anime}})'
+    #[arg(long, conflicts_with_all = ["format", "template_file"])]
+    template: Option<String>,
+    /// Read the template from a file
+    #[arg(long, conflicts_with = "format")]
+    template_file: Option<PathBuf>,
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+impl RandomArgs {
+    fn template(&self) -> Result<Option<Template>> {
+        let source = match (&self.template, &self.template_file) {
+            (Some(source), _) => source.clone(),
+            (None, Some(path)) => fs::read_to_string(path)
+                .wrap_err_with(|| format!("failed to read {}", path.display()))?,
+            (None, None) => return Ok(None),
+        };
+        Template::parse(&source, output::TEMPLATE_FIELDS).map(Some)
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct StatsArgs {
+    #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+    format: StatsFormat,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum StatsFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct GrepArgs {
+    /// Text to look for in the quote, Japanese, romaji, character, and anime
+    pattern: String,
+    /// Match case exactly
+    #[arg(long, short = 's')]
+    case_sensitive: bool,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct GreetArgs {
+    /// Greet with the quote of the day instead of a random one
+    #[arg(long)]
+    daily: bool,
+    /// Leave out the ASCII-art portrait
+    #[arg(long)]
+    no_portrait: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    /// Japanese, romaji, translation, and attribution on separate lines
+    Plain,
+    /// Speech bubble with an ASCII-art portrait of the character
+    Bubble,
+    /// JSON for a waybar custom module (`return-type = "json"`)
+    Waybar,
+    /// Compact colored snippet for tmux `status-right`
+    Tmux,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CardArgs {
+    /// Quote number as shown in the viewer counter (1-based)
+    #[arg(long, default_value_t = 1)]
+    index: usize,
+    /// Where to write the PNG
+    #[arg(long, short, default_value = "card.png")]
+    output: PathBuf,
+    /// Card template name, overriding `card.template`
+    #[arg(long)]
+    template: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ExportArgs {
+    #[command(subcommand)]
+    format: ExportFormat,
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+/// Narrowing of the collection, shared by the commands that pick or list
+/// quotes.
+#[derive(Debug, Args)]
+struct FilterArgs {
+    /// Only quotes from anime whose title contains this (repeatable)
+    #[arg(long, global = true, value_name = "TITLE")]
+    anime: Vec<String>,
+    /// Only quotes from characters whose name contains this (repeatable)
+    #[arg(long, global = true, value_name = "NAME")]
+    character: Vec<String>,
+    /// Only quotes with this tag (repeatable)
+    #[arg(long, global = true, value_name = "TAG")]
+    tag: Vec<String>,
+    /// Only quotes rated at least this
+    #[arg(long, global = true, value_name = "RATING")]
+    min_rating: Option<u8>,
+    /// Only quotes whose translation is at least this many characters long
+    #[arg(long, global = true, value_name = "CHARS")]
+    min_len: Option<usize>,
+    /// Only quotes whose translation is at most this many characters long
+    #[arg(long, global = true, value_name = "CHARS")]
+    max_len: Option<usize>,
+    /// Only quotes matching a query such as 'anime:"One Piece" AND (tag:sad
+    /// OR rating>=4) NOT spoiler'
+    #[arg(long, global = true, value_name = "QUERY", value_parser = Query::parse)]
+    query: Option<Query>,
+}
+
+impl From<FilterArgs> for filter::QuoteFilter {
+    fn from(args: FilterArgs) -> Self {
+        Self {
+            anime: args.anime,
+            character: args.character,
+            tag: args.tag,
+            min_rating: args.min_rating,
+            min_len: args.min_len,
+            max_len: args.max_len,
+            query: args.query,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum ExportFormat {
+    /// Markdown document grouped by anime and character
+    Markdown(OutputArgs),
+    /// Anki plain-text notes (Japanese front, translation back)
+    Anki(AnkiArgs),
+    /// `%`-delimited fortune(6) file
+    Fortune(FortuneArgs),
+    /// JSON array in the HTTP API's shape
+    Json(OutputArgs),
+    /// Data file in the anime.toml format, with explicit IDs
+    Toml(OutputArgs),
+    /// YAML list with the JSON fields
+    Yaml(OutputArgs),
+    /// CSV with a header row
+    Csv(OutputArgs),
+    /// Each quote's review history in the viewer: times shown, last shown,
+    /// favorite, hidden (no spaced-repetition intervals or ease factors)
+    ReviewHistory(ReviewHistoryArgs),
+}
+
+#[derive(Debug, Args)]
+struct ReviewHistoryArgs {
+    #[command(flatten)]
+    output: OutputArgs,
+    #[arg(long, value_enum, default_value_t = ReviewHistoryFormat::Csv)]
+    format: ReviewHistoryFormat,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ReviewHistoryFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Args)]
+struct FortuneArgs {
+    #[command(flatten)]
+    output: OutputArgs,
+    /// Run strfile(1) on the output to build the `.dat` index
+    #[arg(long, requires = "output")]
+    strfile: bool,
+}
+
+#[derive(Debug, Args)]
+struct AnkiArgs {
+    #[command(flatten)]
+    output: OutputArgs,
+    /// Copy referenced images here, e.g. Anki's `collection.media` folder
+    #[arg(long)]
+    media_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct OutputArgs {
+    /// Write to a file instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+pub(crate) fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Card(args) => run_card(args),
+        Command::Export(args) => run_export(args),
+        Command::Random(args) => run_random(args, Pick::Random),
+        Command::Daily(args) => run_random(args, Pick::Daily),
+        Command::Greet(args) => run_greet(args),
+        Command::Grep(args) => run_grep(args),
+        Command::Stats(args) => {
+            let stats = stats::collect(
+                &load_quotes()?,
+                &state::State::load(),
+                Local::now().date_naive(),
+            );
+            let rendered = match args.format {
+                StatsFormat::Table => stats.render_table(),
+                StatsFormat::Json => format!("{}\n", serde_json::to_string_pretty(&stats)?),
+            };
+            write_output(None, &rendered)
+        }
+        Command::Ctl(args) => {
+            let socket = args.socket.unwrap_or_else(control::default_socket_path);
+            let reply = control::send(&socket, args.action.into())?;
+            match reply.trim_end().strip_prefix("error: ") {
+                Some(error) => Err(eyre!("{error}")),
+                None => write_output(None, &reply),
+            }
+        }
+        Command::Site(args) => {
+            let palette = ConfigRoot::load_from_file(config_path())
+                .ui
+                .colors
+                .to_palette();
+            site::build_site(&load_quotes()?, &palette, &args.out)?;
+            println!("wrote site to {}", args.out.display());
+            Ok(())
+        }
+        Command::Pack(args) => run_pack(args),
+        Command::Anki(args) => match args.action {
+            AnkiAction::Sync { filter } => {
+                let config = ConfigRoot::load_from_file(config_path()).anki;
+                let all = load_quotes()?;
+                let quotes: Vec<AnimeQuote> = filter::QuoteFilter::from(filter)
+                    .positions(&all)
+                    .into_iter()
+                    .map(|position| all[position].clone())
+                    .collect();
+                let report = anki::sync(&quotes, &config)?;
+                println!(
+                    "added {}, updated {}, unchanged {}",
+                    report.added, report.updated, report.unchanged
+                );
+                Ok(())
+            }
+        },
+        Command::Add(args) => run_add(args),
+        Command::Images(args) => match args.action {
+            ImagesAction::Optimize {
+                max_dim,
+                format,
+                quality,
+                dry_run,
+            } => {
+                let options = images::Options {
+                    max_dim: max_dim.max(1),
+                    format: match format {
+                        ImageFormatArg::Keep => images::Format::Keep,
+                        ImageFormatArg::Jpeg => images::Format::Jpeg,
+                        ImageFormatArg::Png => images::Format::Png,
+                        ImageFormatArg::Webp => {
+                            return Err(eyre!(
+                                "images can't be converted to WebP: this build only reads and \
+                                 writes JPEG and PNG; use --format jpeg or --format png"
+                            ));
+                        }
+                    },
+                    quality,
+                    dry_run,
+                };
+                let report = images::optimize(&options)?;
+                for line in &report {
+                    println!("{line}");
+                }
+                if report.is_empty() {
+                    println!("every image is already within {max_dim} pixels");
+                }
+                Ok(())
+            }
+            ImagesAction::Pregen { width, height } => {
+                let window = graphics::window_pixels();
+                let largest = (width.unwrap_or(window.0), height.unwrap_or(window.1));
+                let report = images::pregen(&load_quotes()?, largest);
+                for line in &report.failed {
+                    eprintln!("failed to load image from {line}");
+                }
+                println!(
+                    "thumbnails for {}x{}: written {}, already cached {}, small enough as is {}",
+                    largest.0, largest.1, report.written, report.cached, report.small
+                );
+                Ok(())
+            }
+            ImagesAction::Duplicates { threshold, apply } => {
+                let sets = images::duplicates(&load_quotes()?, threshold);
+                for copies in &sets {
+                    println!("{} copies look the same:", copies.len());
+                    for (position, copy) in copies.iter().enumerate() {
+                        let quotes = if copy.quotes == 1 { "quote" } else { "quotes" };
+                        println!(
+                            "  {} ({}x{}, {} {quotes}){}",
+                            copy.path,
+                            copy.size.0,
+                            copy.size.1,
+                            copy.quotes,
+                            if position == 0 { ", keep" } else { "" }
+                        );
+                    }
+                }
+                if sets.is_empty() {
+                    println!("no duplicate images");
+                } else if apply {
+                    let changed = images::consolidate(&sets)?;
+                    println!(
+                        "pointed {changed} quotes at the kept copies; the others can be deleted"
+                    );
+                } else {
+                    println!("run with --apply to point every quote at the copy to keep");
+                }
+                Ok(())
+            }
+        },
+        Command::Check(args) => run_check(args),
+        Command::Import(args) => match args.source {
+            ImportSource::Subs(args) => run_import_subs(args),
+        },
+        Command::Recent(args) => run_recent(args),
+        Command::Merge(args) => run_merge(args),
+        Command::Fmt(args) => run_fmt(args),
+        Command::Organize(args) => {
+            let by = match args.by {
+                OrganizeBy::Anime => organize::GroupBy::Anime,
+                OrganizeBy::Character => organize::GroupBy::Character,
+            };
+            let report = organize::organize(Path::new(data_path()), by, args.split)?;
+            for line in &report {
+                println!("{line}");
+            }
+            if report.is_empty() {
+                println!("already in order");
+            }
+            Ok(())
+        }
+        Command::Completions(args) => {
+            let mut command = with_collection_values(Cli::command());
+            let mut script = Vec::new();
+            clap_complete::generate(args.shell, &mut command, "anime-quotes", &mut script);
+            write_output(None, &String::from_utf8_lossy(&script))
+        }
+        Command::Backup(args) => {
+            let archive = args.output.unwrap_or_else(backup::default_archive_name);
+            let archived = backup::backup(&archive, &data_dir())?;
+            println!("wrote {} ({})", archive.display(), archived.join(", "));
+            Ok(())
+        }
+        Command::Restore(args) => {
+            for path in backup::restore(&args.archive, &data_dir(), args.force)? {
+                println!("restored {}", path.display());
+            }
+            Ok(())
+        }
+        Command::Sync => {
+            let config = ConfigRoot::load_from_file(config_path()).sync;
+            for line in sync::sync(&data_dir(), &config)? {
+                println!("{line}");
+            }
+            Ok(())
+        }
+        Command::Serve(args) => server::serve(
+            load_quotes()?,
+            &args.host,
+            args.port,
+            Duration::from_secs(args.stream_interval.max(1)),
+        ),
+    }
+}
+
+fn run_pack(args: PackArgs) -> Result<()> {
+    match args.action {
+        PackAction::Install { source } => {
+            let config = ConfigRoot::load_from_file(config_path()).packs;
+            let pack = packs::install(&source, &config)?;
+            println!("installed {} ({} quotes)", pack.name, pack.quotes);
+        }
+        PackAction::List { available: true } => {
+            let config = ConfigRoot::load_from_file(config_path()).packs;
+            for (name, description) in packs::available(&config)? {
+                match description {
+                    Some(description) => println!("{name}\t{description}"),
+                    None => println!("{name}"),
+                }
+            }
+        }
+        PackAction::List { available: false } => {
+            let config = ConfigRoot::load_from_file(config_path()).packs;
+            for pack in packs::list(&config)? {
+                let status = match pack.precedence {
+                    Some(precedence) => format!("active #{precedence}"),
+                    None => "disabled".to_string(),
+                };
+                println!("{}\t{} quotes\t{status}", pack.name, pack.quotes);
+            }
+        }
+        PackAction::Shadowed => {
+            let config = ConfigRoot::load_from_file(config_path()).packs;
+            let mut quotes = AnimeData::load_with_split(data_path())
+                .map_err(|error| eyre!("failed to load {}: {error}", data_path()))?
+                .quotes;
+            for entry in packs::merge_active(&mut quotes, &config) {
+                println!(
+                    "{}: \"{}\" ({}, {}) shadowed by {}",
+                    entry.pack,
+                    entry.quote.quote,
+                    entry.quote.character,
+                    entry.quote.anime,
+                    entry.winner
+                );
+            }
+        }
+        PackAction::Remove { name } => {
+            packs::remove(&name)?;
+            println!("removed {name}");
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Pick {
+    Random,
+    Daily,
+}
+
+impl Pick {
+    /// Honors `[schedule]`, so quotes for today's date or season win, and
+    /// skips hidden quotes.
+    fn index(self, quotes: &[AnimeQuote]) -> Option<usize> {
+        let schedule = ConfigRoot::load_from_file(config_path()).schedule;
+        let mut state = state::State::load();
+        match self {
+            _ if quotes.is_empty() => None,
+            Self::Random => {
+                let candidates = schedule::candidates(
+                    quotes,
+                    &state.pickable(quotes),
+                    Local::now().date_naive(),
+                    &schedule,
+                );
+                Some(candidates[rand::rng().random_range(0..candidates.len())])
+            }
+            Self::Daily => state.todays_pick(quotes, &schedule),
+        }
+    }
+
+    /// Like [`Pick::index`], among the quotes `filter` lets through. A
+    /// filtered daily pick is the quote of the day for that slice: today's
+    /// recorded quote when it matches, otherwise a stable pick among the
+    /// matches that isn't recorded, so the unfiltered quote of the day stays
+    /// put.
+    fn filtered_index(self, quotes: &[AnimeQuote], filter: &filter::QuoteFilter) -> Option<usize> {
+        if filter.is_empty() {
+            return self.index(quotes);
+        }
+        let state = state::State::load();
+        let positions: Vec<usize> = filter
+            .positions(quotes)
+            .into_iter()
+            .filter(|&position| !state.is_hidden(&quotes[position]))
+            .collect();
+        let subset: Vec<AnimeQuote> = positions
+            .iter()
+            .map(|&position| quotes[position].clone())
+            .collect();
+        let index = match self {
+            Self::Random => self.index(&subset)?,
+            Self::Daily => {
+                let schedule = ConfigRoot::load_from_file(config_path()).schedule;
+                state
+                    .daily_pick(&subset, Local::now().date_naive(), &schedule)?
+                    .0
+            }
+        };
+        Some(positions[index])
+    }
+}
+
+fn run_greet(args: GreetArgs) -> Result<()> {
+    let quotes = load_quotes()?;
+    let pick = if args.daily {
+        Pick::Daily
+    } else {
+        Pick::Random
+    };
+    let Some(index) = pick.index(&quotes) else {
+        return Err(eyre!("no quotes found in {}", data_path()));
+    };
+    let ui = ConfigRoot::load_from_file(config_path()).ui;
+    let terminal_width = io::stdout()
+        .is_terminal()
+        .then(|| crossterm::terminal::size().ok())
+        .flatten()
+        .map(|(columns, _)| columns as usize);
+    let greeting = output::render_greeting(
+        &quotes[index],
+        &ui.colors.to_palette(),
+        &ui.ascii.to_settings(),
+        terminal_width,
+        !args.no_portrait,
+    );
+    write_output(None, &greeting)
+}
+
+fn run_add(args: AddArgs) -> Result<()> {
+    let draft = if args.from_clipboard {
+        add::Draft::parse(&add::read_clipboard()?)
+    } else {
+        add::Draft::default()
+    };
+    let draft = draft.overridden_by(add::Draft {
+        japanese: args.japanese,
+        romaji: args.romaji,
+        anime: args.anime,
+        character: args.character,
+        quote: args.quote,
+        image: args.image,
+        ..add::Draft::default()
+    });
+    // Without every required field, a terminal user gets the prompts.
+    let prompting = !args.no_input && !draft.missing().is_empty() && io::stdin().is_terminal();
+    let draft = if prompting {
+        let romanization = ConfigRoot::load_from_file(config_path())
+            .romaji
+            .to_romanization();
+        add::prompt(draft, Path::new(data_path()), romanization)?
+    } else {
+        draft
+    };
+    let quote = add::append(Path::new(data_path()), &draft)
+        .wrap_err_with(|| format!("could not add the quote to {}", data_path()))?;
+    let total = AnimeData::load_from_file(data_path())
+        .map_err(|error| eyre!("failed to load {}: {error}", data_path()))?
+        .quotes
+        .len();
+    println!(
+        "added quote {total} ({}): {}, {}",
+        quote.stable_id(),
+        quote.character,
+        quote.anime
+    );
+    Ok(())
+}
+
+fn run_import_subs(args: SubsArgs) -> Result<()> {
+    let cues = subtitles::load(&args.file)?;
+    if cues.is_empty() {
+        return Err(eyre!("{} has no subtitle lines", args.file.display()));
+    }
+    if !io::stdin().is_terminal() {
+        return Err(eyre!("picking subtitle lines needs a terminal"));
+    }
+    let palette = ConfigRoot::load_from_file(config_path())
+        .ui
+        .colors
+        .to_palette();
+    let title = args.file.file_name().map_or_else(
+        || args.file.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let picked = subtitles::pick(&cues, &title, &palette)?;
+    for position in picked {
+        let cue = &cues[position];
+        // Bilingual subtitles carry both lines; otherwise the other half is
+        // asked for.
+        let (japanese, translation): (Vec<&str>, Vec<&str>) =
+            cue.text.lines().partition(|line| add::is_japanese(line));
+        let draft = add::Draft {
+            japanese: (!japanese.is_empty()).then(|| japanese.concat()),
+            quote: (!translation.is_empty()).then(|| translation.join(" ")),
+            anime: args.anime.clone(),
+            character: args.character.clone().or_else(|| cue.speaker.clone()),
+            episode: args.episode,
+            timestamp: Some(cue.timestamp()),
+            ..add::Draft::default()
+        };
+        let draft = if draft.missing().is_empty() {
+            draft
+        } else {
+            eprintln!("{}  {}", cue.timestamp(), cue.text.replace('\n', " / "));
+            add::prompt_missing(draft)?
+        };
+        let quote = add::append(Path::new(data_path()), &draft)
+            .wrap_err_with(|| format!("could not add the quote to {}", data_path()))?;
+        println!(
+            "added {} at {}: {}, {}",
+            quote.stable_id(),
+            cue.timestamp(),
+            quote.character,
+            quote.anime
+        );
+    }
+    Ok(())
+}
+
+fn run_merge(args: MergeArgs) -> Result<()> {
+    let sources = args
+        .files
+        .iter()
+        .map(|path| {
+            let data = AnimeData::load_from_file(&path.to_string_lossy())
+                .map_err(|error| eyre!("failed to load {}: {error}", path.display()))?;
+            Ok((path.display().to_string(), data.quotes))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let on_conflict = args.on_conflict.unwrap_or(if io::stdin().is_terminal() {
+        OnConflict::Ask
+    } else {
+        OnConflict::First
+    });
+    let mut input = io::stdin().lock();
+    let (mut quotes, summary) =
+        merge::merge(&sources, args.threshold, |conflict| match on_conflict {
+            OnConflict::Ask => merge::ask(&mut input, conflict),
+            OnConflict::First => Ok(merge::Keep::First),
+            OnConflict::Last => Ok(merge::Keep::Last),
+            OnConflict::Both => Ok(match conflict.kind {
+                merge::ConflictKind::SameId(_) => merge::Keep::First,
+                merge::ConflictKind::NearDuplicate(_) => merge::Keep::Both,
+            }),
+        })?;
+    if let Some(out) = &args.out {
+        merge::relocate_images(&mut quotes, out.parent().unwrap_or(Path::new("")));
+    }
+    let entries: Vec<export::Entry> = quotes.iter().enumerate().collect();
+    write_output(args.out.as_deref(), &export::render_toml(&entries)?)?;
+    eprintln!(
+        "merged {} quotes from {} files into {}: {} shared an ID ({} with conflicts), \
+         {} near-duplicates ({} kept both)",
+        summary.read,
+        sources.len(),
+        quotes.len(),
+        summary.combined,
+        summary.conflicts,
+        summary.near_duplicates,
+        summary.kept_both
+    );
+    Ok(())
+}
+
+fn run_check(args: CheckArgs) -> Result<()> {
+    let quotes = load_quotes()?;
+    let mut problems = check::images(&quotes);
+    if args.remote {
+        problems.extend(check::remote(&quotes, args.jobs));
+    }
+    let config = ConfigRoot::load_from_file(config_path());
+    problems.extend(lint::lint(&quotes, &config.lint));
+    problems.sort_by_key(|problem| problem.index);
+    for problem in &problems {
+        let quote = &quotes[problem.index];
+        println!(
+            "{:>4}  {}  {}, {}: {}: {}",
+            problem.index + 1,
+            quote.stable_id(),
+            quote.character,
+            quote.anime,
+            problem.severity.label(),
+            problem.message
+        );
+    }
+    if problems
+        .iter()
+        .any(|problem| problem.severity == lint::Severity::Error)
+    {
+        std::process::exit(1);
+    }
+    if !problems.is_empty() {
+        println!("{} quotes checked, only warnings found", quotes.len());
+        return Ok(());
+    }
+    println!("{} quotes checked, no problems found", quotes.len());
+    Ok(())
+}
+
+fn run_fmt(args: FmtArgs) -> Result<()> {
+    let files = if args.files.is_empty() {
+        vec![PathBuf::from(data_path())]
+    } else {
+        args.files
+    };
+    let mut unformatted = 0;
+    for path in &files {
+        let source = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let formatted = crate::format::format(&source)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+        if formatted == source {
+            continue;
+        }
+        unformatted += 1;
+        if args.check {
+            println!("{}", path.display());
+        } else {
+            fs::write(path, formatted)
+                .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+            println!("formatted {}", path.display());
+        }
+    }
+    if args.check && unformatted > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_recent(args: RecentArgs) -> Result<()> {
+    let quotes = load_quotes()?;
+    let state = state::State::load();
+    let recent = state.recent(&quotes);
+    let recent = &recent[..recent.len().min(args.limit)];
+    let rendered = match args.format {
+        StatsFormat::Table => {
+            let now = Local::now().fixed_offset();
+            recent
+                .iter()
+                .map(|&(index, viewed)| {
+                    let quote = &quotes[index];
+                    format!(
+                        "{:<14}{:>4}  {}  {}, {}\n                  \"{}\"\n",
+                        recent::ago(viewed, now),
+                        index + 1,
+                        quote.stable_id(),
+                        quote.character,
+                        quote.anime,
+                        quote.quote
+                    )
+                })
+                .collect()
+        }
+        StatsFormat::Json => {
+            let views: Vec<serde_json::Value> = recent
+                .iter()
+                .map(|&(index, viewed)| {
+                    let mut view =
+                        serde_json::to_value(output::QuoteView::new(index, &quotes[index]))
+                            .unwrap_or_default();
+                    view["viewed_at"] = serde_json::Value::from(viewed.to_rfc3339());
+                    view["views"] = serde_json::Value::from(state.view_count(&quotes[index]));
+                    view
+                })
+                .collect();
+            format!("{}\n", serde_json::to_string_pretty(&views)?)
+        }
+    };
+    write_output(None, &rendered)
+}
+
+/// Like grep(1), exits with status 1 when nothing matches.
+fn run_grep(args: GrepArgs) -> Result<()> {
+    let quotes = load_quotes()?;
+    let palette = io::stdout().is_terminal().then(|| {
+        ConfigRoot::load_from_file(config_path())
+            .ui
+            .colors
+            .to_palette()
+    });
+    let hits: Vec<String> = quotes
+        .iter()
+        .enumerate()
+        .filter_map(|(position, quote)| {
+            output::render_grep(
+                quote,
+                position,
+                &args.pattern,
+                args.case_sensitive,
+                palette.as_ref(),
+            )
+        })
+        .collect();
+    if hits.is_empty() {
+        std::process::exit(1);
+    }
+    write_output(None, &hits.join("\n"))
+}
+
+fn run_random(args: RandomArgs, pick: Pick) -> Result<()> {
+    let template = args.template()?;
+    let quotes = load_quotes()?;
+    let filter = filter::QuoteFilter::from(args.filter);
+    let Some(index) = pick.filtered_index(&quotes, &filter) else {
+        if quotes.is_empty() {
+            return Err(eyre!("no quotes found in {}", data_path()));
+        }
+        return Err(eyre!("no quotes match the filter"));
+    };
+    let quote = &quotes[index];
+    if let Some(template) = template {
+        let rendered =
+            template.render(&|name| output::quote_field(quote, index, quotes.len(), name));
+        return write_output(None, &format!("{}\n", rendered.trim_end_matches('\n')));
+    }
+    let rendered = match args.format {
+        OutputFormat::Plain => output::render_plain(quote),
+        OutputFormat::Bubble => {
+            let settings = ConfigRoot::load_from_file(config_path())
+                .ui
+                .ascii
+                .to_settings();
+            output::render_bubble(quote, &settings, args.width, settings.base_width)
+        }
+        OutputFormat::Waybar => output::render_waybar(quote, args.max_length),
+        OutputFormat::Tmux => {
+            let palette = ConfigRoot::load_from_file(config_path())
+                .ui
+                .colors
+                .to_palette();
+            output::render_tmux(quote, &palette, args.max_length)
+        }
+    };
+    write_output(None, &rendered)
+}
+
+/// Offers the collection's anime and character names as values for
+/// `--anime` and `--character`. They are baked into the generated script, so
+/// it needs regenerating after the collection changes.
+fn with_collection_values(command: clap::Command) -> clap::Command {
+    let quotes = load_quotes().unwrap_or_default();
+    let names = |field: fn(&AnimeQuote) -> &str| {
+        let mut names: Vec<String> = quotes
+            .iter()
+            .map(|quote| field(quote).to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    };
+    let anime = names(|quote| &quote.anime);
+    let characters = names(|quote| &quote.character);
+    offer_values(command, &anime, &characters)
+}
+
+fn offer_values(command: clap::Command, anime: &[String], characters: &[String]) -> clap::Command {
+    command
+        .mut_args(|arg| {
+            let values = match arg.get_id().as_str() {
+                "anime" => anime,
+                "character" => characters,
+                _ => return arg,
+            };
+            arg.value_parser(clap::builder::PossibleValuesParser::new(values.to_vec()))
+        })
+        .mut_subcommands(|subcommand| offer_values(subcommand, anime, characters))
+}
+
+fn load_quotes() -> Result<Vec<AnimeQuote>> {
+    let packs = ConfigRoot::load_from_file(config_path()).packs;
+    AnimeData::load_collection(data_path(), &packs)
+        .map_err(|error| eyre!("failed to load {}: {error}", data_path()))
+}
+
+/// Writes `content` to `output`, or to stdout when no path was given.
+fn write_output(output: Option<&Path>, content: &str) -> Result<()> {
+    match output {
+        Some(path) => {
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content).wrap_err_with(|| format!("failed to write {}", path.display()))
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(content.as_bytes())?;
+            Ok(stdout.flush()?)
+        }
+    }
+}
+
+fn run_export(args: ExportArgs) -> Result<()> {
+    let all = load_quotes()?;
+    let positions = filter::QuoteFilter::from(args.filter).positions(&all);
+    let entries: Vec<export::Entry<'_>> = positions
+        .iter()
+        .map(|&position| (position, &all[position]))
+        .collect();
+    let quotes: Vec<AnimeQuote> = entries.iter().map(|(_, quote)| (*quote).clone()).collect();
+    match args.format {
+        ExportFormat::Json(output) => {
+            write_output(output.output.as_deref(), &export::render_json(&entries)?)
+        }
+        ExportFormat::Toml(output) => {
+            write_output(output.output.as_deref(), &export::render_toml(&entries)?)
+        }
+        ExportFormat::Yaml(output) => {
+            write_output(output.output.as_deref(), &export::render_yaml(&entries)?)
+        }
+        ExportFormat::Csv(output) => {
+            write_output(output.output.as_deref(), &export::render_csv(&entries))
+        }
+        ExportFormat::ReviewHistory(args) => {
+            let state = state::State::load();
+            let content = match args.format {
+                ReviewHistoryFormat::Csv => export::render_review_csv(&entries, &state),
+                ReviewHistoryFormat::Json => export::render_review_json(&entries, &state)?,
+            };
+            write_output(args.output.output.as_deref(), &content)
+        }
+        ExportFormat::Markdown(output) => {
+            write_output(output.output.as_deref(), &export::render_markdown(&quotes))
+        }
+        ExportFormat::Anki(args) => {
+            if let Some(media_dir) = &args.media_dir {
+                copy_anki_media(&quotes, media_dir)?;
+            }
+            write_output(args.output.output.as_deref(), &export::render_anki(&quotes))
+        }
+        ExportFormat::Fortune(args) => {
+            let output = args.output.output.as_deref();
+            write_output(output, &export::render_fortune(&quotes))?;
+            match output {
+                Some(path) if args.strfile => run_strfile(path),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+fn run_strfile(path: &Path) -> Result<()> {
+    let status = ProcessCommand::new("strfile")
+        .arg(path)
+        .status()
+        .wrap_err("failed to run strfile; is fortune installed?")?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(eyre!("strfile exited with {status}"))
+    }
+}
+
+fn copy_anki_media(quotes: &[AnimeQuote], media_dir: &Path) -> Result<()> {
+    fs::create_dir_all(media_dir)?;
+    for image in quotes.iter().filter_map(|quote| quote.image.as_deref()) {
+        if let Some(name) = export::anki_media_name(image) {
+            fs::copy(image, media_dir.join(name))
+                .wrap_err_with(|| format!("failed to copy {image}"))?;
+        }
+    }
+    Ok(())
+}
+
+fn run_card(args: CardArgs) -> Result<()> {
+    let config = ConfigRoot::load_from_file(config_path());
+    let quotes = load_quotes()?;
+    let quote = args
+        .index
+        .checked_sub(1)
+        .and_then(|index| quotes.get(index))
+        .ok_or_else(|| {
+            eyre!(
+                "quote {} does not exist ({} quotes loaded)",
+                args.index,
+                quotes.len()
+            )
+        })?;
+    card::export_card(quote, &config.card, args.template.as_deref(), &args.output)?;
+    println!("wrote {}", args.output.display());
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Smart collections: filter queries saved under a name in `config.toml`,
+//! picked from a list to switch which quotes the viewer covers.
+//!
+//! ```toml
+//! [[collections]]
+//! name = "Short Gintama quotes"
+//! query = "anime:gintama len<40"
+//! ```
+
+use crate::Palette;
+use crate::query::Query;
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use serde::Deserialize;
+
+/// One `[[collections]]` entry.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CollectionConfig {
+    name: String,
+    /// In the `--query` language.
+    query: String,
+}
+
+pub(crate) struct Collection {
+    pub(crate) name: String,
+    pub(crate) query: Query,
+}
+
+/// The collections whose queries parse, in the order listed; the others are
+/// reported and left out.
+pub(crate) fn load(configs: &[CollectionConfig]) -> Vec<Collection> {
+    configs
+        .iter()
+        .filter_map(|config| match Query::parse(&config.query) {
+            Ok(query) => Some(Collection {
+                name: config.name.clone(),
+                query,
+            }),
+            Err(error) => {
+                eprintln!("skipping collection {:?}: {error}", config.name);
+                None
+            }
+        })
+        .collect()
+}
+
+pub(crate) enum CollectionsAction {
+    Stay,
+    Close,
+    /// Switch to the collection at this index, or back to every quote.
+    Switch(Option<usize>),
+}
+
+/// The picker: every quote first, then the saved collections, each with the
+/// digit that switches to it.
+pub(crate) struct Collections {
+    list: ListState,
+}
+
+impl Collections {
+    /// Opens with the cursor on `active`.
+    pub(crate) fn new(active: Option<usize>) -> Self {
+        Self {
+            list: ListState::default().with_selected(Some(active.map_or(0, |index| index + 1))),
+        }
+    }
+
+    pub(crate) fn handle_key(
+        &mut self,
+        code: KeyCode,
+        collections: &[Collection],
+    ) -> CollectionsAction {
+        let choice = |row: usize| match row {
+            0 => Some(CollectionsAction::Switch(None)),
+            row if row <= collections.len() => Some(CollectionsAction::Switch(Some(row - 1))),
+            _ => None,
+        };
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Enter => {
+                let selected = self.list.selected().unwrap_or(0).min(collections.len());
+                if let Some(action) = choice(selected) {
+                    return action;
+                }
+            }
+            KeyCode::Char(digit @ '0'..='9') => {
+                if let Some(action) = choice(usize::from(digit as u8 - b'0')) {
+                    return action;
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('C') | KeyCode::Char('q') => {
+                return CollectionsAction::Close;
+            }
+            _ => {}
+        }
+        CollectionsAction::Stay
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        collections: &[Collection],
+        active: Option<usize>,
+        palette: &Palette,
+    ) {
+        let [title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        let count_style = Style::default().fg(palette.count);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Collections",
+                Style::default().fg(palette.anime).bold(),
+            )))
+            .centered(),
+            title_area,
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Switch <0-9/Enter>  Move <Up/Down>  Back <Esc>",
+                Style::default().fg(palette.instructions),
+            )))
+            .centered(),
+            help_area,
+        );
+
+        let row = |digit: usize, name: &str, query: Option<&str>, is_active: bool| {
+            let digit = if digit < 10 {
+                format!("{digit:>3} ")
+            } else {
+                "    ".to_string()
+            };
+            let mut spans = vec![
+                Span::styled(digit, count_style),
+                Span::styled(if is_active { "● " } else { "  " }, count_style),
+                Span::styled(
+                    name.to_string(),
+                    Style::default().fg(palette.character).bold(),
+                ),
+            ];
+            if let Some(query) = query {
+                spans.push(Span::styled(format!("  {query}"), count_style));
+            }
+            ListItem::new(Line::from(spans))
+        };
+        let mut items = vec![row(0, "All quotes", None, active.is_none())];
+        if collections.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "      Add [[collections]] with a name and query to config.toml",
+                count_style,
+            ))));
+        }
+        items.extend(collections.iter().enumerate().map(|(index, collection)| {
+            row(
+                index + 1,
+                &collection.name,
+                Some(collection.query.as_str()),
+                active == Some(index),
+            )
+        }));
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+    }
+}
@@ -0,0 +1,176 @@
+//! Control socket that lets `anime-quotes ctl …` drive a running viewer.
+//!
+//! The protocol is one command per connection: the client writes a single
+//! line (`next`, `prev`, `random`, `goto 5`, `show-json`) and reads the reply
+//! until the viewer closes the connection.
+
+use color_eyre::eyre::Result;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ControlCommand {
+    Next,
+    Previous,
+    Random,
+    Goto(usize),
+    ShowJson,
+}
+
+impl ControlCommand {
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let command = match words.next()? {
+            "next" => Self::Next,
+            "prev" | "previous" => Self::Previous,
+            "random" => Self::Random,
+            "goto" => Self::Goto(words.next()?.parse().ok()?),
+            "show-json" => Self::ShowJson,
+            _ => return None,
+        };
+        words.next().is_none().then_some(command)
+    }
+
+    pub(crate) fn to_line(self) -> String {
+        match self {
+            Self::Next => "next".to_string(),
+            Self::Previous => "prev".to_string(),
+            Self::Random => "random".to_string(),
+            Self::Goto(index) => format!("goto {index}"),
+            Self::ShowJson => "show-json".to_string(),
+        }
+    }
+}
+
+/// A command received from a client, with the channel its reply goes back on.
+pub(crate) struct ControlRequest {
+    pub(crate) command: ControlCommand,
+    pub(crate) reply: Sender<String>,
+}
+
+/// Socket path shared by the viewer and `ctl`: `$XDG_RUNTIME_DIR` when set,
+/// otherwise a per-user file in the temp directory.
+pub(crate) fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("anime-quotes.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
+            std::env::temp_dir().join(format!("anime-quotes-{user}.sock"))
+        }
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use super::{ControlCommand, ControlRequest};
+    use color_eyre::eyre::{Result, WrapErr};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+
+    /// Removes the socket file when the viewer exits.
+    pub(crate) struct ControlServer {
+        path: PathBuf,
+    }
+
+    impl Drop for ControlServer {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    pub(crate) fn listen(path: &Path) -> Result<(ControlServer, Receiver<ControlRequest>)> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                color_eyre::eyre::bail!(
+                    "another instance is already listening on {}",
+                    path.display()
+                );
+            }
+            // Left behind by a viewer that did not shut down cleanly.
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)
+            .wrap_err_with(|| format!("failed to bind {}", path.display()))?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || handle_client(stream, &sender));
+            }
+        });
+        Ok((
+            ControlServer {
+                path: path.to_path_buf(),
+            },
+            receiver,
+        ))
+    }
+
+    fn handle_client(mut stream: UnixStream, sender: &Sender<ControlRequest>) {
+        let mut line = String::new();
+        if BufReader::new(&stream).read_line(&mut line).is_err() {
+            return;
+        }
+        let reply = match ControlCommand::parse(&line) {
+            Some(command) => {
+                let (reply, response) = mpsc::channel();
+                if sender.send(ControlRequest { command, reply }).is_err() {
+                    return;
+                }
+                response
+                    .recv()
+                    .unwrap_or_else(|_| "error: viewer exited".to_string())
+            }
+            None => format!("error: unknown command {:?}", line.trim()),
+        };
+        let _ = writeln!(stream, "{reply}");
+    }
+
+    pub(crate) fn send(path: &Path, command: ControlCommand) -> Result<String> {
+        let mut stream = UnixStream::connect(path).wrap_err_with(|| {
+            format!(
+                "no running viewer is listening on {} (start `anime-quotes` first)",
+                path.display()
+            )
+        })?;
+        writeln!(stream, "{}", command.to_line())?;
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply)?;
+        Ok(reply)
+    }
+}
+
+#[cfg(unix)]
+pub(crate) use platform::ControlServer;
+
+#[cfg(not(unix))]
+pub(crate) struct ControlServer;
+
+/// Starts listening for control commands on `path`.
+pub(crate) fn listen(path: &Path) -> Result<(ControlServer, Receiver<ControlRequest>)> {
+    #[cfg(unix)]
+    {
+        platform::listen(path)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        color_eyre::eyre::bail!("the control socket is only available on Unix platforms")
+    }
+}
+
+/// Sends `command` to the viewer listening on `path` and returns its reply.
+pub(crate) fn send(path: &Path, command: ControlCommand) -> Result<String> {
+    #[cfg(unix)]
+    {
+        platform::send(path, command)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, command);
+        color_eyre::eyre::bail!("the control socket is only available on Unix platforms")
+    }
+}
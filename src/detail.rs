@@ -0,0 +1,393 @@
+//! The detail screens: everything the collection has for the anime of the
+//! current quote, with its AniList details once fetched, or for its
+//! character across every anime, to jump to one of their quotes or narrow
+//! the viewer to them.
+
+use crate::anime_info::{self, AnimeInfo};
+use crate::artwork::ImageSlot;
+use crate::filter::QuoteFilter;
+use crate::grid::fit;
+use crate::query::Query;
+use crate::{AnimeQuote, Palette, gallery};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph, Wrap};
+use ratatui_image::Resize;
+use ratatui_image::picker::Picker;
+use std::collections::BTreeSet;
+
+pub(crate) enum DetailAction {
+    Stay,
+    Close,
+    /// Show the quote at this index in the viewer.
+    Show(usize),
+    /// Narrow the viewer to the anime's or character's quotes.
+    Filter(QuoteFilter),
+}
+
+pub(crate) struct AnimeDetail {
+    anime: String,
+    /// Indices of its quotes in the collection, in collection order.
+    quotes: Vec<usize>,
+    list: ListState,
+    info: Option<AnimeInfo>,
+    /// Why fetching the details failed, until the next key.
+    message: Option<String>,
+}
+
+impl AnimeDetail {
+    /// The anime of the quote at `current`, with the cursor on that quote.
+    pub(crate) fn new(quotes: &[AnimeQuote], current: usize) -> Option<Self> {
+        let anime = quotes.get(current)?.anime.clone();
+        let indices: Vec<usize> = (0..quotes.len())
+            .filter(|&index| quotes[index].anime == anime)
+            .collect();
+        let selected = indices.iter().position(|&index| index == current);
+        Some(Self {
+            info: anime_info::cached(&anime),
+            anime,
+            quotes: indices,
+            list: ListState::default().with_selected(selected),
+            message: None,
+        })
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode) -> DetailAction {
+        self.message = None;
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Home => self.list.select_first(),
+            KeyCode::End => self.list.select_last(),
+            KeyCode::Enter => {
+                let selected = self.list.selected().unwrap_or(0);
+                if let Some(&index) = self.quotes.get(selected.min(self.quotes.len() - 1)) {
+                    return DetailAction::Show(index);
+                }
+            }
+            KeyCode::Char('f') => {
+                if let Ok(query) = Query::equals("anime", &self.anime) {
+                    return DetailAction::Filter(QuoteFilter {
+                        query: Some(query),
+                        ..QuoteFilter::default()
+                    });
+                }
+            }
+            KeyCode::Char('m') => match anime_info::fetch(&self.anime) {
+                Ok(info) => self.info = Some(info),
+                Err(error) => self.message = Some(format!("{error:#}")),
+            },
+            KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('q') => {
+                return DetailAction::Close;
+            }
+            _ => {}
+        }
+        DetailAction::Stay
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        quotes: &[AnimeQuote],
+        palette: &Palette,
+    ) {
+        let count_style = Style::default().fg(palette.count);
+        let mine: Vec<&AnimeQuote> = self.quotes.iter().map(|&index| &quotes[index]).collect();
+
+        let mut characters: Vec<(&str, usize)> = Vec::new();
+        for quote in &mine {
+            match characters
+                .iter_mut()
+                .find(|(name, _)| *name == quote.character)
+            {
+                Some((_, count)) => *count += 1,
+                None => characters.push((&quote.character, 1)),
+            }
+        }
+        characters.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let years: BTreeSet<u32> = mine.iter().filter_map(|quote| quote.year).collect();
+        let sources: BTreeSet<&str> = mine
+            .iter()
+            .filter_map(|quote| quote.source.as_deref())
+            .map(str::trim)
+            .filter(|source| !source.is_empty())
+            .collect();
+
+        let mut summary = vec![
+            match mine.len() {
+                1 => "1 quote".to_string(),
+                count => format!("{count} quotes"),
+            },
+            match characters.len() {
+                1 => "1 character".to_string(),
+                count => format!("{count} characters"),
+            },
+        ];
+        match (years.first(), years.last()) {
+            (Some(first), Some(last)) if first != last => summary.push(format!("{first}–{last}")),
+            (Some(year), _) => summary.push(year.to_string()),
+            _ => {}
+        }
+        if !sources.is_empty() {
+            summary.push(sources.into_iter().collect::<Vec<_>>().join(", "));
+        }
+
+        let mut header = vec![
+            Line::from(Span::styled(
+                self.anime.clone(),
+                Style::default().fg(palette.anime).bold(),
+            )),
+            Line::from(Span::styled(summary.join(" · "), count_style)),
+            Line::from(""),
+        ];
+        match &self.info {
+            Some(info) => {
+                let title = info.title.english.as_ref().or(info.title.romaji.as_ref());
+                let mut line = vec![Span::styled("AniList: ", count_style)];
+                if let Some(title) = title.filter(|title| **title != self.anime) {
+                    line.push(Span::styled(
+                        format!("{title} · "),
+                        Style::default().fg(palette.anime),
+                    ));
+                }
+                line.push(Span::styled(
+                    info.summary(),
+                    Style::default().fg(palette.quote),
+                ));
+                header.push(Line::from(line));
+                if !info.genres.is_empty() {
+                    header.push(Line::from(Span::styled(
+                        info.genres.join(", "),
+                        Style::default().fg(palette.romaji),
+                    )));
+                }
+            }
+            None => header.push(Line::from(Span::styled(
+                "Fetch details from AniList <M>",
+                count_style,
+            ))),
+        }
+        header.push(Line::from(
+            [Span::styled("Characters: ", count_style)]
+                .into_iter()
+                .chain(
+                    characters
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(place, (name, count))| {
+                            [
+                                Span::raw(if place == 0 { "" } else { " · " }),
+                                Span::styled(
+                                    name.to_string(),
+                                    Style::default().fg(palette.character).bold(),
+                                ),
+                                Span::styled(format!(" ×{count}"), count_style),
+                            ]
+                        }),
+                )
+                .collect::<Vec<_>>(),
+        ));
+        header.push(Line::from(""));
+
+        // The character line may wrap once or twice in a narrow terminal.
+        let header_rows = u16::try_from(header.len() + 1).unwrap_or(u16::MAX);
+        let [header_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(header_rows),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        frame.render_widget(
+            Paragraph::new(Text::from(header))
+                .centered()
+                .wrap(Wrap { trim: true }),
+            header_area,
+        );
+
+        let help = match &self.message {
+            Some(message) => Line::from(Span::styled(
+                message.clone(),
+                Style::default().fg(palette.character).bold(),
+            )),
+            None => Line::from(Span::styled(
+                "Jump <Enter>  Filter to this anime <F>  Fetch details <M>  Back <Esc>",
+                Style::default().fg(palette.instructions),
+            )),
+        };
+        frame.render_widget(Paragraph::new(help).centered(), help_area);
+
+        let items: Vec<ListItem> = mine
+            .iter()
+            .map(|quote| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{}  ", quote.character),
+                        Style::default().fg(palette.character).bold(),
+                    ),
+                    Span::styled(
+                        format!("\"{}\"", quote.quote),
+                        Style::default().fg(palette.quote).italic(),
+                    ),
+                ]))
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+    }
+}
+
+pub(crate) struct CharacterDetail {
+    character: String,
+    /// Indices of their quotes in the collection, in collection order.
+    quotes: Vec<usize>,
+    list: ListState,
+    /// Their portrait from the gallery's folder, and its size in cells.
+    portrait: Option<(ImageSlot, (u16, u16))>,
+    picker: Picker,
+}
+
+impl CharacterDetail {
+    /// The character of the quote at `current`, with the cursor on that
+    /// quote.
+    pub(crate) fn new(quotes: &[AnimeQuote], current: usize, picker: &Picker) -> Option<Self> {
+        let character = quotes.get(current)?.character.clone();
+        let indices: Vec<usize> = (0..quotes.len())
+            .filter(|&index| quotes[index].character == character)
+            .collect();
+        let selected = indices.iter().position(|&index| index == current);
+        Some(Self {
+            portrait: gallery::portrait(&character, picker),
+            character,
+            quotes: indices,
+            list: ListState::default().with_selected(selected),
+            picker: picker.clone(),
+        })
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode) -> DetailAction {
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Home => self.list.select_first(),
+            KeyCode::End => self.list.select_last(),
+            KeyCode::Enter => return DetailAction::Show(self.selected()),
+            KeyCode::Char('f') => {
+                if let Ok(query) = Query::equals("character", &self.character) {
+                    return DetailAction::Filter(QuoteFilter {
+                        query: Some(query),
+                        ..QuoteFilter::default()
+                    });
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('I') | KeyCode::Char('q') => {
+                return DetailAction::Close;
+            }
+            _ => {}
+        }
+        DetailAction::Stay
+    }
+
+    /// The quote under the cursor.
+    fn selected(&self) -> usize {
+        let selected = self.list.selected().unwrap_or(0);
+        self.quotes[selected.min(self.quotes.len() - 1)]
+    }
+
+    /// Draws the screen, and returns where the artwork goes with the quote
+    /// to take it from when the character has no portrait.
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        quotes: &[AnimeQuote],
+        resize: &Resize,
+        palette: &Palette,
+    ) -> Option<(usize, Rect)> {
+        let count_style = Style::default().fg(palette.count);
+        let [body_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+        let [image_area, _, text_area] = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Length(2),
+            Constraint::Fill(1),
+        ])
+        .areas(body_area);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Jump <Enter>  Filter to this character <F>  Back <Esc>",
+                Style::default().fg(palette.instructions),
+            )))
+            .centered(),
+            help_area,
+        );
+
+        let mut anime: Vec<(&str, usize)> = Vec::new();
+        for &index in &self.quotes {
+            let quote = &quotes[index];
+            match anime.iter_mut().find(|(name, _)| *name == quote.anime) {
+                Some((_, count)) => *count += 1,
+                None => anime.push((&quote.anime, 1)),
+            }
+        }
+        let count = match self.quotes.len() {
+            1 => "1 quote".to_string(),
+            count => format!("{count} quotes"),
+        };
+        let header = vec![
+            Line::from(Span::styled(
+                self.character.clone(),
+                Style::default().fg(palette.character).bold(),
+            )),
+            Line::from(
+                [Span::styled(format!("{count} from "), count_style)]
+                    .into_iter()
+                    .chain(anime.iter().enumerate().flat_map(|(place, (name, count))| {
+                        [
+                            Span::raw(if place == 0 { "" } else { " · " }),
+                            Span::styled(name.to_string(), Style::default().fg(palette.anime)),
+                            Span::styled(format!(" ×{count}"), count_style),
+                        ]
+                    }))
+                    .collect::<Vec<_>>(),
+            ),
+            Line::from(""),
+        ];
+        let header_rows = u16::try_from(header.len() + 1).unwrap_or(u16::MAX);
+        let [header_area, list_area] =
+            Layout::vertical([Constraint::Length(header_rows), Constraint::Fill(1)])
+                .areas(text_area);
+        frame.render_widget(
+            Paragraph::new(Text::from(header)).wrap(Wrap { trim: true }),
+            header_area,
+        );
+
+        let items: Vec<ListItem> = self
+            .quotes
+            .iter()
+            .map(|&index| {
+                let quote = &quotes[index];
+                ListItem::new(Text::from(vec![
+                    Line::from(Span::styled(
+                        format!("\"{}\"", quote.quote),
+                        Style::default().fg(palette.quote).italic(),
+                    )),
+                    Line::from(Span::styled(format!("  {}", quote.anime), count_style)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        match &mut self.portrait {
+            Some((slot, cells)) => {
+                slot.draw(frame, fit(image_area, *cells), &self.picker, resize);
+                None
+            }
+            None => Some((self.selected(), image_area)),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Batch changes to quotes where they are written: the data file, its
+//! `quotes.d` files, and the installed packs. Files are edited with `toml_edit` so the rest of each one
+//! keeps its layout, and quotes are found by stable ID.
+
+use crate::schema::CURRENT_SCHEMA_VERSION;
+use crate::{AnimeQuote, data_path, organize, packs, paths, sanitize};
+use color_eyre::eyre::{Result, WrapErr, bail};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{ArrayOfTables, DocumentMut, Table};
+
+/// The data file and its `quotes.d` files followed by every installed pack.
+pub(crate) fn source_files() -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from(data_path())];
+    files.extend(organize::split_files(Path::new(data_path())));
+    match packs::installed_paths() {
+        Ok(installed) => files.extend(installed.into_iter().map(|(_, path)| path)),
+        Err(error) => eprintln!("failed to list packs: {error}"),
+    }
+    files
+}
+
+/// The stable ID of a quote as written, with its text cleaned up the way it
+/// is when loaded so the two agree.
+pub(crate) fn table_id(table: &Table) -> String {
+    let text = |key: &str| {
+        table
+            .get(key)
+            .and_then(|item| item.as_str())
+            .map(sanitize::clean)
+            .unwrap_or_default()
+    };
+    AnimeQuote::id_for(
+        table.get("id").and_then(|item| item.as_str()),
+        &text("anime"),
+        &text("japanese"),
+    )
+}
+
+pub(crate) fn read_document(path: &Path) -> Result<DocumentMut> {
+    fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?
+        .parse()
+        .wrap_err_with(|| format!("failed to parse {}", path.display()))
+}
+
+pub(crate) fn quotes_mut<'a>(
+    document: &'a mut DocumentMut,
+    path: &Path,
+) -> Result<&'a mut ArrayOfTables> {
+    let quotes = document
+        .entry("quotes")
+        .or_insert_with(|| toml_edit::Item::ArrayOfTables(ArrayOfTables::new()));
+    match quotes.as_array_of_tables_mut() {
+        Some(quotes) => Ok(quotes),
+        None => bail!("`quotes` in {} is not an array of tables", path.display()),
+    }
+}
+
+/// Runs `change` on the quotes of each source file, which returns how many
+/// it changed, and writes back the files where that was any.
+fn update(mut change: impl FnMut(&Path, &mut ArrayOfTables) -> usize) -> Result<usize> {
+    let mut total = 0;
+    for path in source_files() {
+        let mut document = read_document(&path)?;
+        let changed = change(&path, quotes_mut(&mut document, &path)?);
+        if changed > 0 {
+            fs::write(&path, document.to_string())
+                .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+            total += changed;
+        }
+    }
+    Ok(total)
+}
+
+/// Adds `tag` to the quotes in `ids` that don't have it yet (in any case).
+pub(crate) fn tag(ids: &HashSet<String>, tag: &str) -> Result<usize> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        bail!("the tag is empty");
+    }
+    update(|_, quotes| tag_tables(quotes, ids, tag))
+}
+
+/// [`tag`] for the quotes of one file, returning how many it changed.
+pub(crate) fn tag_tables(quotes: &mut ArrayOfTables, ids: &HashSet<String>, tag: &str) -> usize {
+    let mut changed = 0;
+    for table in quotes
+        .iter_mut()
+        .filter(|table| ids.contains(&table_id(table)))
+    {
+        let tags = table
+            .entry("tags")
+            .or_insert_with(|| toml_edit::value(toml_edit::Array::new()));
+        let Some(tags) = tags.as_array_mut() else {
+            continue;
+        };
+        let present = tags.iter().any(|existing| {
+            existing
+                .as_str()
+                .is_some_and(|existing| existing.eq_ignore_ascii_case(tag))
+        });
+        if !present {
+            tags.push(tag);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Removes the quotes in `ids` from the files they are written in.
+pub(crate) fn delete(ids: &HashSet<String>) -> Result<usize> {
+    update(|_, quotes| {
+        let before = quotes.len();
+        quotes.retain(|table| !ids.contains(&table_id(table)));
+        before - quotes.len()
+    })
+}
+
+/// Moves the quotes in `ids` to the end of the pack `name`, creating it if
+/// needed. Image paths are rewritten to absolute ones, since they were
+/// relative to the file the quote came from. The pack is written before the
+/// quotes are removed anywhere else.
+pub(crate) fn move_to_pack(ids: &HashSet<String>, name: &str) -> Result<usize> {
+    packs::check_name(name)?;
+    let destination = packs::pack_path(name);
+    let mut moved: Vec<Table> = Vec::new();
+    for path in source_files()
+        .into_iter()
+        .filter(|path| *path != destination)
+    {
+        let mut document = read_document(&path)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        for table in quotes_mut(&mut document, &path)?
+            .iter()
+            .filter(|table| ids.contains(&table_id(table)))
+        {
+            let mut table = table.clone();
+            if let Some(image) = table.get("image").and_then(|item| item.as_str()) {
+                let image = paths::resolve(base, image);
+                let image = fs::canonicalize(&image)
+                    .map_or(image, |absolute| absolute.to_string_lossy().into_owned());
+                table.insert("image", toml_edit::value(image));
+            }
+            moved.push(table);
+        }
+    }
+    if moved.is_empty() {
+        return Ok(0);
+    }
+
+    let mut document = if destination.exists() {
+        read_document(&destination)?
+    } else {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut document = DocumentMut::new();
+        document.insert("schema_version", toml_edit::value(CURRENT_SCHEMA_VERSION));
+        document
+    };
+    let count = moved.len();
+    let quotes = quotes_mut(&mut document, &destination)?;
+    for table in moved {
+        quotes.push(table);
+    }
+    fs::write(&destination, document.to_string())
+        .wrap_err_with(|| format!("failed to write {}", destination.display()))?;
+
+    update(|path, quotes| {
+        if path == destination {
+            return 0;
+        }
+        let before = quotes.len();
+        quotes.retain(|table| !ids.contains(&table_id(table)));
+        before - quotes.len()
+    })?;
+    Ok(count)
+}
@@ -0,0 +1,443 @@
+use crate::AnimeQuote;
+use crate::output::{self, QuoteView};
+use crate::schema::CURRENT_SCHEMA_VERSION;
+use crate::screenshot::escape_html;
+use crate::state::State;
+use color_eyre::eyre::WrapErr;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+pub(crate) struct AnimeGroup<'a> {
+    pub(crate) title: &'a str,
+    pub(crate) characters: BTreeMap<String, CharacterGroup<'a>>,
+}
+
+pub(crate) struct CharacterGroup<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) quotes: Vec<&'a AnimeQuote>,
+}
+
+impl AnimeGroup<'_> {
+    pub(crate) fn quote_count(&self) -> usize {
+        self.characters
+            .values()
+            .map(|group| group.quotes.len())
+            .sum()
+    }
+}
+
+/// Groups quotes by anime and then by character, both sorted case-insensitively,
+/// keeping quotes in data order within each character.
+pub(crate) fn group_by_anime(quotes: &[AnimeQuote]) -> BTreeMap<String, AnimeGroup<'_>> {
+    let mut groups: BTreeMap<String, AnimeGroup<'_>> = BTreeMap::new();
+    for quote in quotes {
+        groups
+            .entry(quote.anime.to_lowercase())
+            .or_insert_with(|| AnimeGroup {
+                title: &quote.anime,
+                characters: BTreeMap::new(),
+            })
+            .characters
+            .entry(quote.character.to_lowercase())
+            .or_insert_with(|| CharacterGroup {
+                name: &quote.character,
+                quotes: Vec::new(),
+            })
+            .quotes
+            .push(quote);
+    }
+    groups
+}
+
+pub(crate) fn render_markdown(quotes: &[AnimeQuote]) -> String {
+    let groups = group_by_anime(quotes);
+    let mut output = String::from("# Anime Quotes\n\n");
+    let _ = writeln!(
+        output,
+        "{} quotes from {} anime.\n",
+        quotes.len(),
+        groups.len()
+    );
+
+    for group in groups.values() {
+        let _ = writeln!(
+            output,
+            "- [{}](#{}) ({})",
+            escape_markdown(group.title),
+            markdown_anchor(group.title),
+            group.quote_count()
+        );
+    }
+
+    for group in groups.values() {
+        let _ = write!(output, "\n## {}\n", escape_markdown(group.title));
+        for character in group.characters.values() {
+            let _ = write!(output, "\n### {}\n", escape_markdown(character.name));
+            for quote in &character.quotes {
+                output.push('\n');
+                render_markdown_quote(&mut output, quote);
+            }
+        }
+    }
+    output
+}
+
+fn render_markdown_quote(output: &mut String, quote: &AnimeQuote) {
+    let _ = writeln!(output, "> {}", escape_markdown(&quote.japanese));
+    if let Some(romaji) = &quote.romaji {
+        let _ = writeln!(output, ">\n> *{}*", escape_markdown(romaji));
+    }
+    let _ = writeln!(output, ">\n> “{}”", escape_markdown(&quote.quote));
+    if let Some(image) = &quote.image {
+        let _ = writeln!(
+            output,
+            "\n![{}]({})",
+            escape_markdown(&quote.character),
+            image.replace(' ', "%20")
+        );
+    }
+    if let Some(url) = &quote.url {
+        let _ = writeln!(output, "\n[{}]({url})", escape_markdown(&quote.anime));
+    }
+    if let Some(source_url) = &quote.source_url {
+        let _ = writeln!(output, "\n[Source]({source_url})");
+    }
+}
+
+fn escape_markdown(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(
+            ch,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// GitHub-style heading anchor: lowercase, spaces to dashes, punctuation dropped.
+pub(crate) fn markdown_anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|ch| match ch {
+            ' ' => Some('-'),
+            '-' | '_' => Some(ch),
+            _ if ch.is_alphanumeric() => Some(ch),
+            _ => None,
+        })
+        .collect()
+}
+
+/// File name an image is referenced by inside Anki's media folder.
+pub(crate) fn anki_media_name(image: &str) -> Option<&str> {
+    Path::new(image).file_name().and_then(|name| name.to_str())
+}
+
+/// A quote as an Anki note: Japanese on the front; romaji, translation,
+/// attribution, and artwork on the back; the anime as a tag.
+pub(crate) struct AnkiNote {
+    pub(crate) front: String,
+    pub(crate) back: String,
+    pub(crate) tag: String,
+}
+
+pub(crate) fn anki_note(quote: &AnimeQuote) -> AnkiNote {
+    let mut back = Vec::new();
+    if let Some(romaji) = &quote.romaji {
+        back.push(format!("<i>{}</i>", escape_html(romaji)));
+    }
+    back.push(escape_html(&quote.quote));
+    back.push(format!(
+        "<small>— {}, {}</small>",
+        escape_html(&quote.character),
+        escape_html(&quote.anime)
+    ));
+    if let Some(source_url) = &quote.source_url {
+        back.push(format!(
+            "<small><a href=\"{}\">Source</a></small>",
+            escape_html(source_url)
+        ));
+    }
+    if let Some(media) = quote.image.as_deref().and_then(anki_media_name) {
+        back.push(format!("<img src=\"{}\">", escape_html(media)));
+    }
+    AnkiNote {
+        front: escape_html(&quote.japanese),
+        back: back.join("<br>"),
+        tag: anki_tag(&quote.anime),
+    }
+}
+
+/// Anki "notes in plain text" import file, one [`anki_note`] per line.
+pub(crate) fn render_anki(quotes: &[AnimeQuote]) -> String {
+    let mut output =
+        String::from("#separator:tab\n#html:true\n#columns:Front\tBack\tTags\n#tags column:3\n");
+    for quote in quotes {
+        let note = anki_note(quote);
+        let _ = writeln!(
+            output,
+            "{}\t{}\t{}",
+            anki_field(&note.front),
+            anki_field(&note.back),
+            note.tag
+        );
+    }
+    output
+}
+
+/// Tabs and newlines would split the record, so they become spaces and `<br>`.
+fn anki_field(value: &str) -> String {
+    value
+        .replace('\t', " ")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+fn anki_tag(anime: &str) -> String {
+    anime
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .replace(':', "")
+}
+
+/// fortune(6) source file: one quote per entry, entries separated by `%` lines.
+pub(crate) fn render_fortune(quotes: &[AnimeQuote]) -> String {
+    let mut output = String::new();
+    for quote in quotes {
+        output.push_str(&fortune_line(&quote.japanese));
+        output.push('\n');
+        if let Some(romaji) = &quote.romaji {
+            let _ = writeln!(output, "({})", fortune_line(romaji));
+        }
+        let _ = writeln!(output, "\"{}\"", fortune_line(&quote.quote));
+        let _ = writeln!(
+            output,
+            "\t\t-- {}, {}",
+            fortune_line(&quote.character),
+            fortune_line(&quote.anime)
+        );
+        if let Some(source_url) = &quote.source_url {
+            let _ = writeln!(output, "\t\t   {}", fortune_line(source_url));
+        }
+        output.push_str("%\n");
+    }
+    output
+}
+
+/// A line consisting of just `%` would end the entry early, and stray
+/// newlines would break the layout, so both are neutralized.
+fn fortune_line(value: &str) -> String {
+    let line = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line == "%" { "%%".to_string() } else { line }
+}
+
+/// A quote together with its 0-based position in the full collection, so
+/// exported subsets keep the numbers shown in the viewer counter.
+pub(crate) type Entry<'a> = (usize, &'a AnimeQuote);
+
+/// Writes `entries` to `path` in the format its extension names: JSON,
+/// YAML, CSV, Markdown, or else a TOML data file.
+pub(crate) fn write_for_path(path: &Path, entries: &[Entry<'_>]) -> color_eyre::Result<()> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let content = match extension.as_str() {
+        "json" => render_json(entries)?,
+        "yaml" | "yml" => render_yaml(entries)?,
+        "csv" => render_csv(entries),
+        "md" | "markdown" => {
+            let quotes: Vec<AnimeQuote> =
+                entries.iter().map(|(_, quote)| (*quote).clone()).collect();
+            render_markdown(&quotes)
+        }
+        _ => render_toml(entries)?,
+    };
+    std::fs::write(path, content).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+/// JSON array in the shape the HTTP API serves.
+pub(crate) fn render_json(entries: &[Entry<'_>]) -> serde_json::Result<String> {
+    let views: Vec<QuoteView<'_>> = entries
+        .iter()
+        .map(|&(position, quote)| QuoteView::new(position, quote))
+        .collect();
+    Ok(format!("{}\n", serde_json::to_string_pretty(&views)?))
+}
+
+#[derive(Serialize)]
+struct DataFile<'a> {
+    schema_version: i64,
+    quotes: Vec<DataQuote<'a>>,
+}
+
+/// Quotes carry their stable ID explicitly, so a subset loaded on its own
+/// keeps the same IDs.
+#[derive(Serialize)]
+struct DataQuote<'a> {
+    id: String,
+    #[serde(flatten)]
+    quote: &'a AnimeQuote,
+}
+
+/// A data file in the `anime.toml` format.
+pub(crate) fn render_toml(entries: &[Entry<'_>]) -> Result<String, toml::ser::Error> {
+    toml::to_string(&DataFile {
+        schema_version: CURRENT_SCHEMA_VERSION,
+        quotes: entries
+            .iter()
+            .map(|&(_, quote)| DataQuote {
+                id: quote.stable_id(),
+                quote,
+            })
+            .collect(),
+    })
+}
+
+/// YAML list with the same fields as the JSON export. JSON strings and
+/// objects are valid YAML flow scalars, so values are written that way.
+pub(crate) fn render_yaml(entries: &[Entry<'_>]) -> serde_json::Result<String> {
+    let mut output = String::new();
+    for &(position, quote) in entries {
+        let serde_json::Value::Object(fields) =
+            serde_json::to_value(QuoteView::new(position, quote))?
+        else {
+            continue;
+        };
+        for (index, (key, value)) in fields.iter().enumerate() {
+            let prefix = if index == 0 { "- " } else { "  " };
+            let _ = writeln!(output, "{prefix}{key}: {value}");
+        }
+    }
+    if output.is_empty() {
+        output.push_str("[]\n");
+    }
+    Ok(output)
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "index",
+    "anime",
+    "character",
+    "japanese",
+    "romaji",
+    "quote",
+    "image",
+    "url",
+    "source",
+    "season",
+    "episode",
+    "year",
+    "source_url",
+    "tags",
+    "rating",
+];
+
+/// RFC 4180 CSV with a header row; empty cells for missing fields.
+pub(crate) fn render_csv(entries: &[Entry<'_>]) -> String {
+    let mut output = format!("{}\r\n", CSV_COLUMNS.join(","));
+    for &(position, quote) in entries {
+        let cells: Vec<String> = CSV_COLUMNS
+            .iter()
+            .map(|column| {
+                csv_cell(&output::quote_field(quote, position, 0, column).unwrap_or_default())
+            })
+            .collect();
+        let _ = write!(output, "{}\r\n", cells.join(","));
+    }
+    output
+}
+
+/// A quote's review history, as far as the viewer keeps one: how often and
+/// when it came up, and whether it was starred or hidden.
+#[derive(Serialize)]
+struct ReviewRow<'a> {
+    id: String,
+    index: usize,
+    anime: &'a str,
+    character: &'a str,
+    japanese: &'a str,
+    views: u64,
+    last_viewed: Option<&'a str>,
+    favorite: bool,
+    hidden: bool,
+}
+
+impl<'a> ReviewRow<'a> {
+    fn new(position: usize, quote: &'a AnimeQuote, state: &'a State) -> Self {
+        let (views, last_viewed) = state.views(quote);
+        Self {
+            id: quote.stable_id(),
+            index: position + 1,
+            anime: &quote.anime,
+            character: &quote.character,
+            japanese: &quote.japanese,
+            views,
+            last_viewed,
+            favorite: state.is_favorite(quote),
+            hidden: state.is_hidden(quote),
+        }
+    }
+}
+
+const REVIEW_COLUMNS: &[&str] = &[
+    "id",
+    "index",
+    "anime",
+    "character",
+    "japanese",
+    "views",
+    "last_viewed",
+    "favorite",
+    "hidden",
+];
+
+/// Per-quote review history as a JSON array, in collection order.
+pub(crate) fn render_review_json(
+    entries: &[Entry<'_>],
+    state: &State,
+) -> serde_json::Result<String> {
+    let rows: Vec<ReviewRow<'_>> = entries
+        .iter()
+        .map(|&(position, quote)| ReviewRow::new(position, quote, state))
+        .collect();
+    Ok(format!("{}\n", serde_json::to_string_pretty(&rows)?))
+}
+
+/// Per-quote review history as RFC 4180 CSV, with the columns of the JSON.
+pub(crate) fn render_review_csv(entries: &[Entry<'_>], state: &State) -> String {
+    let mut output = format!("{}\r\n", REVIEW_COLUMNS.join(","));
+    for &(position, quote) in entries {
+        let row = ReviewRow::new(position, quote, state);
+        let cells = [
+            row.id,
+            row.index.to_string(),
+            csv_cell(row.anime),
+            csv_cell(row.character),
+            csv_cell(row.japanese),
+            row.views.to_string(),
+            row.last_viewed.unwrap_or_default().to_string(),
+            row.favorite.to_string(),
+            row.hidden.to_string(),
+        ];
+        let _ = write!(output, "{}\r\n", cells.join(","));
+    }
+    output
+}
+
+fn csv_cell(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,127 @@
+//! Matching quotes against what the user asked for.
+
+use crate::AnimeQuote;
+use crate::query::Query;
+use std::ops::Range;
+
+/// Byte ranges of the non-overlapping occurrences of `needle` in `text`,
+/// ignoring case unless `case_sensitive` is set. Comparison is per character
+/// so the ranges stay valid in `text` even when lowercasing changes lengths.
+pub(crate) fn find(text: &str, needle: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    let needle: Vec<char> = needle.chars().collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let same =
+        |a: char, b: char| a == b || !case_sensitive && a.to_lowercase().eq(b.to_lowercase());
+    let mut ranges = Vec::new();
+    let mut next_allowed = 0;
+    for (start, _) in text.char_indices() {
+        if start < next_allowed {
+            continue;
+        }
+        let mut chars = text[start..].char_indices();
+        let matched = needle
+            .iter()
+            .all(|&wanted| chars.next().is_some_and(|(_, actual)| same(actual, wanted)));
+        if matched {
+            let end = chars
+                .next()
+                .map_or(text.len(), |(offset, _)| start + offset);
+            ranges.push(start..end);
+            next_allowed = end;
+        }
+    }
+    ranges
+}
+
+/// Narrows the collection by anime, character, tag, rating, and length.
+/// Within each list any value may match (anime and character as a
+/// case-insensitive substring, tags as a whole tag ignoring case); an empty
+/// list lets every quote through. Unrated quotes fail a `min_rating`. The
+/// lengths count the characters of the translation. A `query` has to match
+/// as well.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct QuoteFilter {
+    pub(crate) anime: Vec<String>,
+    pub(crate) character: Vec<String>,
+    pub(crate) tag: Vec<String>,
+    pub(crate) min_rating: Option<u8>,
+    pub(crate) min_len: Option<usize>,
+    pub(crate) max_len: Option<usize>,
+    pub(crate) query: Option<Query>,
+}
+
+impl QuoteFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.anime.is_empty()
+            && self.character.is_empty()
+            && self.tag.is_empty()
+            && self.min_rating.is_none()
+            && self.min_len.is_none()
+            && self.max_len.is_none()
+            && self.query.is_none()
+    }
+
+    /// The filter in a few words, like `anime Naruto, rating ≥ 4`.
+    pub(crate) fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        for (name, values) in [
+            ("anime", &self.anime),
+            ("character", &self.character),
+            ("tag", &self.tag),
+        ] {
+            if !values.is_empty() {
+                parts.push(format!("{name} {}", values.join(" or ")));
+            }
+        }
+        if let Some(rating) = self.min_rating {
+            parts.push(format!("rating ≥ {rating}"));
+        }
+        match (self.min_len, self.max_len) {
+            (Some(min), Some(max)) => parts.push(format!("{min}–{max} characters")),
+            (Some(min), None) => parts.push(format!("≥ {min} characters")),
+            (None, Some(max)) => parts.push(format!("≤ {max} characters")),
+            (None, None) => {}
+        }
+        if let Some(query) = &self.query {
+            parts.push(format!("\"{}\"", query.as_str()));
+        }
+        parts.join(", ")
+    }
+
+    pub(crate) fn matches(&self, quote: &AnimeQuote) -> bool {
+        let any = |wanted: &[String], value: &str| {
+            wanted.is_empty()
+                || wanted
+                    .iter()
+                    .any(|wanted| !find(value, wanted, false).is_empty())
+        };
+        let tagged = self.tag.is_empty()
+            || self.tag.iter().any(|wanted| {
+                quote
+                    .tags
+                    .iter()
+                    .any(|tag| tag.to_lowercase() == wanted.to_lowercase())
+            });
+        let rated = self
+            .min_rating
+            .is_none_or(|min_rating| quote.rating.is_some_and(|rating| rating >= min_rating));
+        let length = quote.quote.chars().count();
+        let fits = self.min_len.is_none_or(|min_len| length >= min_len)
+            && self.max_len.is_none_or(|max_len| length <= max_len);
+        any(&self.anime, &quote.anime)
+            && any(&self.character, &quote.character)
+            && tagged
+            && rated
+            && fits
+            && self.query.as_ref().is_none_or(|query| query.matches(quote))
+    }
+
+    /// Positions of the matching quotes, in collection order.
+    pub(crate) fn positions(&self, quotes: &[AnimeQuote]) -> Vec<usize> {
+        (0..quotes.len())
+            .filter(|&position| self.matches(&quotes[position]))
+            .collect()
+    }
+}
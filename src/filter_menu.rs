@@ -0,0 +1,190 @@
+//! The filter menu: narrows the viewer to the quotes matching a
+//! [`QuoteFilter`], the same filters the commands take as flags.
+
+use crate::Palette;
+use crate::filter::QuoteFilter;
+use crate::query::Query;
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Paragraph, Wrap};
+
+const FIELDS: [&str; 7] = [
+    "Anime",
+    "Character",
+    "Tag",
+    "Min rating",
+    "Min length",
+    "Max length",
+    "Query",
+];
+/// Widths of the labels and values, so the fields line up when centered.
+const LABEL_WIDTH: usize = 12;
+const VALUE_WIDTH: usize = 24;
+
+pub(crate) enum FilterMenuAction {
+    Stay,
+    Close,
+    /// Narrow the viewer to the quotes matching this filter.
+    Apply(QuoteFilter),
+}
+
+pub(crate) struct FilterMenu {
+    /// What is typed in each of [`FIELDS`]; lists are comma-separated.
+    values: [String; 7],
+    selected: usize,
+    /// Why the filter could not be applied, until the next key.
+    message: Option<String>,
+}
+
+impl FilterMenu {
+    /// Opens with the fields set to `filter`.
+    pub(crate) fn new(filter: &QuoteFilter) -> Self {
+        let number =
+            |value: Option<usize>| value.map(|value| value.to_string()).unwrap_or_default();
+        Self {
+            values: [
+                filter.anime.join(", "),
+                filter.character.join(", "),
+                filter.tag.join(", "),
+                number(filter.min_rating.map(usize::from)),
+                number(filter.min_len),
+                number(filter.max_len),
+                filter
+                    .query
+                    .as_ref()
+                    .map(|query| query.as_str().to_string())
+                    .unwrap_or_default(),
+            ],
+            selected: 0,
+            message: None,
+        }
+    }
+
+    pub(crate) fn report(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode) -> FilterMenuAction {
+        self.message = None;
+        match code {
+            KeyCode::Up | KeyCode::BackTab => {
+                self.selected = (self.selected + FIELDS.len() - 1) % FIELDS.len();
+            }
+            KeyCode::Down | KeyCode::Tab => self.selected = (self.selected + 1) % FIELDS.len(),
+            KeyCode::Backspace => {
+                self.values[self.selected].pop();
+            }
+            KeyCode::Delete => self.values[self.selected].clear(),
+            KeyCode::Char(ch) => self.values[self.selected].push(ch),
+            KeyCode::Enter => match self.filter() {
+                Ok(filter) => return FilterMenuAction::Apply(filter),
+                Err(message) => self.message = Some(message),
+            },
+            KeyCode::Esc => return FilterMenuAction::Close,
+            _ => {}
+        }
+        FilterMenuAction::Stay
+    }
+
+    fn filter(&self) -> Result<QuoteFilter, String> {
+        let list = |value: &str| -> Vec<String> {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let number = |field: usize| -> Result<Option<usize>, String> {
+            let value = self.values[field].trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("{} must be a whole number", FIELDS[field]))
+        };
+        let min_rating = number(3)?
+            .map(|rating| u8::try_from(rating).map_err(|_| "Min rating is too large".to_string()))
+            .transpose()?;
+        let query = match self.values[6].trim() {
+            "" => None,
+            query => Some(Query::parse(query).map_err(|error| format!("Query: {error}"))?),
+        };
+        Ok(QuoteFilter {
+            anime: list(&self.values[0]),
+            character: list(&self.values[1]),
+            tag: list(&self.values[2]),
+            min_rating,
+            min_len: number(4)?,
+            max_len: number(5)?,
+            query,
+        })
+    }
+
+    pub(crate) fn render(&self, frame: &mut Frame, area: Rect, palette: &Palette) {
+        let [title_area, fields_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Filter",
+                Style::default().fg(palette.anime).bold(),
+            )))
+            .centered(),
+            title_area,
+        );
+
+        let count_style = Style::default().fg(palette.count);
+        let mut lines: Vec<Line> = FIELDS
+            .iter()
+            .zip(&self.values)
+            .enumerate()
+            .map(|(field, (label, value))| {
+                let selected = field == self.selected;
+                let label_style = if selected {
+                    Style::default().fg(palette.character).bold()
+                } else {
+                    count_style
+                };
+                let padding = VALUE_WIDTH.saturating_sub(value.chars().count());
+                Line::from(vec![
+                    Span::styled(format!("{label:>LABEL_WIDTH$}  "), label_style),
+                    Span::styled(value.clone(), Style::default().fg(palette.quote)),
+                    Span::raw(if selected { "▏" } else { " " }),
+                    Span::raw(" ".repeat(padding)),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Separate anime, characters, or tags with commas; lengths count the translation",
+            count_style,
+        )));
+        frame.render_widget(
+            Paragraph::new(Text::from(lines))
+                .centered()
+                .wrap(Wrap { trim: false }),
+            fields_area,
+        );
+
+        let help = match &self.message {
+            Some(message) => Line::from(Span::styled(
+                message.clone(),
+                Style::default().fg(palette.character).bold(),
+            )),
+            None => Line::from(Span::styled(
+                "Field <Up/Down>  Clear field <Delete>  Apply <Enter>  Cancel <Esc>",
+                Style::default().fg(palette.instructions),
+            )),
+        };
+        frame.render_widget(Paragraph::new(help).centered(), help_area);
+    }
+}
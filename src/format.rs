@@ -0,0 +1,171 @@
+//! `fmt`: rewrites data files in one canonical style, so hand edits and
+//! tool edits alike leave clean diffs.
+//!
+//! Quotes get their keys in a fixed order (the ID, then the lines and who
+//! said them, then artwork, placement in the show, scheduling, and the
+//! reader's own tags, rating, notes, and date added, as listed in
+//! `QUOTE_KEYS`, with unknown keys last in alphabetical order), every
+//! string is quoted the way `toml_edit` writes new ones, and spacing is
+//! `key = value` with one blank line before each table. Comments stay with
+//! the key or table they were written above.
+
+use color_eyre::eyre::{Result, WrapErr};
+use toml_edit::{Decor, DocumentMut, Item, Table, Value};
+
+/// The known quote keys in the order they are written.
+const QUOTE_KEYS: &[&str] = &[
+    "id",
+    "japanese",
+    "anime",
+    "romaji",
+    "character",
+    "quote",
+    "pitch",
+    "furigana",
+    "image",
+    "image_width",
+    "image_height",
+    "crop",
+    "image_sha256",
+    "url",
+    "episode",
+    "timestamp",
+    "video",
+    "season",
+    "year",
+    "source",
+    "source_url",
+    "show_on",
+    "show_season",
+    "birthday",
+    "tags",
+    "rating",
+    "notes",
+    "added",
+];
+
+/// `source`, a data file, in the canonical style.
+pub(crate) fn format(source: &str) -> Result<String> {
+    let mut document: DocumentMut = source.parse().wrap_err("not valid TOML")?;
+    tidy_table(document.as_table_mut(), false);
+    let trailing = comment_lines(document.trailing().as_str().unwrap_or_default());
+    document.set_trailing(trailing);
+    let formatted = document.to_string();
+    Ok(format!("{}\n", formatted.trim_matches('\n')))
+}
+
+fn tidy_table(table: &mut Table, is_quote: bool) {
+    if is_quote {
+        table.sort_values_by(|a, _, b, _| key_rank(a).cmp(&key_rank(b)));
+    }
+    for (mut key, item) in table.iter_mut() {
+        match item {
+            Item::Value(value) => {
+                let decor = key.leaf_decor_mut();
+                let comments = comment_lines(prefix(decor));
+                decor.set_prefix(comments);
+                decor.set_suffix(" ");
+                tidy_value(value);
+                let comment = trailing_comment(value.decor());
+                value.decor_mut().clear();
+                value.decor_mut().set_prefix(" ");
+                value.decor_mut().set_suffix(comment);
+            }
+            // `a.b = 1` is kept as written.
+            Item::Table(table) if table.is_dotted() => {}
+            Item::Table(table) => {
+                key.leaf_decor_mut().clear();
+                tidy_header(table.decor_mut());
+                tidy_table(table, false);
+            }
+            Item::ArrayOfTables(tables) => {
+                key.leaf_decor_mut().clear();
+                let quotes = key.get() == "quotes";
+                for table in tables.iter_mut() {
+                    tidy_header(table.decor_mut());
+                    tidy_table(table, quotes);
+                }
+            }
+            Item::None => {}
+        }
+    }
+}
+
+/// Known quote keys first, in their order, then the rest alphabetically.
+fn key_rank(key: &toml_edit::Key) -> (usize, String) {
+    let name = key.get();
+    let rank = QUOTE_KEYS
+        .iter()
+        .position(|known| *known == name)
+        .unwrap_or(QUOTE_KEYS.len());
+    (rank, name.to_string())
+}
+
+/// A blank line, then the comments above the header.
+fn tidy_header(decor: &mut Decor) {
+    let comments = comment_lines(prefix(decor));
+    let comment = trailing_comment(decor);
+    decor.set_prefix(format!("\n{comments}"));
+    decor.set_suffix(comment);
+}
+
+/// Requotes strings, and lays arrays and inline tables out on one line
+/// unless an array holds comments, which would be lost.
+fn tidy_value(value: &mut Value) {
+    match value {
+        Value::String(text) => {
+            let decor = text.decor().clone();
+            *text = toml_edit::Formatted::new(text.value().clone());
+            *text.decor_mut() = decor;
+        }
+        Value::Array(array) => {
+            let commented = array.iter().any(|element| {
+                prefix(element.decor()).contains('#')
+                    || !trailing_comment(element.decor()).is_empty()
+            }) || array.trailing().as_str().unwrap_or_default().contains('#');
+            if !commented {
+                array.iter_mut().for_each(tidy_value);
+                array.fmt();
+            }
+        }
+        Value::InlineTable(table) => {
+            for (_, value) in table.iter_mut() {
+                tidy_value(value);
+            }
+            table.fmt();
+        }
+        _ => {}
+    }
+}
+
+fn prefix(decor: &Decor) -> &str {
+    decor
+        .prefix()
+        .and_then(|prefix| prefix.as_str())
+        .unwrap_or_default()
+}
+
+/// The comment lines of `raw`, each on a line of its own without
+/// indentation, and without the blank lines around them.
+fn comment_lines(raw: &str) -> String {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+/// The comment after a value or header on its line, as ` # comment`, or
+/// nothing.
+fn trailing_comment(decor: &Decor) -> String {
+    let suffix = decor
+        .suffix()
+        .and_then(|suffix| suffix.as_str())
+        .unwrap_or_default()
+        .trim();
+    if suffix.starts_with('#') {
+        format!(" {suffix}")
+    } else {
+        String::new()
+    }
+}
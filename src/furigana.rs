@@ -0,0 +1,37 @@
+//! Readings for the Japanese line, from a quote's `furigana`: the line in
+//! Anki's format, with each kanji word followed by its reading in brackets,
+//! like `戦[たたか]わなければ 勝[か]てない`.
+//!
+//! A reading replaces the run of kanji just before it. A space in front of
+//! a word only separates it, as in Anki, and is left out.
+
+/// The line with every bracketed reading in place of its kanji, so it reads
+/// in kana alone.
+pub(crate) fn kana(furigana: &str) -> String {
+    let mut output = String::new();
+    let mut rest = furigana;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find(']').map(|close| open + close) else {
+            break;
+        };
+        let before = &rest[..open];
+        let base_start = before
+            .char_indices()
+            .rev()
+            .take_while(|&(_, ch)| is_kanji(ch))
+            .last()
+            .map_or(open, |(index, _)| index);
+        let kept = &before[..base_start];
+        output.push_str(kept.strip_suffix(' ').unwrap_or(kept));
+        output.push_str(&rest[open + 1..close]);
+        rest = &rest[close + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Kanji, and the marks written among them: 々 repeats the one before and
+/// ヶ reads as "ka" or "ga".
+fn is_kanji(ch: char) -> bool {
+    matches!(ch, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' | '々' | 'ヶ' | 'ヵ')
+}
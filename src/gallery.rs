@@ -0,0 +1,259 @@
+//! The galleries: one card per anime with its cover art, or per character
+//! with their portrait, to pick by sight and narrow the viewer to those
+//! quotes. `Tab` switches between the two.
+//!
+//! Covers and portraits are image files in the `covers` and `portraits`
+//! folders of the data directory, named after the anime or character
+//! (`attack-on-titan.jpg`, `eren-yeager.png`); an entry without one shows
+//! the artwork of its first quote that has an image.
+
+use crate::artwork::ImageSlot;
+use crate::export::markdown_anchor;
+use crate::filter::QuoteFilter;
+use crate::grid::{Grid, GridAction, fit};
+use crate::output::truncate_width;
+use crate::query::Query;
+use crate::{AnimeQuote, Palette, data_dir, open_oriented};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui_image::Resize;
+use ratatui_image::picker::Picker;
+
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+pub(crate) enum GalleryAction {
+    Stay,
+    Close,
+    /// Narrow the viewer to the quotes of the chosen entry.
+    Filter(QuoteFilter),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Anime,
+    Character,
+}
+
+impl Kind {
+    fn title(self) -> &'static str {
+        match self {
+            Self::Anime => "Anime gallery",
+            Self::Character => "Character gallery",
+        }
+    }
+
+    /// The folder of the data directory its images are read from.
+    fn folder(self) -> &'static str {
+        match self {
+            Self::Anime => "covers",
+            Self::Character => "portraits",
+        }
+    }
+
+    /// The `--query` field that picks an entry's quotes.
+    fn field(self) -> &'static str {
+        match self {
+            Self::Anime => "anime",
+            Self::Character => "character",
+        }
+    }
+
+    fn key(self, quote: &AnimeQuote) -> &str {
+        match self {
+            Self::Anime => &quote.anime,
+            Self::Character => &quote.character,
+        }
+    }
+}
+
+struct Entry {
+    name: String,
+    /// For a character, the anime of their first quote.
+    anime: Option<String>,
+    count: usize,
+    /// The first of its quotes with an image, or else its first quote.
+    quote: usize,
+    /// Its cover or portrait, and how many cells wide and tall the image is.
+    image: Option<(ImageSlot, (u16, u16))>,
+}
+
+pub(crate) struct Gallery {
+    kind: Kind,
+    grid: Grid,
+    entries: Vec<Entry>,
+    picker: Picker,
+}
+
+impl Gallery {
+    /// The anime gallery, with the cursor on the anime of the quote at
+    /// `current`.
+    pub(crate) fn new(quotes: &[AnimeQuote], current: usize, picker: &Picker) -> Self {
+        let kind = Kind::Anime;
+        let entries = entries(kind, quotes, picker);
+        let selected = selected(kind, &entries, quotes.get(current));
+        Self {
+            kind,
+            grid: Grid::new(selected).with_help("Anime/Characters <Tab>"),
+            entries,
+            picker: picker.clone(),
+        }
+    }
+
+    pub(crate) fn handle_key(
+        &mut self,
+        code: KeyCode,
+        quotes: &[AnimeQuote],
+        current: usize,
+    ) -> GalleryAction {
+        match code {
+            KeyCode::Char('G') => return GalleryAction::Close,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.kind = match self.kind {
+                    Kind::Anime => Kind::Character,
+                    Kind::Character => Kind::Anime,
+                };
+                self.entries = entries(self.kind, quotes, &self.picker);
+                self.grid
+                    .select(selected(self.kind, &self.entries, quotes.get(current)));
+                return GalleryAction::Stay;
+            }
+            _ => {}
+        }
+        match self.grid.handle_key(code, self.entries.len()) {
+            GridAction::Stay => GalleryAction::Stay,
+            GridAction::Close => GalleryAction::Close,
+            GridAction::Show(position) => {
+                match Query::equals(self.kind.field(), &self.entries[position].name) {
+                    Ok(query) => GalleryAction::Filter(QuoteFilter {
+                        query: Some(query),
+                        ..QuoteFilter::default()
+                    }),
+                    Err(_) => GalleryAction::Stay,
+                }
+            }
+        }
+    }
+
+    /// Draws the gallery's page and cards, and returns where the artwork of
+    /// the cards without an image of their own goes with the quote to take
+    /// it from.
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        resize: &Resize,
+        palette: &Palette,
+    ) -> Vec<(usize, Rect)> {
+        let cards = self
+            .grid
+            .render(frame, area, self.kind.title(), self.entries.len(), palette);
+        let mut artwork = Vec::new();
+        for (position, card_area) in cards {
+            let entry = &self.entries[position];
+            let image_area = self
+                .grid
+                .render_card(frame, card_area, position, palette, |width| {
+                    let count = match entry.count {
+                        1 => "1 quote".to_string(),
+                        count => format!("{count} quotes"),
+                    };
+                    let mut lines = vec![Line::from(Span::styled(
+                        truncate_width(&entry.name, Some(width)),
+                        Style::default().fg(palette.character).bold(),
+                    ))];
+                    if let Some(anime) = &entry.anime {
+                        lines.push(Line::from(Span::styled(
+                            truncate_width(anime, Some(width)),
+                            Style::default().fg(palette.anime),
+                        )));
+                    }
+                    lines.push(Line::from(Span::styled(
+                        count,
+                        Style::default().fg(palette.count),
+                    )));
+                    lines
+                });
+            if image_area.height == 0 {
+                continue;
+            }
+            match &mut self.entries[position].image {
+                Some((slot, cells)) => {
+                    slot.draw(frame, fit(image_area, *cells), &self.picker, resize);
+                }
+                None => artwork.push((self.entries[position].quote, image_area)),
+            }
+        }
+        artwork
+    }
+}
+
+/// One entry per anime or character in `quotes`, A→Z.
+fn entries(kind: Kind, quotes: &[AnimeQuote], picker: &Picker) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for (index, quote) in quotes.iter().enumerate() {
+        match entries
+            .iter_mut()
+            .find(|entry| entry.name == kind.key(quote))
+        {
+            Some(entry) => {
+                entry.count += 1;
+                if quotes[entry.quote].image.is_none() && quote.image.is_some() {
+                    entry.quote = index;
+                }
+            }
+            None => entries.push(Entry {
+                name: kind.key(quote).to_string(),
+                anime: (kind == Kind::Character).then(|| quote.anime.clone()),
+                count: 1,
+                quote: index,
+                image: None,
+            }),
+        }
+    }
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+    for entry in &mut entries {
+        entry.image = load_image(kind, &entry.name, picker);
+    }
+    entries
+}
+
+/// Where the entry of `quote` is, or the first one.
+fn selected(kind: Kind, entries: &[Entry], quote: Option<&AnimeQuote>) -> usize {
+    quote
+        .and_then(|quote| {
+            entries
+                .iter()
+                .position(|entry| entry.name == kind.key(quote))
+        })
+        .unwrap_or(0)
+}
+
+/// The portrait of the character `name`, if there is one that loads, and how
+/// many cells wide and tall it is.
+pub(crate) fn portrait(name: &str, picker: &Picker) -> Option<(ImageSlot, (u16, u16))> {
+    load_image(Kind::Character, name, picker)
+}
+
+/// The cover or portrait for `name` from its folder, if there is one that
+/// loads.
+fn load_image(kind: Kind, name: &str, picker: &Picker) -> Option<(ImageSlot, (u16, u16))> {
+    let dir = data_dir().join(kind.folder());
+    let slug = markdown_anchor(name);
+    let path = IMAGE_EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("{slug}.{extension}")))
+        .find(|path| path.is_file())?;
+    let image = match open_oriented(&path.to_string_lossy()) {
+        Ok(image) => image,
+        Err(error) => {
+            eprintln!("failed to load {}: {error}", path.display());
+            return None;
+        }
+    };
+    let slot = ImageSlot::new(image);
+    let cells = slot.cells(picker);
+    Some((slot, cells))
+}
@@ -0,0 +1,161 @@
+//! Choosing how images are drawn in the terminal the viewer runs in.
+//!
+//! `ratatui-image` asks the terminal which graphics protocols it supports,
+//! but some terminals can't answer or answer wrong; for those the viewer
+//! falls back to halfblocks, which every terminal with colors can show.
+//!
+//! Over SSH and inside tmux, `ratatui-image` also guesses kitty or iTerm2
+//! from environment variables that may describe some other terminal, so
+//! there only a protocol the terminal itself reported is used.
+//!
+//! Inside tmux, images reach the outer terminal only when the pane allows
+//! passthrough, so the viewer turns `allow-passthrough` on before asking and
+//! has the escape sequences wrapped for tmux.
+
+use ratatui_image::picker::{Capability, Picker, ProtocolType};
+use std::process::{Command, Stdio};
+
+/// The font size assumed when the terminal doesn't report one.
+const FALLBACK_FONT_SIZE: (u16, u16) = (10, 20);
+
+/// The size in pixels of the terminal window, as the viewer scales images
+/// for it: what the terminal reports, or else its size in cells at the
+/// fallback font size.
+pub(crate) fn window_pixels() -> (u32, u32) {
+    if let Ok(size) = crossterm::terminal::window_size()
+        && size.width > 0
+        && size.height > 0
+    {
+        return (u32::from(size.width), u32::from(size.height));
+    }
+    let (columns, rows) = crossterm::terminal::size().unwrap_or((200, 60));
+    (
+        u32::from(columns) * u32::from(FALLBACK_FONT_SIZE.0),
+        u32::from(rows) * u32::from(FALLBACK_FONT_SIZE.1),
+    )
+}
+
+/// `[ui] image_protocol`, by name.
+pub(crate) fn parse_protocol(name: &str) -> Option<ProtocolType> {
+    match name.trim().to_lowercase().as_str() {
+        "kitty" => Some(ProtocolType::Kitty),
+        "sixel" => Some(ProtocolType::Sixel),
+        "iterm2" => Some(ProtocolType::Iterm2),
+        "halfblocks" => Some(ProtocolType::Halfblocks),
+        _ => None,
+    }
+}
+
+/// The picker for this terminal, drawing with `forced` when it is set and
+/// otherwise with halfblocks when the terminal can't be asked or its answer
+/// can't be used.
+pub(crate) fn picker(forced: Option<ProtocolType>) -> Picker {
+    if windows_console() {
+        let mut picker = Picker::from_fontsize(FALLBACK_FONT_SIZE);
+        match forced {
+            Some(protocol) => picker.set_protocol_type(protocol),
+            None => eprintln!(
+                "the Windows console host has no image protocol, drawing images as halfblocks"
+            ),
+        }
+        return picker;
+    }
+    if remote_or_multiplexed() == Some("tmux") {
+        enable_tmux_passthrough();
+    }
+    let mut picker = match Picker::from_query_stdio() {
+        Ok(picker) => picker,
+        Err(error) => {
+            eprintln!("failed to detect terminal graphics capabilities: {error}");
+            Picker::from_fontsize(FALLBACK_FONT_SIZE)
+        }
+    };
+    if let Some(protocol) = forced {
+        picker.set_protocol_type(protocol);
+    } else if let Some(session) = remote_or_multiplexed() {
+        let protocol = picker.protocol_type();
+        if !reported(&picker, protocol) {
+            let fallback = if reported(&picker, ProtocolType::Sixel) {
+                ProtocolType::Sixel
+            } else {
+                ProtocolType::Halfblocks
+            };
+            eprintln!(
+                "{session} detected and the terminal did not report {protocol:?} support, \
+                 drawing images with {fallback:?} instead (set [ui] image_protocol to override)"
+            );
+            picker.set_protocol_type(fallback);
+        }
+    }
+    picker
+}
+
+/// Turns on `allow-passthrough` for this pane, and makes sure
+/// `ratatui-image`, which looks for tmux in `TERM` and `TERM_PROGRAM`, wraps
+/// its escape sequences for it.
+fn enable_tmux_passthrough() {
+    let tmux = |args: &[&str]| {
+        Command::new("tmux")
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    tmux(&["set", "-p", "allow-passthrough", "on"]);
+    match tmux(&["show", "-pv", "allow-passthrough"]).as_deref() {
+        Some("on" | "all") => {}
+        _ => {
+            eprintln!(
+                "tmux did not turn on allow-passthrough (it needs tmux 3.3 or later), \
+                 so images can only use what tmux itself draws; \
+                 add `set -g allow-passthrough on` to tmux.conf to allow it"
+            );
+            return;
+        }
+    }
+    let named = std::env::var("TERM").is_ok_and(|term| term.starts_with("tmux"))
+        || std::env::var("TERM_PROGRAM").is_ok_and(|program| program == "tmux");
+    if !named {
+        // SAFETY: this runs while the viewer starts, before it spawns any
+        // thread that could read the environment at the same time.
+        unsafe { std::env::set_var("TERM_PROGRAM", "tmux") };
+    }
+}
+
+/// "SSH" or "tmux" when the viewer runs over SSH or inside tmux.
+fn remote_or_multiplexed() -> Option<&'static str> {
+    let set = |name| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    if set("TMUX") {
+        Some("tmux")
+    } else if set("SSH_CONNECTION") || set("SSH_TTY") {
+        Some("SSH")
+    } else {
+        None
+    }
+}
+
+/// Whether the terminal answered the capability query with support for
+/// `protocol`. Halfblocks need none; iTerm2 is never reported, only guessed.
+fn reported(picker: &Picker, protocol: ProtocolType) -> bool {
+    let capability = match protocol {
+        ProtocolType::Halfblocks => return true,
+        ProtocolType::Iterm2 => return false,
+        ProtocolType::Kitty => Capability::Kitty,
+        ProtocolType::Sixel => Capability::Sixel,
+    };
+    picker.capabilities().contains(&capability)
+}
+
+/// Whether this is the classic Windows console host rather than Windows
+/// Terminal (which sets `WT_SESSION`) or another terminal emulator (which
+/// set `TERM_PROGRAM` or `TERM`). The console host doesn't answer the
+/// queries, so asking would only wait for them to time out.
+fn windows_console() -> bool {
+    cfg!(windows)
+        && ["WT_SESSION", "TERM_PROGRAM", "TERM"]
+            .iter()
+            .all(|name| std::env::var_os(name).is_none())
+}
@@ -0,0 +1,1306 @@
+//! GraphQL for `serve`: quotes, anime, and characters with nested relations
+//! and filtering arguments. Like the rest of the server it is hand-written
+//! and small: it covers queries, variables, aliases, fragments, and
+//! `@include`/`@skip`, but not mutations, subscriptions, or introspection
+//! (the schema is served as SDL at `/graphql/schema` for tooling instead).
+
+use crate::AnimeQuote;
+use crate::filter::{self, QuoteFilter};
+use crate::query::Query;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{Map, Value as Json, json};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// The schema in SDL, served at `/graphql/schema`.
+pub(crate) const SCHEMA: &str = r#"type Query {
+  # Quotes in collection order. `anime` and `character` match titles and
+  # names containing the text, `tag` a whole tag, all ignoring case; `search`
+  # looks through the Japanese, romaji, translation, character, and anime;
+  # `query` takes the filter language of `--query`.
+  quotes(anime: String, character: String, tag: String, minRating: Int, search: String, query: String, first: Int, offset: Int): [Quote!]!
+  # By stable ID, or by the 1-based number in the viewer counter.
+  quote(id: ID!): Quote
+  random(anime: String, character: String, tag: String, minRating: Int, search: String, query: String): Quote
+  # By exact title, ignoring case.
+  anime(title: String!): Anime
+  animeList(search: String, first: Int, offset: Int): [Anime!]!
+  # By exact name, ignoring case; `anime` picks between namesakes.
+  character(name: String!, anime: String): Character
+  characters(search: String, anime: String, first: Int, offset: Int): [Character!]!
+}
+
+type Quote {
+  id: ID!
+  # 1-based position in the collection.
+  index: Int!
+  japanese: String!
+  romaji: String
+  quote: String!
+  anime: Anime!
+  character: Character!
+  image: String
+  # Path of the artwork on this server.
+  imageUrl: String
+  url: String
+  episode: Int
+  season: Int
+  year: Int
+  source: String
+  sourceUrl: String
+  tags: [String!]!
+  rating: Int
+}
+
+type Anime {
+  title: String!
+  quoteCount: Int!
+  quotes(character: String, tag: String, minRating: Int, search: String, query: String, first: Int, offset: Int): [Quote!]!
+  characters: [Character!]!
+}
+
+type Character {
+  name: String!
+  birthday: String
+  anime: Anime!
+  quoteCount: Int!
+  quotes(tag: String, minRating: Int, search: String, query: String, first: Int, offset: Int): [Quote!]!
+}
+"#;
+
+const TYPES: &[&str] = &["Query", "Quote", "Anime", "Character"];
+
+/// How deeply selections, list and object values, and types may nest in a
+/// document. Real queries stay far below it; the parser recurses, so a
+/// document nested thousands deep would otherwise overflow the stack.
+const MAX_DEPTH: usize = 32;
+/// How many objects one request may resolve. Relations lead back to lists
+/// of quotes, so a few nested levels can multiply into millions.
+const MAX_OBJECTS: usize = 100_000;
+
+/// The body of a GraphQL request, as POSTed by GraphQL clients.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GraphqlRequest {
+    pub(crate) query: String,
+    #[serde(default)]
+    pub(crate) operation_name: Option<String>,
+    #[serde(default)]
+    pub(crate) variables: Option<Map<String, Json>>,
+}
+
+impl GraphqlRequest {
+    /// Runs the request, returning `{"data": …}` or `{"errors": […]}` and
+    /// the number of quotes in the response.
+    pub(crate) fn execute(&self, quotes: &[AnimeQuote]) -> (Json, usize) {
+        let served = Cell::new(0);
+        match self.run(quotes, &served) {
+            Ok(data) => (json!({ "data": data }), served.get()),
+            Err(message) => (json!({ "errors": [{ "message": message }] }), 0),
+        }
+    }
+
+    fn run(&self, quotes: &[AnimeQuote], served: &Cell<usize>) -> Result<Json, String> {
+        let document = Parser::new(tokenize(&self.query)?).document()?;
+        let operation = match (&self.operation_name, document.operations.as_slice()) {
+            (Some(name), operations) => operations
+                .iter()
+                .find(|operation| operation.name.as_deref() == Some(name))
+                .ok_or_else(|| format!("Unknown operation named \"{name}\""))?,
+            (None, [operation]) => operation,
+            (None, []) => return Err("The document contains no operation".to_string()),
+            (None, _) => {
+                return Err(
+                    "operationName is required for documents with several operations".to_string(),
+                );
+            }
+        };
+        if operation.kind != "query" {
+            return Err(format!("{} operations are not supported", operation.kind));
+        }
+
+        let provided = self.variables.clone().unwrap_or_default();
+        let mut variables = Map::new();
+        for definition in &operation.variables {
+            let value = match (provided.get(&definition.name), &definition.default) {
+                (Some(value), _) => value.clone(),
+                (None, Some(default)) => default.to_json(&Map::new())?,
+                (None, None) if definition.required => {
+                    return Err(format!(
+                        "Variable \"${}\" of required type was not provided",
+                        definition.name
+                    ));
+                }
+                (None, None) => Json::Null,
+            };
+            variables.insert(definition.name.clone(), value);
+        }
+
+        let executor = Executor {
+            quotes,
+            fragments: &document.fragments,
+            variables,
+            served,
+            objects: Cell::new(0),
+        };
+        let selection: Vec<&Selection> = operation.selection.iter().collect();
+        executor.select(Object::Query, &selection, 0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Punct(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        match ch {
+            _ if ch.is_whitespace() || ch == ',' || ch == '\u{feff}' => index += 1,
+            '#' => {
+                while index < chars.len() && chars[index] != '\n' {
+                    index += 1;
+                }
+            }
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' | '$' | '!' | '=' | '@' | '|' | '&' => {
+                tokens.push(Token::Punct(ch));
+                index += 1;
+            }
+            '.' if chars[index..].starts_with(&['.', '.', '.']) => {
+                tokens.push(Token::Spread);
+                index += 3;
+            }
+            '"' => {
+                let (string, next) = lex_string(&chars, index)?;
+                tokens.push(Token::Str(string));
+                index = next;
+            }
+            '-' | '0'..='9' => {
+                let start = index;
+                index += 1;
+                let mut float = false;
+                while let Some(&next) = chars.get(index) {
+                    match next {
+                        '0'..='9' => {}
+                        '.' | 'e' | 'E' => float = true,
+                        '+' | '-' if matches!(chars[index - 1], 'e' | 'E') => {}
+                        _ => break,
+                    }
+                    index += 1;
+                }
+                let text: String = chars[start..index].iter().collect();
+                let token = if float {
+                    text.parse().map(Token::Float).ok()
+                } else {
+                    text.parse().map(Token::Int).ok()
+                };
+                tokens.push(token.ok_or_else(|| format!("Syntax Error: invalid number {text}"))?);
+            }
+            _ if ch == '_' || ch.is_ascii_alphabetic() => {
+                let start = index;
+                while chars
+                    .get(index)
+                    .is_some_and(|next| *next == '_' || next.is_ascii_alphanumeric())
+                {
+                    index += 1;
+                }
+                tokens.push(Token::Name(chars[start..index].iter().collect()));
+            }
+            _ => return Err(format!("Syntax Error: unexpected character {ch:?}")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Reads the string starting at `chars[start]`, a `"` or `"""`, returning it
+/// and the index just past its end. Block strings lose their common
+/// indentation and surrounding blank lines, as in the spec.
+fn lex_string(chars: &[char], start: usize) -> Result<(String, usize), String> {
+    if chars[start..].starts_with(&['"', '"', '"']) {
+        let mut index = start + 3;
+        let mut raw = String::new();
+        loop {
+            match chars.get(index..index + 3) {
+                Some(['"', '"', '"']) => break,
+                Some(['\\', '"', '"']) if chars.get(index + 3) == Some(&'"') => {
+                    raw.push_str("\"\"\"");
+                    index += 4;
+                }
+                _ => {
+                    let ch = chars
+                        .get(index)
+                        .ok_or("Syntax Error: unterminated block string")?;
+                    raw.push(*ch);
+                    index += 1;
+                }
+            }
+        }
+        let lines: Vec<&str> = raw.lines().collect();
+        let indent = lines
+            .iter()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines: Vec<&str> = lines
+            .iter()
+            .enumerate()
+            .map(|(number, line)| {
+                if number == 0 {
+                    line
+                } else {
+                    line.get(indent..).unwrap_or("")
+                }
+            })
+            .collect();
+        let text = lines.join("\n");
+        return Ok((text.trim_matches('\n').to_string(), index + 3));
+    }
+
+    let mut index = start + 1;
+    let mut text = String::new();
+    loop {
+        let ch = *chars
+            .get(index)
+            .ok_or("Syntax Error: unterminated string")?;
+        index += 1;
+        match ch {
+            '"' => return Ok((text, index)),
+            '\n' => return Err("Syntax Error: unterminated string".to_string()),
+            '\\' => {
+                let escape = *chars
+                    .get(index)
+                    .ok_or("Syntax Error: unterminated string")?;
+                index += 1;
+                match escape {
+                    '"' | '\\' | '/' => text.push(escape),
+                    'b' => text.push('\u{8}'),
+                    'f' => text.push('\u{c}'),
+                    'n' => text.push('\n'),
+                    'r' => text.push('\r'),
+                    't' => text.push('\t'),
+                    'u' => {
+                        let hex: String =
+                            chars.get(index..index + 4).unwrap_or(&[]).iter().collect();
+                        let decoded = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                        text.push(
+                            decoded
+                                .ok_or_else(|| format!("Syntax Error: invalid escape \\u{hex}"))?,
+                        );
+                        index += 4;
+                    }
+                    _ => return Err(format!("Syntax Error: invalid escape \\{escape}")),
+                }
+            }
+            _ => text.push(ch),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Enum(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+    Variable(String),
+}
+
+impl Value {
+    fn to_json(&self, variables: &Map<String, Json>) -> Result<Json, String> {
+        Ok(match self {
+            Self::Null => Json::Null,
+            Self::Int(value) => json!(value),
+            Self::Float(value) => json!(value),
+            Self::String(value) | Self::Enum(value) => json!(value),
+            Self::Boolean(value) => json!(value),
+            Self::List(values) => Json::Array(
+                values
+                    .iter()
+                    .map(|value| value.to_json(variables))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::Object(fields) => Json::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), value.to_json(variables)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            Self::Variable(name) => variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Variable \"${name}\" is not defined"))?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Directive {
+    name: String,
+    arguments: Vec<(String, Value)>,
+}
+
+#[derive(Debug)]
+struct Field {
+    alias: Option<String>,
+    name: String,
+    arguments: Vec<(String, Value)>,
+    directives: Vec<Directive>,
+    selection: Vec<Selection>,
+}
+
+#[derive(Debug)]
+enum Selection {
+    Field(Field),
+    Spread {
+        name: String,
+        directives: Vec<Directive>,
+    },
+    Inline {
+        type_condition: Option<String>,
+        directives: Vec<Directive>,
+        selection: Vec<Selection>,
+    },
+}
+
+#[derive(Debug)]
+struct VariableDefinition {
+    name: String,
+    required: bool,
+    default: Option<Value>,
+}
+
+#[derive(Debug)]
+struct Operation {
+    /// `query`, `mutation`, or `subscription`.
+    kind: String,
+    name: Option<String>,
+    variables: Vec<VariableDefinition>,
+    selection: Vec<Selection>,
+}
+
+#[derive(Debug)]
+struct Fragment {
+    type_condition: String,
+    selection: Vec<Selection>,
+}
+
+#[derive(Debug)]
+struct Document {
+    operations: Vec<Operation>,
+    fragments: HashMap<String, Fragment>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    /// Runs `parse` one level deeper, refusing to go past [`MAX_DEPTH`].
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!(
+                "Syntax Error: the document is nested more than {MAX_DEPTH} levels deep"
+            ));
+        }
+        self.depth += 1;
+        let parsed = parse(self);
+        self.depth -= 1;
+        parsed
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or("Syntax Error: unexpected end of document")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn at(&self, punct: char) -> bool {
+        self.peek() == Some(&Token::Punct(punct))
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        let found = self.at(punct);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), String> {
+        match self.next()? {
+            Token::Punct(found) if found == punct => Ok(()),
+            token => Err(format!(
+                "Syntax Error: expected \"{punct}\", found {}",
+                describe(&token)
+            )),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Name(name) => Ok(name),
+            token => Err(format!(
+                "Syntax Error: expected a name, found {}",
+                describe(&token)
+            )),
+        }
+    }
+
+    fn document(&mut self) -> Result<Document, String> {
+        let mut document = Document {
+            operations: Vec::new(),
+            fragments: HashMap::new(),
+        };
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Punct('{') => document.operations.push(Operation {
+                    kind: "query".to_string(),
+                    name: None,
+                    variables: Vec::new(),
+                    selection: self.selection_set()?,
+                }),
+                Token::Name(ref keyword) if keyword == "fragment" => {
+                    self.position += 1;
+                    let name = self.name()?;
+                    if self.name()? != "on" {
+                        return Err(format!(
+                            "Syntax Error: expected \"on\" after fragment {name}"
+                        ));
+                    }
+                    let type_condition = self.name()?;
+                    self.directives()?;
+                    let selection = self.selection_set()?;
+                    document.fragments.insert(
+                        name,
+                        Fragment {
+                            type_condition,
+                            selection,
+                        },
+                    );
+                }
+                Token::Name(_) => {
+                    let kind = self.name()?;
+                    if !matches!(kind.as_str(), "query" | "mutation" | "subscription") {
+                        return Err(format!("Syntax Error: unexpected {kind:?}"));
+                    }
+                    let name = match self.peek() {
+                        Some(Token::Name(_)) => Some(self.name()?),
+                        _ => None,
+                    };
+                    let variables = self.variable_definitions()?;
+                    self.directives()?;
+                    let selection = self.selection_set()?;
+                    document.operations.push(Operation {
+                        kind,
+                        name,
+                        variables,
+                        selection,
+                    });
+                }
+                token => return Err(format!("Syntax Error: unexpected {}", describe(&token))),
+            }
+        }
+        Ok(document)
+    }
+
+    fn variable_definitions(&mut self) -> Result<Vec<VariableDefinition>, String> {
+        let mut definitions = Vec::new();
+        if !self.eat('(') {
+            return Ok(definitions);
+        }
+        while !self.eat(')') {
+            self.expect('$')?;
+            let name = self.name()?;
+            self.expect(':')?;
+            let required = self.type_reference()?;
+            let default = if self.eat('=') {
+                Some(self.value(true)?)
+            } else {
+                None
+            };
+            self.directives()?;
+            definitions.push(VariableDefinition {
+                name,
+                required,
+                default,
+            });
+        }
+        Ok(definitions)
+    }
+
+    /// Skips a type like `[String!]!`, returning whether it is non-null.
+    fn type_reference(&mut self) -> Result<bool, String> {
+        if self.eat('[') {
+            self.nested(Self::type_reference)?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        Ok(self.eat('!'))
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Selection>, String> {
+        self.nested(Self::selection_set_items)
+    }
+
+    fn selection_set_items(&mut self) -> Result<Vec<Selection>, String> {
+        self.expect('{')?;
+        let mut selection = Vec::new();
+        while !self.eat('}') {
+            selection.push(self.selection()?);
+        }
+        if selection.is_empty() {
+            return Err("Syntax Error: empty selection set".to_string());
+        }
+        Ok(selection)
+    }
+
+    fn selection(&mut self) -> Result<Selection, String> {
+        if self.peek() == Some(&Token::Spread) {
+            self.position += 1;
+            return match self.peek() {
+                Some(Token::Name(name)) if name != "on" => {
+                    let name = self.name()?;
+                    Ok(Selection::Spread {
+                        name,
+                        directives: self.directives()?,
+                    })
+                }
+                _ => {
+                    let type_condition = match self.peek() {
+                        Some(Token::Name(_)) => {
+                            self.position += 1;
+                            Some(self.name()?)
+                        }
+                        _ => None,
+                    };
+                    Ok(Selection::Inline {
+                        type_condition,
+                        directives: self.directives()?,
+                        selection: self.selection_set()?,
+                    })
+                }
+            };
+        }
+
+        let mut name = self.name()?;
+        let mut alias = None;
+        if self.eat(':') {
+            alias = Some(name);
+            name = self.name()?;
+        }
+        let arguments = self.arguments()?;
+        let directives = self.directives()?;
+        let selection = if self.at('{') {
+            self.selection_set()?
+        } else {
+            Vec::new()
+        };
+        Ok(Selection::Field(Field {
+            alias,
+            name,
+            arguments,
+            directives,
+            selection,
+        }))
+    }
+
+    fn arguments(&mut self) -> Result<Vec<(String, Value)>, String> {
+        let mut arguments = Vec::new();
+        if !self.eat('(') {
+            return Ok(arguments);
+        }
+        while !self.eat(')') {
+            let name = self.name()?;
+            self.expect(':')?;
+            arguments.push((name, self.value(false)?));
+        }
+        Ok(arguments)
+    }
+
+    fn directives(&mut self) -> Result<Vec<Directive>, String> {
+        let mut directives = Vec::new();
+        while self.eat('@') {
+            directives.push(Directive {
+                name: self.name()?,
+                arguments: self.arguments()?,
+            });
+        }
+        Ok(directives)
+    }
+
+    /// A literal, or (unless `constant`) a `$variable`.
+    fn value(&mut self, constant: bool) -> Result<Value, String> {
+        Ok(match self.next()? {
+            Token::Punct('$') if !constant => Value::Variable(self.name()?),
+            Token::Int(value) => Value::Int(value),
+            Token::Float(value) => Value::Float(value),
+            Token::Str(value) => Value::String(value),
+            Token::Name(name) => match name.as_str() {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                _ => Value::Enum(name),
+            },
+            Token::Punct('[') => self.nested(|parser| {
+                let mut values = Vec::new();
+                while !parser.eat(']') {
+                    values.push(parser.value(constant)?);
+                }
+                Ok(Value::List(values))
+            })?,
+            Token::Punct('{') => self.nested(|parser| {
+                let mut fields = Vec::new();
+                while !parser.eat('}') {
+                    let name = parser.name()?;
+                    parser.expect(':')?;
+                    fields.push((name, parser.value(constant)?));
+                }
+                Ok(Value::Object(fields))
+            })?,
+            token => return Err(format!("Syntax Error: unexpected {}", describe(&token))),
+        })
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Punct(punct) => format!("\"{punct}\""),
+        Token::Spread => "\"...\"".to_string(),
+        Token::Name(name) => format!("\"{name}\""),
+        Token::Int(value) => value.to_string(),
+        Token::Float(value) => value.to_string(),
+        Token::Str(value) => format!("{value:?}"),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Object<'a> {
+    Query,
+    Quote(usize),
+    Anime(&'a str),
+    Character { name: &'a str, anime: &'a str },
+}
+
+impl Object<'_> {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Query => "Query",
+            Self::Quote(_) => "Quote",
+            Self::Anime(_) => "Anime",
+            Self::Character { .. } => "Character",
+        }
+    }
+}
+
+enum Output<'a> {
+    Value(Json),
+    Object(Object<'a>),
+    List(Vec<Output<'a>>),
+}
+
+/// A field's arguments with variables substituted.
+struct Arguments {
+    field: String,
+    values: Vec<(String, Json)>,
+}
+
+impl Arguments {
+    /// Rejects arguments the field doesn't take.
+    fn allow(&self, names: &[&str]) -> Result<(), String> {
+        match self
+            .values
+            .iter()
+            .find(|(name, _)| !names.contains(&name.as_str()))
+        {
+            Some((name, _)) => Err(format!(
+                "Unknown argument \"{name}\" on field \"{}\"",
+                self.field
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&Json> {
+        self.values
+            .iter()
+            .find(|(candidate, _)| candidate == name)
+            .map(|(_, value)| value)
+            .filter(|value| !value.is_null())
+    }
+
+    fn string(&self, name: &str) -> Result<Option<&str>, String> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(Json::String(value)) => Ok(Some(value)),
+            Some(value) => Err(format!("Argument \"{name}\" expects a String, got {value}")),
+        }
+    }
+
+    fn required_string(&self, name: &str) -> Result<&str, String> {
+        self.string(name)?
+            .ok_or_else(|| format!("Field \"{}\" requires the argument \"{name}\"", self.field))
+    }
+
+    fn int(&self, name: &str) -> Result<Option<i64>, String> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(value) => value
+                .as_i64()
+                .map(Some)
+                .ok_or_else(|| format!("Argument \"{name}\" expects an Int, got {value}")),
+        }
+    }
+
+    /// `first` and `offset` must not be negative.
+    fn count(&self, name: &str) -> Result<Option<usize>, String> {
+        match self.int(name)? {
+            None => Ok(None),
+            Some(value) => usize::try_from(value)
+                .map(Some)
+                .map_err(|_| format!("Argument \"{name}\" must not be negative")),
+        }
+    }
+
+    fn page<T>(&self, items: Vec<T>) -> Result<Vec<T>, String> {
+        let offset = self.count("offset")?.unwrap_or(0);
+        let first = self.count("first")?.unwrap_or(usize::MAX);
+        Ok(items.into_iter().skip(offset).take(first).collect())
+    }
+}
+
+struct Executor<'a> {
+    quotes: &'a [AnimeQuote],
+    fragments: &'a HashMap<String, Fragment>,
+    variables: Map<String, Json>,
+    served: &'a Cell<usize>,
+    /// Objects resolved so far, against [`MAX_OBJECTS`].
+    objects: Cell<usize>,
+}
+
+impl<'a> Executor<'a> {
+    /// Resolves `selection` on `object`, `depth` objects below the query.
+    /// Fragments can spread into their own subfields, so the depth is
+    /// checked here as well as in the parser.
+    fn select(
+        &self,
+        object: Object<'a>,
+        selection: &[&'a Selection],
+        depth: usize,
+    ) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "The query selects objects more than {MAX_DEPTH} levels deep"
+            ));
+        }
+        self.objects.set(self.objects.get() + 1);
+        if self.objects.get() > MAX_OBJECTS {
+            return Err(format!(
+                "The query is too complex: it resolves more than {MAX_OBJECTS} objects"
+            ));
+        }
+        if let Object::Quote(_) = object {
+            self.served.set(self.served.get() + 1);
+        }
+        let mut fields: Vec<(String, Vec<&'a Field>)> = Vec::new();
+        self.collect(object, selection, &mut fields, &mut Vec::new())?;
+        let mut output = Map::new();
+        for (key, group) in fields {
+            let field = group[0];
+            let value = if field.name == "__typename" {
+                json!(object.type_name())
+            } else {
+                // Fields selected twice under one name merge their subfields.
+                let selection: Vec<&Selection> =
+                    group.iter().flat_map(|field| &field.selection).collect();
+                let resolved = self.resolve(object, field)?;
+                self.complete(resolved, field, &selection, depth + 1)?
+            };
+            output.insert(key, value);
+        }
+        Ok(Json::Object(output))
+    }
+
+    /// Flattens fragments into the fields to resolve on `object`, grouped by
+    /// response name.
+    fn collect(
+        &self,
+        object: Object<'a>,
+        selection: &[&'a Selection],
+        fields: &mut Vec<(String, Vec<&'a Field>)>,
+        visited: &mut Vec<&'a str>,
+    ) -> Result<(), String> {
+        for &item in selection {
+            match item {
+                Selection::Field(field) => {
+                    if !self.included(&field.directives)? {
+                        continue;
+                    }
+                    let key = field.alias.as_ref().unwrap_or(&field.name);
+                    match fields.iter_mut().find(|(existing, _)| existing == key) {
+                        Some((_, group)) => group.push(field),
+                        None => fields.push((key.clone(), vec![field])),
+                    }
+                }
+                Selection::Spread { name, directives } => {
+                    if !self.included(directives)? || visited.contains(&name.as_str()) {
+                        continue;
+                    }
+                    let fragment = self
+                        .fragments
+                        .get(name)
+                        .ok_or_else(|| format!("Unknown fragment \"{name}\""))?;
+                    if self.applies(object, &fragment.type_condition)? {
+                        visited.push(name);
+                        let selection: Vec<&Selection> = fragment.selection.iter().collect();
+                        self.collect(object, &selection, fields, visited)?;
+                    }
+                }
+                Selection::Inline {
+                    type_condition,
+                    directives,
+                    selection,
+                } => {
+                    let applies = match type_condition {
+                        Some(type_condition) => self.applies(object, type_condition)?,
+                        None => true,
+                    };
+                    if applies && self.included(directives)? {
+                        let selection: Vec<&Selection> = selection.iter().collect();
+                        self.collect(object, &selection, fields, visited)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn applies(&self, object: Object<'a>, type_condition: &str) -> Result<bool, String> {
+        if !TYPES.contains(&type_condition) {
+            return Err(format!("Unknown type \"{type_condition}\""));
+        }
+        Ok(object.type_name() == type_condition)
+    }
+
+    /// Applies `@skip(if:)` and `@include(if:)`.
+    fn included(&self, directives: &[Directive]) -> Result<bool, String> {
+        for directive in directives {
+            let condition = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "if")
+                .map(|(_, value)| value.to_json(&self.variables))
+                .transpose()?
+                .and_then(|value| value.as_bool())
+                .ok_or_else(|| {
+                    format!("Directive \"@{}\" needs a Boolean \"if\"", directive.name)
+                })?;
+            match directive.name.as_str() {
+                "skip" if condition => return Ok(false),
+                "include" if !condition => return Ok(false),
+                "skip" | "include" => {}
+                name => return Err(format!("Unknown directive \"@{name}\"")),
+            }
+        }
+        Ok(true)
+    }
+
+    fn complete(
+        &self,
+        output: Output<'a>,
+        field: &Field,
+        selection: &[&'a Selection],
+        depth: usize,
+    ) -> Result<Json, String> {
+        match output {
+            // Objects that aren't there, like `quote(id:)` for an unknown ID.
+            Output::Value(Json::Null) => Ok(Json::Null),
+            Output::Value(value) if selection.is_empty() => Ok(value),
+            Output::Value(_) => Err(format!(
+                "Field \"{}\" is a scalar and takes no selection of subfields",
+                field.name
+            )),
+            Output::Object(object) if selection.is_empty() => Err(format!(
+                "Field \"{}\" of type \"{}\" must have a selection of subfields",
+                field.name,
+                object.type_name()
+            )),
+            Output::Object(object) => self.select(object, selection, depth),
+            Output::List(items) => items
+                .into_iter()
+                .map(|item| self.complete(item, field, selection, depth))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Json::Array),
+        }
+    }
+
+    fn resolve(&self, object: Object<'a>, field: &Field) -> Result<Output<'a>, String> {
+        let args = Arguments {
+            field: format!("{}.{}", object.type_name(), field.name),
+            values: field
+                .arguments
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), value.to_json(&self.variables)?)))
+                .collect::<Result<_, String>>()?,
+        };
+        let unknown = || {
+            Err(format!(
+                "Cannot query field \"{}\" on type \"{}\"",
+                field.name,
+                object.type_name()
+            ))
+        };
+        match object {
+            Object::Query => match field.name.as_str() {
+                "quotes" => {
+                    args.allow(&[
+                        "anime",
+                        "character",
+                        "tag",
+                        "minRating",
+                        "search",
+                        "query",
+                        "first",
+                        "offset",
+                    ])?;
+                    self.quote_list(0..self.quotes.len(), &args)
+                }
+                "quote" => {
+                    args.allow(&["id"])?;
+                    let id = match args.get("id") {
+                        Some(Json::String(id)) => id.clone(),
+                        Some(Json::Number(number)) => number.to_string(),
+                        Some(value) => {
+                            return Err(format!("Argument \"id\" expects an ID, got {value}"));
+                        }
+                        None => args.required_string("id")?.to_string(),
+                    };
+                    let index = self
+                        .quotes
+                        .iter()
+                        .position(|quote| quote.stable_id() == id)
+                        .or_else(|| id.parse::<usize>().ok()?.checked_sub(1))
+                        .filter(|&index| index < self.quotes.len());
+                    Ok(index.map_or(Output::Value(Json::Null), |index| {
+                        Output::Object(Object::Quote(index))
+                    }))
+                }
+                "random" => {
+                    args.allow(&["anime", "character", "tag", "minRating", "search", "query"])?;
+                    let positions = self.matching(0..self.quotes.len(), &args)?;
+                    Ok(if positions.is_empty() {
+                        Output::Value(Json::Null)
+                    } else {
+                        let pick = rand::rng().random_range(0..positions.len());
+                        Output::Object(Object::Quote(positions[pick]))
+                    })
+                }
+                "anime" => {
+                    args.allow(&["title"])?;
+                    let title = args.required_string("title")?;
+                    Ok(self
+                        .anime_titles()
+                        .into_iter()
+                        .find(|candidate| candidate.to_lowercase() == title.to_lowercase())
+                        .map_or(Output::Value(Json::Null), |title| {
+                            Output::Object(Object::Anime(title))
+                        }))
+                }
+                "animeList" => {
+                    args.allow(&["search", "first", "offset"])?;
+                    let search = args.string("search")?;
+                    let titles: Vec<&str> = self
+                        .anime_titles()
+                        .into_iter()
+                        .filter(|title| {
+                            search
+                                .is_none_or(|search| !filter::find(title, search, false).is_empty())
+                        })
+                        .collect();
+                    Ok(Output::List(
+                        args.page(titles)?
+                            .into_iter()
+                            .map(|title| Output::Object(Object::Anime(title)))
+                            .collect(),
+                    ))
+                }
+                "character" => {
+                    args.allow(&["name", "anime"])?;
+                    let name = args.required_string("name")?.to_lowercase();
+                    let anime = args.string("anime")?.map(str::to_lowercase);
+                    Ok(self
+                        .characters(None)
+                        .into_iter()
+                        .find(|character| match character {
+                            Object::Character {
+                                name: candidate,
+                                anime: title,
+                            } => {
+                                candidate.to_lowercase() == name
+                                    && anime
+                                        .as_ref()
+                                        .is_none_or(|anime| title.to_lowercase() == *anime)
+                            }
+                            _ => false,
+                        })
+                        .map_or(Output::Value(Json::Null), Output::Object))
+                }
+                "characters" => {
+                    args.allow(&["search", "anime", "first", "offset"])?;
+                    let search = args.string("search")?;
+                    let anime = args.string("anime")?;
+                    let characters: Vec<Object<'a>> = self
+                        .characters(None)
+                        .into_iter()
+                        .filter(|character| match character {
+                            Object::Character { name, anime: title } => {
+                                search.is_none_or(|search| {
+                                    !filter::find(name, search, false).is_empty()
+                                }) && anime.is_none_or(|anime| {
+                                    !filter::find(title, anime, false).is_empty()
+                                })
+                            }
+                            _ => false,
+                        })
+                        .collect();
+                    Ok(Output::List(
+                        args.page(characters)?
+                            .into_iter()
+                            .map(Output::Object)
+                            .collect(),
+                    ))
+                }
+                _ => unknown(),
+            },
+            Object::Quote(index) => {
+                args.allow(&[])?;
+                let quote: &'a AnimeQuote = &self.quotes[index];
+                let value = match field.name.as_str() {
+                    "id" => json!(quote.stable_id()),
+                    "index" => json!(index + 1),
+                    "japanese" => json!(quote.japanese),
+                    "romaji" => json!(quote.romaji),
+                    "quote" => json!(quote.quote),
+                    "image" => json!(quote.image),
+                    "imageUrl" => json!(
+                        quote
+                            .image
+                            .as_ref()
+                            .map(|_| format!("/quotes/{}/image", quote.stable_id()))
+                    ),
+                    "url" => json!(quote.url),
+                    "episode" => json!(quote.episode),
+                    "season" => json!(quote.season),
+                    "year" => json!(quote.year),
+                    "source" => json!(quote.source),
+                    "sourceUrl" => json!(quote.source_url),
+                    "tags" => json!(quote.tags),
+                    "rating" => json!(quote.rating),
+                    "anime" => return Ok(Output::Object(Object::Anime(&quote.anime))),
+                    "character" => {
+                        return Ok(Output::Object(Object::Character {
+                            name: &quote.character,
+                            anime: &quote.anime,
+                        }));
+                    }
+                    _ => return unknown(),
+                };
+                Ok(Output::Value(value))
+            }
+            Object::Anime(title) => {
+                let positions = || {
+                    (0..self.quotes.len()).filter(move |&index| self.quotes[index].anime == title)
+                };
+                match field.name.as_str() {
+                    "title" => {
+                        args.allow(&[])?;
+                        Ok(Output::Value(json!(title)))
+                    }
+                    "quoteCount" => {
+                        args.allow(&[])?;
+                        Ok(Output::Value(json!(positions().count())))
+                    }
+                    "quotes" => {
+                        args.allow(&[
+                            "character",
+                            "tag",
+                            "minRating",
+                            "search",
+                            "query",
+                            "first",
+                            "offset",
+                        ])?;
+                        self.quote_list(positions(), &args)
+                    }
+                    "characters" => {
+                        args.allow(&[])?;
+                        Ok(Output::List(
+                            self.characters(Some(title))
+                                .into_iter()
+                                .map(Output::Object)
+                                .collect(),
+                        ))
+                    }
+                    _ => unknown(),
+                }
+            }
+            Object::Character { name, anime } => {
+                let positions = || {
+                    (0..self.quotes.len()).filter(move |&index| {
+                        self.quotes[index].character == name && self.quotes[index].anime == anime
+                    })
+                };
+                match field.name.as_str() {
+                    "name" => {
+                        args.allow(&[])?;
+                        Ok(Output::Value(json!(name)))
+                    }
+                    "birthday" => {
+                        args.allow(&[])?;
+                        let birthday =
+                            positions().find_map(|index| self.quotes[index].birthday.clone());
+                        Ok(Output::Value(json!(birthday)))
+                    }
+                    "anime" => {
+                        args.allow(&[])?;
+                        Ok(Output::Object(Object::Anime(anime)))
+                    }
+                    "quoteCount" => {
+                        args.allow(&[])?;
+                        Ok(Output::Value(json!(positions().count())))
+                    }
+                    "quotes" => {
+                        args.allow(&["tag", "minRating", "search", "query", "first", "offset"])?;
+                        self.quote_list(positions(), &args)
+                    }
+                    _ => unknown(),
+                }
+            }
+        }
+    }
+
+    /// Positions among `positions` that pass the filter arguments.
+    fn matching(
+        &self,
+        positions: impl Iterator<Item = usize>,
+        args: &Arguments,
+    ) -> Result<Vec<usize>, String> {
+        let list = |name: &str| -> Result<Vec<String>, String> {
+            Ok(args.string(name)?.map(str::to_string).into_iter().collect())
+        };
+        let filter = QuoteFilter {
+            anime: list("anime")?,
+            character: list("character")?,
+            tag: list("tag")?,
+            min_rating: args
+                .int("minRating")?
+                .map(|rating| rating.clamp(0, u8::MAX.into()) as u8),
+            query: args
+                .string("query")?
+                .map(|query| {
+                    Query::parse(query)
+                        .map_err(|error| format!("Argument \"query\" is invalid: {error}"))
+                })
+                .transpose()?,
+            ..QuoteFilter::default()
+        };
+        let search = args.string("search")?;
+        Ok(positions
+            .filter(|&index| {
+                let quote = &self.quotes[index];
+                filter.matches(quote) && search.is_none_or(|search| mentions(quote, search))
+            })
+            .collect())
+    }
+
+    fn quote_list(
+        &self,
+        positions: impl Iterator<Item = usize>,
+        args: &Arguments,
+    ) -> Result<Output<'a>, String> {
+        let positions = args.page(self.matching(positions, args)?)?;
+        Ok(Output::List(
+            positions
+                .into_iter()
+                .map(|index| Output::Object(Object::Quote(index)))
+                .collect(),
+        ))
+    }
+
+    /// Anime titles in the order they first appear.
+    fn anime_titles(&self) -> Vec<&'a str> {
+        let mut titles: Vec<&'a str> = Vec::new();
+        for quote in self.quotes {
+            if !titles.contains(&quote.anime.as_str()) {
+                titles.push(&quote.anime);
+            }
+        }
+        titles
+    }
+
+    /// Characters (of one anime, if given) in the order they first appear.
+    /// A name shared by characters of different anime is two characters.
+    fn characters(&self, anime: Option<&str>) -> Vec<Object<'a>> {
+        let mut seen: Vec<(&'a str, &'a str)> = Vec::new();
+        for quote in self.quotes {
+            let key = (quote.character.as_str(), quote.anime.as_str());
+            if anime.is_none_or(|anime| anime == key.1) && !seen.contains(&key) {
+                seen.push(key);
+            }
+        }
+        seen.into_iter()
+            .map(|(name, anime)| Object::Character { name, anime })
+            .collect()
+    }
+}
+
+/// Whether `search` appears in any of the quote's text, as `grep` looks.
+fn mentions(quote: &AnimeQuote, search: &str) -> bool {
+    [
+        Some(quote.japanese.as_str()),
+        quote.romaji.as_deref(),
+        Some(quote.quote.as_str()),
+        Some(quote.character.as_str()),
+        Some(quote.anime.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|text| !filter::find(text, search, false).is_empty())
+}
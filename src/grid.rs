@@ -0,0 +1,206 @@
+//! The grid view: a page of small quote cards at once, each with its artwork
+//! and the start of its text, for skimming a large collection. The galleries
+//! lay out their cards with it too.
+
+use crate::output::truncate_width;
+use crate::{AnimeQuote, Palette};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Paragraph};
+
+/// Cards per row (and rows per page) the grid can show.
+const SIZES: std::ops::RangeInclusive<usize> = 2..=5;
+const DEFAULT_SIZE: usize = 3;
+
+pub(crate) enum GridAction {
+    Stay,
+    Close,
+    /// Show the card at this position: a quote in the viewer's order, or a
+    /// gallery's entry.
+    Show(usize),
+}
+
+pub(crate) struct Grid {
+    /// Position of the card under the cursor.
+    selected: usize,
+    size: usize,
+    /// Keys of the screen using the grid, shown before the grid's own.
+    help: Option<&'static str>,
+}
+
+impl Grid {
+    /// Opens on the page with the card at `position`.
+    pub(crate) fn new(position: usize) -> Self {
+        Self {
+            selected: position,
+            size: DEFAULT_SIZE,
+            help: None,
+        }
+    }
+
+    pub(crate) fn with_help(self, help: &'static str) -> Self {
+        Self {
+            help: Some(help),
+            ..self
+        }
+    }
+
+    /// Moves the cursor to the card at `position`.
+    pub(crate) fn select(&mut self, position: usize) {
+        self.selected = position;
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode, count: usize) -> GridAction {
+        let last = count.saturating_sub(1);
+        let page = self.size * self.size;
+        match code {
+            KeyCode::Left | KeyCode::Char('h') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => self.selected = (self.selected + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(self.size);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + self.size).min(last);
+            }
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(page),
+            KeyCode::PageDown => self.selected = (self.selected + page).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.size = (self.size + 1).min(*SIZES.end());
+            }
+            KeyCode::Char('-') => self.size = (self.size - 1).max(*SIZES.start()),
+            KeyCode::Enter if count > 0 => return GridAction::Show(self.selected.min(last)),
+            KeyCode::Esc | KeyCode::Char('g') | KeyCode::Char('q') => return GridAction::Close,
+            _ => {}
+        }
+        GridAction::Stay
+    }
+
+    /// Draws the title and key help, and returns the areas of the cards on
+    /// the cursor's page with their positions.
+    pub(crate) fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        title: &str,
+        count: usize,
+        palette: &Palette,
+    ) -> Vec<(usize, Rect)> {
+        let [title_area, cards_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        let page_size = self.size * self.size;
+        let selected = self.selected.min(count.saturating_sub(1));
+        let page = selected / page_size;
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(title.to_string(), Style::default().fg(palette.anime).bold()),
+                Span::styled(
+                    format!("  page {}/{}", page + 1, count.div_ceil(page_size).max(1)),
+                    Style::default().fg(palette.count),
+                ),
+            ]))
+            .centered(),
+            title_area,
+        );
+        let help = "Move <Arrows>  Page <PgUp/PgDn>  Size <+/->  Open <Enter>  Back <Esc>";
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                match self.help {
+                    Some(extra) => format!("{extra}  {help}"),
+                    None => help.to_string(),
+                },
+                Style::default().fg(palette.instructions),
+            )))
+            .centered(),
+            help_area,
+        );
+
+        let rows = Layout::vertical(vec![Constraint::Fill(1); self.size]).split(cards_area);
+        let first = page * page_size;
+        rows.iter()
+            .flat_map(|row| {
+                Layout::horizontal(vec![Constraint::Fill(1); self.size])
+                    .split(*row)
+                    .to_vec()
+            })
+            .enumerate()
+            .map(|(offset, card)| (first + offset, card))
+            .filter(|(position, _)| *position < count)
+            .collect()
+    }
+
+    /// Draws the card at `position` in `area` with the lines `text` gives
+    /// for its width, and returns where its artwork goes above them.
+    pub(crate) fn render_card(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        position: usize,
+        palette: &Palette,
+        text: impl FnOnce(usize) -> Vec<Line<'static>>,
+    ) -> Rect {
+        let border_style = if position == self.selected {
+            Style::default().fg(palette.character).bold()
+        } else {
+            Style::default().fg(palette.count)
+        };
+        let block = Block::bordered().border_style(border_style);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let lines = text(usize::from(inner.width));
+        let rows = u16::try_from(lines.len()).unwrap_or(u16::MAX);
+        let [image_area, text_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(rows)]).areas(inner);
+        frame.render_widget(Paragraph::new(Text::from(lines)).centered(), text_area);
+        image_area
+    }
+}
+
+/// A quote's card text: the character, anime, and as much of the quote as
+/// fits on a line.
+pub(crate) fn quote_card(
+    quote: &AnimeQuote,
+    width: usize,
+    palette: &Palette,
+) -> Vec<Line<'static>> {
+    let width = Some(width);
+    vec![
+        Line::from(Span::styled(
+            truncate_width(&quote.character, width),
+            Style::default().fg(palette.character).bold(),
+        )),
+        Line::from(Span::styled(
+            truncate_width(&quote.anime, width),
+            Style::default().fg(palette.anime),
+        )),
+        Line::from(Span::styled(
+            truncate_width(&format!("\"{}\"", quote.quote), width),
+            Style::default().fg(palette.quote).italic(),
+        )),
+    ]
+}
+
+/// The largest area of `width` by `height` proportions that fits in `area`,
+/// centered in it.
+pub(crate) fn fit(area: Rect, (width, height): (u16, u16)) -> Rect {
+    let (width, height) = (u32::from(width.max(1)), u32::from(height.max(1)));
+    let fitted_width = (u32::from(area.height) * width / height).min(u32::from(area.width));
+    let fitted_height = (fitted_width * height / width).min(u32::from(area.height));
+    let fitted_width = u16::try_from(fitted_width).unwrap_or(area.width);
+    let fitted_height = u16::try_from(fitted_height).unwrap_or(area.height);
+    Rect {
+        x: area.x + (area.width - fitted_width) / 2,
+        y: area.y,
+        width: fitted_width,
+        height: fitted_height,
+    }
+}
@@ -0,0 +1,138 @@
+//! The hidden-quotes screen: quotes kept out of the random, daily, and
+//! slideshow picks, to bring them back.
+
+use crate::state::State;
+use crate::{AnimeQuote, Palette};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+
+pub(crate) enum HiddenAction {
+    Stay,
+    Close,
+    /// Show the quote at this index in the viewer.
+    Show(usize),
+    /// Put the quote at this index back into the picks.
+    Unhide(usize),
+}
+
+pub(crate) struct Hidden {
+    list: ListState,
+}
+
+impl Hidden {
+    pub(crate) fn new() -> Self {
+        Self {
+            list: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub(crate) fn handle_key(
+        &mut self,
+        code: KeyCode,
+        state: &State,
+        quotes: &[AnimeQuote],
+    ) -> HiddenAction {
+        let selected = || {
+            let hidden = state.hidden(quotes);
+            let selected = self.list.selected().unwrap_or(0);
+            hidden
+                .get(selected.min(hidden.len().saturating_sub(1)))
+                .copied()
+        };
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Home => self.list.select_first(),
+            KeyCode::End => self.list.select_last(),
+            KeyCode::Enter => {
+                if let Some(index) = selected() {
+                    return HiddenAction::Show(index);
+                }
+            }
+            KeyCode::Char('u') | KeyCode::Char(' ') | KeyCode::Delete => {
+                if let Some(index) = selected() {
+                    return HiddenAction::Unhide(index);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('H') | KeyCode::Char('q') => return HiddenAction::Close,
+            _ => {}
+        }
+        HiddenAction::Stay
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &State,
+        quotes: &[AnimeQuote],
+        palette: &Palette,
+    ) {
+        let [title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        let count_style = Style::default().fg(palette.count);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Hidden quotes",
+                Style::default().fg(palette.anime).bold(),
+            )))
+            .centered(),
+            title_area,
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Move <Up/Down>  Unhide <U>  Show <Enter>  Back <Esc>",
+                Style::default().fg(palette.instructions),
+            )))
+            .centered(),
+            help_area,
+        );
+
+        let hidden = state.hidden(quotes);
+        if hidden.is_empty() {
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    "Nothing hidden (press h on a quote to hide it)",
+                    count_style,
+                ))
+                .centered(),
+                list_area,
+            );
+            return;
+        }
+        let items: Vec<ListItem> = hidden
+            .iter()
+            .map(|&index| {
+                let quote = &quotes[index];
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(format!("{:>4}  ", index + 1), count_style),
+                        Span::styled(
+                            quote.character.clone(),
+                            Style::default().fg(palette.character).bold(),
+                        ),
+                        Span::raw(" — "),
+                        Span::styled(quote.anime.clone(), Style::default().fg(palette.anime)),
+                    ]),
+                    Line::from(vec![
+                        Span::raw(" ".repeat(6)),
+                        Span::styled(
+                            format!("\"{}\"", quote.quote),
+                            Style::default().fg(palette.quote).italic(),
+                        ),
+                    ]),
+                ])
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+    }
+}
@@ -0,0 +1,365 @@
+//! `images`: upkeep of the collection's artwork files.
+//!
+//! `images optimize` shrinks images larger than needed and can convert
+//! them, rewriting the paths (crops, and checksums) in the data file and installed
+//! packs with `toml_edit` so the rest of each file is left as written.
+//! `images pregen` fills the thumbnail cache ahead of the viewer, and
+//! `images duplicates` finds copies of the same picture under different
+//! paths.
+
+use crate::{AnimeQuote, check, edit, open_oriented, paths, thumbnails};
+use color_eyre::eyre::{Result, WrapErr};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// What to write optimized images as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Whatever each image already is.
+    Keep,
+    Jpeg,
+    Png,
+}
+
+pub(crate) struct Options {
+    /// The longest side an image may have, in pixels.
+    pub(crate) max_dim: u32,
+    pub(crate) format: Format,
+    /// JPEG quality, 1 to 100.
+    pub(crate) quality: u8,
+    /// Report what would change without writing anything.
+    pub(crate) dry_run: bool,
+}
+
+/// An image that was (or would be) rewritten.
+struct Rewrite {
+    path: PathBuf,
+    /// How much smaller each side became, for scaling crops.
+    scale: f64,
+}
+
+/// Rewrites the images of every source file that are larger than
+/// `options.max_dim` or not yet in `options.format`. Returns a line per
+/// image for the user.
+pub(crate) fn optimize(options: &Options) -> Result<Vec<String>> {
+    let mut report = Vec::new();
+    // Quotes sharing an image have it rewritten once, by resolved path.
+    let mut rewrites: HashMap<String, Option<Rewrite>> = HashMap::new();
+    for file in edit::source_files() {
+        let mut document = edit::read_document(&file)?;
+        let base = file.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut changed = false;
+        for table in edit::quotes_mut(&mut document, &file)?.iter_mut() {
+            let Some(written) = table
+                .get("image")
+                .and_then(|item| item.as_str())
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let resolved = paths::resolve(&base, &written);
+            if !rewrites.contains_key(&resolved) {
+                let rewrite = match rewrite(&resolved, options) {
+                    Ok(Some((rewrite, line))) => {
+                        report.push(line);
+                        Some(rewrite)
+                    }
+                    Ok(None) => None,
+                    Err(error) => {
+                        report.push(format!("skipped {resolved}: {error}"));
+                        None
+                    }
+                };
+                rewrites.insert(resolved.clone(), rewrite);
+            }
+            let Some(rewrite) = &rewrites[&resolved] else {
+                continue;
+            };
+            if rewrite.path != Path::new(&resolved) {
+                let renamed = Path::new(&written)
+                    .with_extension(rewrite.path.extension().unwrap_or_default());
+                if let Some(value) = table.get_mut("image").and_then(|item| item.as_value_mut()) {
+                    // Keep a comment after the path.
+                    let decor = value.decor().clone();
+                    *value = renamed.to_string_lossy().as_ref().into();
+                    *value.decor_mut() = decor;
+                    changed = true;
+                }
+            }
+            if rewrite.scale < 1.0
+                && let Some(crop) = table
+                    .get_mut("crop")
+                    .and_then(|item| item.as_table_like_mut())
+            {
+                for key in ["x", "y", "width", "height"] {
+                    let Some(value) = crop.get(key).and_then(|item| item.as_integer()) else {
+                        continue;
+                    };
+                    let scaled = (value as f64 * rewrite.scale).round() as i64;
+                    crop.insert(key, toml_edit::value(scaled));
+                }
+                changed = true;
+            }
+            // A recorded checksum was of the old file.
+            if !options.dry_run
+                && let Some(value) = table
+                    .get_mut("image_sha256")
+                    .and_then(|item| item.as_value_mut())
+                && let Ok(bytes) = fs::read(&rewrite.path)
+            {
+                let decor = value.decor().clone();
+                *value = check::sha256_hex(&bytes).into();
+                *value.decor_mut() = decor;
+                changed = true;
+            }
+        }
+        if changed && !options.dry_run {
+            fs::write(&file, document.to_string())
+                .wrap_err_with(|| format!("failed to write {}", file.display()))?;
+        }
+    }
+    Ok(report)
+}
+
+/// Shrinks and converts the image at `path` as `options` ask, writing it
+/// next to the original when the format changes. `None` when it is fine
+/// as it is.
+fn rewrite(path: &str, options: &Options) -> Result<Option<(Rewrite, String)>> {
+    let current = ImageFormat::from_path(path).ok();
+    let format = match options.format {
+        Format::Keep => current,
+        Format::Jpeg => Some(ImageFormat::Jpeg),
+        Format::Png => Some(ImageFormat::Png),
+    };
+    // Only JPEG and PNG can be read and written.
+    let Some(format) =
+        format.filter(|format| matches!(format, ImageFormat::Jpeg | ImageFormat::Png))
+    else {
+        return Ok(None);
+    };
+    let image = open_oriented(path)?;
+    let (width, height) = (image.width(), image.height());
+    let oversized = width.max(height) > options.max_dim;
+    if !oversized && current == Some(format) {
+        return Ok(None);
+    }
+    let image = if oversized {
+        image.resize(options.max_dim, options.max_dim, FilterType::Lanczos3)
+    } else {
+        image
+    };
+    let scale = f64::from(image.width()) / f64::from(width);
+    let target = if current == Some(format) {
+        PathBuf::from(path)
+    } else {
+        Path::new(path).with_extension(format.extensions_str()[0])
+    };
+    let before = fs::metadata(path).map_or(0, |metadata| metadata.len());
+    if !options.dry_run {
+        save(&image, &target, format, options.quality)
+            .wrap_err_with(|| format!("failed to write {}", target.display()))?;
+    }
+    let after = fs::metadata(&target).map_or(0, |metadata| metadata.len());
+    let mut line = format!(
+        "{path}: {width}x{height} -> {}x{}",
+        image.width(),
+        image.height()
+    );
+    if target != Path::new(path) {
+        line.push_str(&format!(" as {} (the original is kept)", target.display()));
+    }
+    if !options.dry_run {
+        line.push_str(&format!(", {} -> {}", kilobytes(before), kilobytes(after)));
+    }
+    Ok(Some((
+        Rewrite {
+            path: target,
+            scale,
+        },
+        line,
+    )))
+}
+
+fn save(image: &DynamicImage, path: &Path, format: ImageFormat, quality: u8) -> Result<()> {
+    match format {
+        ImageFormat::Jpeg => {
+            // JPEG has no alpha channel.
+            let file = BufWriter::new(fs::File::create(path)?);
+            image
+                .to_rgb8()
+                .write_with_encoder(JpegEncoder::new_with_quality(file, quality.clamp(1, 100)))?;
+        }
+        format => image.save_with_format(path, format)?,
+    }
+    Ok(())
+}
+
+fn kilobytes(bytes: u64) -> String {
+    format!("{} KB", bytes.div_ceil(1024))
+}
+
+/// What `pregen` did.
+#[derive(Debug, Default)]
+pub(crate) struct PregenReport {
+    pub(crate) written: usize,
+    pub(crate) cached: usize,
+    /// Images that fit already, which the viewer decodes as they are.
+    pub(crate) small: usize,
+    /// A line per image that couldn't be read.
+    pub(crate) failed: Vec<String>,
+}
+
+/// Writes the thumbnail the viewer would make for each of `quotes`' images
+/// in a window of `largest` pixels, decoding every image (and crop) once.
+pub(crate) fn pregen(quotes: &[AnimeQuote], largest: (u32, u32)) -> PregenReport {
+    let mut report = PregenReport::default();
+    let mut seen = HashSet::new();
+    for quote in quotes {
+        let Some(image) = &quote.image else {
+            continue;
+        };
+        if !seen.insert((image, format!("{:?}", quote.crop))) {
+            continue;
+        }
+        if thumbnails::cached(quote, largest) {
+            report.cached += 1;
+            continue;
+        }
+        match thumbnails::load(quote, largest) {
+            Some(Err(error)) => report.failed.push(format!("{image}: {error}")),
+            Some(Ok(_)) if thumbnails::cached(quote, largest) => report.written += 1,
+            _ => report.small += 1,
+        }
+    }
+    report
+}
+
+/// An image file of a set that look the same.
+pub(crate) struct ImageCopy {
+    pub(crate) path: String,
+    pub(crate) size: (u32, u32),
+    /// How many quotes use this file.
+    pub(crate) quotes: usize,
+}
+
+/// Finds the image files of `quotes` that look the same, whatever their
+/// path or format: those whose difference hashes are at most `threshold`
+/// bits apart. Each set comes with the copy to keep first, the largest one
+/// (and of those, the one most quotes use).
+pub(crate) fn duplicates(quotes: &[AnimeQuote], threshold: u32) -> Vec<Vec<ImageCopy>> {
+    let mut uses: Vec<(String, usize)> = Vec::new();
+    for image in quotes.iter().filter_map(|quote| quote.image.as_deref()) {
+        if image.contains("://") {
+            continue;
+        }
+        match uses.iter_mut().find(|(path, _)| path == image) {
+            Some((_, count)) => *count += 1,
+            None => uses.push((image.to_string(), 1)),
+        }
+    }
+    let mut hashed: Vec<(u64, ImageCopy)> = Vec::new();
+    for (path, count) in uses {
+        match open_oriented(&path) {
+            Ok(image) => hashed.push((
+                difference_hash(&image),
+                ImageCopy {
+                    size: (image.width(), image.height()),
+                    path,
+                    quotes: count,
+                },
+            )),
+            Err(error) => eprintln!("failed to load image from {path}: {error}"),
+        }
+    }
+    // Each image joins the first set it looks like.
+    let mut sets: Vec<(u64, Vec<ImageCopy>)> = Vec::new();
+    for (hash, copy) in hashed {
+        match sets
+            .iter_mut()
+            .find(|(first, _)| (first ^ hash).count_ones() <= threshold)
+        {
+            Some((_, copies)) => copies.push(copy),
+            None => sets.push((hash, vec![copy])),
+        }
+    }
+    sets.into_iter()
+        .map(|(_, mut copies)| {
+            copies.sort_by_key(|copy| {
+                std::cmp::Reverse((u64::from(copy.size.0) * u64::from(copy.size.1), copy.quotes))
+            });
+            copies
+        })
+        .filter(|copies| copies.len() > 1)
+        .collect()
+}
+
+/// A 64-bit difference hash: whether each pixel of a 9x8 grayscale copy is
+/// brighter than the next one in its row. Resizing and recompressing an
+/// image barely change it.
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = hash << 1 | u64::from(brighter);
+        }
+    }
+    hash
+}
+
+/// Points the quotes using a copy in `sets` other than the first at the
+/// first instead, in every source file. Returns how many quotes changed.
+pub(crate) fn consolidate(sets: &[Vec<ImageCopy>]) -> Result<usize> {
+    let mut kept: HashMap<&str, &str> = HashMap::new();
+    for copies in sets {
+        for copy in &copies[1..] {
+            kept.insert(&copy.path, &copies[0].path);
+        }
+    }
+    let mut total = 0;
+    for file in edit::source_files() {
+        let mut document = edit::read_document(&file)?;
+        let base = file.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut changed = 0;
+        for table in edit::quotes_mut(&mut document, &file)?.iter_mut() {
+            let Some(value) = table.get_mut("image").and_then(|item| item.as_value_mut()) else {
+                continue;
+            };
+            let Some(target) = value
+                .as_str()
+                .and_then(|written| kept.get(paths::resolve(&base, written).as_str()))
+            else {
+                continue;
+            };
+            let decor = value.decor().clone();
+            *value = relative_to(target, &base).into();
+            *value.decor_mut() = decor;
+            changed += 1;
+        }
+        if changed > 0 {
+            fs::write(&file, document.to_string())
+                .wrap_err_with(|| format!("failed to write {}", file.display()))?;
+            total += changed;
+        }
+    }
+    Ok(total)
+}
+
+/// `path` as written in a file in `directory`: relative when it is under
+/// the directory, absolute otherwise.
+fn relative_to(path: &str, directory: &Path) -> String {
+    let (Ok(path), Ok(directory)) = (std::path::absolute(path), std::path::absolute(directory))
+    else {
+        return path.to_string();
+    };
+    path.strip_prefix(&directory)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,202 @@
+//! The viewer's keys: which action each one runs, and the hints for them in
+//! the instructions bar, which `[ui] instructions` picks, orders, and
+//! relabels.
+
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Previous,
+    Next,
+    Open,
+    Source,
+    Card,
+    Screen,
+    Browse,
+    Grid,
+    Gallery,
+    Info,
+    Character,
+    Search,
+    Filter,
+    Collections,
+    Calendar,
+    Recent,
+    Favorite,
+    Hide,
+    Hidden,
+    Paste,
+    Sort,
+    Achievements,
+    Queue,
+    Compare,
+    Kana,
+    Notes,
+    NextWord,
+    PreviousWord,
+    Lookup,
+    Play,
+    Quit,
+}
+
+/// Every action with its key, its name in `[ui] instructions`, and its
+/// default label.
+const BINDINGS: [(Action, KeyCode, &str, &str); 31] = [
+    (Action::Previous, KeyCode::Left, "previous", "Previous"),
+    (Action::Next, KeyCode::Right, "next", "Next"),
+    (Action::Open, KeyCode::Char('o'), "open", "Open"),
+    (Action::Source, KeyCode::Char('s'), "source", "Source"),
+    (Action::Card, KeyCode::Char('c'), "card", "Card"),
+    (Action::Screen, KeyCode::Char('x'), "screen", "Screen"),
+    (Action::Browse, KeyCode::Char('l'), "browse", "Browse"),
+    (Action::Grid, KeyCode::Char('g'), "grid", "Grid"),
+    (Action::Gallery, KeyCode::Char('G'), "gallery", "Gallery"),
+    (Action::Info, KeyCode::Char('i'), "info", "Info"),
+    (
+        Action::Character,
+        KeyCode::Char('I'),
+        "character",
+        "Character",
+    ),
+    (Action::Search, KeyCode::Char('/'), "search", "Search"),
+    (Action::Filter, KeyCode::Char('F'), "filter", "Filter"),
+    (
+        Action::Collections,
+        KeyCode::Char('C'),
+        "collections",
+        "Collections",
+    ),
+    (Action::Calendar, KeyCode::Char('d'), "calendar", "Calendar"),
+    (Action::Recent, KeyCode::Char('r'), "recent", "Recent"),
+    (Action::Favorite, KeyCode::Char('f'), "favorite", "Favorite"),
+    (Action::Hide, KeyCode::Char('h'), "hide", "Hide"),
+    (Action::Hidden, KeyCode::Char('H'), "hidden", "Hidden"),
+    (Action::Paste, KeyCode::Char('v'), "paste", "Paste"),
+    (Action::Sort, KeyCode::Char('S'), "sort", "Sort"),
+    (
+        Action::Achievements,
+        KeyCode::Char('a'),
+        "achievements",
+        "Achievements",
+    ),
+    (Action::Queue, KeyCode::Char('Q'), "queue", "Queue"),
+    (Action::Compare, KeyCode::Char('m'), "compare", "Compare"),
+    (Action::Kana, KeyCode::Char('k'), "kana", "Kana"),
+    (Action::Notes, KeyCode::Char('n'), "notes", "Notes"),
+    (Action::NextWord, KeyCode::Char('w'), "next_word", "Word"),
+    (
+        Action::PreviousWord,
+        KeyCode::Char('W'),
+        "previous_word",
+        "Previous word",
+    ),
+    (Action::Lookup, KeyCode::Char('j'), "lookup", "Jisho"),
+    (Action::Play, KeyCode::Char('p'), "play", "Play"),
+    (Action::Quit, KeyCode::Char('q'), "quit", "Quit"),
+];
+
+/// The hints shown when `[ui] instructions` isn't set, before Quit.
+const DEFAULT_HINTS: [Action; 21] = [
+    Action::Previous,
+    Action::Next,
+    Action::Open,
+    Action::Source,
+    Action::Card,
+    Action::Browse,
+    Action::Grid,
+    Action::Gallery,
+    Action::Info,
+    Action::Character,
+    Action::Search,
+    Action::Filter,
+    Action::Collections,
+    Action::Calendar,
+    Action::Recent,
+    Action::Hide,
+    Action::Paste,
+    Action::Sort,
+    Action::Achievements,
+    Action::Queue,
+    Action::Compare,
+];
+
+/// The action bound to `code` in the main view.
+pub(crate) fn action(code: KeyCode) -> Option<Action> {
+    BINDINGS
+        .iter()
+        .find(|(_, key, _, _)| *key == code)
+        .map(|(action, _, _, _)| *action)
+}
+
+fn binding(action: Action) -> &'static (Action, KeyCode, &'static str, &'static str) {
+    BINDINGS
+        .iter()
+        .find(|(bound, _, _, _)| *bound == action)
+        .expect("every action has a binding")
+}
+
+/// An entry of `[ui] instructions`: an action's name, or a table with the
+/// action and the label to show for it.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum HintConfig {
+    Action(String),
+    Labelled { action: String, label: String },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Hint {
+    pub(crate) action: Action,
+    pub(crate) label: String,
+    /// The key as the bar shows it, like `<Left>` or `<Shift+G>`.
+    pub(crate) key: String,
+}
+
+impl Hint {
+    fn new(action: Action, label: Option<&str>) -> Self {
+        let (_, code, _, default_label) = binding(action);
+        Self {
+            action,
+            label: label.unwrap_or(default_label).to_string(),
+            key: key_name(*code),
+        }
+    }
+}
+
+/// The hints from `[ui] instructions` in its order, or the default ones
+/// followed by Quit when it isn't set. Names that aren't actions are
+/// reported and left out.
+pub(crate) fn hints(config: Option<&[HintConfig]>) -> Vec<Hint> {
+    let Some(config) = config else {
+        return DEFAULT_HINTS
+            .iter()
+            .chain([&Action::Quit])
+            .map(|&action| Hint::new(action, None))
+            .collect();
+    };
+    config
+        .iter()
+        .filter_map(|entry| {
+            let (name, label) = match entry {
+                HintConfig::Action(name) => (name, None),
+                HintConfig::Labelled { action, label } => (action, Some(label.as_str())),
+            };
+            let found = BINDINGS
+                .iter()
+                .find(|(_, _, bound, _)| bound.eq_ignore_ascii_case(name.trim()));
+            if found.is_none() {
+                eprintln!("unknown action {name:?} in [ui] instructions");
+            }
+            found.map(|(action, _, _, _)| Hint::new(*action, label))
+        })
+        .collect()
+}
+
+fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(ch) if ch.is_ascii_uppercase() => format!("<Shift+{ch}>"),
+        KeyCode::Char(ch) => format!("<{}>", ch.to_ascii_uppercase()),
+        code => format!("<{code}>"),
+    }
+}
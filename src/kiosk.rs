@@ -0,0 +1,172 @@
+//! `--kiosk`: an unattended slideshow for wall-mounted displays.
+//!
+//! The instructions bar is hidden, quotes advance on a timer, and every key
+//! is ignored except the configured exit chord so passers-by can't wander
+//! off into a browser or leave the viewer.
+
+use crate::Palette;
+use crate::selection::Rotation;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::Alignment;
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_KIOSK_INTERVAL_SECS: u64 = 30;
+const DEFAULT_KIOSK_EXIT_KEY: &str = "ctrl+q";
+
+/// Colors a randomized style picks from; all readable on a dark background.
+const STYLE_COLORS: &[Color] = &[
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+];
+
+const ALIGNMENTS: &[Alignment] = &[Alignment::Left, Alignment::Center, Alignment::Right];
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct KioskConfig {
+    /// Seconds each quote stays on screen.
+    #[serde(default = "default_kiosk_interval_secs")]
+    interval_secs: u64,
+    /// The only key that does anything in kiosk mode, e.g. `"ctrl+q"`.
+    #[serde(default = "default_kiosk_exit_key")]
+    exit_key: String,
+    /// Pick the next quote at random instead of in order.
+    #[serde(default)]
+    shuffle: bool,
+    /// `order`, `shuffle`, or `least-seen`; overrides `shuffle`.
+    #[serde(default)]
+    rotation: Option<String>,
+    /// Give every quote its own colors and alignment.
+    #[serde(default)]
+    randomize_style: bool,
+}
+
+impl Default for KioskConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: DEFAULT_KIOSK_INTERVAL_SECS,
+            exit_key: default_kiosk_exit_key(),
+            shuffle: false,
+            rotation: None,
+            randomize_style: false,
+        }
+    }
+}
+
+fn default_kiosk_interval_secs() -> u64 {
+    DEFAULT_KIOSK_INTERVAL_SECS
+}
+
+fn default_kiosk_exit_key() -> String {
+    DEFAULT_KIOSK_EXIT_KEY.to_string()
+}
+
+/// Kiosk settings resolved from [`KioskConfig`].
+pub(crate) struct Kiosk {
+    pub(crate) interval: Duration,
+    pub(crate) exit: KeyChord,
+    pub(crate) rotation: Rotation,
+    pub(crate) randomize_style: bool,
+}
+
+impl KioskConfig {
+    pub(crate) fn to_kiosk(&self) -> Kiosk {
+        let exit = KeyChord::parse(&self.exit_key).unwrap_or_else(|| {
+            eprintln!(
+                "invalid [kiosk] exit_key {:?}, falling back to {DEFAULT_KIOSK_EXIT_KEY}",
+                self.exit_key
+            );
+            KeyChord::parse(DEFAULT_KIOSK_EXIT_KEY).expect("default exit key parses")
+        });
+        Kiosk {
+            interval: Duration::from_secs(self.interval_secs.max(1)),
+            exit,
+            rotation: Rotation::resolve(self.rotation.as_deref(), self.shuffle, "kiosk"),
+            randomize_style: self.randomize_style,
+        }
+    }
+}
+
+/// A key with modifiers, written like `ctrl+q`, `ctrl+alt+x`, `esc` or `f10`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = value.trim().split('+').map(str::trim).collect();
+        let key = parts.pop()?;
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        let code = match key.to_ascii_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            lower => match lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(number) if (1..=24).contains(&number) => KeyCode::F(number),
+                _ => {
+                    let mut chars = key.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(ch), None) => KeyCode::Char(ch.to_ascii_lowercase()),
+                        _ => return None,
+                    }
+                }
+            },
+        };
+        Some(Self { code, modifiers })
+    }
+
+    /// Letters match regardless of case, since terminals disagree on whether
+    /// `ctrl+Q` arrives with a shift modifier.
+    pub(crate) fn matches(&self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(ch) => {
+                KeyCode::Char(ch.to_ascii_lowercase()) == self.code
+                    && key.modifiers - KeyModifiers::SHIFT == self.modifiers - KeyModifiers::SHIFT
+            }
+            code => code == self.code && key.modifiers == self.modifiers,
+        }
+    }
+}
+
+/// Random text colors and placement for the next quote. The count,
+/// progress, instruction, and highlight colors are kept so the screen still
+/// reads as one app.
+pub(crate) fn random_style(base: &Palette) -> (Palette, Alignment) {
+    let pick = || STYLE_COLORS[rand::random_range(0..STYLE_COLORS.len())];
+    let palette = Palette {
+        anime: pick(),
+        character: pick(),
+        japanese: pick(),
+        romaji: pick(),
+        quote: pick(),
+        count: base.count,
+        instructions: base.instructions,
+        progress: base.progress,
+        highlight: base.highlight,
+    };
+    let alignment = ALIGNMENTS[rand::random_range(0..ALIGNMENTS.len())];
+    (palette, alignment)
+}
@@ -0,0 +1,160 @@
+//! `[lint]`: rules for quote data that `check` enforces, each off, a
+//! warning, or an error, so a shared pack can hold its quotes to the same
+//! standard.
+
+use crate::check::Problem;
+use crate::{AnimeQuote, edit};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const DEFAULT_MAX_QUOTE_LENGTH: usize = 200;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    #[default]
+    Off,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warn => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LintConfig {
+    #[serde(default)]
+    require_romaji: Severity,
+    #[serde(default)]
+    require_image: Severity,
+    /// A character and an anime, and where the line is from: an `episode`,
+    /// `source`, or `source_url`.
+    #[serde(default)]
+    require_attribution: Severity,
+    /// Spaces or tabs at the end of a line of any text, as written in the
+    /// file (loading trims them).
+    #[serde(default)]
+    trailing_whitespace: Severity,
+    /// Translations longer than `max_quote_length` characters.
+    #[serde(default)]
+    long_quote: Severity,
+    #[serde(default = "default_max_quote_length")]
+    max_quote_length: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            require_romaji: Severity::Off,
+            require_image: Severity::Off,
+            require_attribution: Severity::Off,
+            trailing_whitespace: Severity::Off,
+            long_quote: Severity::Off,
+            max_quote_length: default_max_quote_length(),
+        }
+    }
+}
+
+fn default_max_quote_length() -> usize {
+    DEFAULT_MAX_QUOTE_LENGTH
+}
+
+/// The quotes that break the rules `config` turns on.
+pub(crate) fn lint(quotes: &[AnimeQuote], config: &LintConfig) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for (index, quote) in quotes.iter().enumerate() {
+        let mut report = |severity: Severity, message: String| {
+            if severity != Severity::Off {
+                problems.push(Problem {
+                    index,
+                    severity,
+                    message,
+                });
+            }
+        };
+        let empty = |value: &Option<String>| value.as_deref().is_none_or(|value| value.is_empty());
+        if empty(&quote.romaji) {
+            report(config.require_romaji, "no romaji".to_string());
+        }
+        if empty(&quote.image) {
+            report(config.require_image, "no image".to_string());
+        }
+        if quote.character.is_empty() || quote.anime.is_empty() {
+            report(
+                config.require_attribution,
+                "the character or anime is empty".to_string(),
+            );
+        }
+        if quote.episode.is_none() && empty(&quote.source) && empty(&quote.source_url) {
+            report(
+                config.require_attribution,
+                "doesn't say where it's from (episode, source, or source_url)".to_string(),
+            );
+        }
+        let length = quote.quote.chars().count();
+        if length > config.max_quote_length {
+            report(
+                config.long_quote,
+                format!(
+                    "the translation is {length} characters, over the {} allowed",
+                    config.max_quote_length
+                ),
+            );
+        }
+    }
+    if config.trailing_whitespace != Severity::Off {
+        problems.extend(trailing_whitespace(quotes, config.trailing_whitespace));
+    }
+    problems
+}
+
+/// Loading trims the text, so this reads the files themselves. Only the
+/// first table with each ID is read, as the collection only has that one.
+fn trailing_whitespace(quotes: &[AnimeQuote], severity: Severity) -> Vec<Problem> {
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for (index, quote) in quotes.iter().enumerate() {
+        positions.entry(quote.stable_id()).or_insert(index);
+    }
+    let mut problems = Vec::new();
+    for file in edit::source_files() {
+        let mut document = match edit::read_document(&file) {
+            Ok(document) => document,
+            Err(error) => {
+                eprintln!("{error}");
+                continue;
+            }
+        };
+        let Ok(tables) = edit::quotes_mut(&mut document, &file) else {
+            continue;
+        };
+        for table in tables.iter() {
+            let Some(index) = positions.remove(&edit::table_id(table)) else {
+                continue;
+            };
+            for (key, item) in table.iter() {
+                let texts: Vec<&str> = match item.as_array() {
+                    Some(array) => array.iter().filter_map(|value| value.as_str()).collect(),
+                    None => item.as_str().into_iter().collect(),
+                };
+                let trailing = texts.iter().any(|text| {
+                    text != &text.trim_end() || text.lines().any(|line| line != line.trim_end())
+                });
+                if trailing {
+                    problems.push(Problem {
+                        index,
+                        severity,
+                        message: format!("{key} has trailing whitespace in {}", file.display()),
+                    });
+                }
+            }
+        }
+    }
+    problems
+}
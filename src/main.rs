@@ -1,33 +1,482 @@
+mod achievements;
+mod add;
+mod add_form;
+mod anime_info;
+mod anki;
+mod artwork;
+mod ascii;
+mod backup;
+mod browse;
+mod calendar;
+mod card;
+mod celebration;
+mod check;
+mod cli;
+mod collections;
+mod control;
+mod detail;
+mod edit;
+mod export;
+mod filter;
+mod filter_menu;
+mod format;
+mod furigana;
+mod gallery;
+mod graphics;
+mod graphql;
+mod grid;
+mod hidden;
+mod images;
+mod keymap;
+mod kiosk;
+mod lint;
+mod merge;
+mod metrics;
+mod organize;
+mod output;
+mod packs;
+mod paths;
+mod pitch;
+mod player;
+mod playlist;
+mod presence;
+mod query;
+mod recent;
+mod romaji;
+mod sanitize;
+mod schedule;
+mod schema;
+mod screenshot;
+mod search;
+mod selection;
+mod server;
+mod site;
+mod sort;
+mod state;
+mod stats;
+mod subtitles;
+mod sync;
+mod template;
+mod thumbnails;
+mod timings;
+mod words;
+
+#[cfg(test)]
+mod snapshot_tests;
+
+use artwork::ImageSlot;
+use clap::Parser;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
-    DefaultTerminal, Frame,
-    layout::Rect,
+    DefaultTerminal, Frame, Terminal,
+    backend::TestBackend,
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Margin, Rect},
     style::{Color, Style, Stylize},
-    symbols::border,
+    symbols::{self, border},
     text::{Line, Span, Text},
-    widgets::{Block, Paragraph},
+    widgets::{Block, Borders, LineGauge, Padding, Paragraph, Wrap},
 };
 use ratatui_image::{
-    FilterType, Resize, StatefulImage, picker::Picker, protocol::StatefulProtocol,
+    FilterType, Resize,
+    picker::{Picker, ProtocolType},
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
 use std::fs;
 use std::io;
-
-#[derive(Debug, Deserialize)]
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct AnimeQuote {
+    /// Explicit stable ID; see [`AnimeQuote::stable_id`]. Serialized views
+    /// carry the resolved ID instead, so it is skipped here.
+    #[serde(default, skip_serializing)]
+    id: Option<String>,
     japanese: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     romaji: Option<String>,
+    /// The reading in kana with `ꜜ` where the pitch drops; see [`pitch`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pitch: Option<String>,
+    /// The Japanese line with readings, like `戦[たたか]わなければ`; see
+    /// [`furigana`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    furigana: Option<String>,
     anime: String,
     character: String,
     quote: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     image: Option<String>,
+    /// Per-quote overrides of the `[ui.ascii]` image size, in cells.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image_width: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image_height: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crop: Option<ImageCrop>,
+    /// The image file's SHA-256, as `sha256sum` prints it, which `check`
+    /// compares the file against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    episode: Option<u32>,
+    /// Where in the episode the line is said, like `12:34` or `1:02:03`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    /// The episode's video file (or a URL the player opens), played from
+    /// `timestamp`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    video: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    season: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    year: Option<u32>,
+    /// Where the line comes from, e.g. "TV", "Movie", or "Manga ch. 96".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    /// Link to the scene itself: a streaming page, wiki entry, or clip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_url: Option<String>,
+    /// `MM-DD` date around which daily and random picks prefer this quote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    show_on: Option<String>,
+    /// Season (`spring`, `summer`, `autumn`, `winter`) during which picks
+    /// prefer this quote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    show_season: Option<String>,
+    /// The character's birthday as `MM-DD`; also settable once per
+    /// character in the `[characters]` table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    birthday: Option<String>,
+    /// Free-form labels such as "comedy" or "motivational", for filtering.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// How much you like the quote, conventionally 1 to 5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rating: Option<u8>,
+    /// Grammar or culture commentary, shown in a pane under the quote.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    /// `YYYY-MM-DD` the quote was added, written by `add`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    added: Option<String>,
+    /// Keys the data file sets that aren't fields above, such as
+    /// `dub_actor`, shown as extra lines under the character.
+    #[serde(flatten, deserialize_with = "extra_fields")]
+    extra: HashMap<String, toml::Value>,
+}
+
+/// The extra keys of a quote with their types, so exports write them back
+/// the same, except that dates and times become text: JSON has no type for
+/// them.
+fn extra_fields<'de, D>(deserializer: D) -> Result<HashMap<String, toml::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut values = HashMap::<String, toml::Value>::deserialize(deserializer)?;
+    values.values_mut().for_each(dates_to_text);
+    Ok(values)
+}
+
+fn dates_to_text(value: &mut toml::Value) {
+    match value {
+        toml::Value::Datetime(datetime) => *value = toml::Value::String(datetime.to_string()),
+        toml::Value::Array(values) => values.iter_mut().for_each(dates_to_text),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, value)| dates_to_text(value)),
+        _ => {}
+    }
+}
+
+fn extra_text(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        toml::Value::Array(values) => values.iter().map(extra_text).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
+
+/// Region of the source image to show, in pixels from the top-left corner.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+struct ImageCrop {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl AnimeQuote {
+    /// Opens the quote's artwork upright and with its crop applied, or
+    /// `None` when the quote has no image.
+    fn load_image(&self) -> Option<image::ImageResult<image::DynamicImage>> {
+        let path = self.image.as_deref()?;
+        Some(open_oriented(path).map(|image| match self.crop {
+            Some(crop) if crop.width > 0 && crop.height > 0 => {
+                image.crop_imm(crop.x, crop.y, crop.width, crop.height)
+            }
+            _ => image,
+        }))
+    }
+
+    /// The ID that favorites, state files, and the HTTP API refer to: the
+    /// `id` from the data file, or else a hash of the anime and the Japanese
+    /// line, so it survives reordering the file and editing translations.
+    fn stable_id(&self) -> String {
+        Self::id_for(self.id.as_deref(), &self.anime, &self.japanese)
+    }
+
+    /// [`AnimeQuote::stable_id`] from the fields it depends on, for quotes
+    /// read as raw tables; the text has to be cleaned with
+    /// [`sanitize::clean`] first, as it is for loaded quotes.
+    fn id_for(id: Option<&str>, anime: &str, japanese: &str) -> String {
+        if let Some(id) = id.map(str::trim).filter(|id| !id.is_empty()) {
+            return id.to_string();
+        }
+        let content = format!("{}\n{}", anime.trim().to_lowercase(), japanese.trim());
+        format!("{:012x}", selection::fnv1a(content.as_bytes()) >> 16)
+    }
+
+    /// "TV · Season 1, Episode 5 · 2013", or `None` when no metadata is set.
+    fn metadata_line(&self) -> Option<String> {
+        let mut placement: Vec<String> = [
+            self.season.map(|season| format!("Season {season}")),
+            self.episode.map(|episode| format!("Episode {episode}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if let Some(timestamp) = &self.timestamp {
+            match placement.last_mut() {
+                Some(last) => *last = format!("{last} at {timestamp}"),
+                None => placement.push(format!("at {timestamp}")),
+            }
+        }
+        let parts: Vec<String> = [
+            self.source
+                .as_deref()
+                .map(str::trim)
+                .filter(|source| !source.is_empty())
+                .map(str::to_string),
+            (!placement.is_empty()).then(|| placement.join(", ")),
+            self.year.map(|year| year.to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+
+    /// The extra fields as `("Dub actor", value)`, sorted by key. Lists are
+    /// joined with commas; other values are written as in TOML.
+    fn extra_lines(&self) -> Vec<(String, String)> {
+        let mut keys: Vec<&String> = self.extra.keys().collect();
+        keys.sort();
+        keys.into_iter()
+            .map(|key| {
+                let words = key.replace(['_', '-'], " ");
+                let mut chars = words.trim().chars();
+                let label = chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default();
+                (label, extra_text(&self.extra[key]))
+            })
+            .collect()
+    }
+
+    fn page_url(&self, provider: SearchProvider) -> String {
+        match self.url.as_deref().map(str::trim) {
+            Some(url) if !url.is_empty() => url.to_string(),
+            _ => provider.search_url(&self.anime),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct AnimeData {
+    /// Empty in a data file whose quotes all moved to `quotes.d`.
+    #[serde(default)]
     quotes: Vec<AnimeQuote>,
+    /// Details shared by every quote of a character, keyed by name.
+    #[serde(default)]
+    characters: std::collections::HashMap<String, CharacterInfo>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CharacterInfo {
+    #[serde(default)]
+    birthday: Option<String>,
+}
+
+impl AnimeData {
+    fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = std::path::Path::new(path);
+        let content = schema::read_migrated(path)?;
+        let mut data: Self = toml::from_str(&content)?;
+        data.resolve_paths(path);
+        // IDs hash the cleaned text, so state recorded before the text was
+        // cleaned on load is moved over to them.
+        let written: Vec<String> = data.quotes.iter().map(AnimeQuote::stable_id).collect();
+        data.sanitize();
+        let renamed: Vec<(String, String)> = written
+            .into_iter()
+            .zip(&data.quotes)
+            .map(|(written, quote)| (written, quote.stable_id()))
+            .filter(|(written, cleaned)| written != cleaned)
+            .collect();
+        if !renamed.is_empty() {
+            state::State::rename_ids(&renamed);
+        }
+        data.apply_characters();
+        Ok(data)
+    }
+
+    /// Cleans up invisible characters and stray whitespace in the quotes
+    /// and the `[characters]` names; see [`sanitize`].
+    fn sanitize(&mut self) {
+        for quote in &mut self.quotes {
+            quote.sanitize();
+        }
+        self.characters = std::mem::take(&mut self.characters)
+            .into_iter()
+            .map(|(name, info)| (sanitize::clean(&name), info))
+            .collect();
+    }
+
+    /// Fills in character details the quotes leave out from `[characters]`.
+    fn apply_characters(&mut self) {
+        for quote in &mut self.quotes {
+            if quote.birthday.is_none() {
+                quote.birthday = self
+                    .characters
+                    .get(&quote.character)
+                    .and_then(|info| info.birthday.clone());
+            }
+        }
+    }
+
+    /// Image paths are written relative to the file that lists them.
+    fn resolve_paths(&mut self, file: &std::path::Path) {
+        let base = file.parent().unwrap_or(std::path::Path::new(""));
+        for quote in &mut self.quotes {
+            if let Some(image) = quote.image.as_mut().filter(|image| !image.contains("://")) {
+                *image = paths::resolve(base, image);
+            }
+            if let Some(video) = quote.video.as_mut().filter(|video| !video.contains("://")) {
+                *video = paths::resolve(base, video);
+            }
+        }
+    }
+
+    /// The data file at `path` with the quotes of its `quotes.d` files
+    /// after its own, and their `[characters]` details shared.
+    fn load_with_split(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut data = Self::load_from_file(path)?;
+        for file in organize::split_files(std::path::Path::new(path)) {
+            let part = Self::load_from_file(&file.to_string_lossy())
+                .map_err(|error| format!("{}: {error}", file.display()))?;
+            data.quotes.extend(part.quotes);
+            for (name, info) in part.characters {
+                data.characters.entry(name).or_insert(info);
+            }
+        }
+        data.apply_characters();
+        Ok(data)
+    }
+
+    /// The collection at `path` followed by the quotes of active packs.
+    fn load_collection(
+        path: &str,
+        packs: &packs::PacksConfig,
+    ) -> Result<Vec<AnimeQuote>, Box<dyn std::error::Error>> {
+        let mut quotes = Self::load_with_split(path)?.quotes;
+        packs::merge_active(&mut quotes, packs);
+        let mut seen = std::collections::HashSet::new();
+        for quote in &quotes {
+            let id = quote.stable_id();
+            if !seen.insert(id.clone()) {
+                eprintln!("duplicate quote id {id}; only the first is reachable by id");
+            }
+        }
+        schedule::warn_invalid(&quotes);
+        Ok(quotes)
+    }
+}
+
+const DATA_PATH: &str = "anime.toml";
+const CONFIG_PATH: &str = "config.toml";
+
+struct FilePaths {
+    data: String,
+    config: String,
+}
+
+static FILE_PATHS: OnceLock<FilePaths> = OnceLock::new();
+
+/// Records the files chosen with `--data`/`--config` (or their environment
+/// variables) for the rest of the process.
+fn set_file_paths(data: Option<PathBuf>, config: Option<PathBuf>) {
+    let to_string = |path: PathBuf| path.to_string_lossy().into_owned();
+    let _ = FILE_PATHS.set(FilePaths {
+        data: data.map_or_else(|| DATA_PATH.to_string(), to_string),
+        config: config.map_or_else(|| CONFIG_PATH.to_string(), to_string),
+    });
+}
+
+/// The quotes file in use, `anime.toml` unless overridden.
+fn data_path() -> &'static str {
+    FILE_PATHS
+        .get()
+        .map_or(DATA_PATH, |paths| paths.data.as_str())
+}
+
+/// The config file in use, `config.toml` unless overridden.
+fn config_path() -> &'static str {
+    FILE_PATHS
+        .get()
+        .map_or(CONFIG_PATH, |paths| paths.config.as_str())
+}
+
+/// `$HOME`, or on Windows, where it is usually unset, `%USERPROFILE%`.
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+/// `$XDG_DATA_HOME/anime-quotes`, falling back to `%APPDATA%\anime-quotes`
+/// on Windows and `~/.local/share/anime-quotes` elsewhere.
+fn data_dir() -> PathBuf {
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) => PathBuf::from(dir).join("anime-quotes"),
+        None => match std::env::var_os("APPDATA").filter(|_| cfg!(windows)) {
+            Some(dir) => PathBuf::from(dir).join("anime-quotes"),
+            None => home_dir().join(".local/share/anime-quotes"),
+        },
+    }
+}
+
+/// Where files that can be rebuilt from the data are kept:
+/// `$XDG_CACHE_HOME/anime-quotes`, falling back to
+/// `%LOCALAPPDATA%\anime-quotes\cache` on Windows and `~/.cache/anime-quotes`
+/// elsewhere.
+fn cache_dir() -> PathBuf {
+    match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir).join("anime-quotes"),
+        None => match std::env::var_os("LOCALAPPDATA").filter(|_| cfg!(windows)) {
+            Some(dir) => PathBuf::from(dir).join("anime-quotes").join("cache"),
+            None => home_dir().join(".cache/anime-quotes"),
+        },
+    }
 }
 
 const DEFAULT_ASCII_TARGET_WIDTH: u32 = 30;
@@ -37,41 +486,193 @@ const DEFAULT_ASCII_DETAIL_Y: u32 = 2;
 const DEFAULT_ASCII_GRADIENT: &str =
     r#"$@B%8&WM#*oahkbdpqwmZO0QLCJUYXzcvunxrjft/\|()1{}[]?-_+~<>i!lI;:,"^`'. "#;
 const DEFAULT_SHOW_INSTRUCTIONS: bool = true;
-
-#[derive(Debug, Deserialize)]
+const DEFAULT_IDLE_INTERVAL_SECS: u64 = 15;
+const DEFAULT_CLOCK_FORMAT: &str = "%H:%M · %A, %B %-d";
+const DEFAULT_SEARCH_PROVIDER: &str = "myanimelist";
+const DEFAULT_SORT: &str = "data";
+const DEFAULT_HIGHLIGHT: &str = "black on yellow";
+const DEFAULT_PROGRESS: &str = "both";
+const DEFAULT_CELEBRATION: &str = "sakura";
+/// Widest the notes pane gets, in cells.
+const NOTES_WIDTH: u16 = 72;
+
+/// Widest the progress gauge gets, in cells.
+const PROGRESS_GAUGE_WIDTH: u16 = 40;
+
+#[derive(Debug, Default, Deserialize)]
 struct ConfigRoot {
     #[serde(default)]
     ui: UiConfig,
-}
-
-impl Default for ConfigRoot {
-    fn default() -> Self {
-        Self {
-            ui: UiConfig::default(),
-        }
-    }
+    #[serde(default)]
+    card: card::CardConfig,
+    #[serde(default)]
+    screenshot: screenshot::ScreenshotConfig,
+    #[serde(default)]
+    discord: presence::PresenceConfig,
+    #[serde(default)]
+    packs: packs::PacksConfig,
+    #[serde(default)]
+    sync: sync::SyncConfig,
+    #[serde(default)]
+    kiosk: kiosk::KioskConfig,
+    #[serde(default)]
+    schedule: schedule::ScheduleConfig,
+    #[serde(default)]
+    collections: Vec<collections::CollectionConfig>,
+    #[serde(default)]
+    romaji: romaji::RomajiConfig,
+    #[serde(default)]
+    anki: anki::AnkiConfig,
+    #[serde(default)]
+    player: player::PlayerConfig,
+    #[serde(default)]
+    lint: lint::LintConfig,
 }
 
 #[derive(Debug, Deserialize)]
 struct UiConfig {
     #[serde(default = "default_show_instructions")]
     show_instructions: bool,
+    /// The hints in the instructions bar, in order, instead of the default
+    /// ones.
+    #[serde(default)]
+    instructions: Option<Vec<keymap::HintConfig>>,
+    /// A line above the instructions bar with the active filter, the
+    /// slideshow's shuffle, whether the quote is a favorite, and the data
+    /// file.
+    #[serde(default)]
+    status_bar: bool,
+    /// Whether the Japanese line starts with its kanji replaced by the
+    /// readings from `furigana`.
+    #[serde(default)]
+    kana_only: bool,
+    /// The quote's fields the viewer shows, in order.
+    #[serde(default = "default_fields")]
+    fields: Vec<String>,
+    /// A template for the quote's text, like the `random --template` ones,
+    /// instead of the labelled `fields`.
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default = "default_search_provider")]
+    search_provider: String,
+    #[serde(default = "default_sort")]
+    sort: String,
+    #[serde(default = "default_progress")]
+    progress: String,
+    /// `sakura`, `confetti`, or `off`, for achievements and streak
+    /// milestones.
+    #[serde(default = "default_celebration")]
+    celebration: String,
+    /// `kitty`, `sixel`, `iterm2`, or `halfblocks`, instead of what the
+    /// terminal is detected to support.
+    #[serde(default)]
+    image_protocol: Option<String>,
     #[serde(default)]
     ascii: AsciiConfig,
     #[serde(default)]
     colors: ColorConfig,
+    #[serde(default)]
+    idle: IdleConfig,
+    #[serde(default)]
+    clock: ClockConfig,
 }
 
 impl Default for UiConfig {
     fn default() -> Self {
         Self {
             show_instructions: DEFAULT_SHOW_INSTRUCTIONS,
+            instructions: None,
+            status_bar: false,
+            kana_only: false,
+            fields: default_fields(),
+            template: None,
+            search_provider: default_search_provider(),
+            sort: default_sort(),
+            progress: default_progress(),
+            celebration: default_celebration(),
+            image_protocol: None,
             ascii: AsciiConfig::default(),
             colors: ColorConfig::default(),
+            idle: IdleConfig::default(),
+            clock: ClockConfig::default(),
+        }
+    }
+}
+
+/// A header line with the time and date, for using the viewer as a desk clock.
+#[derive(Debug, Deserialize)]
+struct ClockConfig {
+    #[serde(default)]
+    enabled: bool,
+    /// `strftime`-style format, as in `chrono`.
+    #[serde(default = "default_clock_format")]
+    format: String,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: default_clock_format(),
+        }
+    }
+}
+
+fn default_clock_format() -> String {
+    DEFAULT_CLOCK_FORMAT.to_string()
+}
+
+/// Cycling through quotes on its own once nobody has touched a key for a
+/// while.
+#[derive(Debug, Deserialize)]
+struct IdleConfig {
+    /// Seconds without a key press before the slideshow starts; 0 disables it.
+    #[serde(default)]
+    after_secs: u64,
+    #[serde(default = "default_idle_interval_secs")]
+    interval_secs: u64,
+    #[serde(default)]
+    shuffle: bool,
+    /// `order`, `shuffle`, or `least-seen`; overrides `shuffle`.
+    #[serde(default)]
+    rotation: Option<String>,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            after_secs: 0,
+            interval_secs: DEFAULT_IDLE_INTERVAL_SECS,
+            shuffle: false,
+            rotation: None,
         }
     }
 }
 
+fn default_idle_interval_secs() -> u64 {
+    DEFAULT_IDLE_INTERVAL_SECS
+}
+
+struct IdleSettings {
+    after: Duration,
+    interval: Duration,
+    rotation: selection::Rotation,
+}
+
+impl IdleConfig {
+    fn to_settings(&self) -> Option<IdleSettings> {
+        (self.after_secs > 0).then(|| IdleSettings {
+            after: Duration::from_secs(self.after_secs),
+            interval: Duration::from_secs(self.interval_secs.max(1)),
+            rotation: selection::Rotation::resolve(
+                self.rotation.as_deref(),
+                self.shuffle,
+                "ui.idle",
+            ),
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct AsciiConfig {
     #[serde(default = "default_ascii_target_width")]
@@ -114,6 +715,12 @@ struct ColorConfig {
     count: String,
     #[serde(default = "default_color_instructions")]
     instructions: String,
+    #[serde(default = "default_color_progress")]
+    progress: String,
+    /// Search matches: a color, `on <color>` for the background, and any of
+    /// `bold`, `italic`, `underline`, `reversed`.
+    #[serde(default = "default_color_highlight")]
+    highlight: String,
 }
 
 impl Default for ColorConfig {
@@ -126,6 +733,8 @@ impl Default for ColorConfig {
             quote: default_color_quote(),
             count: default_color_count(),
             instructions: default_color_instructions(),
+            progress: default_color_progress(),
+            highlight: default_color_highlight(),
         }
     }
 }
@@ -154,6 +763,99 @@ fn default_show_instructions() -> bool {
     DEFAULT_SHOW_INSTRUCTIONS
 }
 
+fn default_search_provider() -> String {
+    DEFAULT_SEARCH_PROVIDER.to_string()
+}
+
+fn default_sort() -> String {
+    DEFAULT_SORT.to_string()
+}
+
+fn default_progress() -> String {
+    DEFAULT_PROGRESS.to_string()
+}
+
+fn default_celebration() -> String {
+    DEFAULT_CELEBRATION.to_string()
+}
+
+fn default_fields() -> Vec<String> {
+    Field::DEFAULT
+        .iter()
+        .map(|field| field.name().to_string())
+        .collect()
+}
+
+/// A field of the quote the viewer can show, picked and ordered by
+/// `[ui] fields`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    Anime,
+    Character,
+    Japanese,
+    Romaji,
+    Pitch,
+    Quote,
+}
+
+impl Field {
+    const DEFAULT: [Self; 6] = [
+        Self::Anime,
+        Self::Character,
+        Self::Japanese,
+        Self::Romaji,
+        Self::Pitch,
+        Self::Quote,
+    ];
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::DEFAULT
+            .into_iter()
+            .find(|field| value.trim().eq_ignore_ascii_case(field.name()))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Anime => "anime",
+            Self::Character => "character",
+            Self::Japanese => "japanese",
+            Self::Romaji => "romaji",
+            Self::Pitch => "pitch",
+            Self::Quote => "quote",
+        }
+    }
+
+    /// Fields of different groups are set apart by a blank line.
+    fn group(self) -> u8 {
+        match self {
+            Self::Anime | Self::Character => 0,
+            Self::Japanese | Self::Romaji | Self::Pitch => 1,
+            Self::Quote => 2,
+        }
+    }
+}
+
+/// How the viewer shows its position in the collection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ProgressDisplay {
+    /// `(3/42)`
+    Counter,
+    /// A slim bar
+    Gauge,
+    Both,
+}
+
+impl ProgressDisplay {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "counter" => Some(Self::Counter),
+            "gauge" => Some(Self::Gauge),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+}
+
 fn default_color_anime() -> String {
     "yellow".to_string()
 }
@@ -182,18 +884,24 @@ fn default_color_instructions() -> String {
     "blue".to_string()
 }
 
-impl UiConfig {
+fn default_color_progress() -> String {
+    "cyan".to_string()
+}
+
+fn default_color_highlight() -> String {
+    DEFAULT_HIGHLIGHT.to_string()
+}
+
+impl ConfigRoot {
     fn load_from_file(path: &str) -> Self {
         match fs::read_to_string(path) {
-            Ok(content) => toml::from_str::<ConfigRoot>(&content)
-                .map(|root| root.ui)
-                .unwrap_or_else(|error| {
-                    eprintln!("failed to parse {path}: {error}");
-                    UiConfig::default()
-                }),
+            Ok(content) => toml::from_str::<ConfigRoot>(&content).unwrap_or_else(|error| {
+                eprintln!("failed to parse {path}: {error}");
+                ConfigRoot::default()
+            }),
             Err(error) => {
                 eprintln!("failed to read {path}: {error}");
-                UiConfig::default()
+                ConfigRoot::default()
             }
         }
     }
@@ -203,7 +911,6 @@ impl UiConfig {
 struct AsciiSettings {
     base_width: u32,
     char_aspect: f32,
-    #[allow(dead_code)]
     gradient: Vec<char>,
     detail_x: u32,
     detail_y: u32,
@@ -264,6 +971,8 @@ struct Palette {
     quote: Color,
     count: Color,
     instructions: Color,
+    progress: Color,
+    highlight: Style,
 }
 
 impl Default for Palette {
@@ -276,6 +985,8 @@ impl Default for Palette {
             quote: Color::White,
             count: Color::Gray,
             instructions: Color::Blue,
+            progress: Color::Cyan,
+            highlight: default_highlight(),
         }
     }
 }
@@ -290,6 +1001,14 @@ impl ColorConfig {
             quote: parse_color_or_default(&self.quote, Color::White),
             count: parse_color_or_default(&self.count, Color::Gray),
             instructions: parse_color_or_default(&self.instructions, Color::Blue),
+            progress: parse_color_or_default(&self.progress, Color::Cyan),
+            highlight: parse_style(&self.highlight).unwrap_or_else(|| {
+                eprintln!(
+                    "unknown highlight {:?}, falling back to {DEFAULT_HIGHLIGHT:?}",
+                    self.highlight
+                );
+                default_highlight()
+            }),
         }
     }
 }
@@ -298,6 +1017,29 @@ fn parse_color_or_default(value: &str, default: Color) -> Color {
     parse_color(value).unwrap_or(default)
 }
 
+fn default_highlight() -> Style {
+    Style::new().fg(Color::Black).bg(Color::Yellow)
+}
+
+/// Reads styles like `"black on yellow bold"`: a foreground color, `on` and
+/// a background color, and modifiers, in any order.
+fn parse_style(value: &str) -> Option<Style> {
+    let mut style = Style::new();
+    let mut words = value.split_whitespace();
+    while let Some(word) = words.next() {
+        style = match word.to_lowercase().as_str() {
+            "on" => style.bg(parse_color(words.next()?)?),
+            "bold" => style.bold(),
+            "italic" => style.italic(),
+            "underline" | "underlined" => style.underlined(),
+            "reversed" | "reverse" => style.reversed(),
+            "dim" => style.dim(),
+            _ => style.fg(parse_color(word)?),
+        };
+    }
+    (style != Style::new()).then_some(style)
+}
+
 fn parse_color(value: &str) -> Option<Color> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -324,6 +1066,65 @@ fn parse_color(value: &str) -> Option<Color> {
     }
 }
 
+/// Approximate sRGB values for the named terminal colors, used when a palette
+/// color has to leave the terminal (image and document exports).
+fn color_to_rgb(color: Color) -> [u8; 3] {
+    match color {
+        Color::Rgb(r, g, b) => [r, g, b],
+        Color::Black => [0, 0, 0],
+        Color::Red => [205, 49, 49],
+        Color::Green => [13, 188, 121],
+        Color::Yellow => [229, 229, 16],
+        Color::Blue => [36, 114, 200],
+        Color::Magenta => [188, 63, 188],
+        Color::Cyan => [17, 168, 205],
+        Color::Gray => [204, 204, 204],
+        Color::DarkGray => [118, 118, 118],
+        Color::LightRed => [241, 76, 76],
+        Color::LightGreen => [35, 209, 139],
+        Color::LightYellow => [245, 245, 67],
+        Color::LightBlue => [59, 142, 234],
+        Color::LightMagenta => [214, 112, 214],
+        Color::LightCyan => [41, 184, 219],
+        Color::Indexed(index) => indexed_to_rgb(index),
+        _ => [229, 229, 229],
+    }
+}
+
+/// Resolves an xterm 256-color palette index to sRGB.
+fn indexed_to_rgb(index: u8) -> [u8; 3] {
+    const ANSI: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Gray,
+        Color::DarkGray,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::White,
+    ];
+    match index {
+        0..=15 => color_to_rgb(ANSI[index as usize]),
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let cube = index - 16;
+            [level(cube / 36), level((cube / 6) % 6), level(cube % 6)]
+        }
+        232..=255 => {
+            let gray = 8 + (index - 232) * 10;
+            [gray, gray, gray]
+        }
+    }
+}
+
 fn parse_hex_color(value: &str) -> Option<Color> {
     let hex = value.strip_prefix('#')?;
     match hex.len() {
@@ -343,124 +1144,938 @@ fn parse_hex_color(value: &str) -> Option<Color> {
     }
 }
 
-struct ImageSlot {
-    protocol: StatefulProtocol,
+#[derive(Clone, Copy, Debug, Default)]
+enum SearchProvider {
+    #[default]
+    MyAnimeList,
+    AniList,
+}
+
+impl SearchProvider {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "myanimelist" | "mal" => Some(Self::MyAnimeList),
+            "anilist" => Some(Self::AniList),
+            _ => None,
+        }
+    }
+
+    fn search_url(self, title: &str) -> String {
+        let query = encode_query_component(title);
+        match self {
+            Self::MyAnimeList => format!("https://myanimelist.net/anime.php?q={query}"),
+            Self::AniList => format!("https://anilist.co/search/anime?search={query}"),
+        }
+    }
+}
+
+fn encode_query_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Like `image::open`, but applies the EXIF orientation so photos and
+/// screenshots saved rotated are shown the right way up.
+fn open_oriented(path: &str) -> image::ImageResult<image::DynamicImage> {
+    let mut decoder = image::ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = image::ImageDecoder::orientation(&mut decoder)?;
+    let mut image = image::DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+fn open_in_browser(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
 }
 
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long new viewing history waits in memory before `state.toml` is
+/// rewritten, so a slideshow doesn't write it on every quote.
+const VIEWS_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Streaks of a multiple of this many days get a celebration.
+const STREAK_MILESTONE: u32 = 7;
+/// How long an achievement toast stays up.
+const TOAST_DURATION: Duration = Duration::from_secs(4);
 const IMAGE_TOP_PADDING: u16 = 2;
+/// How many quotes on each side of the current one have their images
+/// decoded ahead of time.
+const IMAGE_PREFETCH: usize = 3;
+/// How many images are decoded at most, and how many wait to be at once.
+const DECODED_IMAGES: usize = 48;
+const IMAGES_IN_FLIGHT: usize = 8;
 const IMAGE_TEXT_GAP: u16 = 1;
 
-fn main() -> io::Result<()> {
+fn main() -> color_eyre::Result<()> {
+    color_eyre::install()?;
+    let cli = cli::Cli::parse();
+    set_file_paths(cli.data, cli.config);
+    let command = cli.command.or_else(|| {
+        cli.recent
+            .then(|| cli::Command::Recent(cli::RecentArgs::default()))
+    });
+    if let Some(command) = command {
+        return cli::run(command);
+    }
+
+    let timings = cli.timings.then(timings::Timings::start);
     let mut terminal = ratatui::init();
-    let app_result = App::default().run(&mut terminal);
+    let mut app = App::new(cli.kiosk, cli.playlist.as_deref(), timings);
+    let app_result = app.run(&mut terminal);
     ratatui::restore();
-    app_result
+    if let Some(timings) = &app.timings {
+        eprint!("{}", timings.report());
+    }
+    Ok(app_result?)
 }
 
 pub struct App {
     quotes: Vec<AnimeQuote>,
     image_cache: Vec<Option<ImageSlot>>,
+    image_loader: artwork::ImageLoader,
+    /// Quotes whose images are being decoded, and those whose image failed.
+    images_pending: HashSet<usize>,
+    images_failed: HashSet<usize>,
+    /// Quotes with a decoded image, least recently drawn first.
+    images_recent: VecDeque<usize>,
     image_resize: Resize,
     image_width: u16,
     image_height: u16,
     palette: Palette,
+    ascii_settings: AsciiSettings,
     show_instructions: bool,
+    hints: Vec<keymap::Hint>,
+    status_bar: bool,
+    kana_only: bool,
+    /// Whether the notes pane is open rather than folded to a hint.
+    notes_open: bool,
+    /// The quote and the word of its Japanese line under the word cursor.
+    word: Option<(usize, usize)>,
+    fields: Vec<Field>,
+    template: Option<template::Template>,
+    search_provider: SearchProvider,
+    card_config: card::CardConfig,
+    player_config: player::PlayerConfig,
+    screenshot_config: screenshot::ScreenshotConfig,
+    /// Encodes images for the terminal's graphics protocol.
+    picker: Picker,
+    last_area: Rect,
+    control: Option<(control::ControlServer, Receiver<control::ControlRequest>)>,
+    presence: Option<presence::Presence>,
+    presence_index: Option<usize>,
+    kiosk: Option<kiosk::Kiosk>,
+    alignment: Alignment,
+    idle: Option<IdleSettings>,
+    clock_format: Option<String>,
+    /// The stable IDs of the quotes on screen today.
+    seen_today: (chrono::NaiveDate, HashSet<String>),
+    state: state::State,
+    schedule: schedule::ScheduleConfig,
+    calendar: Option<calendar::Calendar>,
+    recent: Option<recent::Recent>,
+    hidden: Option<hidden::Hidden>,
+    achievements: Option<achievements::Achievements>,
+    browse: Option<browse::Browse>,
+    search: Option<search::Search>,
+    filter_menu: Option<filter_menu::FilterMenu>,
+    add_form: Option<add_form::AddForm>,
+    grid: Option<grid::Grid>,
+    gallery: Option<gallery::Gallery>,
+    detail: Option<detail::AnimeDetail>,
+    character_detail: Option<detail::CharacterDetail>,
+    /// Narrows `order` to the quotes it matches.
+    filter: filter::QuoteFilter,
+    /// The saved filters from `[[collections]]`, and which one `filter` is.
+    collections: Vec<collections::Collection>,
+    collection: Option<usize>,
+    collection_picker: Option<collections::Collections>,
+    /// Stable IDs of the quotes queued from the browse list or a playlist,
+    /// in play order, and whether `order` is the queue instead.
+    queue: Vec<String>,
+    queue_playing: bool,
+    /// The quote pinned on the left of the compare view, beside the current
+    /// one.
+    compare: Option<usize>,
+    /// Which packs are merged in, for reloading the collection.
+    packs: packs::PacksConfig,
+    /// Newly unlocked achievements to announce, the first one on screen
+    /// since `toast_since`.
+    toasts: VecDeque<&'static achievements::Achievement>,
+    toast_since: Instant,
+    celebration_style: celebration::CelebrationStyle,
+    celebration: Option<celebration::Celebration>,
+    /// The quote whose view was last recorded in `state`.
+    viewed_index: Option<usize>,
+    /// When the first view not yet saved was recorded; see
+    /// [`App::save_views`].
+    views_unsaved_since: Option<Instant>,
+    sort: sort::SortOrder,
+    /// The lines of the quotes drawn so far, by stable ID so they stay
+    /// right when quotes are added, as of the search highlight and day in
+    /// `text_cache_key`; emptied when the data or style changes.
+    text_cache: HashMap<String, Text<'static>>,
+    text_cache_key: (Option<String>, chrono::NaiveDate),
+    /// Indices into `quotes` in `sort` order; the counter and navigation
+    /// follow it.
+    order: Vec<usize>,
+    progress: ProgressDisplay,
+    idle_slideshow: bool,
+    last_input: Instant,
+    last_advance: Instant,
+    status: Option<String>,
     current_index: usize,
+    /// Set with `--timings`.
+    timings: Option<timings::Timings>,
     exit: bool,
 }
 
-impl Default for App {
-    fn default() -> Self {
-        let quotes = Self::load_quotes().unwrap_or_default();
-        let ui_config = UiConfig::load_from_file("config.toml");
+impl App {
+    /// Loads the config and collection; `kiosk` starts the `--kiosk`
+    /// slideshow with the `[kiosk]` settings, `playlist` plays the quotes in
+    /// that file, and `timings` records how long each step takes.
+    pub fn new(
+        kiosk: bool,
+        playlist: Option<&Path>,
+        mut timings: Option<timings::Timings>,
+    ) -> Self {
+        let mut phase = |name| {
+            if let Some(timings) = &mut timings {
+                timings.phase(name);
+            }
+        };
+        phase("terminal setup");
+        let config = ConfigRoot::load_from_file(config_path());
+        phase("config parsing");
+        let quotes = AnimeData::load_collection(data_path(), &config.packs).unwrap_or_default();
+        phase("data loading");
+        let protocol = config.ui.image_protocol.as_deref().and_then(|name| {
+            let protocol = graphics::parse_protocol(name);
+            if protocol.is_none() {
+                eprintln!("unknown image protocol {name:?}, detecting the terminal's instead");
+            }
+            protocol
+        });
+        let picker = graphics::picker(protocol);
+        phase("picker detection");
+
+        let (control, control_error) = match control::listen(&control::default_socket_path()) {
+            Ok(control) => (Some(control), None),
+            Err(error) => (None, Some(format!("Control socket disabled: {error}"))),
+        };
+        let (presence, presence_error) = match presence::Presence::start(&config.discord) {
+            Ok(presence) => (presence, None),
+            Err(error) => (None, Some(format!("Discord presence disabled: {error}"))),
+        };
+
+        let mut app =
+            Self::from_parts(config, quotes, picker, state::State::load(), kiosk, timings);
+        app.control = control;
+        app.presence = presence;
+        app.status = control_error.or(presence_error);
+        if let Some(path) = playlist {
+            app.load_playlist(path);
+        }
+        if let Some(timings) = &mut app.timings {
+            timings.phase("other setup");
+        }
+        app
+    }
+
+    /// The viewer for `quotes` with `config` and `state`, without the
+    /// control socket or Discord presence, as the snapshot tests draw it.
+    fn from_parts(
+        config: ConfigRoot,
+        quotes: Vec<AnimeQuote>,
+        picker: Picker,
+        state: state::State,
+        kiosk: bool,
+        timings: Option<timings::Timings>,
+    ) -> Self {
+        let ui_config = config.ui;
         let ascii_settings = ui_config.ascii.to_settings();
         let palette = ui_config.colors.to_palette();
         let (image_width, image_height) = ascii_settings.target_dimensions();
         let image_resize = ascii_settings.resize_strategy();
-
-        let picker = match Picker::from_query_stdio() {
-            Ok(picker) => picker,
-            Err(error) => {
-                eprintln!("failed to detect terminal graphics capabilities: {error}");
-                Picker::from_fontsize((10, 20))
-            }
-        };
-
-        let image_cache = quotes
-            .iter()
-            .map(|quote| {
-                quote
-                    .image
-                    .as_deref()
-                    .and_then(|path| match image::open(path) {
-                        Ok(image) => {
-                            let protocol = picker.new_resize_protocol(image);
-                            Some(ImageSlot { protocol })
-                        }
-                        Err(error) => {
-                            eprintln!("failed to load image from {path}: {error}");
-                            None
-                        }
-                    })
-            })
-            .collect();
-        Self {
+        let search_provider =
+            SearchProvider::parse(&ui_config.search_provider).unwrap_or_else(|| {
+                eprintln!(
+                    "unknown search provider {:?}, falling back to {DEFAULT_SEARCH_PROVIDER}",
+                    ui_config.search_provider
+                );
+                SearchProvider::default()
+            });
+
+        let image_cache = quotes.iter().map(|_| None).collect();
+        let sort = sort::SortOrder::parse(&ui_config.sort).unwrap_or_else(|| {
+            eprintln!(
+                "unknown sort {:?}, falling back to {DEFAULT_SORT}",
+                ui_config.sort
+            );
+            sort::SortOrder::default()
+        });
+        let order = sort.order(&quotes);
+        let progress = ProgressDisplay::parse(&ui_config.progress).unwrap_or_else(|| {
+            eprintln!(
+                "unknown progress display {:?}, falling back to {DEFAULT_PROGRESS}",
+                ui_config.progress
+            );
+            ProgressDisplay::Both
+        });
+        let celebration_style = celebration::CelebrationStyle::parse(&ui_config.celebration)
+            .unwrap_or_else(|| {
+                eprintln!(
+                    "unknown celebration {:?}, falling back to {DEFAULT_CELEBRATION}",
+                    ui_config.celebration
+                );
+                celebration::CelebrationStyle::Sakura
+            });
+        let kiosk = kiosk.then(|| config.kiosk.to_kiosk());
+        let mut app = Self {
             quotes,
             image_cache,
+            image_loader: artwork::ImageLoader::new(
+                &picker,
+                timings.as_ref().map(|timings| timings.decoding.clone()),
+            ),
+            images_pending: HashSet::new(),
+            images_failed: HashSet::new(),
+            images_recent: VecDeque::new(),
             image_resize,
             image_width,
             image_height,
             palette,
-            show_instructions: ui_config.show_instructions,
-            current_index: 0,
+            ascii_settings,
+            show_instructions: ui_config.show_instructions && kiosk.is_none(),
+            hints: keymap::hints(ui_config.instructions.as_deref()),
+            status_bar: ui_config.status_bar,
+            kana_only: ui_config.kana_only,
+            notes_open: false,
+            word: None,
+            fields: ui_config
+                .fields
+                .iter()
+                .filter_map(|name| {
+                    let field = Field::parse(name);
+                    if field.is_none() {
+                        eprintln!("unknown field {name:?} in [ui] fields");
+                    }
+                    field
+                })
+                .collect(),
+            template: ui_config.template.as_deref().and_then(|source| {
+                template::Template::parse(source, output::TEMPLATE_FIELDS)
+                    .inspect_err(|error| eprintln!("ignoring [ui] template: {error}"))
+                    .ok()
+            }),
+            search_provider,
+            card_config: config.card,
+            player_config: config.player,
+            screenshot_config: config.screenshot,
+            picker,
+            last_area: Rect::default(),
+            control: None,
+            presence: None,
+            presence_index: None,
+            idle: ui_config.idle.to_settings(),
+            clock_format: ui_config.clock.enabled.then_some(ui_config.clock.format),
+            seen_today: (chrono::Local::now().date_naive(), HashSet::new()),
+            state,
+            schedule: config.schedule,
+            calendar: None,
+            recent: None,
+            hidden: None,
+            achievements: None,
+            browse: None,
+            search: None,
+            filter_menu: None,
+            add_form: None,
+            grid: None,
+            gallery: None,
+            detail: None,
+            character_detail: None,
+            filter: filter::QuoteFilter::default(),
+            collections: collections::load(&config.collections),
+            collection: None,
+            collection_picker: None,
+            queue: Vec::new(),
+            queue_playing: false,
+            compare: None,
+            packs: config.packs,
+            toasts: VecDeque::new(),
+            toast_since: Instant::now(),
+            celebration_style,
+            celebration: None,
+            viewed_index: None,
+            views_unsaved_since: None,
+            text_cache: HashMap::new(),
+            text_cache_key: (None, chrono::Local::now().date_naive()),
+            current_index: order.first().copied().unwrap_or(0),
+            timings,
+            sort,
+            order,
+            progress,
+            idle_slideshow: false,
+            last_input: Instant::now(),
+            kiosk,
+            alignment: Alignment::Center,
+            last_advance: Instant::now(),
+            status: None,
             exit: false,
-        }
-    }
-}
-
-impl App {
-    fn load_quotes() -> Result<Vec<AnimeQuote>, Box<dyn std::error::Error>> {
-        let content = fs::read_to_string("anime.toml")?;
-        let data: AnimeData = toml::from_str(&content)?;
-        Ok(data.quotes)
+        };
+        app.restyle();
+        app
     }
 
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         while !self.exit {
-            terminal.draw(|frame| self.draw(frame))?;
+            self.update_presence();
+            self.track_seen_today();
+            self.track_view();
+            self.prefetch_images();
+            terminal.draw(|frame| self.draw_frame(frame))?;
+            if self
+                .celebration
+                .as_ref()
+                .is_some_and(|celebration| celebration.is_over())
+            {
+                self.celebration = None;
+            }
+            if let Some(timings) = &mut self.timings {
+                timings.frame_drawn();
+            }
             self.handle_events()?;
+            self.receive_images();
+            self.advance_slideshow();
+            self.save_views(false);
         }
+        self.save_views(true);
         Ok(())
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
-        let area = frame.area();
-
-        let title = Line::from(" Anime Quotes ".bold());
-        let mut block = Block::bordered()
-            .title(title.centered())
-            .border_set(border::THICK);
-        if self.show_instructions {
-            block = block.title_bottom(self.instructions_line().centered());
+    /// Writes the viewing history recorded by [`App::track_view`] once it
+    /// has waited [`VIEWS_SAVE_INTERVAL`], or right away with `now`.
+    fn save_views(&mut self, now: bool) {
+        let Some(since) = self.views_unsaved_since else {
+            return;
+        };
+        if !now && since.elapsed() < VIEWS_SAVE_INTERVAL {
+            return;
         }
+        self.views_unsaved_since = None;
+        if let Err(error) = self.state.save() {
+            self.status = Some(format!("Could not save viewing history: {error}"));
+        }
+    }
 
-        let inner = block.inner(area);
-        frame.render_widget(block, area);
-
-        if inner.width == 0 || inner.height == 0 {
-            return;
+    /// Asks for the images of the current quote and its neighbours in the
+    /// viewing order, so flipping to them doesn't wait on decoding.
+    fn prefetch_images(&mut self) {
+        let position = self
+            .order
+            .iter()
+            .position(|&index| index == self.current_index)
+            .unwrap_or(0);
+        let neighbours: Vec<usize> = (1..=IMAGE_PREFETCH)
+            .flat_map(|offset| [position + offset, position.wrapping_sub(offset)])
+            .filter_map(|position| self.order.get(position).copied())
+            .collect();
+        self.request_image(self.current_index);
+        for index in neighbours {
+            self.request_image(index);
+        }
+    }
+
+    /// Queues the image of the quote at `index` unless it is decoded, being
+    /// decoded, or failed, or enough others are already being decoded.
+    fn request_image(&mut self, index: usize) {
+        let Some(quote) = self.quotes.get(index) else {
+            return;
+        };
+        if quote.image.is_none()
+            || self.image_cache[index].is_some()
+            || self.images_pending.len() >= IMAGES_IN_FLIGHT
+            || self.images_failed.contains(&index)
+            || !self.images_pending.insert(index)
+        {
+            return;
+        }
+        self.image_loader.request(index, quote);
+    }
+
+    /// Puts the images decoded since the last frame into the cache, dropping
+    /// the least recently drawn ones past `DECODED_IMAGES`.
+    fn receive_images(&mut self) {
+        for (index, image) in self.image_loader.decoded() {
+            self.images_pending.remove(&index);
+            match image {
+                Some(image) => {
+                    self.image_cache[index] = Some(ImageSlot::new(image));
+                    self.images_recent.push_back(index);
+                }
+                None => {
+                    self.images_failed.insert(index);
+                }
+            }
+        }
+        while self.images_recent.len() > DECODED_IMAGES {
+            if let Some(index) = self.images_recent.pop_front() {
+                self.image_cache[index] = None;
+            }
+        }
+    }
+
+    /// Everything on screen: the current view with any toast and
+    /// celebration over it.
+    fn draw_frame(&mut self, frame: &mut Frame) {
+        self.draw(frame);
+        self.draw_toast(frame);
+        if let Some(celebration) = &self.celebration {
+            celebration.render(frame, frame.area().inner(Margin::new(1, 1)));
+        }
+    }
+
+    /// Draws a frame of `width` by `height` cells into a buffer rather than
+    /// the terminal, to check what the viewer draws without a terminal.
+    /// Images not decoded yet are left blank, as on screen.
+    pub fn render_to_buffer(&mut self, width: u16, height: u16) -> io::Result<Buffer> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+        terminal.draw(|frame| self.draw_frame(frame))?;
+        Ok(terminal.backend().buffer().clone())
+    }
+
+    /// Starts over with a new loader after quotes moved, since queued
+    /// indices may point at other quotes now.
+    fn restart_image_loading(&mut self) {
+        self.image_loader = artwork::ImageLoader::new(
+            &self.picker,
+            self.timings
+                .as_ref()
+                .map(|timings| timings.decoding.clone()),
+        );
+        self.images_pending.clear();
+        self.images_failed.clear();
+        self.images_recent = (0..self.image_cache.len())
+            .filter(|&index| self.image_cache[index].is_some())
+            .collect();
+    }
+
+    /// Moves on once the slideshow interval has passed since the last change:
+    /// always in kiosk mode, otherwise once the viewer has been idle for
+    /// `[ui.idle] after_secs` (starting with an immediate change).
+    fn advance_slideshow(&mut self) {
+        let (interval, rotation) = match (&self.kiosk, &self.idle) {
+            (Some(kiosk), _) => (kiosk.interval, kiosk.rotation),
+            (None, Some(idle)) if self.last_input.elapsed() >= idle.after => {
+                if self.idle_slideshow {
+                    (idle.interval, idle.rotation)
+                } else {
+                    self.idle_slideshow = true;
+                    self.status = Some("Slideshow (press any key to take over)".to_string());
+                    (Duration::ZERO, idle.rotation)
+                }
+            }
+            _ => return,
+        };
+        if self.last_advance.elapsed() < interval {
+            return;
+        }
+        let pickable = self.pickable();
+        match rotation {
+            selection::Rotation::Shuffle if pickable.len() > 1 => {
+                let previous = self.current_index;
+                while self.current_index == previous {
+                    self.random_quote();
+                }
+            }
+            selection::Rotation::LeastSeen if pickable.len() > 1 => {
+                let others: Vec<usize> = pickable
+                    .into_iter()
+                    .filter(|&index| index != self.current_index)
+                    .collect();
+                let least_seen = self.state.least_seen(&self.quotes, &others);
+                self.current_index = least_seen[rand::random_range(0..least_seen.len())];
+            }
+            _ => {
+                // Steps in the sort order, past hidden quotes.
+                self.next_quote();
+                for _ in 1..self.order.len() {
+                    if pickable.contains(&self.current_index) {
+                        break;
+                    }
+                    self.next_quote();
+                }
+            }
+        }
+        self.last_advance = Instant::now();
+        self.restyle();
+    }
+
+    /// Picks new colors and alignment for the quote on screen when the kiosk
+    /// asks for a randomized style.
+    fn restyle(&mut self) {
+        if self
+            .kiosk
+            .as_ref()
+            .is_some_and(|kiosk| kiosk.randomize_style)
+        {
+            (self.palette, self.alignment) = kiosk::random_style(&self.palette);
+            self.text_cache.clear();
+        }
+    }
+
+    /// Records the quote on screen for the clock header's daily count,
+    /// starting over at midnight.
+    fn track_seen_today(&mut self) {
+        let today = chrono::Local::now().date_naive();
+        if self.seen_today.0 != today {
+            self.seen_today = (today, HashSet::new());
+        }
+        if let Some(quote) = self.quotes.get(self.current_index) {
+            self.seen_today.1.insert(quote.stable_id());
+        }
+    }
+
+    /// Records the quote on screen in `state` whenever it changes, for the
+    /// recently-viewed list; [`App::save_views`] writes it out.
+    fn track_view(&mut self) {
+        if self.viewed_index == Some(self.current_index) {
+            return;
+        }
+        let Some(quote) = self.quotes.get(self.current_index) else {
+            return;
+        };
+        let today = chrono::Local::now().date_naive();
+        let first_today = self
+            .state
+            .activity(today, 1)
+            .first()
+            .is_some_and(|(_, views)| *views == 0);
+        self.state.record_view(quote);
+        if self.kiosk.is_none() {
+            let (streak, _) = self.state.streaks(today);
+            let milestone = first_today && streak > 0 && streak % STREAK_MILESTONE == 0;
+            if milestone {
+                self.status = Some(format!("{streak}-day streak!"));
+                self.celebrate();
+            }
+            self.check_achievements();
+        }
+        self.views_unsaved_since.get_or_insert_with(Instant::now);
+        self.viewed_index = Some(self.current_index);
+    }
+
+    /// Records newly reached achievements and announces them. Saved by the
+    /// caller.
+    fn check_achievements(&mut self) {
+        let unlocked = achievements::check(&mut self.state, &self.quotes, chrono::Local::now());
+        if unlocked.is_empty() {
+            return;
+        }
+        self.celebrate();
+        if self.toasts.is_empty() {
+            self.toast_since = Instant::now();
+        }
+        self.toasts.extend(unlocked);
+    }
+
+    fn celebrate(&mut self) {
+        let terminal_width =
+            crossterm::terminal::size().map_or(self.last_area.width, |(columns, _)| columns);
+        self.celebration = celebration::Celebration::start(self.celebration_style, terminal_width);
+    }
+
+    /// Shows the next unlocked achievement for [`TOAST_DURATION`] over
+    /// whatever is on screen.
+    fn draw_toast(&mut self, frame: &mut Frame) {
+        if self.toast_since.elapsed() >= TOAST_DURATION && self.toasts.pop_front().is_some() {
+            self.toast_since = Instant::now();
+        }
+        if let Some(achievement) = self.toasts.front() {
+            achievements::render_toast(frame, frame.area(), achievement, &self.palette);
+        }
+    }
+
+    /// Publishes the current quote to Discord whenever it changes.
+    fn update_presence(&mut self) {
+        let Some(presence) = &self.presence else {
+            return;
+        };
+        if self.presence_index == Some(self.current_index) {
+            return;
+        }
+        if let Some(quote) = self.quotes.get(self.current_index) {
+            presence.show(quote);
+            self.presence_index = Some(self.current_index);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        self.last_area = area;
+
+        let title = Line::from(" Anime Quotes ".bold());
+        let mut block = Block::bordered()
+            .title(title.centered())
+            .border_set(border::THICK);
+        if self.show_instructions {
+            block = block.title_bottom(
+                self.instructions_line(area.width.saturating_sub(2))
+                    .centered(),
+            );
+        }
+
+        let mut inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        if self.status_bar && inner.height > 1 {
+            inner.height -= 1;
+            let status_area = Rect {
+                y: inner.bottom(),
+                height: 1,
+                ..inner
+            };
+            frame.render_widget(Paragraph::new(self.status_line()).centered(), status_area);
+        }
+
+        if let Some(format) = &self.clock_format {
+            let header = self.clock_line(format);
+            let header_area = Rect { height: 1, ..inner };
+            frame.render_widget(
+                Paragraph::new(header).alignment(self.alignment),
+                header_area,
+            );
+        }
+
+        if let Some(calendar) = &self.calendar {
+            let calendar_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            calendar.render(
+                frame,
+                calendar_area,
+                &self.state,
+                &self.quotes,
+                &self.schedule,
+                &self.palette,
+            );
+            return;
+        }
+
+        if let Some(recent) = &mut self.recent {
+            let recent_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            recent.render(frame, recent_area, &self.state, &self.quotes, &self.palette);
+            return;
+        }
+
+        if let Some(browse) = &mut self.browse {
+            let browse_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            browse.render(
+                frame,
+                browse_area,
+                &self.quotes,
+                &self.order,
+                &self.state,
+                &self.palette,
+            );
+            return;
+        }
+
+        if let Some(achievements) = &self.achievements {
+            let achievements_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            achievements.render(
+                frame,
+                achievements_area,
+                &self.state,
+                &self.quotes,
+                &self.palette,
+            );
+            return;
+        }
+
+        if let Some(picker) = &mut self.collection_picker {
+            let picker_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            picker.render(
+                frame,
+                picker_area,
+                &self.collections,
+                self.collection,
+                &self.palette,
+            );
+            return;
+        }
+
+        if let Some(filter_menu) = &self.filter_menu {
+            let filter_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            filter_menu.render(frame, filter_area, &self.palette);
+            return;
+        }
+
+        if let Some(add_form) = &self.add_form {
+            let add_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            add_form.render(frame, add_area, &self.palette);
+            return;
+        }
+
+        if let Some(hidden) = &mut self.hidden {
+            let hidden_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            hidden.render(frame, hidden_area, &self.state, &self.quotes, &self.palette);
+            return;
+        }
+
+        if let Some(detail) = &mut self.detail {
+            let detail_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            detail.render(frame, detail_area, &self.quotes, &self.palette);
+            return;
+        }
+
+        if let Some(mut character_detail) = self.character_detail.take() {
+            let detail_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            let artwork = character_detail.render(
+                frame,
+                detail_area,
+                &self.quotes,
+                &self.image_resize,
+                &self.palette,
+            );
+            if let Some((index, artwork_area)) = artwork {
+                let artwork_area = grid::fit(artwork_area, self.image_dimensions(index));
+                self.draw_artwork(frame, artwork_area, index);
+            }
+            self.character_detail = Some(character_detail);
+            return;
+        }
+
+        // Taken out while drawing, since the cards' artwork is drawn by the
+        // app.
+        if let Some(grid) = self.grid.take() {
+            let grid_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            let cards = grid.render(frame, grid_area, "Grid", self.order.len(), &self.palette);
+            for (position, card_area) in cards {
+                let index = self.order[position];
+                let artwork_area =
+                    grid.render_card(frame, card_area, position, &self.palette, |width| {
+                        grid::quote_card(&self.quotes[index], width, &self.palette)
+                    });
+                if artwork_area.height > 0 {
+                    let artwork_area = grid::fit(artwork_area, self.image_dimensions(index));
+                    self.draw_artwork(frame, artwork_area, index);
+                }
+            }
+            self.grid = Some(grid);
+            return;
+        }
+
+        if let Some(mut gallery) = self.gallery.take() {
+            let gallery_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            let artwork = gallery.render(frame, gallery_area, &self.image_resize, &self.palette);
+            for (index, artwork_area) in artwork {
+                let artwork_area = grid::fit(artwork_area, self.image_dimensions(index));
+                self.draw_artwork(frame, artwork_area, index);
+            }
+            self.gallery = Some(gallery);
+            return;
+        }
+
+        if let Some(pinned) = self.compare {
+            let compare_area = Rect {
+                y: inner.y + IMAGE_TOP_PADDING.min(inner.height),
+                height: inner.height.saturating_sub(IMAGE_TOP_PADDING),
+                ..inner
+            };
+            self.draw_compare(frame, compare_area, pinned);
+            return;
         }
 
         let reserved_vertical = IMAGE_TOP_PADDING + IMAGE_TEXT_GAP;
         let available_for_image = inner.height.saturating_sub(reserved_vertical);
-        let image_height = self.image_height.min(available_for_image);
+        let (image_width, image_height) = self.image_dimensions(self.current_index);
+        let image_height = image_height.min(available_for_image);
         let text_height = inner
             .height
             .saturating_sub(IMAGE_TOP_PADDING + image_height + IMAGE_TEXT_GAP);
 
         if image_height > 0 {
-            let image_width = self.image_width.min(inner.width);
-            let image_x = inner.x + (inner.width.saturating_sub(image_width)) / 2;
+            let image_width = image_width.min(inner.width);
+            let image_x = inner.x
+                + match self.alignment {
+                    Alignment::Left => 0,
+                    Alignment::Center => inner.width.saturating_sub(image_width) / 2,
+                    Alignment::Right => inner.width.saturating_sub(image_width),
+                };
             let image_area = Rect {
                 x: image_x,
                 y: inner.y + IMAGE_TOP_PADDING,
@@ -468,23 +2083,7 @@ impl App {
                 height: image_height,
             };
 
-            let resize = self.image_resize.clone();
-            if let Some(slot) = self.current_image_mut() {
-                let widget = StatefulImage::<StatefulProtocol>::new().resize(resize);
-                frame.render_stateful_widget(widget, image_area, &mut slot.protocol);
-                if let Some(result) = slot.protocol.last_encoding_result() {
-                    if let Err(error) = result {
-                        eprintln!("failed to encode image: {error}");
-                    }
-                }
-            } else {
-                let placeholder = Paragraph::new(Text::from(Line::from(Span::styled(
-                    "Image not available",
-                    Style::default().fg(Color::Gray),
-                ))))
-                .alignment(ratatui::layout::Alignment::Center);
-                frame.render_widget(placeholder, image_area);
-            }
+            self.draw_artwork(frame, image_area, self.current_index);
         }
 
         if text_height == 0 {
@@ -498,127 +2097,1510 @@ impl App {
             height: text_height,
         };
 
+        let mut quote_rows = self.draw_quote_text(frame, text_area);
+        quote_rows += self.draw_notes(
+            frame,
+            Rect {
+                y: text_area.y + quote_rows,
+                height: text_area.height - quote_rows,
+                ..text_area
+            },
+        );
+        let text_area = Rect {
+            y: text_area.y + quote_rows,
+            height: text_area.height - quote_rows,
+            ..text_area
+        };
         let mut lines: Vec<Line> = Vec::new();
+        let mut gauge_row = None;
 
         if let Some(quote) = self.current_quote() {
-            let anime_style = Style::default().fg(self.palette.anime).bold();
-            let character_style = Style::default().fg(self.palette.character).bold();
-            let japanese_style = Style::default().fg(self.palette.japanese).bold();
-            let romaji_style = Style::default().fg(self.palette.romaji);
-            let quote_style = Style::default().fg(self.palette.quote).italic();
             let count_style = Style::default().fg(self.palette.count);
-
-            lines.extend(vec![
-                Line::from(vec![
-                    Span::raw("Anime: "),
-                    Span::styled(quote.anime.clone(), anime_style),
-                ]),
-                Line::from(vec![
-                    Span::raw("Character: "),
-                    Span::styled(quote.character.clone(), character_style),
-                ]),
-                Line::from(""),
-                Line::from(vec![
-                    Span::raw("Japanese: "),
-                    Span::styled(quote.japanese.clone(), japanese_style),
-                ]),
-            ]);
-
-            if let Some(romaji) = &quote.romaji {
-                lines.push(Line::from(vec![
-                    Span::raw("Romaji: "),
-                    Span::styled(romaji.clone(), romaji_style),
-                ]));
-            }
-
-            lines.extend(vec![
-                Line::from(""),
-                Line::from(vec![
-                    Span::raw("\""),
-                    Span::styled(quote.quote.clone(), quote_style),
-                    Span::raw("\""),
-                ]),
-                Line::from(""),
-                Line::from(vec![Span::styled(
-                    format!("({}/{})", self.current_index + 1, self.quotes.len()),
+            lines.push(Line::from(""));
+            if self.progress != ProgressDisplay::Gauge {
+                let hidden = if self.state.is_hidden(quote) {
+                    " · hidden"
+                } else {
+                    ""
+                };
+                let filtered = match self
+                    .collection
+                    .and_then(|index| self.collections.get(index))
+                {
+                    _ if self.queue_playing => " · queue".to_string(),
+                    Some(collection) => format!(" · {}", collection.name),
+                    None if self.filter.is_empty() => String::new(),
+                    None => format!(" · filtered from {}", self.quotes.len()),
+                };
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "({}/{}){hidden}{filtered}",
+                        self.position() + 1,
+                        self.order.len()
+                    ),
                     count_style,
-                )]),
-            ]);
+                )));
+            }
+            if self.progress != ProgressDisplay::Counter {
+                // The gauge is drawn over this blank line once the text is
+                // laid out.
+                gauge_row = Some(lines.len() as u16);
+                lines.push(Line::from(""));
+            }
+
+            if let Some(search) = &self.search {
+                // Only the quotes the viewer can move to, as `n` finds them.
+                let matches = self
+                    .order
+                    .iter()
+                    .filter(|&&index| search.matches(&self.quotes[index]))
+                    .count();
+                lines.push(search.status_line(matches, &self.palette));
+            }
+            if let Some(status) = &self.status {
+                lines.push(Line::from(Span::styled(status.clone(), count_style)));
+            }
         } else {
             lines.push(Line::from(Span::styled(
                 "No quotes found!",
                 Style::default().fg(Color::Red),
             )));
             lines.push(Line::from(Span::styled(
-                "Make sure anime.toml exists in project root.",
+                format!("Make sure {} exists.", data_path()),
                 Style::default().fg(Color::Gray),
             )));
         }
 
-        let paragraph =
-            Paragraph::new(Text::from(lines)).alignment(ratatui::layout::Alignment::Center);
+        let paragraph = Paragraph::new(Text::from(lines)).alignment(self.alignment);
         frame.render_widget(paragraph, text_area);
+
+        if let Some(row) = gauge_row.filter(|row| *row < text_area.height) {
+            let width = text_area.width.min(PROGRESS_GAUGE_WIDTH);
+            let gauge_area = Rect {
+                x: text_area.x
+                    + match self.alignment {
+                        Alignment::Left => 0,
+                        Alignment::Center => (text_area.width - width) / 2,
+                        Alignment::Right => text_area.width - width,
+                    },
+                y: text_area.y + row,
+                width,
+                height: 1,
+            };
+            let ratio = (self.position() + 1) as f64 / self.order.len().max(1) as f64;
+            let gauge = LineGauge::default()
+                .ratio(ratio)
+                .label("")
+                .line_set(symbols::line::THICK)
+                .filled_style(Style::default().fg(self.palette.progress))
+                .unfilled_style(Style::default().fg(self.palette.count).dim());
+            frame.render_widget(gauge, gauge_area);
+        }
     }
 
-    fn handle_events(&mut self) -> io::Result<()> {
-        match event::read()? {
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event)
+    /// Draws the current quote's notes at the top of `area`, after a blank
+    /// line: a hint while the pane is folded, or the notes wrapped in a pane
+    /// once it is open. Returns the rows they take.
+    fn draw_notes(&self, frame: &mut Frame, area: Rect) -> u16 {
+        let Some(notes) = self
+            .current_quote()
+            .and_then(|quote| quote.notes.as_deref())
+        else {
+            return 0;
+        };
+        if area.height < 2 {
+            return 0;
+        }
+        let count_style = Style::default().fg(self.palette.count);
+        let key_style = Style::default().fg(self.palette.instructions).bold();
+        if !self.notes_open || area.height < 4 {
+            let hint = Line::from(vec![
+                Span::styled("▸ Notes ", count_style),
+                Span::styled("<N>", key_style),
+            ]);
+            frame.render_widget(
+                Paragraph::new(hint).alignment(self.alignment),
+                Rect {
+                    y: area.y + 1,
+                    height: 1,
+                    ..area
+                },
+            );
+            return 2;
+        }
+        let width = area.width.min(NOTES_WIDTH);
+        // Borders and padding take two cells on each side.
+        let text_width = usize::from(width.saturating_sub(4).max(1));
+        let rows = notes
+            .lines()
+            .map(|line| line.width().div_ceil(text_width).max(1))
+            .sum::<usize>();
+        let height = u16::try_from(rows.saturating_add(2))
+            .unwrap_or(u16::MAX)
+            .min(area.height - 1);
+        let pane = Rect {
+            x: area.x
+                + match self.alignment {
+                    Alignment::Left => 0,
+                    Alignment::Center => (area.width - width) / 2,
+                    Alignment::Right => area.width - width,
+                },
+            y: area.y + 1,
+            width,
+            height,
+        };
+        let block = Block::bordered()
+            .title(Line::from(vec![
+                Span::styled(" ▾ Notes ", count_style),
+                Span::styled("<N> ", key_style),
+            ]))
+            .border_style(count_style)
+            .padding(Padding::horizontal(1));
+        frame.render_widget(
+            Paragraph::new(notes.to_string())
+                .wrap(Wrap { trim: false })
+                .block(block),
+            pane,
+        );
+        height + 1
+    }
+
+    /// Draws the current quote's lines at the top of `area` from the text
+    /// cache, building them the first time, and returns the rows they take.
+    fn draw_quote_text(&mut self, frame: &mut Frame, area: Rect) -> u16 {
+        let Some(id) = self.current_quote().map(AnimeQuote::stable_id) else {
+            return 0;
+        };
+        let highlighting = self.search.as_ref().and_then(search::Search::highlighting);
+        let today = chrono::Local::now().date_naive();
+        if self.text_cache_key.0.as_deref() != highlighting || self.text_cache_key.1 != today {
+            self.text_cache_key = (highlighting.map(str::to_string), today);
+            self.text_cache.clear();
+        }
+        if !self.text_cache.contains_key(&id) {
+            let text = Text::from(self.quote_lines(self.current_index)).alignment(self.alignment);
+            self.text_cache.insert(id.clone(), text);
+        }
+        let text = &self.text_cache[&id];
+        let rows = u16::try_from(text.height())
+            .unwrap_or(u16::MAX)
+            .min(area.height);
+        frame.render_widget(
+            text,
+            Rect {
+                height: rows,
+                ..area
+            },
+        );
+        rows
+    }
+
+    /// The pinned quote and the current one side by side, each with its
+    /// artwork above its lines.
+    fn draw_compare(&mut self, frame: &mut Frame, area: Rect, pinned: usize) {
+        let [sides_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(2)]).areas(area);
+        let [left_area, divider_area, right_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(sides_area);
+        let count_style = Style::default().fg(self.palette.count);
+        frame.render_widget(
+            Block::new()
+                .borders(Borders::LEFT)
+                .border_style(count_style),
+            divider_area,
+        );
+
+        let position = format!("({}/{})", self.position() + 1, self.order.len());
+        for (index, side_area, label) in [
+            (pinned, left_area, "Pinned".to_string()),
+            (self.current_index, right_area, position),
+        ] {
+            if index >= self.quotes.len() {
+                continue;
             }
-            _ => {}
+            let mut lines = self.quote_lines(index);
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(label, count_style)));
+            // Rows the lines take once wrapped, give or take a word.
+            let width = usize::from(side_area.width.max(1));
+            let text_rows = lines
+                .iter()
+                .map(|line| line.width().div_ceil(width).max(1))
+                .sum::<usize>();
+            let text_rows = u16::try_from(text_rows).unwrap_or(u16::MAX);
+
+            let (image_width, image_height) = self.image_dimensions(index);
+            let image_height = image_height.min(
+                side_area
+                    .height
+                    .saturating_sub(text_rows.saturating_add(IMAGE_TEXT_GAP)),
+            );
+            let image_width = image_width.min(side_area.width);
+            if image_height > 0 {
+                let image_area = Rect {
+                    x: side_area.x + (side_area.width - image_width) / 2,
+                    y: side_area.y,
+                    width: image_width,
+                    height: image_height,
+                };
+                self.draw_artwork(frame, image_area, index);
+            }
+            let gap = if image_height > 0 { IMAGE_TEXT_GAP } else { 0 };
+            let text_area = Rect {
+                y: side_area.y + image_height + gap,
+                height: side_area.height.saturating_sub(image_height + gap),
+                ..side_area
+            };
+            frame.render_widget(
+                Paragraph::new(Text::from(lines))
+                    .centered()
+                    .wrap(Wrap { trim: true }),
+                text_area,
+            );
+        }
+
+        let mut help = vec![Line::from(Span::styled(
+            "Move right side <Left/Right>  Swap <Tab>  Close <M>",
+            Style::default().fg(self.palette.instructions),
+        ))];
+        if let Some(status) = &self.status {
+            help.push(Line::from(Span::styled(status.clone(), count_style)));
+        }
+        frame.render_widget(Paragraph::new(Text::from(help)).centered(), help_area);
+    }
+
+    /// The artwork of the quote at `index` in `area`: its image, or an ASCII
+    /// avatar when it has none.
+    fn draw_artwork(&mut self, frame: &mut Frame, area: Rect, index: usize) {
+        if let Some(slot) = self.image_cache.get_mut(index).and_then(Option::as_mut) {
+            slot.draw(frame, area, &self.picker, &self.image_resize);
+            if let Some(position) = self
+                .images_recent
+                .iter()
+                .position(|&recent| recent == index)
+            {
+                self.images_recent.remove(position);
+                self.images_recent.push_back(index);
+            }
+            return;
+        }
+        // An image that isn't decoded yet is left blank until it is, rather
+        // than flashing the avatar.
+        self.request_image(index);
+        if self
+            .quotes
+            .get(index)
+            .is_some_and(|quote| quote.image.is_some())
+            && !self.images_failed.contains(&index)
+        {
+            return;
+        }
+        let seed = self
+            .quotes
+            .get(index)
+            .map(|quote| quote.character.clone())
+            .unwrap_or_default();
+        let avatar = ascii::avatar(
+            &seed,
+            &self.ascii_settings,
+            area.width as usize,
+            area.height as usize,
+        );
+        let style = Style::default().fg(self.palette.character);
+        let placeholder = Paragraph::new(Text::from(
+            avatar
+                .into_iter()
+                .map(|line| Line::from(Span::styled(line, style)))
+                .collect::<Vec<_>>(),
+        ));
+        frame.render_widget(placeholder, area);
+    }
+
+    /// The `[ui] fields` of the quote at `index`, or its `[ui] template`,
+    /// with the search's matches highlighted.
+    fn quote_lines(&self, index: usize) -> Vec<Line<'static>> {
+        let quote = &self.quotes[index];
+        let anime_style = Style::default().fg(self.palette.anime).bold();
+        let character_style = Style::default().fg(self.palette.character).bold();
+        let japanese_style = Style::default().fg(self.palette.japanese).bold();
+        let romaji_style = Style::default().fg(self.palette.romaji);
+        let quote_style = Style::default().fg(self.palette.quote).italic();
+        let count_style = Style::default().fg(self.palette.count);
+        let highlight = |text: &str, style: Style| match &self.search {
+            Some(search) => search.highlight(text, style, self.palette.highlight),
+            None => vec![Span::styled(text.to_string(), style)],
         };
+
+        if let Some(template) = &self.template {
+            let lookup = |name: &str| output::quote_field(quote, index, self.quotes.len(), name);
+            let mut lines = vec![Vec::new()];
+            for (text, field) in template.render_parts(&lookup) {
+                let style = match field {
+                    None => Style::default(),
+                    Some("anime") => anime_style,
+                    Some("character") => character_style,
+                    Some("japanese") => japanese_style,
+                    Some("romaji") => romaji_style,
+                    Some("quote") => quote_style,
+                    Some(_) => count_style,
+                };
+                for (row, piece) in text.split('\n').enumerate() {
+                    if row > 0 {
+                        lines.push(Vec::new());
+                    }
+                    if let Some(line) = lines.last_mut() {
+                        line.extend(highlight(piece, style));
+                    }
+                }
+            }
+            return lines.into_iter().map(Line::from).collect();
+        }
+
+        let mut lines = Vec::new();
+        let mut previous: Option<Field> = None;
+        for &field in &self.fields {
+            if (field == Field::Romaji && quote.romaji.is_none())
+                || (field == Field::Pitch && quote.pitch.is_none())
+            {
+                continue;
+            }
+            if previous.is_some_and(|previous| previous.group() != field.group()) {
+                lines.push(Line::from(""));
+            }
+            previous = Some(field);
+            match field {
+                Field::Anime => lines.push(Line::from(vec![
+                    Span::raw("Anime: "),
+                    Span::styled(quote.anime.clone(), anime_style),
+                ])),
+                Field::Character => lines.push(Line::from(
+                    [
+                        Span::raw("Character: "),
+                        Span::styled(quote.character.clone(), character_style),
+                    ]
+                    .into_iter()
+                    .chain(
+                        schedule::is_birthday(quote, chrono::Local::now().date_naive())
+                            .then(|| Span::styled("  🎂 Birthday", anime_style)),
+                    )
+                    .collect::<Vec<_>>(),
+                )),
+                Field::Japanese => {
+                    let japanese = self.japanese_line(quote);
+                    let selected = self
+                        .word
+                        .filter(|(selected, _)| *selected == index)
+                        .and_then(|(_, word)| words::split(&japanese).get(word).cloned());
+                    let spans = match selected {
+                        Some(range) => vec![
+                            Span::styled(japanese[..range.start].to_string(), japanese_style),
+                            Span::styled(
+                                japanese[range.clone()].to_string(),
+                                japanese_style.reversed(),
+                            ),
+                            Span::styled(japanese[range.end..].to_string(), japanese_style),
+                        ],
+                        None => highlight(&japanese, japanese_style),
+                    };
+                    lines.push(Line::from(
+                        [Span::raw("Japanese: ")]
+                            .into_iter()
+                            .chain(spans)
+                            .collect::<Vec<_>>(),
+                    ));
+                }
+                Field::Romaji => lines.push(Line::from(
+                    [Span::raw("Romaji: ")]
+                        .into_iter()
+                        .chain(highlight(
+                            quote.romaji.as_deref().unwrap_or_default(),
+                            romaji_style,
+                        ))
+                        .collect::<Vec<_>>(),
+                )),
+                Field::Pitch => {
+                    let label = "Pitch: ";
+                    let (marks, reading) = pitch::lines(quote.pitch.as_deref().unwrap_or_default());
+                    lines.push(Line::from(Span::styled(
+                        format!("{}{marks}", " ".repeat(label.len())),
+                        romaji_style,
+                    )));
+                    lines.push(Line::from(vec![
+                        Span::raw(label),
+                        Span::styled(reading, japanese_style),
+                    ]));
+                }
+                Field::Quote => lines.push(Line::from(
+                    [Span::raw("\"")]
+                        .into_iter()
+                        .chain(highlight(&quote.quote, quote_style))
+                        .chain([Span::raw("\"")])
+                        .collect::<Vec<_>>(),
+                )),
+            }
+            // Where the quote was said and the extra fields go under the
+            // character, or under the anime when the character isn't shown.
+            let metadata_here = field == Field::Character
+                || (field == Field::Anime && !self.fields.contains(&Field::Character));
+            if metadata_here {
+                if let Some(metadata) = quote.metadata_line() {
+                    lines.push(Line::from(Span::styled(metadata, count_style)));
+                }
+                for (label, value) in quote.extra_lines() {
+                    lines.push(Line::from(vec![
+                        Span::raw(format!("{label}: ")),
+                        Span::styled(value, count_style),
+                    ]));
+                }
+            }
+        }
+        lines
+    }
+
+    /// The quote's Japanese line, in kana when that is toggled on and the
+    /// quote has furigana.
+    fn japanese_line(&self, quote: &AnimeQuote) -> String {
+        match &quote.furigana {
+            Some(furigana) if self.kana_only => furigana::kana(furigana),
+            _ => quote.japanese.clone(),
+        }
+    }
+
+    fn handle_events(&mut self) -> io::Result<()> {
+        if event::poll(EVENT_POLL_INTERVAL)? {
+            match event::read()? {
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    self.handle_key_event(key_event)
+                }
+                _ => {}
+            };
+        }
+        self.handle_control_requests();
         Ok(())
     }
 
+    fn handle_control_requests(&mut self) {
+        let Some((_, receiver)) = &self.control else {
+            return;
+        };
+        let requests: Vec<_> = receiver.try_iter().collect();
+        for request in requests {
+            let reply = self.apply_control_command(request.command);
+            let _ = request.reply.send(reply);
+        }
+    }
+
+    fn apply_control_command(&mut self, command: control::ControlCommand) -> String {
+        use control::ControlCommand;
+        match command {
+            ControlCommand::Next => self.next_quote(),
+            ControlCommand::Previous => self.previous_quote(),
+            ControlCommand::Random => self.random_quote(),
+            ControlCommand::Goto(index) => {
+                if index == 0 || index > self.order.len() {
+                    return format!(
+                        "error: quote {index} does not exist ({} quotes loaded)",
+                        self.order.len()
+                    );
+                }
+                self.current_index = self.order[index - 1];
+            }
+            ControlCommand::ShowJson => {
+                return match self.current_quote() {
+                    Some(quote) => {
+                        serde_json::to_string(&output::QuoteView::new(self.current_index, quote))
+                            .unwrap_or_default()
+                    }
+                    None => "error: no quotes loaded".to_string(),
+                };
+            }
+        }
+        format!("ok {}/{}", self.position() + 1, self.order.len())
+    }
+
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if let Some(kiosk) = &self.kiosk {
+            if kiosk.exit.matches(&key_event) {
+                self.exit();
+            }
+            return;
+        }
+        self.last_input = Instant::now();
+        self.idle_slideshow = false;
+        if self.celebration.take().is_some() {
+            return;
+        }
+        self.status = None;
+        if let Some(calendar) = &mut self.calendar {
+            match calendar.handle_key(key_event.code, &self.state, &self.quotes, &self.schedule) {
+                calendar::CalendarAction::Stay => {}
+                calendar::CalendarAction::Close => self.calendar = None,
+                calendar::CalendarAction::Show(index) => {
+                    self.current_index = index;
+                    self.calendar = None;
+                }
+            }
+            return;
+        }
+        if let Some(recent) = &mut self.recent {
+            match recent.handle_key(key_event.code, &self.state, &self.quotes) {
+                recent::RecentAction::Stay => {}
+                recent::RecentAction::Close => self.recent = None,
+                recent::RecentAction::Show(index) => {
+                    self.current_index = index;
+                    self.recent = None;
+                }
+            }
+            return;
+        }
+        if let Some(browse) = &mut self.browse {
+            match browse.handle_key(key_event.code, &self.order) {
+                browse::BrowseAction::Stay => {}
+                browse::BrowseAction::Close => self.browse = None,
+                browse::BrowseAction::Show(index) => {
+                    self.current_index = index;
+                    self.browse = None;
+                }
+                browse::BrowseAction::Apply(batch, targets) => {
+                    let message = self.apply_batch(batch, &targets);
+                    let queued = self.queue_positions();
+                    if let Some(browse) = &mut self.browse {
+                        browse.report(message);
+                        browse.set_queued(queued);
+                    }
+                }
+            }
+            return;
+        }
+        if let Some(achievements) = &mut self.achievements {
+            match achievements.handle_key(key_event.code) {
+                achievements::AchievementsAction::Stay => {}
+                achievements::AchievementsAction::Close => self.achievements = None,
+            }
+            return;
+        }
+        if let Some(hidden) = &mut self.hidden {
+            match hidden.handle_key(key_event.code, &self.state, &self.quotes) {
+                hidden::HiddenAction::Stay => {}
+                hidden::HiddenAction::Close => self.hidden = None,
+                hidden::HiddenAction::Show(index) => {
+                    self.current_index = index;
+                    self.hidden = None;
+                }
+                hidden::HiddenAction::Unhide(index) => self.unhide(index),
+            }
+            return;
+        }
+        if let Some(grid) = &mut self.grid {
+            match grid.handle_key(key_event.code, self.order.len()) {
+                grid::GridAction::Stay => {}
+                grid::GridAction::Close => self.grid = None,
+                grid::GridAction::Show(position) => {
+                    self.current_index = self.order[position];
+                    self.grid = None;
+                }
+            }
+            return;
+        }
+        let detail_action = match (&mut self.detail, &mut self.character_detail) {
+            (Some(detail), _) => Some(detail.handle_key(key_event.code)),
+            (None, Some(detail)) => Some(detail.handle_key(key_event.code)),
+            (None, None) => None,
+        };
+        if let Some(action) = detail_action {
+            if !matches!(action, detail::DetailAction::Stay) {
+                self.detail = None;
+                self.character_detail = None;
+            }
+            match action {
+                detail::DetailAction::Stay | detail::DetailAction::Close => {}
+                detail::DetailAction::Show(index) => {
+                    if !self.order.contains(&index) {
+                        self.collection = None;
+                        self.queue_playing = false;
+                        self.apply_filter(filter::QuoteFilter::default());
+                    }
+                    self.current_index = index;
+                }
+                detail::DetailAction::Filter(filter) => {
+                    self.collection = None;
+                    self.apply_filter(filter);
+                }
+            }
+            return;
+        }
+        if let Some(gallery) = &mut self.gallery {
+            match gallery.handle_key(key_event.code, &self.quotes, self.current_index) {
+                gallery::GalleryAction::Stay => {}
+                gallery::GalleryAction::Close => self.gallery = None,
+                gallery::GalleryAction::Filter(filter) => {
+                    self.gallery = None;
+                    self.collection = None;
+                    self.apply_filter(filter);
+                }
+            }
+            return;
+        }
+        if let Some(picker) = &mut self.collection_picker {
+            match picker.handle_key(key_event.code, &self.collections) {
+                collections::CollectionsAction::Stay => {}
+                collections::CollectionsAction::Close => self.collection_picker = None,
+                collections::CollectionsAction::Switch(choice) => {
+                    self.collection_picker = None;
+                    self.switch_collection(choice);
+                }
+            }
+            return;
+        }
+        if let Some(filter_menu) = &mut self.filter_menu {
+            match filter_menu.handle_key(key_event.code) {
+                filter_menu::FilterMenuAction::Stay => {}
+                filter_menu::FilterMenuAction::Close => self.filter_menu = None,
+                filter_menu::FilterMenuAction::Apply(filter) => {
+                    if self.apply_filter(filter) {
+                        self.filter_menu = None;
+                        self.collection = None;
+                    } else if let Some(filter_menu) = &mut self.filter_menu {
+                        filter_menu.report("No quote matches that filter".to_string());
+                    }
+                }
+            }
+            return;
+        }
+        if let Some(add_form) = &mut self.add_form {
+            match add_form.handle_key(key_event.code) {
+                add_form::AddFormAction::Stay => {}
+                add_form::AddFormAction::Close => self.add_form = None,
+                add_form::AddFormAction::Add(draft) => match self.add_draft(&draft) {
+                    Ok(summary) => {
+                        self.add_form = None;
+                        self.status = Some(summary);
+                    }
+                    Err(error) => {
+                        if let Some(add_form) = &mut self.add_form {
+                            add_form.report(format!("Could not add the quote: {error}"));
+                        }
+                    }
+                },
+            }
+            return;
+        }
+        if let Some(search) = &mut self.search {
+            match search.handle_key(key_event.code) {
+                search::SearchAction::Pass => {}
+                search::SearchAction::Stay => return,
+                search::SearchAction::Close => {
+                    self.search = None;
+                    return;
+                }
+                search::SearchAction::Next => return self.find_match(true),
+                search::SearchAction::Previous => return self.find_match(false),
+            }
+        }
+        if let Some(action) = keymap::action(key_event.code) {
+            return self.run_action(action);
+        }
         match key_event.code {
-            KeyCode::Char('q') => self.exit(),
-            KeyCode::Left => self.previous_quote(),
-            KeyCode::Right => self.next_quote(),
+            KeyCode::Tab => {
+                if let Some(pinned) = &mut self.compare {
+                    std::mem::swap(pinned, &mut self.current_index);
+                }
+            }
+            KeyCode::Esc => {
+                self.compare = None;
+                if self.word.take().is_some() {
+                    self.text_cache.clear();
+                }
+            }
             _ => {}
         }
     }
 
+    fn run_action(&mut self, action: keymap::Action) {
+        use keymap::Action;
+        match action {
+            Action::Quit => self.exit(),
+            Action::Previous => self.previous_quote(),
+            Action::Next => self.next_quote(),
+            Action::Open => self.open_anime_page(),
+            Action::Source => self.open_source_url(),
+            Action::Card => self.export_card(),
+            Action::Screen => self.export_screen(),
+            Action::Calendar => self.open_calendar(),
+            Action::Recent => self.recent = Some(recent::Recent::new()),
+            Action::Achievements => self.achievements = Some(achievements::Achievements::new()),
+            Action::Favorite => self.toggle_favorite(),
+            Action::Hide => self.toggle_hidden(),
+            Action::Browse => {
+                self.browse = Some(browse::Browse::new(self.position(), self.queue_positions()));
+            }
+            Action::Hidden => self.hidden = Some(hidden::Hidden::new()),
+            Action::Paste => self.add_from_clipboard(),
+            Action::Sort => self.cycle_sort(),
+            Action::Search => self.search = Some(search::Search::new()),
+            Action::Collections => {
+                self.collection_picker = Some(collections::Collections::new(self.collection));
+            }
+            Action::Filter => {
+                self.filter_menu = Some(filter_menu::FilterMenu::new(&self.filter));
+            }
+            Action::Queue => self.toggle_queue(),
+            Action::Compare => self.toggle_compare(),
+            Action::Kana => self.toggle_kana(),
+            Action::Notes => self.notes_open = !self.notes_open,
+            Action::NextWord => self.move_word(1),
+            Action::PreviousWord => self.move_word(-1),
+            Action::Lookup => self.look_up_word(),
+            Action::Play => {
+                if let Some(quote) = self.current_quote() {
+                    self.status = Some(player::play(quote, &self.player_config));
+                }
+            }
+            Action::Grid => self.grid = Some(grid::Grid::new(self.position())),
+            Action::Info => {
+                self.detail = detail::AnimeDetail::new(&self.quotes, self.current_index);
+            }
+            Action::Character => {
+                self.character_detail =
+                    detail::CharacterDetail::new(&self.quotes, self.current_index, &self.picker);
+            }
+            Action::Gallery => {
+                self.gallery = Some(gallery::Gallery::new(
+                    &self.quotes,
+                    self.current_index,
+                    &self.picker,
+                ));
+            }
+        }
+    }
+
     fn exit(&mut self) {
         self.exit = true;
     }
 
     fn next_quote(&mut self) {
-        if !self.quotes.is_empty() {
-            self.current_index = (self.current_index + 1) % self.quotes.len();
+        if !self.order.is_empty() {
+            self.current_index = self.order[(self.position() + 1) % self.order.len()];
+        }
+    }
+
+    /// Jumps to a random quote that isn't hidden.
+    fn random_quote(&mut self) {
+        let pickable = self.pickable();
+        if !pickable.is_empty() {
+            self.current_index = pickable[rand::random_range(0..pickable.len())];
+        }
+    }
+
+    /// The quotes random picks and the slideshow choose from: those in
+    /// `order` (the filter's, or the queue), without the hidden ones unless
+    /// that is all of them.
+    fn pickable(&self) -> Vec<usize> {
+        let in_order: HashSet<usize> = self.order.iter().copied().collect();
+        let pickable: Vec<usize> = self
+            .state
+            .pickable(&self.quotes)
+            .into_iter()
+            .filter(|index| in_order.contains(index))
+            .collect();
+        if pickable.is_empty() {
+            self.order.clone()
+        } else {
+            pickable
         }
     }
 
     fn previous_quote(&mut self) {
-        if !self.quotes.is_empty() {
-            self.current_index = if self.current_index == 0 {
-                self.quotes.len() - 1
+        if !self.order.is_empty() {
+            let position = self.position();
+            let previous = if position == 0 {
+                self.order.len() - 1
             } else {
-                self.current_index - 1
+                position - 1
             };
+            self.current_index = self.order[previous];
+        }
+    }
+
+    /// Moves to the next quote in the sort order (or the previous one) that
+    /// the search matches, wrapping around.
+    fn find_match(&mut self, forward: bool) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        let count = self.order.len();
+        let position = self.position();
+        let found = (1..=count)
+            .map(|step| {
+                if forward {
+                    (position + step) % count
+                } else {
+                    (position + count - step) % count
+                }
+            })
+            .map(|position| self.order[position])
+            .find(|&index| search.matches(&self.quotes[index]));
+        match found {
+            Some(index) => self.current_index = index,
+            None => self.status = Some("No quote matches the search".to_string()),
+        }
+    }
+
+    /// Where the current quote is in the sort order.
+    fn position(&self) -> usize {
+        self.order
+            .iter()
+            .position(|&index| index == self.current_index)
+            .unwrap_or(0)
+    }
+
+    /// Sorts and filters `order` again after the collection, sort, filter,
+    /// or queue changed. Playing the queue stops when the filter leaves none
+    /// of it, and a filter that leaves nothing is dropped.
+    fn refresh_order(&mut self) {
+        if self.queue_playing {
+            self.order = self.queue_positions();
+            self.order
+                .retain(|&index| self.filter.matches(&self.quotes[index]));
+            if !self.order.is_empty() {
+                return;
+            }
+            self.queue_playing = false;
+        }
+        self.order = self
+            .sort
+            .order(&self.quotes)
+            .into_iter()
+            .filter(|&index| self.filter.matches(&self.quotes[index]))
+            .collect();
+        if self.order.is_empty() && !self.filter.is_empty() {
+            self.filter = filter::QuoteFilter::default();
+            self.collection = None;
+            self.refresh_order();
+        }
+    }
+
+    /// Narrows the viewer to `filter`, staying on the current quote when it
+    /// still matches. A filter that matches nothing is refused.
+    fn apply_filter(&mut self, filter: filter::QuoteFilter) -> bool {
+        let matching = self
+            .quotes
+            .iter()
+            .filter(|quote| filter.matches(quote))
+            .count();
+        if matching == 0 {
+            return false;
+        }
+        self.filter = filter;
+        self.refresh_order();
+        if !self.order.contains(&self.current_index) {
+            self.current_index = self.order[0];
+        }
+        self.status = Some(format!(
+            "Showing {matching} of {} quotes",
+            self.quotes.len()
+        ));
+        true
+    }
+
+    /// Switches to the saved collection at `choice` from the picker, or back
+    /// to every quote.
+    fn switch_collection(&mut self, choice: Option<usize>) {
+        let Some(collection) = choice.and_then(|index| self.collections.get(index)) else {
+            self.apply_filter(filter::QuoteFilter::default());
+            self.collection = None;
+            return;
+        };
+        let filter = filter::QuoteFilter {
+            query: Some(collection.query.clone()),
+            ..filter::QuoteFilter::default()
+        };
+        let name = collection.name.clone();
+        if self.apply_filter(filter) {
+            self.collection = choice;
+        } else {
+            self.status = Some(format!("No quote is in {name}"));
         }
     }
 
-    fn current_image_mut(&mut self) -> Option<&mut ImageSlot> {
-        self.image_cache
-            .get_mut(self.current_index)
-            .and_then(|slot| slot.as_mut())
+    /// Where the queued quotes are in `quotes`, in queue order.
+    fn queue_positions(&self) -> Vec<usize> {
+        let positions: HashMap<String, usize> = self
+            .quotes
+            .iter()
+            .enumerate()
+            .map(|(index, quote)| (quote.stable_id(), index))
+            .collect();
+        self.queue
+            .iter()
+            .filter_map(|id| positions.get(id).copied())
+            .collect()
+    }
+
+    /// Pins the current quote for the compare view and moves on to the next
+    /// one beside it, or closes the view.
+    fn toggle_compare(&mut self) {
+        if self.compare.take().is_some() {
+            return;
+        }
+        self.compare = Some(self.current_index);
+        self.next_quote();
+    }
+
+    /// Moves the word cursor `step` words along the current quote's Japanese
+    /// line, starting from its first or last word.
+    fn move_word(&mut self, step: isize) {
+        let Some(quote) = self.current_quote() else {
+            return;
+        };
+        let count = words::split(&self.japanese_line(quote)).len();
+        if count == 0 {
+            return;
+        }
+        let word = match self.word {
+            Some((index, word)) if index == self.current_index => {
+                word.saturating_add_signed(step).min(count - 1)
+            }
+            _ if step < 0 => count - 1,
+            _ => 0,
+        };
+        self.word = Some((self.current_index, word));
+        self.text_cache.clear();
+    }
+
+    /// The word of the current quote under the word cursor.
+    fn selected_word(&self) -> Option<String> {
+        let (index, word) = self
+            .word
+            .filter(|(index, _)| *index == self.current_index)?;
+        let line = self.japanese_line(&self.quotes[index]);
+        let range = words::split(&line).get(word)?.clone();
+        Some(line[range].to_string())
+    }
+
+    /// Opens jisho.org for the word under the cursor, or for the whole
+    /// Japanese line when no word is selected.
+    fn look_up_word(&mut self) {
+        let Some(quote) = self.current_quote() else {
+            return;
+        };
+        let word = self
+            .selected_word()
+            .unwrap_or_else(|| self.japanese_line(quote));
+        let url = words::jisho_url(&word);
+        if let Err(error) = open_in_browser(&url) {
+            self.status = Some(format!("Failed to open {url}: {error}"));
+        }
+    }
+
+    /// Switches the Japanese line between kanji and the readings in kana.
+    fn toggle_kana(&mut self) {
+        self.kana_only = !self.kana_only;
+        self.text_cache.clear();
+        let has_furigana = self
+            .current_quote()
+            .is_some_and(|quote| quote.furigana.is_some());
+        self.status = Some(match (self.kana_only, has_furigana) {
+            (true, true) => "Showing the Japanese in kana".to_string(),
+            (true, false) => {
+                "Showing the Japanese in kana where quotes have furigana; this one has none"
+                    .to_string()
+            }
+            (false, _) => "Showing the Japanese with kanji".to_string(),
+        });
+    }
+
+    /// Steps through just the queue, or back through every quote.
+    fn toggle_queue(&mut self) {
+        if self.queue_positions().is_empty() {
+            self.status =
+                Some("The queue is empty; add quotes with p in the browse list".to_string());
+            return;
+        }
+        self.queue_playing = !self.queue_playing;
+        self.refresh_order();
+        if !self.order.contains(&self.current_index) {
+            self.current_index = self.order[0];
+        }
+        self.status = Some(if self.queue_playing {
+            format!("Playing the queue of {}", self.order.len())
+        } else {
+            "Stopped playing the queue".to_string()
+        });
+    }
+
+    /// Queues the quotes in the playlist at `path` and plays them from the
+    /// first.
+    fn load_playlist(&mut self, path: &Path) {
+        let (ids, missing) = match playlist::load(path, &self.quotes) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                self.status = Some(format!("{error:#}"));
+                return;
+            }
+        };
+        if ids.is_empty() {
+            self.status = Some(format!(
+                "No quote in {} is in the collection",
+                path.display()
+            ));
+            return;
+        }
+        self.queue = ids;
+        self.queue_playing = true;
+        self.refresh_order();
+        self.current_index = self.order[0];
+        self.status = Some(match missing {
+            0 => format!(
+                "Playing {} quote(s) from {}",
+                self.order.len(),
+                path.display()
+            ),
+            missing => format!(
+                "Playing {} quote(s) from {}; {missing} not found",
+                self.order.len(),
+                path.display()
+            ),
+        });
+    }
+
+    /// Switches to the next sort order, staying on the current quote.
+    fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.refresh_order();
+        self.status = Some(format!("Sorted by {}", self.sort.label()));
+    }
+
+    /// Hides the current quote from the random, daily, and slideshow picks,
+    /// or brings it back.
+    fn toggle_hidden(&mut self) {
+        let Some(quote) = self.quotes.get(self.current_index) else {
+            return;
+        };
+        let hidden = !self.state.is_hidden(quote);
+        self.state.set_hidden(quote, hidden);
+        self.status = Some(match self.state.save() {
+            Ok(()) if hidden => "Hidden from random, daily, and slideshow picks".to_string(),
+            Ok(()) => "Back in the random, daily, and slideshow picks".to_string(),
+            Err(error) => format!("Could not save hidden quotes: {error}"),
+        });
+    }
+
+    fn toggle_favorite(&mut self) {
+        let Some(quote) = self.quotes.get(self.current_index) else {
+            return;
+        };
+        let favorite = !self.state.is_favorite(quote);
+        self.state.set_favorite(quote, favorite);
+        self.check_achievements();
+        self.status = Some(match self.state.save() {
+            Ok(()) if favorite => "Added to favorites".to_string(),
+            Ok(()) => "Removed from favorites".to_string(),
+            Err(error) => format!("Could not save favorites: {error}"),
+        });
+    }
+
+    /// Applies a change from the browse list to the quotes at `targets`,
+    /// returning what happened.
+    fn apply_batch(&mut self, batch: browse::Batch, targets: &[usize]) -> String {
+        let ids: HashSet<String> = targets
+            .iter()
+            .map(|&index| self.quotes[index].stable_id())
+            .collect();
+        let result = match batch {
+            browse::Batch::Tag(tag) => edit::tag(&ids, &tag).and_then(|count| {
+                self.reload()?;
+                Ok(format!("Tagged {count} quote(s) {tag:?}"))
+            }),
+            browse::Batch::Favorite => {
+                let favorite = !targets
+                    .iter()
+                    .all(|&index| self.state.is_favorite(&self.quotes[index]));
+                for &index in targets {
+                    self.state.set_favorite(&self.quotes[index], favorite);
+                }
+                self.check_achievements();
+                self.state.save().map(|()| {
+                    let count = targets.len();
+                    if favorite {
+                        format!("Added {count} quote(s) to favorites")
+                    } else {
+                        format!("Removed {count} quote(s) from favorites")
+                    }
+                })
+            }
+            browse::Batch::Delete => edit::delete(&ids).and_then(|count| {
+                self.reload()?;
+                Ok(format!("Deleted {count} quote(s)"))
+            }),
+            browse::Batch::Export(path) => {
+                let entries: Vec<export::Entry<'_>> = targets
+                    .iter()
+                    .map(|&index| (index, &self.quotes[index]))
+                    .collect();
+                export::write_for_path(&path, &entries)
+                    .map(|()| format!("Exported {} quote(s) to {}", targets.len(), path.display()))
+            }
+            browse::Batch::Move(name) => edit::move_to_pack(&ids, &name).and_then(|count| {
+                self.reload()?;
+                Ok(format!("Moved {count} quote(s) to pack {name}"))
+            }),
+            browse::Batch::Queue => {
+                let queued = targets
+                    .iter()
+                    .all(|&index| self.queue.contains(&self.quotes[index].stable_id()));
+                if queued {
+                    self.queue.retain(|id| !ids.contains(id));
+                } else {
+                    for &index in targets {
+                        let id = self.quotes[index].stable_id();
+                        if !self.queue.contains(&id) {
+                            self.queue.push(id);
+                        }
+                    }
+                }
+                self.refresh_order();
+                if !self.order.contains(&self.current_index) {
+                    self.current_index = self.order[0];
+                }
+                let count = targets.len();
+                let length = self.queue.len();
+                Ok(if queued {
+                    format!("Took {count} quote(s) out of the queue ({length} left)")
+                } else {
+                    format!("Queued {count} quote(s) ({length} in the queue)")
+                })
+            }
+            browse::Batch::SaveQueue(path) => {
+                playlist::save(&path, &self.queue, &self.quotes).map(|()| {
+                    format!(
+                        "Saved {} queued quote(s) to {}",
+                        self.queue.len(),
+                        path.display()
+                    )
+                })
+            }
+        };
+        match result {
+            Ok(message) => message,
+            Err(error) => format!("Failed: {error}"),
+        }
+    }
+
+    /// Reads the collection again after its files changed, keeping the
+    /// artwork of quotes that are still in it and staying on the same quote
+    /// when it is.
+    fn reload(&mut self) -> color_eyre::Result<()> {
+        let quotes = AnimeData::load_collection(data_path(), &self.packs)
+            .map_err(|error| color_eyre::eyre::eyre!("{error}"))?;
+        let current = self.current_quote().map(AnimeQuote::stable_id);
+        let pinned = self
+            .compare
+            .and_then(|index| self.quotes.get(index))
+            .map(AnimeQuote::stable_id);
+        let mut images: HashMap<String, ImageSlot> = self
+            .quotes
+            .iter()
+            .map(AnimeQuote::stable_id)
+            .zip(self.image_cache.drain(..))
+            .filter_map(|(id, slot)| Some((id, slot?)))
+            .collect();
+        self.image_cache = quotes
+            .iter()
+            .map(|quote| images.remove(&quote.stable_id()))
+            .collect();
+        self.quotes = quotes;
+        self.text_cache.clear();
+        self.refresh_order();
+        self.current_index = current
+            .and_then(|id| self.quotes.iter().position(|quote| quote.stable_id() == id))
+            .filter(|index| self.order.contains(index))
+            .or(self.order.first().copied())
+            .unwrap_or(0);
+        self.compare =
+            pinned.and_then(|id| self.quotes.iter().position(|quote| quote.stable_id() == id));
+        self.viewed_index = Some(self.current_index);
+        self.presence_index = None;
+        self.word = None;
+        self.restart_image_loading();
+        if let Some(browse) = &mut self.browse {
+            browse.clear_marks();
+        }
+        Ok(())
+    }
+
+    /// Puts the quote at `index` back into the picks from the hidden-quotes
+    /// screen.
+    fn unhide(&mut self, index: usize) {
+        self.state.set_hidden(&self.quotes[index], false);
+        if let Err(error) = self.state.save() {
+            self.status = Some(format!("Could not save hidden quotes: {error}"));
+        }
+    }
+
+    /// Shows the quote-of-the-day calendar, recording today's pick first so
+    /// it is part of the history.
+    fn open_calendar(&mut self) {
+        self.state.todays_pick(&self.quotes, &self.schedule);
+        self.calendar = Some(calendar::Calendar::new());
+    }
+
+    /// Opens the add form on the quote on the clipboard, to be checked
+    /// before it is added.
+    fn add_from_clipboard(&mut self) {
+        match add::read_clipboard() {
+            Ok(text) => self.add_form = Some(add_form::AddForm::new(&add::Draft::parse(&text))),
+            Err(error) => self.status = Some(format!("Could not read the clipboard: {error}")),
+        }
+    }
+
+    /// Appends `draft` to the data file and shows it, returning a summary
+    /// for the status line.
+    fn add_draft(&mut self, draft: &add::Draft) -> color_eyre::Result<String> {
+        add::append(std::path::Path::new(data_path()), draft)?;
+        let data = AnimeData::load_from_file(data_path())
+            .map_err(|error| color_eyre::eyre::eyre!("{error}"))?;
+        // New quotes go after the others from the data file and before the
+        // packs.
+        let position = data.quotes.len() - 1;
+        let quote = data.quotes[position].clone();
+        let summary = format!("Added {} — {}", quote.character, quote.anime);
+        // Show it even when the filter would leave it out.
+        if !self.filter.matches(&quote) {
+            self.filter = filter::QuoteFilter::default();
+            self.collection = None;
+        }
+        self.quotes.insert(position, quote);
+        self.image_cache.insert(position, None);
+        self.shift_indices(position);
+        self.refresh_order();
+        self.current_index = position;
+        self.restart_image_loading();
+        Ok(summary)
+    }
+
+    /// Moves the indices the viewer holds past a quote inserted at
+    /// `position`, so they keep pointing at the same quotes.
+    fn shift_indices(&mut self, position: usize) {
+        let shift = |index: &mut usize| {
+            if *index >= position {
+                *index += 1;
+            }
+        };
+        for index in [
+            self.compare.as_mut(),
+            self.viewed_index.as_mut(),
+            self.presence_index.as_mut(),
+            self.word.as_mut().map(|(index, _)| index),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            shift(index);
+        }
+    }
+
+    fn open_anime_page(&mut self) {
+        if let Some(quote) = self.current_quote() {
+            let url = quote.page_url(self.search_provider);
+            if let Err(error) = open_in_browser(&url) {
+                self.status = Some(format!("Could not open {url}: {error}"));
+            }
+        }
+    }
+
+    fn open_source_url(&mut self) {
+        let Some(quote) = self.current_quote() else {
+            return;
+        };
+        match quote.source_url.as_deref().map(str::trim) {
+            Some(url) if !url.is_empty() => {
+                if let Err(error) = open_in_browser(url) {
+                    self.status = Some(format!("Failed to open {url}: {error}"));
+                }
+            }
+            _ => self.status = Some("No source link for this quote".to_string()),
+        }
+    }
+
+    fn export_card(&mut self) {
+        if let Some(quote) = self.current_quote() {
+            let output = self.card_config.default_output(self.current_index);
+            self.status = Some(
+                match card::export_card(quote, &self.card_config, None, &output) {
+                    Ok(()) => format!("Saved card to {}", output.display()),
+                    Err(error) => format!("Card export failed: {error}"),
+                },
+            );
+        }
+    }
+
+    fn export_screen(&mut self) {
+        let format = self.screenshot_config.screen_format();
+        let output = self
+            .screenshot_config
+            .output_path(self.current_index, format);
+        self.status = Some(match self.write_screen(format, &output) {
+            Ok(()) => format!("Saved screen to {}", output.display()),
+            Err(error) => format!("Screen export failed: {error}"),
+        });
+    }
+
+    fn write_screen(
+        &mut self,
+        format: screenshot::ScreenFormat,
+        output: &std::path::Path,
+    ) -> io::Result<()> {
+        let area = self.last_area;
+        let mut terminal = Terminal::new(TestBackend::new(area.width, area.height))?;
+
+        // Graphics protocols only leave escape sequences in the buffer, so the
+        // artwork is encoded as halfblocks for the duration of the capture.
+        let mut halfblocks = Picker::from_fontsize(self.picker.font_size());
+        halfblocks.set_protocol_type(ProtocolType::Halfblocks);
+        let picker = std::mem::replace(&mut self.picker, halfblocks);
+        let drawn = terminal.draw(|frame| self.draw(frame)).map(|_| ());
+        self.picker = picker;
+        drawn?;
+        self.last_area = area;
+
+        let title = self
+            .current_quote()
+            .map(|quote| format!("{} — {}", quote.character, quote.anime))
+            .unwrap_or_else(|| "Anime Quotes".to_string());
+        let document = format.render(terminal.backend().buffer(), &title);
+        if let Some(parent) = output
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(output, document)
+    }
+
+    /// The image area for the quote at `index`: its own `image_width` and
+    /// `image_height` when set (a lone width keeps the configured aspect),
+    /// otherwise the `[ui.ascii]` size.
+    fn image_dimensions(&self, index: usize) -> (u16, u16) {
+        let Some(quote) = self.quotes.get(index) else {
+            return (self.image_width, self.image_height);
+        };
+        match (quote.image_width, quote.image_height) {
+            (None, None) => (self.image_width, self.image_height),
+            (Some(width), None) => {
+                let height = (f32::from(width) * self.image_height as f32
+                    / self.image_width.max(1) as f32)
+                    .round() as u16;
+                (width.max(1), height.max(1))
+            }
+            (None, Some(height)) => {
+                let width = (f32::from(height) * self.image_width as f32
+                    / self.image_height.max(1) as f32)
+                    .round() as u16;
+                (width.max(1), height.max(1))
+            }
+            (Some(width), Some(height)) => (width.max(1), height.max(1)),
+        }
     }
 
     fn current_quote(&self) -> Option<&AnimeQuote> {
         self.quotes.get(self.current_index)
     }
 
-    fn instructions_line(&self) -> Line<'static> {
-        let key_style = Style::default().fg(self.palette.instructions).bold();
+    /// The time and date, plus how many of the quotes have been shown today.
+    fn clock_line(&self, format: &str) -> Line<'static> {
+        let mut clock = String::new();
+        if write!(clock, "{}", chrono::Local::now().format(format)).is_err() {
+            clock = format!("invalid [ui.clock] format {format:?}");
+        }
+        let count_style = Style::default().fg(self.palette.count);
         Line::from(vec![
-            Span::raw(" Previous "),
-            Span::styled("<Left>", key_style),
-            Span::raw(" Next "),
-            Span::styled("<Right>", key_style),
-            Span::raw(" Quit "),
-            Span::styled("<Q>", key_style),
-            Span::raw(" "),
+            Span::styled(clock, Style::default().bold()),
+            Span::styled(
+                format!(
+                    "  ·  {} of {} quotes today",
+                    self.seen_today.1.len(),
+                    self.quotes.len()
+                ),
+                count_style,
+            ),
         ])
     }
+
+    /// The active filter or collection, the slideshow's shuffle, whether the
+    /// current quote is a favorite, and the data file's name.
+    fn status_line(&self) -> Line<'static> {
+        let style = Style::default().fg(self.palette.count);
+        let filter = match self
+            .collection
+            .and_then(|index| self.collections.get(index))
+        {
+            _ if self.queue_playing => "Queue".to_string(),
+            Some(collection) => format!("Collection: {}", collection.name),
+            None if self.filter.is_empty() => "All quotes".to_string(),
+            None => format!("Filter: {}", self.filter.describe()),
+        };
+        let rotation = self
+            .kiosk
+            .as_ref()
+            .map(|kiosk| kiosk.rotation)
+            .or(self.idle.as_ref().map(|idle| idle.rotation));
+        let shuffle = match rotation {
+            None => "Slideshow off",
+            Some(selection::Rotation::Order) => "Shuffle off",
+            Some(selection::Rotation::Shuffle) => "Shuffle on",
+            Some(selection::Rotation::LeastSeen) => "Least seen first",
+        };
+        let favorite = match self.current_quote() {
+            Some(quote) if self.state.is_favorite(quote) => "★ Favorite",
+            _ => "☆ Not a favorite",
+        };
+        let file = Path::new(data_path())
+            .file_name()
+            .map_or_else(|| data_path().into(), |name| name.to_string_lossy());
+        let separator = || Span::styled("  ·  ", style);
+        Line::from(vec![
+            Span::styled(filter, style),
+            separator(),
+            Span::styled(shuffle, style),
+            separator(),
+            Span::styled(favorite, style),
+            separator(),
+            Span::styled(file.into_owned(), style),
+        ])
+    }
+
+    /// The key hints that fit in `width` columns; the later hints give way
+    /// first, but Quit always stays when it is one of them.
+    fn instructions_line(&self, width: u16) -> Line<'static> {
+        let key_style = Style::default().fg(self.palette.instructions).bold();
+        let mut hints = self.hints.clone();
+        let length = |hints: &[keymap::Hint]| {
+            hints
+                .iter()
+                .map(|hint| hint.label.width() + hint.key.len() + 2)
+                .sum::<usize>()
+                + 1
+        };
+        while hints.len() > 3 && length(&hints) > usize::from(width) {
+            match hints
+                .iter()
+                .rposition(|hint| hint.action != keymap::Action::Quit)
+            {
+                Some(position) => hints.remove(position),
+                None => break,
+            };
+        }
+        let mut spans = Vec::new();
+        for hint in hints {
+            spans.push(Span::raw(format!(" {} ", hint.label)));
+            spans.push(Span::styled(hint.key, key_style));
+        }
+        spans.push(Span::raw(" "));
+        Line::from(spans)
+    }
 }
@@ -0,0 +1,336 @@
+//! Merging collections: the union of several data files, with quotes that
+//! share an ID combined and near-duplicates (the same line entered twice with
+//! different wording or IDs) settled one way or the other.
+
+use crate::AnimeQuote;
+use color_eyre::eyre::{Result, bail};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Which side of a conflict to keep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Keep {
+    /// The quote from the file listed earlier.
+    First,
+    /// The quote from the file listed later.
+    Last,
+    /// Both quotes; only offered for near-duplicates, since two quotes with
+    /// one ID would collide in the state file.
+    Both,
+}
+
+pub(crate) enum ConflictKind {
+    /// The same ID with different values in these fields.
+    SameId(Vec<&'static str>),
+    /// Different IDs, but lines this similar (0 to 1) from the same anime.
+    NearDuplicate(f64),
+}
+
+pub(crate) struct Conflict<'a> {
+    pub(crate) kind: ConflictKind,
+    /// The quote so far and the file it came from.
+    pub(crate) first: (&'a str, &'a AnimeQuote),
+    pub(crate) last: (&'a str, &'a AnimeQuote),
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Summary {
+    pub(crate) read: usize,
+    /// Quotes found in more than one file under the same ID.
+    pub(crate) combined: usize,
+    pub(crate) conflicts: usize,
+    pub(crate) near_duplicates: usize,
+    /// Near-duplicates kept as two quotes.
+    pub(crate) kept_both: usize,
+}
+
+/// Unions `sources` (file name and quotes) in order. A quote whose ID is
+/// already in gets the fields it lacks filled in from the other and their
+/// tags combined; fields set differently on both sides, and near-duplicates
+/// at or above `threshold`, are settled by `resolve`.
+pub(crate) fn merge<'a>(
+    sources: &'a [(String, Vec<AnimeQuote>)],
+    threshold: f64,
+    mut resolve: impl FnMut(&Conflict<'_>) -> Result<Keep>,
+) -> Result<(Vec<AnimeQuote>, Summary)> {
+    let mut merged: Vec<(&'a str, AnimeQuote)> = Vec::new();
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    let mut summary = Summary::default();
+    for (name, quotes) in sources {
+        for quote in quotes {
+            summary.read += 1;
+            let id = quote.stable_id();
+            if let Some(&position) = by_id.get(&id) {
+                summary.combined += 1;
+                let (first_name, first) = &merged[position];
+                let fields = differing_fields(first, quote);
+                let keep = if fields.is_empty() {
+                    Keep::First
+                } else {
+                    summary.conflicts += 1;
+                    resolve(&Conflict {
+                        kind: ConflictKind::SameId(fields),
+                        first: (first_name, first),
+                        last: (name, quote),
+                    })?
+                };
+                let combined = match keep {
+                    Keep::Last => combine(quote, first),
+                    Keep::First | Keep::Both => combine(first, quote),
+                };
+                merged[position].1 = combined;
+                continue;
+            }
+
+            let near = merged
+                .iter()
+                .enumerate()
+                .filter_map(|(position, (_, other))| {
+                    let score = likeness(other, quote);
+                    (score >= threshold).then_some((position, score))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((position, score)) = near {
+                summary.near_duplicates += 1;
+                let (first_name, first) = &merged[position];
+                let keep = resolve(&Conflict {
+                    kind: ConflictKind::NearDuplicate(score),
+                    first: (first_name, first),
+                    last: (name, quote),
+                })?;
+                match keep {
+                    Keep::First => continue,
+                    Keep::Last => {
+                        by_id.remove(&merged[position].1.stable_id());
+                        by_id.insert(id, position);
+                        merged[position] = (name, quote.clone());
+                        continue;
+                    }
+                    Keep::Both => summary.kept_both += 1,
+                }
+            }
+            by_id.insert(id, merged.len());
+            merged.push((name, quote.clone()));
+        }
+    }
+    Ok((
+        merged.into_iter().map(|(_, quote)| quote).collect(),
+        summary,
+    ))
+}
+
+/// The fields set on both quotes to different values.
+fn differing_fields(a: &AnimeQuote, b: &AnimeQuote) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    macro_rules! compare {
+        (required: $($required:ident),*; optional: $($optional:ident),*) => {
+            $(if a.$required.trim() != b.$required.trim() {
+                fields.push(stringify!($required));
+            })*
+            $(if let (Some(first), Some(last)) = (&a.$optional, &b.$optional) {
+                if first != last {
+                    fields.push(stringify!($optional));
+                }
+            })*
+        };
+    }
+    compare!(
+        required: japanese, anime, character, quote;
+        optional: romaji, pitch, furigana, image, image_width, image_height, crop, image_sha256, url, episode, timestamp, video, season, year,
+            source, source_url, show_on, show_season, birthday, rating, notes, added
+    );
+    let extra_differs = a
+        .extra
+        .iter()
+        .any(|(key, value)| b.extra.get(key).is_some_and(|other| other != value));
+    if extra_differs {
+        fields.push("extra fields");
+    }
+    fields
+}
+
+/// `preferred` with the fields it leaves out taken from `other`, and the two
+/// quotes' tags combined.
+fn combine(preferred: &AnimeQuote, other: &AnimeQuote) -> AnimeQuote {
+    let mut combined = preferred.clone();
+    macro_rules! fill {
+        ($($field:ident),*) => {
+            $(if combined.$field.is_none() {
+                combined.$field = other.$field.clone();
+            })*
+        };
+    }
+    fill!(
+        id,
+        romaji,
+        pitch,
+        furigana,
+        image,
+        image_width,
+        image_height,
+        crop,
+        image_sha256,
+        url,
+        episode,
+        timestamp,
+        video,
+        season,
+        year,
+        source,
+        source_url,
+        show_on,
+        show_season,
+        birthday,
+        rating,
+        notes,
+        added
+    );
+    for (key, value) in &other.extra {
+        combined
+            .extra
+            .entry(key.clone())
+            .or_insert_with(|| value.clone());
+    }
+    for tag in &other.tags {
+        if !combined.tags.contains(tag) {
+            combined.tags.push(tag.clone());
+        }
+    }
+    combined
+}
+
+/// How alike two quotes are, from 0 to 1: the closer of their Japanese lines
+/// and, for the same character, their translations. Quotes from different
+/// anime are never alike.
+fn likeness(a: &AnimeQuote, b: &AnimeQuote) -> f64 {
+    if !a.anime.trim().eq_ignore_ascii_case(b.anime.trim()) {
+        return 0.0;
+    }
+    let japanese = similarity(&a.japanese, &b.japanese);
+    if a.character.trim().eq_ignore_ascii_case(b.character.trim()) {
+        japanese.max(similarity(&a.quote, &b.quote))
+    } else {
+        japanese
+    }
+}
+
+/// The Dice coefficient of the two texts' character pairs, ignoring case,
+/// spacing, and punctuation.
+fn similarity(a: &str, b: &str) -> f64 {
+    let normalize = |text: &str| -> Vec<char> {
+        text.chars()
+            .filter(|ch| ch.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (normalize(a), normalize(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    let mut pairs: HashMap<(char, char), usize> = HashMap::new();
+    for pair in a.windows(2) {
+        *pairs.entry((pair[0], pair[1])).or_default() += 1;
+    }
+    let mut shared = 0;
+    for pair in b.windows(2) {
+        if let Some(count) = pairs
+            .get_mut(&(pair[0], pair[1]))
+            .filter(|count| **count > 0)
+        {
+            *count -= 1;
+            shared += 1;
+        }
+    }
+    let total = a.len() + b.len() - 2;
+    if total == 0 {
+        return 0.0;
+    }
+    2.0 * shared as f64 / total as f64
+}
+
+/// Image paths relative to `directory`, where the merged file goes, when the
+/// image is under it and absolute otherwise; as loaded they are relative to
+/// the working directory.
+pub(crate) fn relocate_images(quotes: &mut [AnimeQuote], directory: &Path) {
+    let Ok(directory) = std::path::absolute(directory) else {
+        return;
+    };
+    for image in quotes.iter_mut().filter_map(|quote| quote.image.as_mut()) {
+        let Ok(absolute) = std::path::absolute(&*image) else {
+            continue;
+        };
+        *image = absolute
+            .strip_prefix(&directory)
+            .unwrap_or(&absolute)
+            .to_string_lossy()
+            .into_owned();
+    }
+}
+
+/// Shows both sides of `conflict` on stderr and asks which to keep.
+pub(crate) fn ask(input: &mut impl BufRead, conflict: &Conflict<'_>) -> Result<Keep> {
+    let heading = match &conflict.kind {
+        ConflictKind::SameId(fields) => format!(
+            "{} differs in {}:",
+            conflict.first.1.stable_id(),
+            fields.join(", ")
+        ),
+        ConflictKind::NearDuplicate(score) => {
+            format!("possible duplicate ({:.0}% alike):", score * 100.0)
+        }
+    };
+    eprintln!("\n{heading}");
+    for (label, (name, quote)) in [("1", conflict.first), ("2", conflict.last)] {
+        eprintln!("  {label}) {name}: {}", describe(quote));
+        if let ConflictKind::SameId(fields) = &conflict.kind {
+            for field in fields {
+                eprintln!("       {field}: {}", field_value(quote, field));
+            }
+        }
+    }
+    let near_duplicate = matches!(conflict.kind, ConflictKind::NearDuplicate(_));
+    let prompt = if near_duplicate {
+        "keep 1, 2, or b(oth)? [1] "
+    } else {
+        "keep the values of 1 or 2? [1] "
+    };
+    loop {
+        eprint!("{prompt}");
+        io::stderr().flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            bail!("input ended before every conflict was settled");
+        }
+        match line.trim().to_ascii_lowercase().as_str() {
+            "" | "1" => return Ok(Keep::First),
+            "2" => return Ok(Keep::Last),
+            "b" | "both" if near_duplicate => return Ok(Keep::Both),
+            _ => eprintln!(
+                "  answer 1 or 2{}",
+                if near_duplicate { " or b" } else { "" }
+            ),
+        }
+    }
+}
+
+fn describe(quote: &AnimeQuote) -> String {
+    format!(
+        "{} — {}: \"{}\" ({})",
+        quote.character, quote.anime, quote.quote, quote.japanese
+    )
+}
+
+fn field_value(quote: &AnimeQuote, field: &str) -> String {
+    let value = serde_json::to_value(quote)
+        .ok()
+        .and_then(|value| value.get(field).cloned());
+    match value {
+        Some(serde_json::Value::String(text)) => text,
+        Some(value) => value.to_string(),
+        None => "(none)".to_string(),
+    }
+}
@@ -0,0 +1,158 @@
+//! Counters for `serve`, exposed at `/metrics` in the Prometheus text
+//! format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub(crate) struct Metrics {
+    started: Instant,
+    started_at: f64,
+    /// By endpoint pattern and status code.
+    requests: Mutex<BTreeMap<(&'static str, u16), u64>>,
+    quotes_served: AtomicU64,
+    image_cache_hits: AtomicU64,
+    image_cache_misses: AtomicU64,
+    stream_clients: AtomicU64,
+}
+
+/// Decrements the connected-clients gauge when a `/stream` client leaves.
+pub(crate) struct StreamClient<'a>(&'a Metrics);
+
+impl Drop for StreamClient<'_> {
+    fn drop(&mut self) {
+        self.0.stream_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |elapsed| elapsed.as_secs_f64()),
+            requests: Mutex::new(BTreeMap::new()),
+            quotes_served: AtomicU64::new(0),
+            image_cache_hits: AtomicU64::new(0),
+            image_cache_misses: AtomicU64::new(0),
+            stream_clients: AtomicU64::new(0),
+        }
+    }
+
+    /// `endpoint` is the route pattern, like `/quotes/{id}`, so IDs don't
+    /// each become a series.
+    pub(crate) fn request(&self, endpoint: &'static str, status: u16) {
+        if let Ok(mut requests) = self.requests.lock() {
+            *requests.entry((endpoint, status)).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn served(&self, quotes: usize) {
+        self.quotes_served
+            .fetch_add(quotes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn image_cache(&self, hit: bool) {
+        let counter = if hit {
+            &self.image_cache_hits
+        } else {
+            &self.image_cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stream_client(&self) -> StreamClient<'_> {
+        self.stream_clients.fetch_add(1, Ordering::Relaxed);
+        StreamClient(self)
+    }
+
+    pub(crate) fn render(&self, quotes_loaded: usize) -> String {
+        let mut output = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(output, "# HELP anime_quotes_{name} {help}");
+            let _ = writeln!(output, "# TYPE anime_quotes_{name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(output, "anime_quotes_{name}{labels} {value}");
+            }
+        };
+        let single = |value: String| vec![(String::new(), value)];
+        let count = |counter: &AtomicU64| single(counter.load(Ordering::Relaxed).to_string());
+
+        let requests: Vec<(String, String)> = self
+            .requests
+            .lock()
+            .map(|requests| {
+                requests
+                    .iter()
+                    .map(|((endpoint, status), count)| {
+                        (
+                            format!("{{endpoint=\"{endpoint}\",status=\"{status}\"}}"),
+                            count.to_string(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        metric(
+            "http_requests_total",
+            "counter",
+            "HTTP requests by endpoint and status code.",
+            &requests,
+        );
+        metric(
+            "quotes_served_total",
+            "counter",
+            "Quotes sent in responses and stream events.",
+            &count(&self.quotes_served),
+        );
+        metric(
+            "image_cache_hits_total",
+            "counter",
+            "Artwork requests answered from memory.",
+            &count(&self.image_cache_hits),
+        );
+        metric(
+            "image_cache_misses_total",
+            "counter",
+            "Artwork requests that read the file.",
+            &count(&self.image_cache_misses),
+        );
+        metric(
+            "stream_clients",
+            "gauge",
+            "Clients connected to /stream.",
+            &count(&self.stream_clients),
+        );
+        metric(
+            "quotes_loaded",
+            "gauge",
+            "Quotes in the served collection.",
+            &single(quotes_loaded.to_string()),
+        );
+        metric(
+            "uptime_seconds",
+            "gauge",
+            "Seconds since the server started.",
+            &single(format!("{:.3}", self.started.elapsed().as_secs_f64())),
+        );
+        metric(
+            "start_time_seconds",
+            "gauge",
+            "Unix time the server started.",
+            &single(format!("{:.3}", self.started_at)),
+        );
+        metric(
+            "build_info",
+            "gauge",
+            "Always 1; the version is in the label.",
+            &[(
+                format!("{{version=\"{}\"}}", env!("CARGO_PKG_VERSION")),
+                "1".to_string(),
+            )],
+        );
+        output
+    }
+}
@@ -0,0 +1,187 @@
+//! `organize`: reorders the quotes of the data file into groups, and can
+//! move them out into one file per anime under `quotes.d/`.
+//!
+//! `quotes.d/` sits next to the data file, and its `*.toml` files are read
+//! after the data file, in name order, as if they were part of it. Quotes
+//! are moved as `toml_edit` tables, so their comments move with them.
+
+use crate::export::markdown_anchor;
+use crate::schema::CURRENT_SCHEMA_VERSION;
+use crate::{edit, paths};
+use color_eyre::eyre::{Result, WrapErr};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{ArrayOfTables, DocumentMut, Table};
+
+const SPLIT_DIR: &str = "quotes.d";
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum GroupBy {
+    Anime,
+    /// By character, then anime for characters who share a name.
+    Character,
+}
+
+/// The `quotes.d` directory of the data file at `data`.
+pub(crate) fn split_dir(data: &Path) -> PathBuf {
+    data.with_file_name(SPLIT_DIR)
+}
+
+/// The files in `quotes.d` next to the data file at `data`, in the order
+/// they are read.
+pub(crate) fn split_files(data: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(split_dir(data)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Sorts the quotes of the data file at `data` and of its `quotes.d` files
+/// into groups, keeping their order within each group. With `split`, the
+/// data file's quotes are first moved into a file per anime in `quotes.d`,
+/// joining the quotes already there. Returns a line per file written.
+pub(crate) fn organize(data: &Path, by: GroupBy, split: bool) -> Result<Vec<String>> {
+    let mut report = Vec::new();
+    let mut document = edit::read_document(data)?;
+    let mut files = split_files(data);
+    if split {
+        let moved = std::mem::take(edit::quotes_mut(&mut document, data)?);
+        document.remove("quotes");
+        let dir = split_dir(data);
+        for (slug, tables) in by_anime(moved) {
+            let path = dir.join(format!("{slug}.toml"));
+            let mut part = if path.exists() {
+                edit::read_document(&path)?
+            } else {
+                let mut part = DocumentMut::new();
+                part.insert("schema_version", toml_edit::value(CURRENT_SCHEMA_VERSION));
+                part
+            };
+            let quotes = edit::quotes_mut(&mut part, &path)?;
+            report.push(format!(
+                "moved {} quotes to {}",
+                tables.len(),
+                path.display()
+            ));
+            for mut table in tables {
+                // Paths were relative to the data file's directory.
+                for key in ["image", "video"] {
+                    rebase(&mut table, key);
+                }
+                table.set_position(None);
+                quotes.push(table);
+            }
+            fs::create_dir_all(&dir)
+                .wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+            write(&path, &part)?;
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+        write(data, &document)?;
+    }
+    files.insert(0, data.to_path_buf());
+    for path in files {
+        let mut document = edit::read_document(&path)?;
+        let quotes = edit::quotes_mut(&mut document, &path)?;
+        if sort(quotes, by) {
+            write(&path, &document)?;
+            report.push(format!(
+                "sorted {} quotes in {}",
+                quotes_len(&document),
+                path.display()
+            ));
+        }
+    }
+    Ok(report)
+}
+
+fn quotes_len(document: &DocumentMut) -> usize {
+    document
+        .get("quotes")
+        .and_then(|item| item.as_array_of_tables())
+        .map_or(0, ArrayOfTables::len)
+}
+
+fn write(path: &Path, document: &DocumentMut) -> Result<()> {
+    fs::write(path, document.to_string())
+        .wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+fn text(table: &Table, key: &str) -> String {
+    table
+        .get(key)
+        .and_then(|item| item.as_str())
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// Sorts `quotes` stably, returning whether the order changed.
+fn sort(quotes: &mut ArrayOfTables, by: GroupBy) -> bool {
+    let key = |table: &Table| match by {
+        GroupBy::Anime => (text(table, "anime"), String::new()),
+        GroupBy::Character => (text(table, "character"), text(table, "anime")),
+    };
+    let keys: Vec<_> = quotes.iter().map(key).collect();
+    if keys.is_sorted() {
+        return false;
+    }
+    // Tables are written in order of their position in the file, so giving
+    // them all the first one's keeps them together in the new order.
+    let position = quotes.iter().filter_map(Table::position).min();
+    let mut tables: Vec<Table> = std::mem::take(quotes).into_iter().collect();
+    tables.sort_by_cached_key(key);
+    for mut table in tables {
+        table.set_position(position);
+        quotes.push(table);
+    }
+    true
+}
+
+/// `quotes` grouped by anime, each under the file name its anime gets.
+fn by_anime(quotes: ArrayOfTables) -> BTreeMap<String, Vec<Table>> {
+    let mut names: BTreeMap<String, String> = BTreeMap::new();
+    let mut groups: BTreeMap<String, Vec<Table>> = BTreeMap::new();
+    for table in quotes {
+        let anime = text(&table, "anime");
+        let count = names.len();
+        let slug = names.entry(anime.clone()).or_insert_with(|| {
+            let slug = markdown_anchor(&anime);
+            if slug.is_empty() {
+                format!("anime-{}", count + 1)
+            } else {
+                slug
+            }
+        });
+        groups.entry(slug.clone()).or_default().push(table);
+    }
+    groups
+}
+
+/// Points the relative path in `table[key]` at the same file from inside
+/// `quotes.d`.
+fn rebase(table: &mut Table, key: &str) {
+    let Some(value) = table.get_mut(key).and_then(|item| item.as_value_mut()) else {
+        return;
+    };
+    let Some(written) = value.as_str() else {
+        return;
+    };
+    let expanded = paths::expand(written);
+    if written.contains("://") || expanded != written || !Path::new(written).is_relative() {
+        return;
+    }
+    let decor = value.decor().clone();
+    *value = format!("../{written}").into();
+    *value.decor_mut() = decor;
+}
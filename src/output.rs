@@ -0,0 +1,449 @@
+use crate::export::markdown_anchor;
+use crate::screenshot::{escape_html, sgr};
+use crate::{AnimeQuote, AsciiSettings, Palette, ascii, filter};
+use ratatui::style::{Color, Modifier};
+use serde::Serialize;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+pub(crate) const DEFAULT_BUBBLE_WIDTH: usize = 40;
+
+/// JSON shape of a quote shared by the HTTP API and the control socket:
+/// `id` is the stable ID and `index` the 1-based position shown in the
+/// viewer counter.
+#[derive(Serialize)]
+pub(crate) struct QuoteView<'a> {
+    pub(crate) id: String,
+    pub(crate) index: usize,
+    #[serde(flatten)]
+    pub(crate) quote: &'a AnimeQuote,
+}
+
+impl<'a> QuoteView<'a> {
+    /// `position` is 0-based.
+    pub(crate) fn new(position: usize, quote: &'a AnimeQuote) -> Self {
+        Self {
+            id: quote.stable_id(),
+            index: position + 1,
+            quote,
+        }
+    }
+}
+
+/// Fields available to user templates.
+pub(crate) const TEMPLATE_FIELDS: &[&str] = &[
+    "id",
+    "japanese",
+    "romaji",
+    "pitch",
+    "furigana",
+    "anime",
+    "character",
+    "quote",
+    "image",
+    "url",
+    "episode",
+    "timestamp",
+    "video",
+    "season",
+    "year",
+    "source",
+    "source_url",
+    "tags",
+    "rating",
+    "metadata",
+    "notes",
+    "index",
+    "total",
+];
+
+/// Looks up a template field for the quote at `index` (0-based) of `total`.
+pub(crate) fn quote_field(
+    quote: &AnimeQuote,
+    index: usize,
+    total: usize,
+    name: &str,
+) -> Option<String> {
+    match name {
+        "id" => Some(quote.stable_id()),
+        "japanese" => Some(quote.japanese.clone()),
+        "romaji" => quote.romaji.clone(),
+        "pitch" => quote.pitch.clone(),
+        "furigana" => quote.furigana.clone(),
+        "anime" => Some(quote.anime.clone()),
+        "character" => Some(quote.character.clone()),
+        "quote" => Some(quote.quote.clone()),
+        "image" => quote.image.clone(),
+        "url" => quote.url.clone(),
+        "episode" => quote.episode.map(|episode| episode.to_string()),
+        "timestamp" => quote.timestamp.clone(),
+        "video" => quote.video.clone(),
+        "season" => quote.season.map(|season| season.to_string()),
+        "year" => quote.year.map(|year| year.to_string()),
+        "source" => quote.source.clone(),
+        "source_url" => quote.source_url.clone(),
+        "tags" => (!quote.tags.is_empty()).then(|| quote.tags.join(", ")),
+        "rating" => quote.rating.map(|rating| rating.to_string()),
+        "metadata" => quote.metadata_line(),
+        "notes" => quote.notes.clone(),
+        "index" => Some((index + 1).to_string()),
+        "total" => Some(total.to_string()),
+        _ => None,
+    }
+}
+
+pub(crate) fn render_plain(quote: &AnimeQuote) -> String {
+    let mut output = format!("{}\n", quote.japanese);
+    if let Some(romaji) = &quote.romaji {
+        output.push_str(&format!("{romaji}\n"));
+    }
+    output.push_str(&format!(
+        "\"{}\"\n  — {}, {}\n",
+        quote.quote, quote.character, quote.anime
+    ));
+    if let Some(metadata) = quote.metadata_line() {
+        output.push_str(&format!("    {metadata}\n"));
+    }
+    output
+}
+
+const GREETING_MIN_PORTRAIT_WIDTH: usize = 50;
+const GREETING_GAP: usize = 3;
+
+/// Login-shell greeting: the quote in palette colors with the character's
+/// ASCII portrait beside it, neofetch-style. Without a terminal width (e.g.
+/// when piped) it falls back to uncolored plain output, and on narrow
+/// terminals it drops the portrait.
+pub(crate) fn render_greeting(
+    quote: &AnimeQuote,
+    palette: &Palette,
+    ascii_settings: &AsciiSettings,
+    terminal_width: Option<usize>,
+    show_portrait: bool,
+) -> String {
+    let Some(width) = terminal_width else {
+        return render_plain(quote);
+    };
+
+    let base_width = quote
+        .image_width
+        .map_or(ascii_settings.base_width as usize, usize::from);
+    let portrait_width = base_width.min(width / 3);
+    let portrait = if show_portrait && width >= GREETING_MIN_PORTRAIT_WIDTH {
+        portrait_lines(quote, ascii_settings, portrait_width)
+    } else {
+        Vec::new()
+    };
+    let text_width = if portrait.is_empty() {
+        width
+    } else {
+        width.saturating_sub(portrait_width + GREETING_GAP).max(10)
+    };
+
+    let styled = |color: Color, modifier: Modifier, line: String| {
+        format!("{}{line}\x1b[0m", sgr((color, Color::Reset, modifier)))
+    };
+    let mut text = Vec::new();
+    text.extend(
+        wrap_columns(&quote.japanese, text_width)
+            .into_iter()
+            .map(|line| styled(palette.japanese, Modifier::BOLD, line)),
+    );
+    if let Some(romaji) = &quote.romaji {
+        text.extend(
+            wrap_columns(romaji, text_width)
+                .into_iter()
+                .map(|line| styled(palette.romaji, Modifier::empty(), line)),
+        );
+    }
+    text.push(String::new());
+    text.extend(
+        wrap_columns(&format!("\"{}\"", quote.quote), text_width)
+            .into_iter()
+            .map(|line| styled(palette.quote, Modifier::ITALIC, line)),
+    );
+    text.push(format!(
+        "  — {}, {}",
+        styled(palette.character, Modifier::BOLD, quote.character.clone()),
+        styled(palette.anime, Modifier::BOLD, quote.anime.clone())
+    ));
+    if let Some(metadata) = quote.metadata_line() {
+        text.push(format!(
+            "    {}",
+            styled(palette.count, Modifier::empty(), metadata)
+        ));
+    }
+
+    let rows = text.len().max(portrait.len());
+    let text_top = (rows - text.len()) / 2;
+    let mut output = String::new();
+    for row in 0..rows {
+        let mut line = String::new();
+        if !portrait.is_empty() {
+            let art = portrait.get(row).map(String::as_str).unwrap_or("");
+            line.push_str(&format!(
+                "{}{art}\x1b[0m{}",
+                sgr((palette.count, Color::Reset, Modifier::empty())),
+                " ".repeat(portrait_width.saturating_sub(art.width()) + GREETING_GAP)
+            ));
+        }
+        if let Some(text_line) = row.checked_sub(text_top).and_then(|index| text.get(index)) {
+            line.push_str(text_line);
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// A `grep` hit for the quote at `position` (0-based): its number, ID, and
+/// attribution, then the translation and any Japanese or romaji line that
+/// matches. With a palette the output is colored and matches are
+/// highlighted; `None` when nothing in the quote matches.
+pub(crate) fn render_grep(
+    quote: &AnimeQuote,
+    position: usize,
+    pattern: &str,
+    case_sensitive: bool,
+    palette: Option<&Palette>,
+) -> Option<String> {
+    let highlight = |text: &str, color: Color, modifier: Modifier| -> (String, bool) {
+        let ranges = filter::find(text, pattern, case_sensitive);
+        let Some(palette) = palette else {
+            return (text.to_string(), !ranges.is_empty());
+        };
+        let base = sgr((color, Color::Reset, modifier));
+        let mut styled = base.clone();
+        let mut last = 0;
+        for range in &ranges {
+            styled.push_str(&text[last..range.start]);
+            styled.push_str(&sgr((
+                palette.quote,
+                Color::Reset,
+                Modifier::BOLD | Modifier::REVERSED,
+            )));
+            styled.push_str(&text[range.clone()]);
+            styled.push_str(&base);
+            last = range.end;
+        }
+        styled.push_str(&text[last..]);
+        styled.push_str("\x1b[0m");
+        (styled, !ranges.is_empty())
+    };
+    let colors = palette.cloned().unwrap_or_default();
+    let (character, character_hit) = highlight(&quote.character, colors.character, Modifier::BOLD);
+    let (anime, anime_hit) = highlight(&quote.anime, colors.anime, Modifier::BOLD);
+    let (japanese, japanese_hit) = highlight(&quote.japanese, colors.japanese, Modifier::empty());
+    let romaji = quote
+        .romaji
+        .as_deref()
+        .map(|romaji| highlight(romaji, colors.romaji, Modifier::empty()));
+    let (translation, quote_hit) = highlight(&quote.quote, colors.quote, Modifier::ITALIC);
+    let romaji_hit = romaji.as_ref().is_some_and(|(_, hit)| *hit);
+    if !(character_hit || anime_hit || japanese_hit || romaji_hit || quote_hit) {
+        return None;
+    }
+
+    let (id, number) = (quote.stable_id(), position + 1);
+    let mut output = match palette {
+        Some(palette) => format!(
+            "{}{number}  {id}\x1b[0m  {character}, {anime}\n",
+            sgr((palette.count, Color::Reset, Modifier::empty()))
+        ),
+        None => format!("{number}  {id}  {character}, {anime}\n"),
+    };
+    if japanese_hit {
+        output.push_str(&format!("    {japanese}\n"));
+    }
+    if let Some((romaji, true)) = romaji {
+        output.push_str(&format!("    {romaji}\n"));
+    }
+    output.push_str(&format!("    \"{translation}\"\n"));
+    Some(output)
+}
+
+/// cowsay-style speech bubble with the character's ASCII-art portrait below.
+pub(crate) fn render_bubble(
+    quote: &AnimeQuote,
+    ascii_settings: &AsciiSettings,
+    width: usize,
+    portrait_width: u32,
+) -> String {
+    let width = width.max(8);
+    let mut text = wrap_columns(&quote.japanese, width);
+    text.extend(wrap_columns(&format!("\"{}\"", quote.quote), width));
+
+    let inner = text.iter().map(|line| line.width()).max().unwrap_or(0);
+    let mut output = format!(" {}\n", "_".repeat(inner + 2));
+    for (index, line) in text.iter().enumerate() {
+        let (left, right) = match (text.len(), index) {
+            (1, _) => ('<', '>'),
+            (_, 0) => ('/', '\\'),
+            (count, index) if index == count - 1 => ('\\', '/'),
+            _ => ('|', '|'),
+        };
+        let padding = " ".repeat(inner - line.width());
+        output.push_str(&format!("{left} {line}{padding} {right}\n"));
+    }
+    output.push_str(&format!(" {}\n", "-".repeat(inner + 2)));
+    output.push_str("        \\\n         \\\n");
+
+    let portrait_width = quote
+        .image_width
+        .map_or(portrait_width as usize, usize::from);
+    let portrait = portrait_lines(quote, ascii_settings, portrait_width);
+    for line in portrait {
+        output.push_str(&format!("    {line}\n"));
+    }
+    output.push_str(&format!("    — {}, {}\n", quote.character, quote.anime));
+    output
+}
+
+/// The quote's artwork as ASCII art, or its generated avatar when it has no
+/// usable image.
+fn portrait_lines(
+    quote: &AnimeQuote,
+    ascii_settings: &AsciiSettings,
+    columns: usize,
+) -> Vec<String> {
+    match quote.load_image().and_then(Result::ok) {
+        Some(image) => ascii::image_to_ascii(&image, ascii_settings, columns as u32),
+        None => {
+            let rows = (columns as f32 * ascii_settings.char_aspect)
+                .round()
+                .max(1.0) as usize;
+            ascii::avatar(&quote.character, ascii_settings, columns, rows)
+        }
+    }
+}
+
+/// Wraps `text` to at most `width` terminal columns, preferring word breaks
+/// and falling back to breaking anywhere for unspaced (e.g. Japanese) text.
+pub(crate) fn wrap_columns(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let separator = usize::from(!current.is_empty());
+        if current.width() + separator + word.width() <= width {
+            if separator == 1 {
+                current.push(' ');
+            }
+            current.push_str(word);
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        for ch in word.chars() {
+            if current.width() + ch.width().unwrap_or(0) > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Cuts `text` down to `max_width` terminal columns, ending with `…` when
+/// anything was removed.
+pub(crate) fn truncate_width(text: &str, max_width: Option<usize>) -> String {
+    let Some(max_width) = max_width else {
+        return text.to_string();
+    };
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut width = 0;
+    for ch in text.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if width + ch_width + 1 > max_width {
+            break;
+        }
+        truncated.push(ch);
+        width += ch_width;
+    }
+    let mut truncated = truncated.trim_end().to_string();
+    if max_width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+/// JSON for a waybar `custom` module with `return-type = "json"`. Waybar
+/// parses Pango markup in both text and tooltip, so they are escaped.
+pub(crate) fn render_waybar(quote: &AnimeQuote, max_length: Option<usize>) -> String {
+    let mut tooltip = quote.japanese.clone();
+    if let Some(romaji) = &quote.romaji {
+        tooltip.push('\n');
+        tooltip.push_str(romaji);
+    }
+    tooltip.push_str(&format!(
+        "\n\"{}\"\n— {}, {}",
+        quote.quote, quote.character, quote.anime
+    ));
+    let value = serde_json::json!({
+        "text": escape_html(&truncate_width(&quote.quote, max_length)),
+        "tooltip": escape_html(&tooltip),
+        "class": markdown_anchor(&quote.anime),
+        "alt": quote.character,
+    });
+    format!("{value}\n")
+}
+
+/// A `status-right` snippet: the quote and its speaker in palette colors,
+/// using tmux `#[fg=…]` styles. `#` in the text is doubled so tmux prints it.
+pub(crate) fn render_tmux(
+    quote: &AnimeQuote,
+    palette: &Palette,
+    max_width: Option<usize>,
+) -> String {
+    let attribution = format!(" — {}", quote.character);
+    let text = match max_width {
+        Some(max_width) if quote.quote.width() + attribution.width() > max_width => {
+            let room = max_width.saturating_sub(attribution.width());
+            if room < 8 {
+                return format!(
+                    "#[fg={}]{}#[default]\n",
+                    tmux_color(palette.quote),
+                    truncate_width(&quote.quote, Some(max_width)).replace('#', "##")
+                );
+            }
+            truncate_width(&quote.quote, Some(room))
+        }
+        _ => quote.quote.clone(),
+    };
+    format!(
+        "#[fg={}]{}#[fg={}]{}#[default]\n",
+        tmux_color(palette.quote),
+        text.replace('#', "##"),
+        tmux_color(palette.character),
+        attribution.replace('#', "##")
+    )
+}
+
+fn tmux_color(color: Color) -> String {
+    match color {
+        Color::Reset => "default".to_string(),
+        Color::Black => "black".to_string(),
+        Color::Red => "red".to_string(),
+        Color::Green => "green".to_string(),
+        Color::Yellow => "yellow".to_string(),
+        Color::Blue => "blue".to_string(),
+        Color::Magenta => "magenta".to_string(),
+        Color::Cyan => "cyan".to_string(),
+        Color::Gray => "white".to_string(),
+        Color::DarkGray => "brightblack".to_string(),
+        Color::LightRed => "brightred".to_string(),
+        Color::LightGreen => "brightgreen".to_string(),
+        Color::LightYellow => "brightyellow".to_string(),
+        Color::LightBlue => "brightblue".to_string(),
+        Color::LightMagenta => "brightmagenta".to_string(),
+        Color::LightCyan => "brightcyan".to_string(),
+        Color::White => "brightwhite".to_string(),
+        Color::Indexed(index) => format!("colour{index}"),
+        Color::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+    }
+}
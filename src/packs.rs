@@ -0,0 +1,271 @@
+//! Community quote packs: TOML files in the same format as `anime.toml`,
+//! installed under the data directory and merged into the collection.
+
+use crate::{AnimeData, AnimeQuote, data_dir, data_path, schema};
+use color_eyre::eyre::{Result, WrapErr, bail, eyre};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PacksConfig {
+    /// Index consulted when `pack install` is given a name instead of a URL.
+    /// There is no official one, so it is unset until configured.
+    #[serde(default)]
+    index: Option<String>,
+    /// Active packs, highest precedence first. When unset every installed
+    /// pack is active, in name order.
+    #[serde(default)]
+    enabled: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackIndex {
+    #[serde(default)]
+    packs: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    name: String,
+    url: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// An installed pack, the number of quotes it holds, and its precedence
+/// (1 is highest) when active.
+pub(crate) struct InstalledPack {
+    pub(crate) name: String,
+    pub(crate) quotes: usize,
+    pub(crate) precedence: Option<usize>,
+}
+
+/// A pack quote hidden by an identical quote from a source with higher
+/// precedence (`anime.toml` itself or an earlier pack).
+pub(crate) struct Shadowed {
+    pub(crate) pack: String,
+    pub(crate) quote: AnimeQuote,
+    pub(crate) winner: String,
+}
+
+fn packs_dir() -> PathBuf {
+    data_dir().join("packs")
+}
+
+pub(crate) fn pack_path(name: &str) -> PathBuf {
+    packs_dir().join(format!("{name}.toml"))
+}
+
+/// Downloads a pack from `source` (a URL, or a name looked up in the index)
+/// and stores it once it parses as a quote collection. Returns the pack name.
+pub(crate) fn install(source: &str, config: &PacksConfig) -> Result<InstalledPack> {
+    let (name, url) = if source.contains("://") {
+        (name_from_url(source)?, source.to_string())
+    } else {
+        let (url, index) = fetch_index(config)?;
+        let entry = index
+            .packs
+            .into_iter()
+            .find(|entry| entry.name == source)
+            .ok_or_else(|| eyre!("no pack named {source:?} in {url}"))?;
+        (entry.name, entry.url)
+    };
+    check_name(&name)?;
+
+    let content = fetch(&url)?;
+    let data: AnimeData = toml::from_str(&schema::upgrade_in_memory(content.clone())?)
+        .wrap_err_with(|| format!("{url} is not a valid quote pack"))?;
+    fs::create_dir_all(packs_dir())?;
+    let path = pack_path(&name);
+    fs::write(&path, content).wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    Ok(InstalledPack {
+        name,
+        quotes: data.quotes.len(),
+        precedence: None,
+    })
+}
+
+/// The configured index's URL and contents.
+fn fetch_index(config: &PacksConfig) -> Result<(&str, PackIndex)> {
+    let Some(url) = config.index.as_deref() else {
+        bail!(
+            "no pack index is configured; set `index` in the [packs] section of config.toml \
+             to the URL of one, or install packs by URL"
+        );
+    };
+    let index =
+        toml::from_str(&fetch(url)?).wrap_err_with(|| format!("invalid pack index at {url}"))?;
+    Ok((url, index))
+}
+
+/// Names available in the index, with their descriptions.
+pub(crate) fn available(config: &PacksConfig) -> Result<Vec<(String, Option<String>)>> {
+    let (_, index) = fetch_index(config)?;
+    Ok(index
+        .packs
+        .into_iter()
+        .map(|entry| (entry.name, entry.description))
+        .collect())
+}
+
+pub(crate) fn list(config: &PacksConfig) -> Result<Vec<InstalledPack>> {
+    let active: Vec<String> = active_paths(config)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    Ok(installed_paths()?
+        .into_iter()
+        .map(|(name, path)| InstalledPack {
+            quotes: load_pack(&path).map(|quotes| quotes.len()).unwrap_or(0),
+            precedence: active
+                .iter()
+                .position(|active| *active == name)
+                .map(|index| index + 1),
+            name,
+        })
+        .collect())
+}
+
+pub(crate) fn remove(name: &str) -> Result<()> {
+    check_name(name)?;
+    let path = pack_path(name);
+    if !path.exists() {
+        bail!("pack {name:?} is not installed");
+    }
+    fs::remove_file(&path).wrap_err_with(|| format!("failed to remove {}", path.display()))
+}
+
+/// Appends the quotes of every active pack to `quotes`, in precedence order.
+/// A pack quote matching one already collected is left out and returned as
+/// shadowed. Packs that fail to parse are reported and skipped so one bad
+/// download can't hide the collection.
+pub(crate) fn merge_active(quotes: &mut Vec<AnimeQuote>, config: &PacksConfig) -> Vec<Shadowed> {
+    let paths = active_paths(config).unwrap_or_else(|error| {
+        eprintln!("failed to list packs: {error}");
+        Vec::new()
+    });
+    let mut sources: HashMap<(String, String), String> = quotes
+        .iter()
+        .map(|quote| (quote_key(quote), data_path().to_string()))
+        .collect();
+    let mut shadowed = Vec::new();
+    for (name, path) in paths {
+        let pack = match load_pack(&path) {
+            Ok(pack) => pack,
+            Err(error) => {
+                eprintln!("failed to load pack {name}: {error}");
+                continue;
+            }
+        };
+        for quote in pack {
+            let key = quote_key(&quote);
+            match sources.get(&key) {
+                Some(winner) if *winner != name => shadowed.push(Shadowed {
+                    pack: name.clone(),
+                    quote,
+                    winner: winner.clone(),
+                }),
+                _ => {
+                    sources.insert(key, name.clone());
+                    quotes.push(quote);
+                }
+            }
+        }
+    }
+    shadowed
+}
+
+/// Quotes count as the same when the anime and Japanese line match, ignoring
+/// case and surrounding whitespace.
+fn quote_key(quote: &AnimeQuote) -> (String, String) {
+    (
+        quote.anime.trim().to_lowercase(),
+        quote.japanese.trim().to_string(),
+    )
+}
+
+/// Active packs in precedence order. Names in `[packs] enabled` that are not
+/// installed are reported and skipped.
+fn active_paths(config: &PacksConfig) -> Result<Vec<(String, PathBuf)>> {
+    let installed = installed_paths()?;
+    let Some(enabled) = &config.enabled else {
+        return Ok(installed);
+    };
+    Ok(enabled
+        .iter()
+        .filter_map(|name| {
+            let found = installed.iter().find(|(installed, _)| installed == name);
+            if found.is_none() {
+                eprintln!("pack {name} is enabled in config but not installed");
+            }
+            found.cloned()
+        })
+        .collect())
+}
+
+fn load_pack(path: &Path) -> Result<Vec<AnimeQuote>> {
+    let content = schema::upgrade_in_memory(fs::read_to_string(path)?)?;
+    let mut data: AnimeData = toml::from_str(&content)?;
+    data.resolve_paths(path);
+    data.sanitize();
+    data.apply_characters();
+    Ok(data.quotes)
+}
+
+pub(crate) fn installed_paths() -> Result<Vec<(String, PathBuf)>> {
+    let dir = packs_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut packs: Vec<(String, PathBuf)> = fs::read_dir(&dir)
+        .wrap_err_with(|| format!("failed to read {}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .filter_map(|path| Some((path.file_stem()?.to_str()?.to_string(), path)))
+        .collect();
+    packs.sort();
+    Ok(packs)
+}
+
+fn fetch(url: &str) -> Result<String> {
+    ureq::get(url)
+        .call()
+        .wrap_err_with(|| format!("failed to download {url}"))?
+        .body_mut()
+        .read_to_string()
+        .wrap_err_with(|| format!("failed to read {url}"))
+}
+
+fn name_from_url(url: &str) -> Result<String> {
+    let file = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let name = file.strip_suffix(".toml").unwrap_or(file);
+    if name.is_empty() {
+        bail!("cannot derive a pack name from {url}");
+    }
+    Ok(name.to_string())
+}
+
+/// Pack names become file names, so keep them to a safe character set.
+pub(crate) fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if !valid {
+        bail!("invalid pack name {name:?} (use letters, digits, `-`, `_` and `.`)");
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Expansion of user-written paths in data files.
+
+use std::path::Path;
+
+/// Expands a leading `~/` (or `~\` on Windows) to the home directory and
+/// `$VAR` / `${VAR}` to environment variables. Unset variables are left as
+/// written so the resulting "file not found" error still shows what was
+/// meant.
+pub(crate) fn expand(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let home_relative = value
+        .strip_prefix("~/")
+        .or_else(|| value.strip_prefix("~\\").filter(|_| cfg!(windows)));
+    let rest = match home_relative {
+        Some(rest) => match std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+            Ok(home) => {
+                expanded.push_str(home.trim_end_matches(['/', '\\']));
+                expanded.push('/');
+                rest
+            }
+            Err(_) => value,
+        },
+        None => value,
+    };
+
+    let mut chars = rest.char_indices().peekable();
+    while let Some((start, ch)) = chars.next() {
+        if ch != '$' {
+            expanded.push(ch);
+            continue;
+        }
+        let braced = chars.next_if(|&(_, next)| next == '{').is_some();
+        let mut name = String::new();
+        while let Some((_, next)) =
+            chars.next_if(|&(_, next)| next.is_ascii_alphanumeric() || next == '_')
+        {
+            name.push(next);
+        }
+        let closed = !braced || chars.next_if(|&(_, next)| next == '}').is_some();
+        let end = chars.peek().map_or(rest.len(), |&(index, _)| index);
+        match std::env::var(&name) {
+            Ok(value) if !name.is_empty() && closed => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[start..end]),
+        }
+    }
+    expanded
+}
+
+/// Expands `value` and, when it is relative, resolves it against `base`
+/// (the directory of the file it was written in).
+pub(crate) fn resolve(base: &Path, value: &str) -> String {
+    let expanded = expand(value);
+    if Path::new(&expanded).is_absolute() {
+        expanded
+    } else {
+        base.join(&expanded).to_string_lossy().into_owned()
+    }
+}
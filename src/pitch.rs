@@ -0,0 +1,63 @@
+//! Pitch accent in Tokyo Japanese, from the `pitch` notation on a quote: the
+//! reading in kana, words apart, with `ꜜ` (or `\`, `＼`, `'`) after the mora
+//! where the pitch drops. A word without one is flat (heiban).
+//!
+//! The viewer draws the reading with a line over the high morae, so
+//! `pitch = "はꜜし"` shows the chopsticks pattern, high then low.
+
+use unicode_width::UnicodeWidthStr;
+
+const DOWNSTEP: &[char] = &['ꜜ', '\\', '＼', '\''];
+
+/// Small kana that share a mora with the kana before them.
+const SMALL_KANA: &str = "ゃゅょぁぃぅぇぉゎャュョァィゥェォヮ";
+
+/// The line of accent marks and the reading under it, the same width so
+/// they stay lined up however the text is aligned.
+pub(crate) fn lines(notation: &str) -> (String, String) {
+    let mut marks = Vec::new();
+    let mut reading = Vec::new();
+    for word in notation.split_whitespace() {
+        let (morae, downstep) = morae(word);
+        let mut word_marks = String::new();
+        for (index, mora) in morae.iter().enumerate() {
+            let width = mora.width();
+            // Flat and mid-drop words start low and rise on the second
+            // mora; a drop after the first mora starts high instead.
+            let high = match downstep {
+                Some(1) => index == 0,
+                Some(drop) => index >= 1 && index < drop,
+                None => index >= 1,
+            };
+            if !high {
+                word_marks.push_str(&" ".repeat(width));
+            } else if downstep == Some(index + 1) {
+                word_marks.push_str(&"▔".repeat(width.saturating_sub(1)));
+                word_marks.push('╮');
+            } else {
+                word_marks.push_str(&"▔".repeat(width));
+            }
+        }
+        marks.push(word_marks);
+        reading.push(morae.concat());
+    }
+    (marks.join(" "), reading.join(" "))
+}
+
+/// The word's morae and how many come before the drop.
+fn morae(word: &str) -> (Vec<String>, Option<usize>) {
+    let mut morae: Vec<String> = Vec::new();
+    let mut downstep = None;
+    for ch in word.chars() {
+        if DOWNSTEP.contains(&ch) {
+            downstep = Some(morae.len()).filter(|&count| count > 0);
+        } else if SMALL_KANA.contains(ch)
+            && let Some(last) = morae.last_mut()
+        {
+            last.push(ch);
+        } else {
+            morae.push(ch.to_string());
+        }
+    }
+    (morae, downstep)
+}
@@ -0,0 +1,70 @@
+//! Rewatching a quote's scene: its `video` played from its `timestamp`
+//! with the `[player]` command, mpv unless configured otherwise.
+
+use crate::AnimeQuote;
+use serde::Deserialize;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PlayerConfig {
+    /// The program and its arguments. `{video}` becomes the quote's video,
+    /// `{start}` its timestamp as written (`00:00` without one), and
+    /// `{seconds}` the timestamp in seconds.
+    #[serde(default = "default_player_command")]
+    command: Vec<String>,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            command: default_player_command(),
+        }
+    }
+}
+
+fn default_player_command() -> Vec<String> {
+    ["mpv", "--start={start}", "{video}"]
+        .map(String::from)
+        .to_vec()
+}
+
+/// `12:34` or `1:02:03` (with optional fractions of a second) in seconds.
+fn seconds(timestamp: &str) -> Option<f64> {
+    timestamp.split(':').try_fold(0.0, |total, part| {
+        Some(total * 60.0 + part.trim().parse::<f64>().ok()?)
+    })
+}
+
+/// Starts the player on `quote`'s video without waiting for it. Returns
+/// what to tell the user.
+pub(crate) fn play(quote: &AnimeQuote, config: &PlayerConfig) -> String {
+    let Some(video) = quote.video.as_deref() else {
+        return "No video for this quote".to_string();
+    };
+    let start = quote.timestamp.as_deref().unwrap_or("00:00");
+    let Some(seconds) = seconds(start) else {
+        return format!("Can't read the timestamp {start:?}; use 12:34 or 1:02:03");
+    };
+    let args: Vec<String> = config
+        .command
+        .iter()
+        .map(|arg| {
+            arg.replace("{video}", video)
+                .replace("{start}", start)
+                .replace("{seconds}", &seconds.to_string())
+        })
+        .collect();
+    let Some((program, args)) = args.split_first() else {
+        return "[player] command is empty".to_string();
+    };
+    let spawned = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(_) => format!("Playing from {start} in {program}"),
+        Err(error) => format!("Failed to start {program}: {error}"),
+    }
+}
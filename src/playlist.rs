@@ -0,0 +1,51 @@
+//! Playlist files: a queue of quotes saved as one stable ID per line, with
+//! the character and anime after a `#` for whoever reads the file.
+
+use crate::AnimeQuote;
+use color_eyre::eyre::{Result, WrapErr};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// The IDs listed in the playlist at `path`, in order, and how many of them
+/// are not in `quotes`; those are left out.
+pub(crate) fn load(path: &Path, quotes: &[AnimeQuote]) -> Result<(Vec<String>, usize)> {
+    let content = fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read playlist {}", path.display()))?;
+    let known: std::collections::HashSet<String> =
+        quotes.iter().map(AnimeQuote::stable_id).collect();
+    let mut missing = 0;
+    let ids = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|id| !id.is_empty())
+        .filter(|id| {
+            let found = known.contains(*id);
+            missing += usize::from(!found);
+            found
+        })
+        .map(str::to_string)
+        .collect();
+    Ok((ids, missing))
+}
+
+pub(crate) fn save(path: &Path, ids: &[String], quotes: &[AnimeQuote]) -> Result<()> {
+    let mut content = String::from("# anime-quotes playlist: one quote ID per line\n");
+    for id in ids {
+        match quotes.iter().find(|quote| quote.stable_id() == *id) {
+            Some(quote) => {
+                let _ = writeln!(content, "{id}  # {} — {}", quote.character, quote.anime);
+            }
+            None => {
+                let _ = writeln!(content, "{id}");
+            }
+        }
+    }
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
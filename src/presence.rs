@@ -0,0 +1,110 @@
+//! Discord Rich Presence for the viewer, behind the `discord` cargo feature.
+//!
+//! Discord is talked to from a background thread so a missing or slow client
+//! never stalls the UI; the viewer only sends the quote to show.
+
+use crate::AnimeQuote;
+use color_eyre::eyre::Result;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PresenceConfig {
+    #[serde(default)]
+    enabled: bool,
+    /// Application ID from the Discord developer portal; the activity is
+    /// shown under that application's name and assets.
+    #[serde(default)]
+    client_id: String,
+}
+
+/// What the activity shows: the anime as details and the character as state.
+#[cfg(feature = "discord")]
+struct Activity {
+    anime: String,
+    character: String,
+}
+
+pub(crate) struct Presence {
+    #[cfg(feature = "discord")]
+    sender: std::sync::mpsc::Sender<Activity>,
+}
+
+impl Presence {
+    /// Connects when `[discord] enabled = true`; `Ok(None)` when disabled.
+    pub(crate) fn start(config: &PresenceConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.client_id.trim().is_empty() {
+            color_eyre::eyre::bail!("[discord] client_id is not set");
+        }
+        #[cfg(feature = "discord")]
+        {
+            Ok(Some(Self {
+                sender: platform::spawn(config.client_id.trim().to_string()),
+            }))
+        }
+        #[cfg(not(feature = "discord"))]
+        {
+            color_eyre::eyre::bail!("built without the `discord` feature")
+        }
+    }
+
+    pub(crate) fn show(&self, quote: &AnimeQuote) {
+        #[cfg(feature = "discord")]
+        {
+            let _ = self.sender.send(Activity {
+                anime: quote.anime.clone(),
+                character: quote.character.clone(),
+            });
+        }
+        #[cfg(not(feature = "discord"))]
+        {
+            let _ = quote;
+        }
+    }
+}
+
+#[cfg(feature = "discord")]
+mod platform {
+    use super::Activity;
+    use discord_rich_presence::{DiscordIpc, DiscordIpcClient, activity};
+    use std::sync::mpsc::{self, Sender};
+    use std::thread;
+
+    /// Runs the IPC client until the viewer drops the sender. Failed updates
+    /// drop the connection, and the next quote tries to connect again, so
+    /// starting Discord after the viewer still works.
+    pub(super) fn spawn(client_id: String) -> Sender<Activity> {
+        let (sender, receiver) = mpsc::channel::<Activity>();
+        thread::spawn(move || {
+            let mut client: Option<DiscordIpcClient> = None;
+            let started = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs() as i64)
+                .unwrap_or_default();
+            for update in receiver {
+                if client.is_none() {
+                    let mut connecting = DiscordIpcClient::new(&client_id);
+                    if connecting.connect().is_ok() {
+                        client = Some(connecting);
+                    }
+                }
+                let Some(connected) = client.as_mut() else {
+                    continue;
+                };
+                let payload = activity::Activity::new()
+                    .details(&update.anime)
+                    .state(&update.character)
+                    .timestamps(activity::Timestamps::new().start(started));
+                if connected.set_activity(payload).is_err() {
+                    client = None;
+                }
+            }
+            if let Some(mut connected) = client {
+                let _ = connected.close();
+            }
+        });
+        sender
+    }
+}
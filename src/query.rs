@@ -0,0 +1,467 @@
+//! A small query language for narrowing the collection, shared by the
+//! viewer's search prompt, `--query`, and the HTTP and GraphQL APIs:
+//!
+//! ```text
+//! anime:"One Piece" AND (tag:sad OR rating>=4) NOT spoiler
+//! ```
+//!
+//! Terms are `field:text`, `field=text`, numeric comparisons such as
+//! `rating>=4` or `len<40`, or bare text that may appear anywhere in the
+//! quote or as one of its tags. Terms next to each other must all match;
+//! `OR`, `NOT` (or a leading `-`), and parentheses combine them, with `NOT`
+//! binding tightest and `OR` loosest.
+
+use crate::{AnimeQuote, filter};
+use std::fmt;
+
+/// A parsed query, keeping the text it was written as.
+#[derive(Clone)]
+pub(crate) struct Query {
+    source: String,
+    root: Node,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Term(Term),
+    Not(Box<Node>),
+    /// Kept flat rather than as pairs, so a long run of terms doesn't make
+    /// a tree as deep as the query is long.
+    And(Vec<Node>),
+    Or(Vec<Node>),
+}
+
+#[derive(Clone, Debug)]
+enum Term {
+    /// Text anywhere in the quote, or one of its tags.
+    Anywhere(String),
+    Text(TextField, Match, String),
+    Tag(String),
+    Number(NumberField, Comparison, u32),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TextField {
+    Anime,
+    Character,
+    Japanese,
+    Romaji,
+    Quote,
+    Source,
+    Id,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Match {
+    /// `field:text`: contains it, ignoring case.
+    Contains,
+    /// `field=text`: is exactly it, ignoring case.
+    Equals,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NumberField {
+    Rating,
+    /// Characters in the translation.
+    Length,
+    Year,
+    Season,
+    Episode,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// How deeply parentheses and `NOT`s may nest. The parser recurses into
+/// each, so without a limit a query of thousands of `(` would overflow the
+/// stack.
+const MAX_DEPTH: usize = 64;
+
+const FIELDS: &str = "anime, character, japanese, romaji, quote, source, id, tag, rating, len, \
+                      year, season, episode";
+
+impl Query {
+    pub(crate) fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err("the query is empty".to_string());
+        }
+        let mut parser = Parser {
+            tokens,
+            next: 0,
+            depth: 0,
+        };
+        let root = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.next) {
+            return Err(format!("unexpected {token}"));
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            root,
+        })
+    }
+
+    /// `field=value`, with `value` quoted so it is matched whole whatever
+    /// it contains.
+    pub(crate) fn equals(field: &str, value: &str) -> Result<Self, String> {
+        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+        Self::parse(&format!("{field}=\"{escaped}\""))
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub(crate) fn matches(&self, quote: &AnimeQuote) -> bool {
+        self.root.matches(quote)
+    }
+
+    /// The text the matching quotes contain, for highlighting: every text
+    /// term that isn't negated.
+    pub(crate) fn highlights(&self) -> Vec<&str> {
+        let mut texts = Vec::new();
+        self.root.collect_highlights(false, &mut texts);
+        texts
+    }
+}
+
+impl fmt::Debug for Query {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.source)
+    }
+}
+
+impl Node {
+    fn matches(&self, quote: &AnimeQuote) -> bool {
+        match self {
+            Self::Term(term) => term.matches(quote),
+            Self::Not(node) => !node.matches(quote),
+            Self::And(nodes) => nodes.iter().all(|node| node.matches(quote)),
+            Self::Or(nodes) => nodes.iter().any(|node| node.matches(quote)),
+        }
+    }
+
+    fn collect_highlights<'a>(&'a self, negated: bool, texts: &mut Vec<&'a str>) {
+        match self {
+            Self::Term(Term::Anywhere(text) | Term::Text(_, _, text)) if !negated => {
+                texts.push(text);
+            }
+            Self::Term(_) => {}
+            Self::Not(node) => node.collect_highlights(!negated, texts),
+            Self::And(nodes) | Self::Or(nodes) => {
+                for node in nodes {
+                    node.collect_highlights(negated, texts);
+                }
+            }
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, quote: &AnimeQuote) -> bool {
+        let tagged = |wanted: &str| quote.tags.iter().any(|tag| tag.to_lowercase() == wanted);
+        match self {
+            Self::Anywhere(text) => {
+                tagged(&text.to_lowercase())
+                    || [
+                        Some(quote.anime.as_str()),
+                        Some(quote.character.as_str()),
+                        Some(quote.japanese.as_str()),
+                        quote.romaji.as_deref(),
+                        Some(quote.quote.as_str()),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .any(|value| !filter::find(value, text, false).is_empty())
+            }
+            Self::Text(field, how, text) => {
+                let id;
+                let value = match field {
+                    TextField::Anime => Some(quote.anime.as_str()),
+                    TextField::Character => Some(quote.character.as_str()),
+                    TextField::Japanese => Some(quote.japanese.as_str()),
+                    TextField::Romaji => quote.romaji.as_deref(),
+                    TextField::Quote => Some(quote.quote.as_str()),
+                    TextField::Source => quote.source.as_deref(),
+                    TextField::Id => {
+                        id = quote.stable_id();
+                        Some(id.as_str())
+                    }
+                };
+                value.is_some_and(|value| match how {
+                    Match::Contains => !filter::find(value, text, false).is_empty(),
+                    Match::Equals => value.trim().to_lowercase() == text.to_lowercase(),
+                })
+            }
+            Self::Tag(tag) => tagged(&tag.to_lowercase()),
+            Self::Number(field, comparison, wanted) => {
+                let value = match field {
+                    NumberField::Rating => quote.rating.map(u32::from),
+                    NumberField::Length => Some(quote.quote.chars().count() as u32),
+                    NumberField::Year => quote.year,
+                    NumberField::Season => quote.season,
+                    NumberField::Episode => quote.episode,
+                };
+                value.is_some_and(|value| match comparison {
+                    Comparison::Equal => value == *wanted,
+                    Comparison::Less => value < *wanted,
+                    Comparison::LessOrEqual => value <= *wanted,
+                    Comparison::Greater => value > *wanted,
+                    Comparison::GreaterOrEqual => value >= *wanted,
+                })
+            }
+        }
+    }
+
+    /// The term for `field`, the operator after it, and `value`.
+    fn field(field: &str, operator: &str, value: String) -> Result<Self, String> {
+        let text = |field| match operator {
+            ":" => Ok(Self::Text(field, Match::Contains, value.clone())),
+            "=" => Ok(Self::Text(field, Match::Equals, value.clone())),
+            _ => Err(format!("{operator} only compares numbers; use : or =")),
+        };
+        let number = |field| {
+            let comparison = match operator {
+                ":" | "=" => Comparison::Equal,
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessOrEqual,
+                ">" => Comparison::Greater,
+                _ => Comparison::GreaterOrEqual,
+            };
+            let wanted = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("{value:?} is not a whole number"))?;
+            Ok(Self::Number(field, comparison, wanted))
+        };
+        match field.to_lowercase().as_str() {
+            "anime" => text(TextField::Anime),
+            "character" | "char" => text(TextField::Character),
+            "japanese" | "jp" => text(TextField::Japanese),
+            "romaji" => text(TextField::Romaji),
+            "quote" | "translation" => text(TextField::Quote),
+            "source" => text(TextField::Source),
+            "id" => text(TextField::Id),
+            "tag" => match operator {
+                ":" | "=" => Ok(Self::Tag(value)),
+                _ => Err(format!("{operator} only compares numbers; use tag:")),
+            },
+            "rating" => number(NumberField::Rating),
+            "len" | "length" => number(NumberField::Length),
+            "year" => number(NumberField::Year),
+            "season" => number(NumberField::Season),
+            "episode" => number(NumberField::Episode),
+            _ => Err(format!("unknown field {field:?} (fields: {FIELDS})")),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Term),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => formatter.write_str("("),
+            Self::Close => formatter.write_str(")"),
+            Self::And => formatter.write_str("AND"),
+            Self::Or => formatter.write_str("OR"),
+            Self::Not => formatter.write_str("NOT"),
+            Self::Term(_) => formatter.write_str("term"),
+        }
+    }
+}
+
+/// Operators between a field name and its value, longest first.
+const OPERATORS: [&str; 6] = [">=", "<=", ":", "=", ">", "<"];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Term(Term::Anywhere(quoted(&mut chars)?)));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || matches!(ch, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "AND" | "&&" => {
+                        tokens.push(Token::And);
+                        continue;
+                    }
+                    "OR" | "||" => {
+                        tokens.push(Token::Or);
+                        continue;
+                    }
+                    "NOT" => {
+                        tokens.push(Token::Not);
+                        continue;
+                    }
+                    _ => {}
+                }
+                let word = match word.strip_prefix('-') {
+                    Some(rest) if !rest.is_empty() || chars.peek() == Some(&'"') => {
+                        tokens.push(Token::Not);
+                        rest.to_string()
+                    }
+                    _ => word,
+                };
+                if word.is_empty() {
+                    // A `-` right before a quoted phrase.
+                    chars.next();
+                    tokens.push(Token::Term(Term::Anywhere(quoted(&mut chars)?)));
+                    continue;
+                }
+                let split = word.char_indices().find_map(|(start, _)| {
+                    OPERATORS
+                        .iter()
+                        .find(|operator| word[start..].starts_with(*operator))
+                        .map(|operator| (start, *operator))
+                });
+                let term = match split {
+                    Some((start, operator)) if start > 0 => {
+                        let mut value = word[start + operator.len()..].to_string();
+                        if value.is_empty() && chars.peek() == Some(&'"') {
+                            chars.next();
+                            value = quoted(&mut chars)?;
+                        }
+                        if value.is_empty() {
+                            return Err(format!("{word} needs a value after {operator}"));
+                        }
+                        Term::field(&word[..start], operator, value)?
+                    }
+                    _ => Term::Anywhere(word),
+                };
+                tokens.push(Token::Term(term));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// The rest of a quoted phrase whose opening `"` has been read.
+fn quoted(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<String, String> {
+    let mut phrase = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(phrase),
+            Some('\\') => phrase.extend(chars.next()),
+            Some(ch) => phrase.push(ch),
+            None => return Err("a quoted phrase is missing its closing \"".to_string()),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    /// Runs `parse` one level deeper, refusing to go past [`MAX_DEPTH`].
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Node, String>,
+    ) -> Result<Node, String> {
+        if self.depth == MAX_DEPTH {
+            return Err("query nested too deeply".to_string());
+        }
+        self.depth += 1;
+        let node = parse(self);
+        self.depth -= 1;
+        node
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![self.and()?];
+        while matches!(self.peek(), Some(Token::Or)) {
+            self.next += 1;
+            nodes.push(self.and()?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            Node::Or(nodes)
+        })
+    }
+
+    /// Terms joined by `AND`, or just written one after another.
+    fn and(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![self.not()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.next += 1,
+                Some(Token::Open | Token::Not | Token::Term(_)) => {}
+                _ if nodes.len() == 1 => return Ok(nodes.remove(0)),
+                _ => return Ok(Node::And(nodes)),
+            }
+            nodes.push(self.not()?);
+        }
+    }
+
+    fn not(&mut self) -> Result<Node, String> {
+        if matches!(self.peek(), Some(Token::Not)) {
+            self.next += 1;
+            return Ok(Node::Not(Box::new(self.nested(Self::not)?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        let token = self.tokens.get(self.next);
+        self.next += 1;
+        match token {
+            Some(Token::Open) => {
+                let node = self.nested(Self::or)?;
+                match self.peek() {
+                    Some(Token::Close) => {
+                        self.next += 1;
+                        Ok(node)
+                    }
+                    _ => Err("a ( is missing its )".to_string()),
+                }
+            }
+            Some(Token::Term(term)) => Ok(Node::Term(term.clone())),
+            Some(token) => Err(format!("expected a term, found {token}")),
+            None => Err("the query ends too early".to_string()),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! The recently-viewed screen: quotes in the order they were last shown, to
+//! find "that quote from a couple of days ago" again.
+
+use crate::state::State;
+use crate::{AnimeQuote, Palette};
+use chrono::{DateTime, FixedOffset, Local};
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+
+pub(crate) enum RecentAction {
+    Stay,
+    Close,
+    /// Show the quote at this index in the viewer.
+    Show(usize),
+}
+
+pub(crate) struct Recent {
+    list: ListState,
+}
+
+impl Recent {
+    pub(crate) fn new() -> Self {
+        Self {
+            list: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub(crate) fn handle_key(
+        &mut self,
+        code: KeyCode,
+        state: &State,
+        quotes: &[AnimeQuote],
+    ) -> RecentAction {
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Home => self.list.select_first(),
+            KeyCode::End => self.list.select_last(),
+            KeyCode::Enter => {
+                let recent = state.recent(quotes);
+                let selected = self.list.selected().unwrap_or(0);
+                if let Some((index, _)) = recent.get(selected.min(recent.len().saturating_sub(1))) {
+                    return RecentAction::Show(*index);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('r') | KeyCode::Char('q') => return RecentAction::Close,
+            _ => {}
+        }
+        RecentAction::Stay
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &State,
+        quotes: &[AnimeQuote],
+        palette: &Palette,
+    ) {
+        let [title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        let count_style = Style::default().fg(palette.count);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Recently viewed",
+                Style::default().fg(palette.anime).bold(),
+            )))
+            .centered(),
+            title_area,
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Move <Up/Down>  Show <Enter>  Back <Esc>",
+                Style::default().fg(palette.instructions),
+            )))
+            .centered(),
+            help_area,
+        );
+
+        let recent = state.recent(quotes);
+        if recent.is_empty() {
+            frame.render_widget(
+                Paragraph::new(Span::styled("Nothing viewed yet", count_style)).centered(),
+                list_area,
+            );
+            return;
+        }
+        let now = Local::now().fixed_offset();
+        let items: Vec<ListItem> = recent
+            .iter()
+            .map(|&(index, viewed)| {
+                let quote = &quotes[index];
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(format!("{:<16}", ago(viewed, now)), count_style),
+                        Span::styled(
+                            quote.character.clone(),
+                            Style::default().fg(palette.character).bold(),
+                        ),
+                        Span::raw(" — "),
+                        Span::styled(quote.anime.clone(), Style::default().fg(palette.anime)),
+                    ]),
+                    Line::from(vec![
+                        Span::raw(" ".repeat(16)),
+                        Span::styled(
+                            format!("\"{}\"", quote.quote),
+                            Style::default().fg(palette.quote).italic(),
+                        ),
+                    ]),
+                ])
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+    }
+}
+
+/// `then` relative to `now`, the way people say it.
+pub(crate) fn ago(then: DateTime<FixedOffset>, now: DateTime<FixedOffset>) -> String {
+    let elapsed = now.signed_duration_since(then);
+    let minutes = elapsed.num_minutes();
+    let days = (now.date_naive() - then.date_naive()).num_days();
+    match () {
+        _ if minutes < 1 => "just now".to_string(),
+        _ if minutes < 60 => format!("{minutes} min ago"),
+        _ if days == 0 => format!("{} h ago", elapsed.num_hours()),
+        _ if days == 1 => "yesterday".to_string(),
+        _ if days < 7 => format!("{days} days ago"),
+        _ => then.format("%b %-d, %Y").to_string(),
+    }
+}
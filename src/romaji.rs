@@ -0,0 +1,284 @@
+//! Romanization of kana, for suggesting a romaji line: Hepburn by default,
+//! or Kunrei-shiki or Nihon-shiki as `[romaji] system` picks, with long
+//! vowels spelled out or marked as `[romaji] long_vowels` picks.
+//!
+//! Kanji readings need a dictionary, so text containing kanji is left to the
+//! user.
+
+use serde::Deserialize;
+
+const DEFAULT_SYSTEM: &str = "hepburn";
+const DEFAULT_LONG_VOWELS: &str = "spelled";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RomajiConfig {
+    /// `hepburn`, `kunrei`, or `nihon`.
+    #[serde(default = "default_system")]
+    system: String,
+    /// `spelled` (ou), `macron` (ō), or `circumflex` (ô).
+    #[serde(default = "default_long_vowels")]
+    long_vowels: String,
+}
+
+impl Default for RomajiConfig {
+    fn default() -> Self {
+        Self {
+            system: default_system(),
+            long_vowels: default_long_vowels(),
+        }
+    }
+}
+
+fn default_system() -> String {
+    DEFAULT_SYSTEM.to_string()
+}
+
+fn default_long_vowels() -> String {
+    DEFAULT_LONG_VOWELS.to_string()
+}
+
+impl RomajiConfig {
+    pub(crate) fn to_romanization(&self) -> Romanization {
+        let system = match self.system.trim().to_ascii_lowercase().as_str() {
+            "hepburn" => System::Hepburn,
+            "kunrei" | "kunrei-shiki" => System::Kunrei,
+            "nihon" | "nihon-shiki" => System::Nihon,
+            other => {
+                eprintln!("unknown [romaji] system {other:?}, falling back to {DEFAULT_SYSTEM}");
+                System::Hepburn
+            }
+        };
+        let long_vowels = match self.long_vowels.trim().to_ascii_lowercase().as_str() {
+            "spelled" => LongVowels::Spelled,
+            "macron" => LongVowels::Macron,
+            "circumflex" => LongVowels::Circumflex,
+            other => {
+                eprintln!(
+                    "unknown [romaji] long_vowels {other:?}, falling back to {DEFAULT_LONG_VOWELS}"
+                );
+                LongVowels::Spelled
+            }
+        };
+        Romanization {
+            system,
+            long_vowels,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum System {
+    #[default]
+    Hepburn,
+    /// The ISO 3602 system: si, ti, tu, hu, zi, sya.
+    Kunrei,
+    /// Kunrei-shiki that also tells ぢ, づ, を, ゐ, and ゑ apart: di, du, wo.
+    Nihon,
+}
+
+/// How おう, うう, ああ, ええ, おお, and ー are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum LongVowels {
+    /// As the kana spell them: ou, uu, and a doubled vowel for ー.
+    #[default]
+    Spelled,
+    Macron,
+    Circumflex,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Romanization {
+    pub(crate) system: System,
+    pub(crate) long_vowels: LongVowels,
+}
+
+/// Readings that Kunrei-shiki and Nihon-shiki write differently from
+/// Hepburn.
+#[rustfmt::skip]
+const KUNREI: &[(&str, &str)] = &[
+    ("しゃ", "sya"), ("しゅ", "syu"), ("しょ", "syo"), ("しぇ", "sye"),
+    ("ちゃ", "tya"), ("ちゅ", "tyu"), ("ちょ", "tyo"), ("ちぇ", "tye"),
+    ("じゃ", "zya"), ("じゅ", "zyu"), ("じょ", "zyo"), ("じぇ", "zye"),
+    ("ぢゃ", "zya"), ("ぢゅ", "zyu"), ("ぢょ", "zyo"),
+    ("し", "si"), ("ち", "ti"), ("つ", "tu"), ("ふ", "hu"),
+    ("じ", "zi"), ("ぢ", "zi"), ("づ", "zu"),
+];
+
+/// Readings that Nihon-shiki writes differently from Kunrei-shiki.
+#[rustfmt::skip]
+const NIHON: &[(&str, &str)] = &[
+    ("ぢゃ", "dya"), ("ぢゅ", "dyu"), ("ぢょ", "dyo"),
+    ("ぢ", "di"), ("づ", "du"), ("を", "wo"), ("ゐ", "wi"), ("ゑ", "we"),
+];
+
+#[rustfmt::skip]
+const HIRAGANA: &[(&str, &str)] = &[
+    ("きゃ", "kya"), ("きゅ", "kyu"), ("きょ", "kyo"),
+    ("しゃ", "sha"), ("しゅ", "shu"), ("しょ", "sho"), ("しぇ", "she"),
+    ("ちゃ", "cha"), ("ちゅ", "chu"), ("ちょ", "cho"), ("ちぇ", "che"),
+    ("にゃ", "nya"), ("にゅ", "nyu"), ("にょ", "nyo"),
+    ("ひゃ", "hya"), ("ひゅ", "hyu"), ("ひょ", "hyo"),
+    ("みゃ", "mya"), ("みゅ", "myu"), ("みょ", "myo"),
+    ("りゃ", "rya"), ("りゅ", "ryu"), ("りょ", "ryo"),
+    ("ぎゃ", "gya"), ("ぎゅ", "gyu"), ("ぎょ", "gyo"),
+    ("じゃ", "ja"), ("じゅ", "ju"), ("じょ", "jo"), ("じぇ", "je"),
+    ("ぢゃ", "ja"), ("ぢゅ", "ju"), ("ぢょ", "jo"),
+    ("びゃ", "bya"), ("びゅ", "byu"), ("びょ", "byo"),
+    ("ぴゃ", "pya"), ("ぴゅ", "pyu"), ("ぴょ", "pyo"),
+    ("ふぁ", "fa"), ("ふぃ", "fi"), ("ふぇ", "fe"), ("ふぉ", "fo"),
+    ("てぃ", "ti"), ("でぃ", "di"), ("とぅ", "tu"), ("どぅ", "du"),
+    ("うぃ", "wi"), ("うぇ", "we"), ("うぉ", "wo"), ("ゔぁ", "va"),
+    ("ゔぃ", "vi"), ("ゔぇ", "ve"), ("ゔぉ", "vo"),
+    ("あ", "a"), ("い", "i"), ("う", "u"), ("え", "e"), ("お", "o"),
+    ("か", "ka"), ("き", "ki"), ("く", "ku"), ("け", "ke"), ("こ", "ko"),
+    ("さ", "sa"), ("し", "shi"), ("す", "su"), ("せ", "se"), ("そ", "so"),
+    ("た", "ta"), ("ち", "chi"), ("つ", "tsu"), ("て", "te"), ("と", "to"),
+    ("な", "na"), ("に", "ni"), ("ぬ", "nu"), ("ね", "ne"), ("の", "no"),
+    ("は", "ha"), ("ひ", "hi"), ("ふ", "fu"), ("へ", "he"), ("ほ", "ho"),
+    ("ま", "ma"), ("み", "mi"), ("む", "mu"), ("め", "me"), ("も", "mo"),
+    ("や", "ya"), ("ゆ", "yu"), ("よ", "yo"),
+    ("ら", "ra"), ("り", "ri"), ("る", "ru"), ("れ", "re"), ("ろ", "ro"),
+    ("わ", "wa"), ("ゐ", "i"), ("ゑ", "e"), ("を", "o"),
+    ("が", "ga"), ("ぎ", "gi"), ("ぐ", "gu"), ("げ", "ge"), ("ご", "go"),
+    ("ざ", "za"), ("じ", "ji"), ("ず", "zu"), ("ぜ", "ze"), ("ぞ", "zo"),
+    ("だ", "da"), ("ぢ", "ji"), ("づ", "zu"), ("で", "de"), ("ど", "do"),
+    ("ば", "ba"), ("び", "bi"), ("ぶ", "bu"), ("べ", "be"), ("ぼ", "bo"),
+    ("ぱ", "pa"), ("ぴ", "pi"), ("ぷ", "pu"), ("ぺ", "pe"), ("ぽ", "po"),
+    ("ゔ", "vu"),
+    ("ぁ", "a"), ("ぃ", "i"), ("ぅ", "u"), ("ぇ", "e"), ("ぉ", "o"),
+    ("ゃ", "ya"), ("ゅ", "yu"), ("ょ", "yo"), ("ゎ", "wa"),
+];
+
+const PUNCTUATION: &[(char, &str)] = &[
+    ('。', "."),
+    ('、', ","),
+    ('！', "!"),
+    ('？', "?"),
+    ('　', " "),
+    ('「', "\""),
+    ('」', "\""),
+    ('『', "\""),
+    ('』', "\""),
+    ('・', " "),
+    ('〜', "~"),
+];
+
+/// Katakana share the hiragana table, 0x60 code points further on.
+fn to_hiragana(ch: char) -> char {
+    match ch {
+        '\u{30a1}'..='\u{30f6}' => char::from_u32(ch as u32 - 0x60).unwrap_or(ch),
+        _ => ch,
+    }
+}
+
+fn is_kanji(ch: char) -> bool {
+    matches!(ch, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '々')
+}
+
+/// Romanizes kana in `text`, returning `None` when it contains kanji.
+/// Anything that isn't kana (Latin letters, digits, `…`) is kept as is.
+pub(crate) fn from_kana(text: &str, romanization: Romanization) -> Option<String> {
+    if text.chars().any(is_kanji) {
+        return None;
+    }
+    let chars: Vec<char> = text.chars().map(to_hiragana).collect();
+    let mut output = String::new();
+    let mut double_next = false;
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        if ch == 'っ' {
+            double_next = true;
+            index += 1;
+            continue;
+        }
+        if ch == 'ん' {
+            let before_vowel = syllable(&chars[index + 1..], romanization.system)
+                .is_some_and(|(romaji, _)| romaji.starts_with(['a', 'i', 'u', 'e', 'o', 'y']));
+            output.push_str(if before_vowel { "n'" } else { "n" });
+            index += 1;
+            continue;
+        }
+        if ch == 'ー' {
+            if let Some(vowel) = output.chars().last().filter(|last| "aiueo".contains(*last)) {
+                lengthen(&mut output, vowel, romanization.long_vowels);
+            }
+            index += 1;
+            continue;
+        }
+        if let Some((romaji, length)) = syllable(&chars[index..], romanization.system) {
+            if double_next {
+                // っち is written tchi in Hepburn.
+                let first = if romaji.starts_with("ch") {
+                    't'
+                } else {
+                    romaji.chars().next().unwrap_or_default()
+                };
+                output.push(first);
+            }
+            output.push_str(romaji);
+            index += length;
+            // おう and おお are a long o, うう a long u, ああ a long a, and
+            // ええ a long e; いい stays doubled, as in most Hepburn.
+            let vowel = romaji.chars().last().unwrap_or_default();
+            let next = chars.get(index).copied();
+            let long = matches!(
+                (vowel, next),
+                ('o', Some('う' | 'お'))
+                    | ('u', Some('う'))
+                    | ('a', Some('あ'))
+                    | ('e', Some('え'))
+            );
+            if long && romanization.long_vowels != LongVowels::Spelled {
+                lengthen(&mut output, vowel, romanization.long_vowels);
+                index += 1;
+            }
+        } else {
+            match PUNCTUATION.iter().find(|(mark, _)| *mark == ch) {
+                Some((_, replacement)) => output.push_str(replacement),
+                None => output.push(ch),
+            }
+            index += 1;
+        }
+        double_next = false;
+    }
+    Some(output)
+}
+
+/// Writes the long form of `vowel`, which `output` already ends with once.
+fn lengthen(output: &mut String, vowel: char, style: LongVowels) {
+    let marked = |long: &str| {
+        "aiueo"
+            .find(vowel)
+            .and_then(|position| long.chars().nth(position))
+    };
+    let long = match style {
+        LongVowels::Spelled => Some(vowel),
+        LongVowels::Macron => marked("āīūēō"),
+        LongVowels::Circumflex => marked("âîûêô"),
+    };
+    if let Some(long) = long {
+        if style != LongVowels::Spelled {
+            output.pop();
+        }
+        output.push(long);
+    }
+}
+
+/// The longest kana sequence at the start of `chars` with a reading.
+fn syllable(chars: &[char], system: System) -> Option<(&'static str, usize)> {
+    let tables: &[&[(&str, &str)]] = match system {
+        System::Hepburn => &[HIRAGANA],
+        System::Kunrei => &[KUNREI, HIRAGANA],
+        System::Nihon => &[NIHON, KUNREI, HIRAGANA],
+    };
+    [2, 1].into_iter().find_map(|length| {
+        let kana: String = chars.get(..length)?.iter().collect();
+        tables.iter().find_map(|table| {
+            table
+                .iter()
+                .find(|(candidate, _)| *candidate == kana)
+                .map(|(_, romaji)| (*romaji, length))
+        })
+    })
+}
@@ -0,0 +1,165 @@
+//! Cleaning up quote text as it is loaded. Lines pasted from the web often
+//! carry zero-width spaces, byte order marks, direction marks, and odd
+//! spaces that are invisible but throw off width calculations and search.
+//!
+//! Decomposed characters are composed too, for the cases pasted Japanese
+//! and romaji run into: kana followed by a separate (han)dakuten, and Latin
+//! letters followed by a separate macron or accent. Other text is left as
+//! written.
+
+use crate::AnimeQuote;
+
+/// Characters that take no space and are dropped.
+const INVISIBLE: &[char] = &[
+    '\u{00AD}', // soft hyphen
+    '\u{180E}', // Mongolian vowel separator
+    '\u{200B}', '\u{200C}', '\u{200D}', // zero-width space, non-joiner, joiner
+    '\u{200E}', '\u{200F}', // left-to-right and right-to-left marks
+    '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}', // embeddings
+    '\u{2060}', '\u{2061}', '\u{2062}', '\u{2063}', '\u{2064}', // word joiner
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}', // isolates
+    '\u{FEFF}', // byte order mark
+];
+
+/// Spaces other than the ordinary one, read as an ordinary space. The
+/// ideographic space is left alone because Japanese text uses it on purpose.
+const SPACES: &[char] = &[
+    '\u{00A0}', '\u{1680}', '\u{2000}', '\u{2001}', '\u{2002}', '\u{2003}', '\u{2004}', '\u{2005}',
+    '\u{2006}', '\u{2007}', '\u{2008}', '\u{2009}', '\u{200A}', '\u{202F}', '\u{205F}',
+];
+
+/// Kana that take a dakuten, each followed in Unicode by its voiced form.
+const DAKUTEN_BASES: &str =
+    "かきくけこさしすせそたちつてとはひふへほカキクケコサシスセソタチツテトハヒフヘホ";
+
+/// Kana that take a handakuten, two code points before their p- form.
+const HANDAKUTEN_BASES: &str = "はひふへほハヒフヘホ";
+
+/// Voiced forms that aren't the next code point after their base.
+const OTHER_DAKUTEN: &[(char, char)] = &[
+    ('う', 'ゔ'),
+    ('ゝ', 'ゞ'),
+    ('ウ', 'ヴ'),
+    ('ワ', 'ヷ'),
+    ('ヰ', 'ヸ'),
+    ('ヱ', 'ヹ'),
+    ('ヲ', 'ヺ'),
+    ('ヽ', 'ヾ'),
+];
+
+/// Combining marks with the letters they compose with and the results.
+const LATIN: &[(char, &str, &str)] = &[
+    ('\u{0304}', "aeiouAEIOU", "āēīōūĀĒĪŌŪ"),
+    ('\u{0301}', "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+    ('\u{0300}', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    ('\u{0302}', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ('\u{0308}', "aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+    ('\u{0303}', "anoANO", "ãñõÃÑÕ"),
+    ('\u{0327}', "cC", "çÇ"),
+];
+
+/// `text` with invisible characters dropped, decomposed characters
+/// composed, every run of other whitespace within a line made a single
+/// space, and the ends of the text and of each line trimmed. Line breaks
+/// are kept.
+pub(crate) fn clean(text: &str) -> String {
+    let mut cleaned = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if INVISIBLE.contains(&ch) || ch == '\r' {
+            continue;
+        }
+        if ch == '\n' {
+            cleaned.truncate(cleaned.trim_end_matches(' ').len());
+            if !cleaned.is_empty() {
+                cleaned.push('\n');
+            }
+            continue;
+        }
+        if (ch.is_whitespace() && ch != '\u{3000}') || SPACES.contains(&ch) {
+            if !cleaned.is_empty() && !cleaned.ends_with([' ', '\n']) {
+                cleaned.push(' ');
+            }
+            continue;
+        }
+        if ch.is_control() {
+            continue;
+        }
+        if let Some(composed) = cleaned
+            .chars()
+            .next_back()
+            .and_then(|base| compose(base, ch))
+        {
+            cleaned.pop();
+            cleaned.push(composed);
+            continue;
+        }
+        cleaned.push(ch);
+    }
+    cleaned.truncate(cleaned.trim_end().len());
+    cleaned
+}
+
+fn compose(base: char, mark: char) -> Option<char> {
+    let offset_from = |bases: &str, offset: u32| {
+        bases
+            .contains(base)
+            .then(|| char::from_u32(u32::from(base) + offset))
+            .flatten()
+    };
+    match mark {
+        // The combining marks, and the spacing ones that are pasted in
+        // their place.
+        '\u{3099}' | '\u{309B}' => OTHER_DAKUTEN
+            .iter()
+            .find(|(plain, _)| *plain == base)
+            .map(|(_, voiced)| *voiced)
+            .or_else(|| offset_from(DAKUTEN_BASES, 1)),
+        '\u{309A}' | '\u{309C}' => offset_from(HANDAKUTEN_BASES, 2),
+        _ => {
+            let (_, bases, composed) = LATIN.iter().find(|(combining, ..)| *combining == mark)?;
+            let position = bases.chars().position(|letter| letter == base)?;
+            composed.chars().nth(position)
+        }
+    }
+}
+
+/// Cleans the text in an extra field, however deeply it is nested.
+fn clean_value(value: &mut toml::Value) {
+    match value {
+        toml::Value::String(text) => *text = clean(text),
+        toml::Value::Array(values) => values.iter_mut().for_each(clean_value),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, value)| clean_value(value)),
+        _ => {}
+    }
+}
+
+impl AnimeQuote {
+    /// Cleans every text field a person reads or searches; IDs, paths, and
+    /// links are left as written.
+    pub(crate) fn sanitize(&mut self) {
+        for text in [
+            &mut self.japanese,
+            &mut self.anime,
+            &mut self.character,
+            &mut self.quote,
+        ] {
+            *text = clean(text);
+        }
+        for text in [
+            &mut self.romaji,
+            &mut self.pitch,
+            &mut self.furigana,
+            &mut self.source,
+            &mut self.notes,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *text = clean(text);
+        }
+        for tag in &mut self.tags {
+            *tag = clean(tag);
+        }
+        self.extra.values_mut().for_each(clean_value);
+    }
+}
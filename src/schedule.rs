@@ -0,0 +1,171 @@
+//! Quotes tied to a time of year: `show_on = "12-25"` for a date and
+//! `show_season = "winter"` for a season. On a matching day the daily and
+//! random picks choose among those quotes, and among quotes from characters
+//! whose `birthday` it is.
+
+use crate::AnimeQuote;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ScheduleConfig {
+    /// Keep scheduled quotes out of the picks on every other day, too.
+    #[serde(default)]
+    exclusive: bool,
+    /// How many days either side of a `show_on` date still count.
+    #[serde(default)]
+    days_around: u32,
+    /// Flip the seasons, so December is summer.
+    #[serde(default)]
+    southern_hemisphere: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "spring" => Some(Self::Spring),
+            "summer" => Some(Self::Summer),
+            "autumn" | "fall" => Some(Self::Autumn),
+            "winter" => Some(Self::Winter),
+            _ => None,
+        }
+    }
+
+    /// Meteorological seasons: whole months, winter starting in December.
+    fn of(date: NaiveDate, southern_hemisphere: bool) -> Self {
+        let northern = match date.month() {
+            3..=5 => Self::Spring,
+            6..=8 => Self::Summer,
+            9..=11 => Self::Autumn,
+            _ => Self::Winter,
+        };
+        match (southern_hemisphere, northern) {
+            (false, season) => season,
+            (true, Self::Spring) => Self::Autumn,
+            (true, Self::Summer) => Self::Winter,
+            (true, Self::Autumn) => Self::Spring,
+            (true, Self::Winter) => Self::Summer,
+        }
+    }
+}
+
+/// Parses `show_on` as `MM-DD`.
+fn parse_month_day(value: &str) -> Option<(u32, u32)> {
+    let (month, day) = value.trim().split_once('-')?;
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+    // 2000 is a leap year, so 02-29 is accepted.
+    NaiveDate::from_ymd_opt(2000, month, day).map(|_| (month, day))
+}
+
+fn is_scheduled(quote: &AnimeQuote) -> bool {
+    quote.show_on.is_some() || quote.show_season.is_some()
+}
+
+/// Whether `date` is the character's birthday. Leap-day birthdays are
+/// celebrated on February 28 in other years.
+pub(crate) fn is_birthday(quote: &AnimeQuote, date: NaiveDate) -> bool {
+    let Some((month, day)) = quote.birthday.as_deref().and_then(parse_month_day) else {
+        return false;
+    };
+    let day = if (month, day) == (2, 29) && NaiveDate::from_ymd_opt(date.year(), 2, 29).is_none() {
+        28
+    } else {
+        day
+    };
+    (date.month(), date.day()) == (month, day)
+}
+
+fn matches(quote: &AnimeQuote, date: NaiveDate, config: &ScheduleConfig) -> bool {
+    let on_date = quote
+        .show_on
+        .as_deref()
+        .and_then(parse_month_day)
+        .is_some_and(|(month, day)| {
+            // Check the neighbouring years too, so a window can span New Year.
+            (date.year() - 1..=date.year() + 1).any(|year| {
+                NaiveDate::from_ymd_opt(year, month, day).is_some_and(|target| {
+                    (date - target).num_days().unsigned_abs() <= u64::from(config.days_around)
+                })
+            })
+        });
+    let in_season = quote
+        .show_season
+        .as_deref()
+        .and_then(Season::parse)
+        .is_some_and(|season| season == Season::of(date, config.southern_hemisphere));
+    on_date || in_season || is_birthday(quote, date)
+}
+
+/// Which of the quotes at `positions` a pick on `date` chooses from: the
+/// scheduled and birthday quotes matching it when there are any, otherwise
+/// all of them (minus scheduled quotes when `exclusive` is set and anything
+/// else is left).
+pub(crate) fn candidates(
+    quotes: &[AnimeQuote],
+    positions: &[usize],
+    date: NaiveDate,
+    config: &ScheduleConfig,
+) -> Vec<usize> {
+    let matching: Vec<usize> = positions
+        .iter()
+        .copied()
+        .filter(|&index| matches(&quotes[index], date, config))
+        .collect();
+    if !matching.is_empty() {
+        return matching;
+    }
+    let unscheduled: Vec<usize> = positions
+        .iter()
+        .copied()
+        .filter(|&index| !config.exclusive || !is_scheduled(&quotes[index]))
+        .collect();
+    if unscheduled.is_empty() {
+        // Everything is scheduled for other days; better any quote than none.
+        return positions.to_vec();
+    }
+    unscheduled
+}
+
+/// Reports `show_on` and `show_season` values that would never match.
+pub(crate) fn warn_invalid(quotes: &[AnimeQuote]) {
+    for quote in quotes {
+        if let Some(show_on) = quote
+            .show_on
+            .as_deref()
+            .filter(|value| parse_month_day(value).is_none())
+        {
+            eprintln!(
+                "ignoring show_on {show_on:?} for {} (expected MM-DD)",
+                quote.character
+            );
+        }
+        if let Some(birthday) = quote
+            .birthday
+            .as_deref()
+            .filter(|value| parse_month_day(value).is_none())
+        {
+            eprintln!(
+                "ignoring birthday {birthday:?} for {} (expected MM-DD)",
+                quote.character
+            );
+        }
+        if let Some(season) = quote
+            .show_season
+            .as_deref()
+            .filter(|value| Season::parse(value).is_none())
+        {
+            eprintln!(
+                "ignoring show_season {season:?} for {} (expected spring, summer, autumn, or winter)",
+                quote.character
+            );
+        }
+    }
+}
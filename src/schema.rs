@@ -0,0 +1,78 @@
+//! Versioning for the quote data format.
+//!
+//! Data files carry a top-level `schema_version`; files without one are
+//! version 0. Each migration upgrades a document by one version and edits it
+//! in place with `toml_edit`, so comments and layout in hand-written files
+//! survive the upgrade.
+
+use color_eyre::eyre::{Result, WrapErr, bail};
+use std::fs;
+use std::path::Path;
+use toml_edit::DocumentMut;
+
+pub(crate) const CURRENT_SCHEMA_VERSION: i64 = 1;
+
+type Migration = fn(&mut DocumentMut) -> Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`.
+const MIGRATIONS: &[Migration] = &[version_0_to_1];
+
+/// Version 1 only introduces `schema_version` itself.
+fn version_0_to_1(_document: &mut DocumentMut) -> Result<()> {
+    Ok(())
+}
+
+/// Upgrades `source` to the current schema. Returns the version it started
+/// at and the migrated text, or `None` when it is already current.
+pub(crate) fn upgrade(source: &str) -> Result<Option<(i64, String)>> {
+    let mut document: DocumentMut = source.parse()?;
+    let version = match document.get("schema_version") {
+        None => 0,
+        Some(item) => match item.as_integer() {
+            Some(version) if version >= 0 => version,
+            _ => bail!("schema_version must be a non-negative integer"),
+        },
+    };
+    if version > CURRENT_SCHEMA_VERSION {
+        bail!(
+            "schema_version {version} is newer than this build supports \
+             ({CURRENT_SCHEMA_VERSION}); upgrade anime-quotes"
+        );
+    }
+    if version == CURRENT_SCHEMA_VERSION {
+        return Ok(None);
+    }
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(&mut document)
+            .wrap_err_with(|| format!("failed to migrate from schema_version {from}"))?;
+    }
+    document["schema_version"] = toml_edit::value(CURRENT_SCHEMA_VERSION);
+    Ok(Some((version, document.to_string())))
+}
+
+/// Reads `path`, migrating it on disk when it uses an older schema. The
+/// original is kept next to it as `<name>.v<old version>.bak`.
+pub(crate) fn read_migrated(path: &Path) -> Result<String> {
+    let content =
+        fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let Some((from, migrated)) = upgrade(&content)? else {
+        return Ok(content);
+    };
+    let backup = path.with_file_name(format!(
+        "{}.v{from}.bak",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    fs::copy(path, &backup).wrap_err_with(|| format!("failed to back up {}", path.display()))?;
+    fs::write(path, &migrated).wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    eprintln!(
+        "migrated {} from schema_version {from} to {CURRENT_SCHEMA_VERSION} (backup: {})",
+        path.display(),
+        backup.display()
+    );
+    Ok(migrated)
+}
+
+/// Like [`upgrade`] but for content that is only read, such as packs.
+pub(crate) fn upgrade_in_memory(content: String) -> Result<String> {
+    Ok(upgrade(&content)?.map_or(content, |(_, migrated)| migrated))
+}
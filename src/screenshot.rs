@@ -0,0 +1,270 @@
+use crate::color_to_rgb;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+use serde::Deserialize;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+const DEFAULT_SCREENSHOT_FORMAT: &str = "html";
+const DEFAULT_SCREENSHOT_OUTPUT_DIR: &str = ".";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ScreenshotConfig {
+    #[serde(default = "default_screenshot_format")]
+    format: String,
+    #[serde(default = "default_screenshot_output_dir")]
+    output_dir: String,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            format: default_screenshot_format(),
+            output_dir: default_screenshot_output_dir(),
+        }
+    }
+}
+
+fn default_screenshot_format() -> String {
+    DEFAULT_SCREENSHOT_FORMAT.to_string()
+}
+
+fn default_screenshot_output_dir() -> String {
+    DEFAULT_SCREENSHOT_OUTPUT_DIR.to_string()
+}
+
+impl ScreenshotConfig {
+    pub(crate) fn screen_format(&self) -> ScreenFormat {
+        ScreenFormat::parse(&self.format).unwrap_or_else(|| {
+            eprintln!(
+                "unknown screenshot format {:?}, falling back to {DEFAULT_SCREENSHOT_FORMAT}",
+                self.format
+            );
+            ScreenFormat::default()
+        })
+    }
+
+    /// Path used for the screenshot of the quote at `index` (0-based).
+    pub(crate) fn output_path(&self, index: usize, format: ScreenFormat) -> PathBuf {
+        Path::new(&self.output_dir).join(format!("screen-{}.{}", index + 1, format.extension()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum ScreenFormat {
+    Ansi,
+    #[default]
+    Html,
+}
+
+impl ScreenFormat {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "ansi" | "ans" => Some(Self::Ansi),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Ansi => "ans",
+            Self::Html => "html",
+        }
+    }
+
+    pub(crate) fn render(self, buffer: &Buffer, title: &str) -> String {
+        match self {
+            Self::Ansi => render_ansi(buffer),
+            Self::Html => render_html(buffer, title),
+        }
+    }
+}
+
+/// Walks the buffer row by row, yielding the cells that are actually visible:
+/// cells hidden behind a wide grapheme and cells marked `skip` by image
+/// widgets are dropped so the output keeps the on-screen column layout.
+fn visible_rows(buffer: &Buffer) -> Vec<Vec<&ratatui::buffer::Cell>> {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let mut row = Vec::with_capacity(area.width as usize);
+            let mut hidden = 0;
+            for x in area.left()..area.right() {
+                if hidden > 0 {
+                    hidden -= 1;
+                    continue;
+                }
+                let Some(cell) = buffer.cell((x, y)) else {
+                    continue;
+                };
+                if cell.skip {
+                    continue;
+                }
+                hidden = cell.symbol().width().saturating_sub(1);
+                row.push(cell);
+            }
+            row
+        })
+        .collect()
+}
+
+/// Image widgets may leave raw graphics escape sequences in a cell; those
+/// are blanked so the export stays printable.
+fn printable(symbol: &str) -> &str {
+    if symbol.contains('\x1b') { " " } else { symbol }
+}
+
+fn render_ansi(buffer: &Buffer) -> String {
+    let mut output = String::new();
+    for row in visible_rows(buffer) {
+        let mut previous: Option<(Color, Color, Modifier)> = None;
+        for cell in row {
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if previous != Some(style) {
+                output.push_str(&sgr(style));
+                previous = Some(style);
+            }
+            output.push_str(printable(cell.symbol()));
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+pub(crate) fn sgr((fg, bg, modifier): (Color, Color, Modifier)) -> String {
+    let mut codes = vec!["0".to_string()];
+    if modifier.contains(Modifier::BOLD) {
+        codes.push("1".to_string());
+    }
+    if modifier.contains(Modifier::DIM) {
+        codes.push("2".to_string());
+    }
+    if modifier.contains(Modifier::ITALIC) {
+        codes.push("3".to_string());
+    }
+    if modifier.contains(Modifier::UNDERLINED) {
+        codes.push("4".to_string());
+    }
+    if modifier.contains(Modifier::REVERSED) {
+        codes.push("7".to_string());
+    }
+    if let Some(code) = ansi_color(fg, false) {
+        codes.push(code);
+    }
+    if let Some(code) = ansi_color(bg, true) {
+        codes.push(code);
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn ansi_color(color: Color, background: bool) -> Option<String> {
+    let base = if background { 40 } else { 30 };
+    let code = match color {
+        Color::Reset => return None,
+        Color::Black => base,
+        Color::Red => base + 1,
+        Color::Green => base + 2,
+        Color::Yellow => base + 3,
+        Color::Blue => base + 4,
+        Color::Magenta => base + 5,
+        Color::Cyan => base + 6,
+        Color::Gray => base + 7,
+        Color::DarkGray => base + 60,
+        Color::LightRed => base + 61,
+        Color::LightGreen => base + 62,
+        Color::LightYellow => base + 63,
+        Color::LightBlue => base + 64,
+        Color::LightMagenta => base + 65,
+        Color::LightCyan => base + 66,
+        Color::White => base + 67,
+        Color::Indexed(index) => return Some(format!("{};5;{index}", base + 8)),
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{r};{g};{b}", base + 8)),
+    };
+    Some(code.to_string())
+}
+
+fn render_html(buffer: &Buffer, title: &str) -> String {
+    let mut body = String::new();
+    for row in visible_rows(buffer) {
+        let mut rest = row.as_slice();
+        while let Some(first) = rest.first() {
+            let style = (first.fg, first.bg, first.modifier);
+            let run = rest
+                .iter()
+                .take_while(|cell| (cell.fg, cell.bg, cell.modifier) == style)
+                .count();
+            let text: String = rest[..run]
+                .iter()
+                .map(|cell| printable(cell.symbol()))
+                .collect();
+            let css = css_style(style);
+            if css.is_empty() {
+                body.push_str(&escape_html(&text));
+            } else {
+                let _ = write!(body, "<span style=\"{css}\">{}</span>", escape_html(&text));
+            }
+            rest = &rest[run..];
+        }
+        body.push('\n');
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ margin: 0; background: #1e1e1e; }}\n\
+         pre {{ margin: 0; padding: 1em; color: #e5e5e5; background: #1e1e1e; \
+         font-family: ui-monospace, Menlo, Consolas, \"DejaVu Sans Mono\", monospace; line-height: 1.1; }}\n\
+         </style>\n</head>\n<body>\n<pre>{body}</pre>\n</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+fn css_style((fg, bg, modifier): (Color, Color, Modifier)) -> String {
+    let (mut fg, mut bg) = (css_color(fg), css_color(bg));
+    if modifier.contains(Modifier::REVERSED) {
+        std::mem::swap(&mut fg, &mut bg);
+    }
+    let mut css = String::new();
+    if let Some(fg) = fg {
+        let _ = write!(css, "color:{fg};");
+    }
+    if let Some(bg) = bg {
+        let _ = write!(css, "background:{bg};");
+    }
+    if modifier.contains(Modifier::BOLD) {
+        css.push_str("font-weight:bold;");
+    }
+    if modifier.contains(Modifier::DIM) {
+        css.push_str("opacity:0.6;");
+    }
+    if modifier.contains(Modifier::ITALIC) {
+        css.push_str("font-style:italic;");
+    }
+    if modifier.contains(Modifier::UNDERLINED) {
+        css.push_str("text-decoration:underline;");
+    }
+    css
+}
+
+fn css_color(color: Color) -> Option<String> {
+    if color == Color::Reset {
+        return None;
+    }
+    let [r, g, b] = color_to_rgb(color);
+    Some(format!("#{r:02x}{g:02x}{b:02x}"))
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
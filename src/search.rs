@@ -0,0 +1,163 @@
+//! Searching from the viewer: `/` and a query (some text, or the filter
+//! language in [`crate::query`]) jumps to the next quote matching it, with
+//! the text it looked for highlighted in the quote's lines.
+
+use crate::query::Query;
+use crate::{AnimeQuote, Palette, filter};
+use crossterm::event::KeyCode;
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use std::ops::Range;
+
+pub(crate) enum SearchAction {
+    Stay,
+    Close,
+    /// Jump to the next quote that matches.
+    Next,
+    Previous,
+    /// Not a search key; the viewer handles it as usual.
+    Pass,
+}
+
+pub(crate) struct Search {
+    input: String,
+    /// Whether keys are still going into `input`.
+    editing: bool,
+    /// The last query searched for.
+    query: Option<Query>,
+    /// Why `input` could not be searched for, until it changes.
+    error: Option<String>,
+}
+
+impl Search {
+    pub(crate) fn new() -> Self {
+        Self {
+            input: String::new(),
+            editing: true,
+            query: None,
+            error: None,
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, code: KeyCode) -> SearchAction {
+        if self.editing {
+            return match code {
+                KeyCode::Esc => SearchAction::Close,
+                KeyCode::Enter if self.input.trim().is_empty() => SearchAction::Close,
+                KeyCode::Enter => match Query::parse(&self.input) {
+                    Ok(query) => {
+                        self.query = Some(query);
+                        self.editing = false;
+                        SearchAction::Next
+                    }
+                    Err(error) => {
+                        self.error = Some(error);
+                        SearchAction::Stay
+                    }
+                },
+                KeyCode::Backspace => {
+                    self.input.pop();
+                    self.error = None;
+                    SearchAction::Stay
+                }
+                KeyCode::Char(ch) => {
+                    self.input.push(ch);
+                    self.error = None;
+                    SearchAction::Stay
+                }
+                _ => SearchAction::Stay,
+            };
+        }
+        match code {
+            KeyCode::Char('n') => SearchAction::Next,
+            KeyCode::Char('N') => SearchAction::Previous,
+            KeyCode::Char('/') => {
+                self.editing = true;
+                SearchAction::Stay
+            }
+            KeyCode::Esc => SearchAction::Close,
+            _ => SearchAction::Pass,
+        }
+    }
+
+    pub(crate) fn matches(&self, quote: &AnimeQuote) -> bool {
+        self.query
+            .as_ref()
+            .is_some_and(|query| query.matches(quote))
+    }
+
+    /// The query whose matches [`Search::highlight`] marks, if any.
+    pub(crate) fn highlighting(&self) -> Option<&str> {
+        self.query
+            .as_ref()
+            .filter(|_| !self.editing)
+            .map(Query::as_str)
+    }
+
+    /// `text` in `style`, with the text the query looks for in `highlight`
+    /// over it. While a query is being typed nothing is highlighted.
+    pub(crate) fn highlight(
+        &self,
+        text: &str,
+        style: Style,
+        highlight: Style,
+    ) -> Vec<Span<'static>> {
+        let mut ranges: Vec<Range<usize>> = match (&self.query, self.editing) {
+            (Some(query), false) => query
+                .highlights()
+                .into_iter()
+                .flat_map(|needle| filter::find(text, needle, false))
+                .collect(),
+            _ => Vec::new(),
+        };
+        ranges.sort_by_key(|range| range.start);
+        let mut spans = Vec::new();
+        let mut last = 0;
+        for range in ranges {
+            // Overlapping matches of different terms run together.
+            let start = range.start.max(last);
+            if range.end <= start {
+                continue;
+            }
+            if start > last {
+                spans.push(Span::styled(text[last..start].to_string(), style));
+            }
+            spans.push(Span::styled(
+                text[start..range.end].to_string(),
+                style.patch(highlight),
+            ));
+            last = range.end;
+        }
+        if last < text.len() || spans.is_empty() {
+            spans.push(Span::styled(text[last..].to_string(), style));
+        }
+        spans
+    }
+
+    /// The prompt while typing, then the query and how many quotes match.
+    pub(crate) fn status_line(&self, matches: usize, palette: &Palette) -> Line<'static> {
+        let count_style = Style::default().fg(palette.count);
+        if self.editing {
+            let hint = match &self.error {
+                Some(error) => Span::styled(
+                    format!("  {error}"),
+                    Style::default().fg(palette.character).bold(),
+                ),
+                None => Span::styled("  <Enter> search  <Esc> cancel", count_style),
+            };
+            return Line::from(vec![
+                Span::styled("/", Style::default().fg(palette.character).bold()),
+                Span::raw(self.input.clone()),
+                Span::raw("▏"),
+                hint,
+            ]);
+        }
+        Line::from(Span::styled(
+            format!(
+                "{}: {matches} matching  ·  Next <N>  Previous <Shift+N>  Clear <Esc>",
+                self.query.as_ref().map_or("", Query::as_str)
+            ),
+            count_style,
+        ))
+    }
+}
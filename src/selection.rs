@@ -0,0 +1,69 @@
+use chrono::{Datelike, NaiveDate};
+
+/// How a slideshow moves on to the next quote.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Rotation {
+    /// The next quote in the viewer's sort order.
+    #[default]
+    Order,
+    Shuffle,
+    /// A random quote among those shown the fewest times, so the whole
+    /// collection comes around.
+    LeastSeen,
+}
+
+impl Rotation {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "order" => Some(Self::Order),
+            "shuffle" => Some(Self::Shuffle),
+            "least-seen" | "least_seen" => Some(Self::LeastSeen),
+            _ => None,
+        }
+    }
+
+    /// The `rotation` setting of `section` when there is one, otherwise
+    /// what the older `shuffle` flag asks for.
+    pub(crate) fn resolve(rotation: Option<&str>, shuffle: bool, section: &str) -> Self {
+        let from_shuffle = if shuffle { Self::Shuffle } else { Self::Order };
+        let Some(rotation) = rotation else {
+            return from_shuffle;
+        };
+        Self::parse(rotation).unwrap_or_else(|| {
+            eprintln!(
+                "unknown [{section}] rotation {rotation:?}, expected order, shuffle, or least-seen"
+            );
+            from_shuffle
+        })
+    }
+}
+
+/// Index of the quote of the day for `date` in a collection of `len` quotes.
+///
+/// The day number is scrambled so consecutive days don't walk the collection
+/// in file order, but the same date always yields the same quote.
+pub(crate) fn daily_index(len: usize, date: NaiveDate) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let day = date.num_days_from_ce() as u64;
+    Some((splitmix64(day) % len as u64) as usize)
+}
+
+/// FNV-1a: unlike `DefaultHasher`, guaranteed stable across releases, so its
+/// output can be stored or used to seed visuals.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+pub(crate) fn splitmix64(seed: u64) -> u64 {
+    let mut value = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
@@ -0,0 +1,642 @@
+use crate::AnimeQuote;
+use crate::graphql::{self, GraphqlRequest};
+use crate::metrics::Metrics;
+use crate::output::QuoteView;
+use crate::query::Query;
+use color_eyre::eyre::{Result, WrapErr};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// A client that stops reading is dropped after this long, instead of
+/// holding its thread on a full socket.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+/// More connections than this at once get a 503 right away, since each one
+/// holds a thread.
+const MAX_CONNECTIONS: usize = 128;
+/// `/stream` connections are closed after this long; `EventSource` clients
+/// reconnect on their own, and abandoned ones don't linger.
+const MAX_STREAM: Duration = Duration::from_secs(60 * 60);
+/// Larger request bodies are cut off; only GraphQL queries are sent.
+const MAX_BODY: u64 = 1 << 20;
+/// Longer request lines and header lines are refused rather than read on
+/// into memory.
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec_pretty(value).unwrap_or_default(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+struct Server {
+    quotes: Vec<AnimeQuote>,
+    stream_interval: Duration,
+    metrics: Metrics,
+    /// Artwork by path, read on first request and kept for the server's
+    /// lifetime.
+    images: Mutex<HashMap<String, Arc<Vec<u8>>>>,
+    /// The directories artwork is served from: the data file's, and the
+    /// data and cache directories where packs and thumbnails live.
+    image_roots: Vec<PathBuf>,
+}
+
+/// Serves the collection over HTTP until the process is stopped.
+pub(crate) fn serve(
+    quotes: Vec<AnimeQuote>,
+    host: &str,
+    port: u16,
+    stream_interval: Duration,
+) -> Result<()> {
+    let listener = TcpListener::bind((host, port))
+        .wrap_err_with(|| format!("failed to listen on {host}:{port}"))?;
+    eprintln!(
+        "serving {} quotes on http://{}",
+        quotes.len(),
+        listener.local_addr()?
+    );
+    let server = Arc::new(Server {
+        quotes,
+        stream_interval,
+        metrics: Metrics::new(),
+        images: Mutex::new(HashMap::new()),
+        image_roots: image_roots(),
+    });
+    let open = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        match stream {
+            Ok(mut stream) => {
+                let Some(slot) = Connection::open(&open) else {
+                    server.metrics.request("other", 503);
+                    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                    let _ = write_response(
+                        &mut stream,
+                        &Response::error(503, "too many connections; try again shortly"),
+                        false,
+                        true,
+                    );
+                    continue;
+                };
+                let server = Arc::clone(&server);
+                thread::spawn(move || {
+                    let _slot = slot;
+                    if let Err(error) = server.handle_connection(stream) {
+                        eprintln!("request failed: {error}");
+                    }
+                });
+            }
+            Err(error) => eprintln!("failed to accept connection: {error}"),
+        }
+    }
+    Ok(())
+}
+
+/// One of the [`MAX_CONNECTIONS`] open connections, given back when dropped.
+struct Connection(Arc<AtomicUsize>);
+
+impl Connection {
+    fn open(open: &Arc<AtomicUsize>) -> Option<Self> {
+        open.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+            (count < MAX_CONNECTIONS).then_some(count + 1)
+        })
+        .ok()
+        .map(|_| Self(Arc::clone(open)))
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Server {
+    fn handle_connection(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let request = read_request(&stream)?;
+        let head_only = request
+            .as_ref()
+            .is_ok_and(|request| request.method == "HEAD");
+        let endpoint = request
+            .as_ref()
+            .map_or("other", |request| endpoint(&request.path));
+        let response = match request {
+            Ok(request)
+                if (request.method == "GET" || head_only)
+                    && request.path.trim_end_matches('/') == "/stream" =>
+            {
+                return self.stream(&mut stream, &request, head_only);
+            }
+            Ok(request) if request.method == "GET" || head_only => self.route(&request),
+            Ok(request)
+                if request.method == "POST" && request.path.trim_end_matches('/') == "/graphql" =>
+            {
+                self.graphql(&request)
+            }
+            // CORS preflight for browsers POSTing JSON to /graphql.
+            Ok(request) if request.method == "OPTIONS" => Response {
+                status: 204,
+                content_type: "text/plain",
+                body: Vec::new(),
+            },
+            Ok(_) => Response::error(405, "only GET (and POST to /graphql) is supported"),
+            Err(response) => response,
+        };
+        self.metrics.request(endpoint, response.status);
+        // Artwork is for `<img>` tags, which need no CORS; scripts on other
+        // sites have no business reading the files.
+        let cross_origin = endpoint != "/quotes/{id}/image";
+        write_response(&mut stream, &response, head_only, cross_origin)
+    }
+
+    fn route(&self, request: &Request) -> Response {
+        let segments: Vec<String> = request
+            .path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| decode_component(segment, false))
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        match segments.as_slice() {
+            [] => Response::json(
+                200,
+                &serde_json::json!({
+                    "endpoints": ["/random", "/quotes", "/quotes/{id}", "/quotes/{id}/image", "/anime/{title}", "/stream", "/graphql", "/graphql/schema", "/metrics"],
+                    "quotes": self.quotes.len(),
+                }),
+            ),
+            ["random"] => self.random(),
+            ["quotes"] => {
+                let query = match request.query.get("q").map(|query| Query::parse(query)) {
+                    Some(Err(error)) => {
+                        return Response::error(400, &format!("bad query: {error}"));
+                    }
+                    query => query.and_then(Result::ok),
+                };
+                let views = self.views(self.filtered(&request.query, query.as_ref()));
+                self.metrics.served(views.len());
+                Response::json(200, &views)
+            }
+            ["quotes", id] => match self.quote(id) {
+                Some(view) => {
+                    self.metrics.served(1);
+                    Response::json(200, &view)
+                }
+                None => Response::error(404, "quote not found"),
+            },
+            ["quotes", id, "image"] => self.image(id),
+            ["graphql"] => self.graphql(request),
+            ["graphql", "schema"] => Response {
+                status: 200,
+                content_type: "text/plain; charset=utf-8",
+                body: graphql::SCHEMA.as_bytes().to_vec(),
+            },
+            ["metrics"] => Response {
+                status: 200,
+                content_type: "text/plain; version=0.0.4; charset=utf-8",
+                body: self.metrics.render(self.quotes.len()).into_bytes(),
+            },
+            ["anime", title] => {
+                let matches: Vec<_> = self
+                    .views(self.quotes.iter().enumerate())
+                    .into_iter()
+                    .filter(|view| view.quote.anime.eq_ignore_ascii_case(title))
+                    .collect();
+                if matches.is_empty() {
+                    Response::error(404, "anime not found")
+                } else {
+                    self.metrics.served(matches.len());
+                    Response::json(200, &matches)
+                }
+            }
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    /// Server-sent events: pushes a `quote` event with a random quote right
+    /// away and then every interval (`?interval=` seconds overrides the
+    /// server default) until the client disconnects or stops reading, or
+    /// [`MAX_STREAM`] has passed. `HEAD` gets the headers alone.
+    fn stream(
+        &self,
+        stream: &mut TcpStream,
+        request: &Request,
+        head_only: bool,
+    ) -> std::io::Result<()> {
+        let interval = request
+            .query
+            .get("interval")
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .map(|seconds| Duration::from_secs(seconds.max(1)))
+            .unwrap_or(self.stream_interval);
+        if self.quotes.is_empty() {
+            self.metrics.request("/stream", 404);
+            return write_response(
+                stream,
+                &Response::error(404, "no quotes loaded"),
+                head_only,
+                true,
+            );
+        }
+        self.metrics.request("/stream", 200);
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+             Access-Control-Allow-Origin: *\r\nConnection: keep-alive\r\n\r\n"
+        )?;
+        if head_only {
+            return stream.flush();
+        }
+        let _client = self.metrics.stream_client();
+
+        write!(stream, "retry: {}\n\n", interval.as_millis())?;
+        let mut rng = rand::rng();
+        let started = Instant::now();
+        while started.elapsed() < MAX_STREAM {
+            let index = rng.random_range(0..self.quotes.len());
+            let view = QuoteView::new(index, &self.quotes[index]);
+            let data = serde_json::to_string(&view).unwrap_or_default();
+            write!(stream, "event: quote\nid: {}\ndata: {data}\n\n", view.id)?;
+            stream.flush()?;
+            self.metrics.served(1);
+            thread::sleep(interval);
+        }
+        Ok(())
+    }
+
+    /// `GET /graphql?query=…&variables=…`, or `POST /graphql` with the usual
+    /// JSON body (`query`, `variables`, `operationName`) or a bare query sent
+    /// as `application/graphql`.
+    fn graphql(&self, request: &Request) -> Response {
+        let is_raw = request
+            .content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.starts_with("application/graphql"));
+        let parsed = if request.method != "POST" {
+            let variables = match request.query.get("variables") {
+                Some(variables) => match serde_json::from_str(variables) {
+                    Ok(variables) => Some(variables),
+                    Err(_) => return Response::error(400, "variables must be a JSON object"),
+                },
+                None => None,
+            };
+            request.query.get("query").map(|query| GraphqlRequest {
+                query: query.clone(),
+                operation_name: request.query.get("operationName").cloned(),
+                variables,
+            })
+        } else if is_raw {
+            Some(GraphqlRequest {
+                query: String::from_utf8_lossy(&request.body).into_owned(),
+                operation_name: None,
+                variables: None,
+            })
+        } else {
+            serde_json::from_slice(&request.body).ok()
+        };
+        match parsed {
+            Some(parsed) => {
+                let (response, served) = parsed.execute(&self.quotes);
+                self.metrics.served(served);
+                Response::json(200, &response)
+            }
+            None => Response::error(400, "expected a GraphQL query"),
+        }
+    }
+
+    fn views<'a>(
+        &'a self,
+        quotes: impl Iterator<Item = (usize, &'a AnimeQuote)>,
+    ) -> Vec<QuoteView<'a>> {
+        quotes
+            .map(|(index, quote)| QuoteView::new(index, quote))
+            .collect()
+    }
+
+    /// `?anime=` and `?character=` narrow `/quotes` case-insensitively, and
+    /// `?q=` with a [`Query`].
+    fn filtered<'a>(
+        &'a self,
+        query: &'a HashMap<String, String>,
+        expression: Option<&'a Query>,
+    ) -> impl Iterator<Item = (usize, &'a AnimeQuote)> {
+        self.quotes.iter().enumerate().filter(move |(_, quote)| {
+            expression.is_none_or(|expression| expression.matches(quote))
+                && query
+                    .get("anime")
+                    .is_none_or(|anime| quote.anime.eq_ignore_ascii_case(anime))
+                && query
+                    .get("character")
+                    .is_none_or(|character| quote.character.eq_ignore_ascii_case(character))
+        })
+    }
+
+    /// Looks a quote up by stable ID, falling back to its 1-based position
+    /// so counter numbers from the viewer keep working.
+    fn quote(&self, id: &str) -> Option<QuoteView<'_>> {
+        let index = self
+            .quotes
+            .iter()
+            .position(|quote| quote.stable_id() == id)
+            .or_else(|| id.parse::<usize>().ok()?.checked_sub(1))?;
+        self.quotes
+            .get(index)
+            .map(|quote| QuoteView::new(index, quote))
+    }
+
+    fn random(&self) -> Response {
+        if self.quotes.is_empty() {
+            return Response::error(404, "no quotes loaded");
+        }
+        let index = rand::rng().random_range(0..self.quotes.len());
+        self.metrics.served(1);
+        Response::json(200, &QuoteView::new(index, &self.quotes[index]))
+    }
+
+    fn image(&self, id: &str) -> Response {
+        let Some(path) = self.quote(id).and_then(|view| view.quote.image.as_deref()) else {
+            return Response::error(404, "image not found");
+        };
+        let cached = self
+            .images
+            .lock()
+            .ok()
+            .and_then(|images| images.get(path).cloned());
+        self.metrics.image_cache(cached.is_some());
+        let body = match cached {
+            Some(body) => Ok(body),
+            None => self.read_image(path).map(|body| {
+                let body = Arc::new(body);
+                if let Ok(mut images) = self.images.lock() {
+                    images.insert(path.to_string(), Arc::clone(&body));
+                }
+                body
+            }),
+        };
+        match body {
+            Ok(body) => Response {
+                status: 200,
+                content_type: image_content_type(Path::new(path)),
+                body: body.to_vec(),
+            },
+            Err(error) => {
+                eprintln!("not serving {path}: {error}");
+                Response::error(404, "image not found")
+            }
+        }
+    }
+
+    /// Reads the image at `path` if it may be served: a file with an image
+    /// extension and an image's first bytes, inside [`Server::image_roots`].
+    /// Quotes from packs name their own images, so without this a pack
+    /// could point one at any file the user can read.
+    fn read_image(&self, path: &str) -> std::io::Result<Vec<u8>> {
+        let refuse =
+            |reason: &str| std::io::Error::new(std::io::ErrorKind::PermissionDenied, reason);
+        if image_content_type(Path::new(path)) == "application/octet-stream" {
+            return Err(refuse("not an image file name"));
+        }
+        let resolved = fs::canonicalize(path)?;
+        if !self
+            .image_roots
+            .iter()
+            .any(|root| resolved.starts_with(root))
+        {
+            return Err(refuse(
+                "outside the data file's folder and the data and cache directories",
+            ));
+        }
+        let body = fs::read(&resolved)?;
+        match image::guess_format(&body) {
+            Ok(
+                image::ImageFormat::Png
+                | image::ImageFormat::Jpeg
+                | image::ImageFormat::Gif
+                | image::ImageFormat::WebP,
+            ) => Ok(body),
+            _ => Err(refuse("not an image")),
+        }
+    }
+}
+
+/// The directories of [`Server::image_roots`], resolved so they compare
+/// with resolved image paths.
+fn image_roots() -> Vec<PathBuf> {
+    let data_file = Path::new(crate::data_path());
+    let data_folder = match data_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    [data_folder, crate::data_dir(), crate::cache_dir()]
+        .into_iter()
+        .filter_map(|root| fs::canonicalize(root).ok())
+        .collect()
+}
+
+/// The route pattern `path` falls under, as the `endpoint` metrics label.
+fn endpoint(path: &str) -> &'static str {
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    match segments.as_slice() {
+        [] => "/",
+        ["random"] => "/random",
+        ["quotes"] => "/quotes",
+        ["quotes", _] => "/quotes/{id}",
+        ["quotes", _, "image"] => "/quotes/{id}/image",
+        ["anime", _] => "/anime/{title}",
+        ["stream"] => "/stream",
+        ["graphql"] => "/graphql",
+        ["graphql", "schema"] => "/graphql/schema",
+        ["metrics"] => "/metrics",
+        _ => "other",
+    }
+}
+
+/// Reads a request, or the error response for one that is malformed or
+/// whose headers are too large.
+fn read_request(stream: &TcpStream) -> std::io::Result<Result<Request, Response>> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if !read_line(&mut reader, &mut request_line)? {
+        return Ok(Err(Response::error(400, "the request line is too long")));
+    }
+    let mut content_length = 0;
+    let mut content_type = None;
+    let mut header = String::new();
+    let mut headers = 0;
+    loop {
+        if !read_line(&mut reader, &mut header)? {
+            return Ok(Err(Response::error(431, "a header line is too long")));
+        }
+        if header.len() <= 2 {
+            break;
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            return Ok(Err(Response::error(431, "too many headers")));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.to_ascii_lowercase());
+            }
+        }
+        header.clear();
+    }
+    let mut body = Vec::new();
+    reader
+        .take(content_length.min(MAX_BODY))
+        .read_to_end(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(Err(Response::error(400, "malformed request")));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    Ok(Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: parse_query(query),
+        content_type,
+        body,
+    }))
+}
+
+/// Reads a line of at most [`MAX_LINE`] bytes into `line`, returning
+/// whether it fit.
+fn read_line(reader: &mut BufReader<&TcpStream>, line: &mut String) -> std::io::Result<bool> {
+    let read = reader.by_ref().take(MAX_LINE).read_line(line)?;
+    Ok(read < MAX_LINE as usize || line.ends_with('\n'))
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_component(key, true), decode_component(value, true))
+        })
+        .collect()
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    response: &Response,
+    head_only: bool,
+    cross_origin: bool,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n",
+        response.status,
+        status_reason(response.status),
+        response.content_type,
+        response.body.len()
+    )?;
+    if cross_origin {
+        write!(
+            stream,
+            "Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n"
+        )?;
+    }
+    write!(stream, "Connection: close\r\n\r\n")?;
+    if !head_only {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+fn status_reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+fn image_content_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Decodes `%XX` escapes in a URL path segment or query value, and `+` as a
+/// space when `plus_as_space` (only query strings use that form encoding).
+fn decode_component(value: &str, plus_as_space: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' if index + 2 < bytes.len() => {
+                let high = (bytes[index + 1] as char).to_digit(16);
+                let low = (bytes[index + 2] as char).to_digit(16);
+                if let (Some(high), Some(low)) = (high, low) {
+                    decoded.push((high * 16 + low) as u8);
+                    index += 3;
+                    continue;
+                }
+                decoded.push(b'%');
+            }
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,27 @@
+const search = document.getElementById("search");
+const results = document.getElementById("results");
+const animeList = document.getElementById("anime");
+
+function escapeHtml(value) {
+  return value.replace(/[&<>"]/g, (ch) => ({ "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;" })[ch]);
+}
+
+search.addEventListener("input", () => {
+  const needle = search.value.trim().toLowerCase();
+  animeList.hidden = needle.length > 0;
+  if (!needle) {
+    results.innerHTML = "";
+    return;
+  }
+  const matches = QUOTES.filter((entry) =>
+    [entry.japanese, entry.romaji || "", entry.quote, entry.character, entry.anime]
+      .some((field) => field.toLowerCase().includes(needle))
+  );
+  results.innerHTML = matches
+    .map((entry) =>
+      `<li><p class="japanese" lang="ja">${escapeHtml(entry.japanese)}</p>` +
+      `<blockquote>“${escapeHtml(entry.quote)}”</blockquote>` +
+      `<a href="${entry.page}">${escapeHtml(entry.character)} — ${escapeHtml(entry.anime)}</a></li>`
+    )
+    .join("") || '<li class="count">No matching quotes</li>';
+});
@@ -0,0 +1,233 @@
+use crate::export::{AnimeGroup, group_by_anime, markdown_anchor};
+use crate::screenshot::escape_html;
+use crate::{AnimeQuote, Palette, color_to_rgb};
+use color_eyre::eyre::{Result, WrapErr};
+use ratatui::style::Color;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    japanese: &'a str,
+    romaji: Option<&'a str>,
+    quote: &'a str,
+    character: &'a str,
+    anime: &'a str,
+    page: String,
+}
+
+/// Renders the collection as a static website: an index with client-side
+/// search plus one page per anime, with artwork copied next to the pages.
+pub(crate) fn build_site(quotes: &[AnimeQuote], palette: &Palette, out: &Path) -> Result<()> {
+    fs::create_dir_all(out.join("anime"))
+        .wrap_err_with(|| format!("failed to create {}", out.display()))?;
+    let images = copy_images(quotes, &out.join("images"))?;
+    let groups = group_by_anime(quotes);
+    let slugs = page_slugs(&groups);
+
+    write_file(&out.join("style.css"), &stylesheet(palette))?;
+    write_file(
+        &out.join("index.html"),
+        &index_page(quotes, &groups, &slugs)?,
+    )?;
+    for (key, group) in &groups {
+        let page = anime_page(group, &images);
+        write_file(
+            &out.join("anime").join(format!("{}.html", slugs[key])),
+            &page,
+        )?;
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+/// Copies every referenced image into `dir`, returning the site-relative
+/// path for each source path. Clashing file names get a numeric prefix.
+fn copy_images(quotes: &[AnimeQuote], dir: &Path) -> Result<HashMap<String, String>> {
+    let mut copied = HashMap::new();
+    let mut used = Vec::new();
+    for source in quotes.iter().filter_map(|quote| quote.image.as_deref()) {
+        if copied.contains_key(source) {
+            continue;
+        }
+        let Some(name) = Path::new(source).file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let mut target = name.to_string();
+        let mut counter = 1;
+        while used.contains(&target) {
+            counter += 1;
+            target = format!("{counter}-{name}");
+        }
+        fs::create_dir_all(dir)?;
+        match fs::copy(source, dir.join(&target)) {
+            Ok(_) => {
+                copied.insert(source.to_string(), format!("images/{target}"));
+                used.push(target);
+            }
+            Err(error) => eprintln!("failed to copy image {source}: {error}"),
+        }
+    }
+    Ok(copied)
+}
+
+fn page_slugs(groups: &BTreeMap<String, AnimeGroup<'_>>) -> HashMap<String, String> {
+    let mut slugs = HashMap::new();
+    let mut used: Vec<String> = Vec::new();
+    for (index, (key, group)) in groups.iter().enumerate() {
+        let mut slug = markdown_anchor(group.title);
+        if slug.is_empty() || used.contains(&slug) {
+            slug = format!("{slug}-{}", index + 1)
+                .trim_start_matches('-')
+                .to_string();
+        }
+        used.push(slug.clone());
+        slugs.insert(key.clone(), slug);
+    }
+    slugs
+}
+
+fn quote_count_label(count: usize) -> String {
+    if count == 1 {
+        "1 quote".to_string()
+    } else {
+        format!("{count} quotes")
+    }
+}
+
+fn css_color(color: Color) -> String {
+    let [r, g, b] = color_to_rgb(color);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn stylesheet(palette: &Palette) -> String {
+    format!(
+        ":root {{\n  --anime: {};\n  --character: {};\n  --japanese: {};\n  --romaji: {};\n  --quote: {};\n  --count: {};\n}}\n{}",
+        css_color(palette.anime),
+        css_color(palette.character),
+        css_color(palette.japanese),
+        css_color(palette.romaji),
+        css_color(palette.quote),
+        css_color(palette.count),
+        include_str!("site.css")
+    )
+}
+
+fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n\
+         <body>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+fn index_page(
+    quotes: &[AnimeQuote],
+    groups: &BTreeMap<String, AnimeGroup<'_>>,
+    slugs: &HashMap<String, String>,
+) -> Result<String> {
+    let mut body = format!(
+        "<h1>Anime Quotes</h1>\n<p class=\"count\">{} from {} anime</p>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search quotes, characters, anime…\" autofocus>\n\
+         <ol id=\"results\" class=\"results\"></ol>\n<ul id=\"anime\" class=\"anime-list\">\n",
+        quote_count_label(quotes.len()),
+        groups.len()
+    );
+    for (key, group) in groups {
+        let _ = writeln!(
+            body,
+            "<li><a href=\"anime/{}.html\">{}</a> <span class=\"count\">{}</span></li>",
+            slugs[key],
+            escape_html(group.title),
+            group.quote_count()
+        );
+    }
+    body.push_str("</ul>\n");
+
+    let entries: Vec<SearchEntry> = quotes
+        .iter()
+        .map(|quote| SearchEntry {
+            japanese: &quote.japanese,
+            romaji: quote.romaji.as_deref(),
+            quote: &quote.quote,
+            character: &quote.character,
+            anime: &quote.anime,
+            page: format!("anime/{}.html", slugs[&quote.anime.to_lowercase()]),
+        })
+        .collect();
+    // `</` inside the JSON would end the script element early.
+    let data = serde_json::to_string(&entries)?.replace("</", "<\\/");
+    let _ = write!(
+        body,
+        "<script>\nconst QUOTES = {data};\n{}</script>\n",
+        include_str!("site.js")
+    );
+    Ok(page("Anime Quotes", "", &body))
+}
+
+fn anime_page(group: &AnimeGroup<'_>, images: &HashMap<String, String>) -> String {
+    let mut body = format!(
+        "<p><a href=\"../index.html\">← All anime</a></p>\n<h1 class=\"anime\">{}</h1>\n\
+         <p class=\"count\">{}</p>\n",
+        escape_html(group.title),
+        quote_count_label(group.quote_count())
+    );
+    for character in group.characters.values() {
+        let _ = writeln!(
+            body,
+            "<h2 class=\"character\">{}</h2>",
+            escape_html(character.name)
+        );
+        for quote in &character.quotes {
+            body.push_str("<article class=\"quote\">\n");
+            if let Some(src) = quote.image.as_deref().and_then(|image| images.get(image)) {
+                let _ = writeln!(
+                    body,
+                    "<img src=\"../{}\" alt=\"{}\" loading=\"lazy\">",
+                    escape_html(src),
+                    escape_html(&quote.character)
+                );
+            }
+            let _ = writeln!(
+                body,
+                "<div>\n<p class=\"japanese\" lang=\"ja\">{}</p>",
+                escape_html(&quote.japanese)
+            );
+            if let Some(romaji) = &quote.romaji {
+                let _ = writeln!(body, "<p class=\"romaji\">{}</p>", escape_html(romaji));
+            }
+            let _ = writeln!(
+                body,
+                "<blockquote>“{}”</blockquote>",
+                escape_html(&quote.quote)
+            );
+            if let Some(metadata) = quote.metadata_line() {
+                let _ = writeln!(body, "<p class=\"meta\">{}</p>", escape_html(&metadata));
+            }
+            if let Some(url) = &quote.url {
+                let _ = writeln!(
+                    body,
+                    "<p><a href=\"{}\">Anime page</a></p>",
+                    escape_html(url)
+                );
+            }
+            if let Some(source_url) = &quote.source_url {
+                let _ = writeln!(
+                    body,
+                    "<p><a href=\"{}\">Source</a></p>",
+                    escape_html(source_url)
+                );
+            }
+            body.push_str("</div>\n</article>\n");
+        }
+    }
+    page(group.title, "../", &body)
+}
@@ -0,0 +1,232 @@
+//! Snapshot tests of the viewer's layout, drawn headless with
+//! `App::render_to_buffer`.
+//!
+//! Each snapshot is a frame as plain text in `src/snapshots/`. After a
+//! change that is meant to move things around, run
+//! `UPDATE_SNAPSHOTS=1 cargo test` to rewrite them and review the diff.
+
+use crate::template::Template;
+use crate::{AnimeData, App, ConfigRoot, Field, edit, grid, output, state};
+use ratatui::buffer::Buffer;
+use ratatui_image::picker::Picker;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+
+const DATA: &str = r#"
+[[quotes]]
+japanese = "戦わなければ勝てない"
+romaji = "Tatakawanakereba katenai"
+anime = "Attack on Titan"
+character = "Eren Yeager"
+quote = "You can't win if you don't fight"
+source = "TV"
+season = 1
+episode = 8
+year = 2013
+
+[[quotes]]
+japanese = "海賊王に俺はなる"
+anime = "One Piece"
+character = "Monkey D. Luffy"
+quote = "I'm gonna be king of the pirates"
+
+[[quotes]]
+japanese = "まっすぐ自分の言葉は曲げねえ"
+anime = "Naruto"
+character = "Naruto Uzumaki"
+quote = "I won't go back on my word"
+"#;
+
+/// The viewer on the quotes above with the default config and a fresh
+/// state, so nothing on disk changes what is drawn.
+fn app() -> App {
+    let data: AnimeData = toml::from_str(DATA).expect("test data parses");
+    App::from_parts(
+        ConfigRoot::default(),
+        data.quotes,
+        Picker::from_fontsize((10, 20)),
+        state::State::default(),
+        false,
+        None,
+    )
+}
+
+fn text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        // The cell after a double-width character only holds its right half.
+        let mut skip = 0;
+        for x in area.left()..area.right() {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let symbol = buffer[(x, y)].symbol();
+            line.push_str(symbol);
+            skip = symbol.width().saturating_sub(1);
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Compares the frame with the snapshot `name`, or writes it when
+/// `UPDATE_SNAPSHOTS` is set. A missing snapshot fails, so one that was
+/// never committed can't pass unnoticed.
+fn assert_snapshot(name: &str, buffer: &Buffer) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/snapshots")
+        .join(format!("{name}.txt"));
+    let actual = text(buffer);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().expect("snapshots have a folder"))
+            .expect("snapshot folder is writable");
+        fs::write(&path, actual).expect("snapshot is writable");
+        return;
+    }
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!(
+            "snapshot missing; rerun with UPDATE_SNAPSHOTS=1 ({})",
+            path.display()
+        );
+    };
+    assert!(
+        expected == actual,
+        "{name} no longer matches {}\n--- expected\n{expected}--- actual\n{actual}",
+        path.display()
+    );
+}
+
+#[test]
+fn main_view() {
+    let mut app = app();
+    let buffer = app.render_to_buffer(80, 30).expect("renders");
+    assert_snapshot("main_view", &buffer);
+}
+
+#[test]
+fn main_view_narrow() {
+    let mut app = app();
+    let buffer = app.render_to_buffer(40, 30).expect("renders");
+    assert_snapshot("main_view_narrow", &buffer);
+}
+
+#[test]
+fn without_instructions() {
+    let mut app = app();
+    app.show_instructions = false;
+    let buffer = app.render_to_buffer(80, 30).expect("renders");
+    assert_snapshot("without_instructions", &buffer);
+}
+
+#[test]
+fn status_bar() {
+    let mut app = app();
+    app.status_bar = true;
+    let buffer = app.render_to_buffer(100, 30).expect("renders");
+    assert_snapshot("status_bar", &buffer);
+}
+
+#[test]
+fn japanese_and_translation_only() {
+    let mut app = app();
+    app.fields = vec![Field::Japanese, Field::Quote];
+    let buffer = app.render_to_buffer(80, 30).expect("renders");
+    assert_snapshot("japanese_and_translation_only", &buffer);
+}
+
+#[test]
+fn caption_template() {
+    let mut app = app();
+    let template = "\"{{quote}}\"\n— {{character}}, {{anime}}";
+    app.template = Some(Template::parse(template, output::TEMPLATE_FIELDS).expect("parses"));
+    let buffer = app.render_to_buffer(80, 30).expect("renders");
+    assert_snapshot("caption_template", &buffer);
+}
+
+#[test]
+fn pitch_accent() {
+    let mut app = app();
+    app.quotes[0].pitch = Some("たたかわꜜなければ かꜜてない".to_string());
+    let buffer = app.render_to_buffer(80, 30).expect("renders");
+    assert_snapshot("pitch_accent", &buffer);
+}
+
+#[test]
+fn kana_only() {
+    let mut app = app();
+    app.quotes[0].furigana = Some("戦[たたか]わなければ 勝[か]てない".to_string());
+    app.kana_only = true;
+    let buffer = app.render_to_buffer(80, 30).expect("renders");
+    assert_snapshot("kana_only", &buffer);
+}
+
+#[test]
+fn notes_pane() {
+    let mut app = app();
+    app.quotes[0].notes = Some(
+        "〜なければ〜ない: \"unless you ..., you can't ...\". Eren says it to Armin \
+         in episode 8, and it becomes the series' refrain."
+            .to_string(),
+    );
+    let buffer = app.render_to_buffer(80, 34).expect("renders");
+    assert_snapshot("notes_folded", &buffer);
+    app.notes_open = true;
+    let buffer = app.render_to_buffer(80, 34).expect("renders");
+    assert_snapshot("notes_open", &buffer);
+}
+
+#[test]
+fn grid_view() {
+    let mut app = app();
+    app.grid = Some(grid::Grid::new(1));
+    let buffer = app.render_to_buffer(80, 30).expect("renders");
+    assert_snapshot("grid_view", &buffer);
+}
+
+#[test]
+fn compare_view() {
+    let mut app = app();
+    app.toggle_compare();
+    let buffer = app.render_to_buffer(100, 30).expect("renders");
+    assert_snapshot("compare_view", &buffer);
+}
+
+#[test]
+fn empty_collection() {
+    let mut app = App::from_parts(
+        ConfigRoot::default(),
+        Vec::new(),
+        Picker::from_fontsize((10, 20)),
+        state::State::default(),
+        false,
+        None,
+    );
+    let buffer = app.render_to_buffer(60, 20).expect("renders");
+    assert_snapshot("empty_collection", &buffer);
+}
+
+/// Batch edits find quotes by the ID they get once loaded, which is
+/// hashed from the cleaned text, even when the file has a zero-width space.
+#[test]
+fn edit_finds_quote_with_invisible_characters() {
+    let written = "[[quotes]]\njapanese = \"海賊王に\u{200B}俺はなる\"\nanime = \"One Piece\"\n\
+                   character = \"Monkey D. Luffy\"\nquote = \"I'm gonna be  \\nking of the pirates\"\n";
+    let mut data: AnimeData = toml::from_str(written).expect("test data parses");
+    data.sanitize();
+    let quote = &data.quotes[0];
+    assert_eq!(quote.japanese, "海賊王に俺はなる");
+    assert_eq!(quote.quote, "I'm gonna be\nking of the pirates");
+
+    let mut document: toml_edit::DocumentMut = written.parse().expect("test data parses");
+    let path = PathBuf::from("anime.toml");
+    let tables = edit::quotes_mut(&mut document, &path).expect("has quotes");
+    let ids = HashSet::from([quote.stable_id()]);
+    assert_eq!(edit::tag_tables(tables, &ids, "pirates"), 1);
+    assert!(document.to_string().contains("tags = [\"pirates\"]"));
+}
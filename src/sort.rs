@@ -0,0 +1,84 @@
+//! Orders for stepping through the collection. The collection itself keeps
+//! the data-file order (indices into it are what state and the control
+//! socket use); a sort only changes which quote comes next.
+
+use crate::AnimeQuote;
+use std::cmp::Reverse;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum SortOrder {
+    /// As listed in the data file, then packs.
+    #[default]
+    Data,
+    Anime,
+    Character,
+    /// Shortest translation first.
+    Length,
+    /// Highest rated first; unrated quotes last.
+    Rating,
+    /// Newest `added` date first, then undated quotes from the end of the
+    /// file back, since new quotes are appended.
+    Added,
+}
+
+const ORDERS: &[SortOrder] = &[
+    SortOrder::Data,
+    SortOrder::Anime,
+    SortOrder::Character,
+    SortOrder::Length,
+    SortOrder::Rating,
+    SortOrder::Added,
+];
+
+impl SortOrder {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "data" => Some(Self::Data),
+            "anime" => Some(Self::Anime),
+            "character" => Some(Self::Character),
+            "length" => Some(Self::Length),
+            "rating" => Some(Self::Rating),
+            "added" => Some(Self::Added),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Data => "data order",
+            Self::Anime => "anime A→Z",
+            Self::Character => "character A→Z",
+            Self::Length => "quote length",
+            Self::Rating => "rating",
+            Self::Added => "recently added",
+        }
+    }
+
+    pub(crate) fn next(self) -> Self {
+        let position = ORDERS.iter().position(|order| *order == self).unwrap_or(0);
+        ORDERS[(position + 1) % ORDERS.len()]
+    }
+
+    /// Indices into `quotes` in this order; ties keep the data order.
+    pub(crate) fn order(self, quotes: &[AnimeQuote]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..quotes.len()).collect();
+        let text = |value: &str| value.to_lowercase();
+        match self {
+            Self::Data => {}
+            Self::Anime => order.sort_by_cached_key(|&index| {
+                let quote = &quotes[index];
+                (text(&quote.anime), text(&quote.character))
+            }),
+            Self::Character => order.sort_by_cached_key(|&index| {
+                let quote = &quotes[index];
+                (text(&quote.character), text(&quote.anime))
+            }),
+            Self::Length => order.sort_by_key(|&index| quotes[index].quote.chars().count()),
+            Self::Rating => order.sort_by_key(|&index| Reverse(quotes[index].rating)),
+            Self::Added => {
+                order.sort_by_key(|&index| (Reverse(quotes[index].added.clone()), Reverse(index)))
+            }
+        }
+        order
+    }
+}
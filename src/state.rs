@@ -0,0 +1,312 @@
+//! What the app remembers between runs, kept in `state.toml` in the data
+//! directory (so `sync` and `backup` carry it along). Quotes are referred to
+//! by stable ID, so edits and reordering in `anime.toml` don't scramble it.
+
+use crate::schedule::{self, ScheduleConfig};
+use crate::{AnimeQuote, data_dir, selection};
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate};
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct State {
+    /// The quote of the day as it was shown, by `YYYY-MM-DD` date.
+    #[serde(default)]
+    daily: BTreeMap<String, String>,
+    /// When each quote was last on screen, by stable ID.
+    #[serde(default)]
+    views: BTreeMap<String, Views>,
+    /// Stable IDs of quotes kept out of the random, daily, and slideshow
+    /// picks.
+    #[serde(default)]
+    hidden: BTreeSet<String>,
+    /// Stable IDs of favorite quotes.
+    #[serde(default)]
+    favorites: BTreeSet<String>,
+    /// How many quotes came up in the viewer each day, by `YYYY-MM-DD` date.
+    #[serde(default)]
+    activity: BTreeMap<String, u32>,
+    /// The `YYYY-MM-DD` date each achievement was unlocked, by ID.
+    #[serde(default)]
+    achievements: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct Views {
+    /// RFC 3339 timestamp.
+    #[serde(default)]
+    last: Option<String>,
+    /// How many times the quote has come up in the viewer.
+    #[serde(default)]
+    count: u64,
+}
+
+fn state_path() -> PathBuf {
+    data_dir().join("state.toml")
+}
+
+impl State {
+    /// Reads the state file; a missing or unreadable one starts fresh.
+    pub(crate) fn load() -> Self {
+        let path = state_path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|error| {
+            eprintln!("failed to parse {}: {error}", path.display());
+            Self::default()
+        })
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        let path = state_path();
+        fs::create_dir_all(data_dir())?;
+        fs::write(&path, toml::to_string(self)?)
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Moves what is recorded under each old ID to the new one, for quotes
+    /// whose IDs changed when their text was cleaned up. Whatever is already
+    /// under a new ID wins. Saves only when something moved.
+    pub(crate) fn rename_ids(renamed: &[(String, String)]) {
+        let mut state = Self::load();
+        let mut changed = false;
+        for (old, new) in renamed {
+            if let Some(views) = state.views.remove(old) {
+                state.views.entry(new.clone()).or_insert(views);
+                changed = true;
+            }
+            for ids in [&mut state.hidden, &mut state.favorites] {
+                if ids.remove(old) {
+                    ids.insert(new.clone());
+                    changed = true;
+                }
+            }
+            for id in state.daily.values_mut().filter(|id| *id == old) {
+                id.clone_from(new);
+                changed = true;
+            }
+        }
+        if changed && let Err(error) = state.save() {
+            eprintln!("{error}");
+        }
+    }
+
+    /// The quote of the day for `date`: the one recorded for it while it is
+    /// still in the collection, otherwise [`selection::daily_index`] over the
+    /// quotes scheduled for that day. The flag is true when the pick comes
+    /// from the record.
+    pub(crate) fn daily_pick(
+        &self,
+        quotes: &[AnimeQuote],
+        date: NaiveDate,
+        schedule: &ScheduleConfig,
+    ) -> Option<(usize, bool)> {
+        let recorded = self
+            .daily
+            .get(&date.format(DATE_FORMAT).to_string())
+            .and_then(|id| quotes.iter().position(|quote| quote.stable_id() == *id));
+        match recorded {
+            Some(index) => Some((index, true)),
+            None => {
+                let candidates =
+                    schedule::candidates(quotes, &self.pickable(quotes), date, schedule);
+                selection::daily_index(candidates.len(), date)
+                    .map(|index| (candidates[index], false))
+            }
+        }
+    }
+
+    pub(crate) fn is_hidden(&self, quote: &AnimeQuote) -> bool {
+        self.hidden.contains(&quote.stable_id())
+    }
+
+    /// Hides `quote` from the picks, or shows it again. Saved by the caller.
+    pub(crate) fn set_hidden(&mut self, quote: &AnimeQuote, hidden: bool) {
+        if hidden {
+            self.hidden.insert(quote.stable_id());
+        } else {
+            self.hidden.remove(&quote.stable_id());
+        }
+    }
+
+    pub(crate) fn is_favorite(&self, quote: &AnimeQuote) -> bool {
+        self.favorites.contains(&quote.stable_id())
+    }
+
+    /// Saved by the caller.
+    pub(crate) fn set_favorite(&mut self, quote: &AnimeQuote, favorite: bool) {
+        if favorite {
+            self.favorites.insert(quote.stable_id());
+        } else {
+            self.favorites.remove(&quote.stable_id());
+        }
+    }
+
+    /// Positions of the hidden quotes in `quotes`.
+    pub(crate) fn hidden(&self, quotes: &[AnimeQuote]) -> Vec<usize> {
+        (0..quotes.len())
+            .filter(|&index| self.is_hidden(&quotes[index]))
+            .collect()
+    }
+
+    /// Positions of the quotes random, daily, and slideshow picks choose
+    /// from: everything that isn't hidden, or everything when it all is.
+    pub(crate) fn pickable(&self, quotes: &[AnimeQuote]) -> Vec<usize> {
+        let shown: Vec<usize> = (0..quotes.len())
+            .filter(|&index| !self.is_hidden(&quotes[index]))
+            .collect();
+        if shown.is_empty() {
+            return (0..quotes.len()).collect();
+        }
+        shown
+    }
+
+    /// Notes that `quote` is being shown now. Saved by the caller.
+    pub(crate) fn record_view(&mut self, quote: &AnimeQuote) {
+        let views = self.views.entry(quote.stable_id()).or_default();
+        views.last = Some(Local::now().to_rfc3339());
+        views.count += 1;
+        let today = Local::now().date_naive().format(DATE_FORMAT).to_string();
+        *self.activity.entry(today).or_default() += 1;
+    }
+
+    /// Quotes viewed on each of the `days` days up to `today`, oldest first.
+    pub(crate) fn activity(&self, today: NaiveDate, days: u64) -> Vec<(NaiveDate, u32)> {
+        (0..days)
+            .rev()
+            .filter_map(|back| today.checked_sub_days(Days::new(back)))
+            .map(|date| {
+                let key = date.format(DATE_FORMAT).to_string();
+                (date, self.activity.get(&key).copied().unwrap_or(0))
+            })
+            .collect()
+    }
+
+    /// The current and longest runs of consecutive days with the viewer in
+    /// use. The current streak still counts when today hasn't had a view
+    /// yet, as long as yesterday did.
+    pub(crate) fn streaks(&self, today: NaiveDate) -> (u32, u32) {
+        let days: BTreeSet<NaiveDate> = self
+            .activity
+            .iter()
+            .filter(|(_, views)| **views > 0)
+            .filter_map(|(date, _)| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+            .collect();
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for &date in &days {
+            run = match previous {
+                Some(previous) if previous.succ_opt() == Some(date) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            previous = Some(date);
+        }
+        let mut current = 0;
+        let mut date = if days.contains(&today) {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        while let Some(day) = date.filter(|day| days.contains(day)) {
+            current += 1;
+            date = day.pred_opt();
+        }
+        (current, longest)
+    }
+
+    /// Positions of the quotes in `quotes` that have been viewed.
+    pub(crate) fn viewed(&self, quotes: &[AnimeQuote]) -> Vec<usize> {
+        (0..quotes.len())
+            .filter(|&index| self.view_count(&quotes[index]) > 0)
+            .collect()
+    }
+
+    /// When the achievement `id` was unlocked, as `YYYY-MM-DD`.
+    pub(crate) fn unlocked(&self, id: &str) -> Option<&str> {
+        self.achievements.get(id).map(String::as_str)
+    }
+
+    /// Saved by the caller.
+    pub(crate) fn unlock(&mut self, id: &str, date: NaiveDate) {
+        self.achievements
+            .insert(id.to_string(), date.format(DATE_FORMAT).to_string());
+    }
+
+    /// How many times `quote` has been shown.
+    pub(crate) fn view_count(&self, quote: &AnimeQuote) -> u64 {
+        self.views
+            .get(&quote.stable_id())
+            .map_or(0, |views| views.count)
+    }
+
+    /// How many times `quote` has been shown, and when it last was (RFC
+    /// 3339).
+    pub(crate) fn views(&self, quote: &AnimeQuote) -> (u64, Option<&str>) {
+        self.views
+            .get(&quote.stable_id())
+            .map_or((0, None), |views| (views.count, views.last.as_deref()))
+    }
+
+    /// Those of the quotes at `positions` shown the fewest times.
+    pub(crate) fn least_seen(&self, quotes: &[AnimeQuote], positions: &[usize]) -> Vec<usize> {
+        let fewest = positions
+            .iter()
+            .map(|&index| self.view_count(&quotes[index]))
+            .min()
+            .unwrap_or(0);
+        positions
+            .iter()
+            .copied()
+            .filter(|&index| self.view_count(&quotes[index]) == fewest)
+            .collect()
+    }
+
+    /// Quotes that have been viewed, most recent first, with when.
+    pub(crate) fn recent(&self, quotes: &[AnimeQuote]) -> Vec<(usize, DateTime<FixedOffset>)> {
+        let mut recent: Vec<(usize, DateTime<FixedOffset>)> = quotes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, quote)| {
+                let last = self.views.get(&quote.stable_id())?.last.as_deref()?;
+                Some((index, DateTime::parse_from_rfc3339(last).ok()?))
+            })
+            .collect();
+        recent.sort_by_key(|&(_, viewed)| std::cmp::Reverse(viewed));
+        recent
+    }
+
+    /// Today's quote of the day, recorded on first use so it stays the same
+    /// all day (and in the calendar later) even if the collection changes.
+    /// Hiding today's quote picks another one.
+    pub(crate) fn todays_pick(
+        &mut self,
+        quotes: &[AnimeQuote],
+        schedule: &ScheduleConfig,
+    ) -> Option<usize> {
+        let today = Local::now().date_naive();
+        let key = today.format(DATE_FORMAT).to_string();
+        if self
+            .daily
+            .get(&key)
+            .is_some_and(|id| self.hidden.contains(id))
+        {
+            self.daily.remove(&key);
+        }
+        let (index, recorded) = self.daily_pick(quotes, today, schedule)?;
+        if !recorded {
+            self.daily.insert(key, quotes[index].stable_id());
+            if let Err(error) = self.save() {
+                eprintln!("failed to record the quote of the day: {error}");
+            }
+        }
+        Some(index)
+    }
+}
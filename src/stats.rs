@@ -0,0 +1,214 @@
+//! `anime-quotes stats`: a summary of the collection for maintenance, and
+//! of how much the viewer has been used.
+
+use crate::AnimeQuote;
+use crate::state::State;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Serialize)]
+pub(crate) struct Stats {
+    total: usize,
+    anime: Vec<Count>,
+    characters: Vec<Count>,
+    /// Quotes without an `image`.
+    missing_image: Vec<QuoteRef>,
+    /// Quotes whose `image` file does not exist.
+    broken_image: Vec<QuoteRef>,
+    missing_romaji: Vec<QuoteRef>,
+    /// By the length of the translation, in characters.
+    longest: Option<QuoteRef>,
+    shortest: Option<QuoteRef>,
+    activity: Activity,
+}
+
+/// Days to show in the activity history.
+const ACTIVITY_DAYS: u64 = 30;
+
+/// How much the viewer has been used, from the state file.
+#[derive(Serialize)]
+struct Activity {
+    /// Consecutive days, up to today, with at least one quote viewed.
+    current_streak: u32,
+    longest_streak: u32,
+    /// The last [`ACTIVITY_DAYS`] days, oldest first.
+    days: Vec<Day>,
+}
+
+#[derive(Serialize)]
+struct Day {
+    /// `YYYY-MM-DD`.
+    date: String,
+    views: u32,
+}
+
+#[derive(Serialize)]
+struct Count {
+    name: String,
+    quotes: usize,
+}
+
+#[derive(Serialize)]
+struct QuoteRef {
+    /// 1-based, as in the viewer counter.
+    index: usize,
+    id: String,
+    character: String,
+    anime: String,
+    length: usize,
+}
+
+impl QuoteRef {
+    fn new(position: usize, quote: &AnimeQuote) -> Self {
+        Self {
+            index: position + 1,
+            id: quote.stable_id(),
+            character: quote.character.clone(),
+            anime: quote.anime.clone(),
+            length: quote.quote.chars().count(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{:>4}  {}  {}, {}",
+            self.index, self.id, self.character, self.anime
+        )
+    }
+}
+
+/// Most quotes first, then by name.
+fn counts<'a>(names: impl Iterator<Item = &'a str>) -> Vec<Count> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in names {
+        *counts.entry(name).or_default() += 1;
+    }
+    let mut counts: Vec<Count> = counts
+        .into_iter()
+        .map(|(name, quotes)| Count {
+            name: name.to_string(),
+            quotes,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.quotes.cmp(&a.quotes).then_with(|| a.name.cmp(&b.name)));
+    counts
+}
+
+pub(crate) fn collect(quotes: &[AnimeQuote], state: &State, today: NaiveDate) -> Stats {
+    let refs = |keep: &dyn Fn(&AnimeQuote) -> bool| -> Vec<QuoteRef> {
+        quotes
+            .iter()
+            .enumerate()
+            .filter(|(_, quote)| keep(quote))
+            .map(|(position, quote)| QuoteRef::new(position, quote))
+            .collect()
+    };
+    let by_length = |position: &usize| quotes[*position].quote.chars().count();
+    Stats {
+        total: quotes.len(),
+        anime: counts(quotes.iter().map(|quote| quote.anime.as_str())),
+        characters: counts(quotes.iter().map(|quote| quote.character.as_str())),
+        missing_image: refs(&|quote| quote.image.is_none()),
+        broken_image: refs(&|quote| {
+            quote
+                .image
+                .as_deref()
+                .is_some_and(|image| !Path::new(image).is_file())
+        }),
+        missing_romaji: refs(&|quote| quote.romaji.is_none()),
+        longest: (0..quotes.len())
+            .max_by_key(by_length)
+            .map(|position| QuoteRef::new(position, &quotes[position])),
+        shortest: (0..quotes.len())
+            .min_by_key(by_length)
+            .map(|position| QuoteRef::new(position, &quotes[position])),
+        activity: {
+            let (current_streak, longest_streak) = state.streaks(today);
+            Activity {
+                current_streak,
+                longest_streak,
+                days: state
+                    .activity(today, ACTIVITY_DAYS)
+                    .into_iter()
+                    .map(|(date, views)| Day {
+                        date: date.format("%Y-%m-%d").to_string(),
+                        views,
+                    })
+                    .collect(),
+            }
+        },
+    }
+}
+
+/// One block per value, scaled to the largest; days without any are dots.
+fn sparkline(values: &[u32]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&value| match value {
+            0 => '·',
+            _ => BLOCKS[((value - 1) as usize * BLOCKS.len()) / max as usize],
+        })
+        .collect()
+}
+
+fn days(count: u32) -> String {
+    match count {
+        1 => "1 day".to_string(),
+        _ => format!("{count} days"),
+    }
+}
+
+impl Stats {
+    pub(crate) fn render_table(&self) -> String {
+        let mut output = format!("{} quotes\n", self.total);
+        for (title, counts) in [("Anime", &self.anime), ("Characters", &self.characters)] {
+            let width = counts
+                .iter()
+                .map(|count| count.name.width())
+                .max()
+                .unwrap_or(0);
+            let _ = writeln!(output, "\n{title} ({})", counts.len());
+            for count in counts {
+                let padding = " ".repeat(width - count.name.width());
+                let _ = writeln!(output, "  {}{padding}  {:>4}", count.name, count.quotes);
+            }
+        }
+        for (title, refs) in [
+            ("Missing image", &self.missing_image),
+            ("Image file not found", &self.broken_image),
+            ("Missing romaji", &self.missing_romaji),
+        ] {
+            let _ = writeln!(output, "\n{title} ({})", refs.len());
+            for quote in refs {
+                let _ = writeln!(output, "  {}", quote.describe());
+            }
+        }
+        for (title, quote) in [("Longest", &self.longest), ("Shortest", &self.shortest)] {
+            if let Some(quote) = quote {
+                let _ = writeln!(
+                    output,
+                    "\n{title}: {} ({} characters)",
+                    quote.describe().trim_start(),
+                    quote.length
+                );
+            }
+        }
+        let activity = &self.activity;
+        let views: Vec<u32> = activity.days.iter().map(|day| day.views).collect();
+        let _ = writeln!(
+            output,
+            "\nActivity\n  Streak: {} (longest {})\n  Last {ACTIVITY_DAYS} days: {}  {} quotes viewed",
+            days(activity.current_streak),
+            days(activity.longest_streak),
+            sparkline(&views),
+            views.iter().sum::<u32>()
+        );
+        output
+    }
+}
@@ -0,0 +1,329 @@
+//! `import subs`: reading SubRip (`.srt`) and Advanced SubStation (`.ass`,
+//! `.ssa`) subtitle files, and the picker for choosing which lines become
+//! quotes.
+
+use crate::Palette;
+use color_eyre::eyre::{Result, WrapErr, bail};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::fs;
+use std::path::Path;
+
+/// One subtitle line, with when it starts in milliseconds.
+#[derive(Clone, Debug)]
+pub(crate) struct Cue {
+    pub(crate) start: u64,
+    /// The ASS `Name` field: who says the line, when the file records it.
+    pub(crate) speaker: Option<String>,
+    /// The text, one subtitle row per line, without formatting tags.
+    pub(crate) text: String,
+}
+
+impl Cue {
+    /// The start as `12:34`, or `1:02:03` past the first hour.
+    pub(crate) fn timestamp(&self) -> String {
+        let seconds = self.start / 1000;
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        if hours > 0 {
+            format!("{hours}:{minutes:02}:{seconds:02}")
+        } else {
+            format!("{minutes:02}:{seconds:02}")
+        }
+    }
+}
+
+/// The lines of the subtitle file at `path`, in order of their start time.
+/// Styled copies of a line (the same text at the same time) appear once.
+pub(crate) fn load(path: &Path) -> Result<Vec<Cue>> {
+    let bytes = fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let content = String::from_utf8_lossy(&bytes);
+    let content = content.trim_start_matches('\u{feff}');
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let mut cues = match extension.as_str() {
+        "srt" => parse_srt(content),
+        "ass" | "ssa" => parse_ass(content),
+        _ => bail!(
+            "{} is not a subtitle file this can read (.srt, .ass, or .ssa)",
+            path.display()
+        ),
+    };
+    cues.retain(|cue| !cue.text.is_empty());
+    cues.sort_by_key(|cue| cue.start);
+    cues.dedup_by(|a, b| a.start == b.start && a.text == b.text);
+    Ok(cues)
+}
+
+fn parse_srt(content: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut lines = content.lines().map(str::trim_end);
+    while let Some(line) = lines.next() {
+        let Some((start, _)) = line.split_once("-->") else {
+            continue;
+        };
+        let Some(start) = parse_time(start.trim()) else {
+            continue;
+        };
+        let text: Vec<String> = lines
+            .by_ref()
+            .take_while(|line| !line.trim().is_empty())
+            .map(strip_html_tags)
+            .collect();
+        cues.push(Cue {
+            start,
+            speaker: None,
+            text: text.join("\n").trim().to_string(),
+        });
+    }
+    cues
+}
+
+fn parse_ass(content: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    // The standard column order, until the section's `Format:` line says
+    // otherwise.
+    let mut columns: Vec<String> = [
+        "layer", "start", "end", "style", "name", "marginl", "marginr", "marginv", "effect", "text",
+    ]
+    .map(String::from)
+    .to_vec();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(format) = line.strip_prefix("Format:") {
+            columns = format
+                .split(',')
+                .map(|column| column.trim().to_ascii_lowercase())
+                .collect();
+            continue;
+        }
+        let Some(dialogue) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+        // The text is last and may itself contain commas.
+        let fields: Vec<&str> = dialogue.trim_start().splitn(columns.len(), ',').collect();
+        let field = |name: &str| {
+            columns
+                .iter()
+                .position(|column| column == name)
+                .and_then(|index| fields.get(index))
+                .map(|field| field.trim())
+        };
+        let (Some(start), Some(text)) = (field("start").and_then(parse_time), field("text")) else {
+            continue;
+        };
+        cues.push(Cue {
+            start,
+            speaker: field("name")
+                .filter(|name| !name.is_empty())
+                .map(str::to_string),
+            text: ass_text(text),
+        });
+    }
+    cues
+}
+
+/// `00:01:02,345` (SRT) or `0:01:02.34` (ASS) in milliseconds.
+fn parse_time(value: &str) -> Option<u64> {
+    let (clock, fraction) = value.split_once([',', '.']).unwrap_or((value, "0"));
+    let mut seconds = 0;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.trim().parse::<u64>().ok()?;
+    }
+    // Pad so `34` hundredths and `345` thousandths both come out in
+    // milliseconds.
+    let fraction = format!("{:0<3}", fraction.trim());
+    let millis: u64 = fraction.get(..3)?.parse().ok()?;
+    Some(seconds * 1000 + millis)
+}
+
+/// SRT lines may carry `<i>` and `<font>` tags, and some files borrow the
+/// ASS `{\an8}` overrides too.
+fn strip_html_tags(line: &str) -> String {
+    let mut output = String::new();
+    let mut closing = None;
+    for ch in line.chars() {
+        match (closing, ch) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (Some(close), ch) if ch == close => closing = None,
+            (Some(_), _) => {}
+            (None, ch) => output.push(ch),
+        }
+    }
+    output.trim().to_string()
+}
+
+/// ASS text without its `{...}` override blocks, with `\N` as a line break
+/// and `\h` as a space.
+fn ass_text(text: &str) -> String {
+    let mut output = String::new();
+    let mut in_override = false;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' => in_override = true,
+            '}' => in_override = false,
+            _ if in_override => {}
+            '\\' => match chars.peek() {
+                Some('N' | 'n') => {
+                    chars.next();
+                    output.push('\n');
+                }
+                Some('h') => {
+                    chars.next();
+                    output.push(' ');
+                }
+                _ => output.push(ch),
+            },
+            _ => output.push(ch),
+        }
+    }
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct Picker<'a> {
+    cues: &'a [Cue],
+    chosen: Vec<bool>,
+    list: ListState,
+}
+
+/// Lists `cues` full-screen for choosing the ones to import: Space marks a
+/// line, Enter imports the marked ones (or the highlighted one when none
+/// are), and Esc gives up. Returns the positions chosen, in order.
+pub(crate) fn pick(cues: &[Cue], title: &str, palette: &Palette) -> Result<Vec<usize>> {
+    let mut picker = Picker {
+        cues,
+        chosen: vec![false; cues.len()],
+        list: ListState::default().with_selected(Some(0)),
+    };
+    let mut terminal = ratatui::init();
+    let result = picker.run(&mut terminal, title, palette);
+    ratatui::restore();
+    result
+}
+
+impl Picker<'_> {
+    fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        title: &str,
+        palette: &Palette,
+    ) -> Result<Vec<usize>> {
+        loop {
+            terminal.draw(|frame| self.render(frame, title, palette))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let selected = self.list.selected().unwrap_or(0).min(self.cues.len() - 1);
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+                KeyCode::PageUp => self.list.scroll_up_by(10),
+                KeyCode::PageDown => self.list.scroll_down_by(10),
+                KeyCode::Home => self.list.select_first(),
+                KeyCode::End => self.list.select_last(),
+                KeyCode::Char(' ') => {
+                    self.chosen[selected] = !self.chosen[selected];
+                    self.list.select_next();
+                }
+                KeyCode::Enter => {
+                    let chosen: Vec<usize> = (0..self.cues.len())
+                        .filter(|&position| self.chosen[position])
+                        .collect();
+                    return Ok(if chosen.is_empty() {
+                        vec![selected]
+                    } else {
+                        chosen
+                    });
+                }
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(Vec::new()),
+                _ => {}
+            }
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, title: &str, palette: &Palette) {
+        let [title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let count_style = Style::default().fg(palette.count);
+        let marked = self.chosen.iter().filter(|&&chosen| chosen).count();
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(title.to_string(), Style::default().fg(palette.anime).bold()),
+                Span::styled(format!("  ({marked} marked)"), count_style),
+            ]))
+            .centered(),
+            title_area,
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                "Move <Up/Down>  Mark <Space>  Import <Enter>  Cancel <Esc>",
+                Style::default().fg(palette.instructions),
+            )))
+            .centered(),
+            help_area,
+        );
+        let items: Vec<ListItem> = self
+            .cues
+            .iter()
+            .zip(&self.chosen)
+            .map(|(cue, &chosen)| {
+                let mark = if chosen { "[x] " } else { "[ ] " };
+                let mut lines = Vec::new();
+                for (row, text) in cue.text.lines().enumerate() {
+                    let lead = if row == 0 {
+                        vec![
+                            Span::styled(mark, Style::default().fg(palette.romaji)),
+                            Span::styled(format!("{:>8}  ", cue.timestamp()), count_style),
+                        ]
+                    } else {
+                        vec![Span::raw(" ".repeat(14))]
+                    };
+                    let mut spans = lead;
+                    if row == 0
+                        && let Some(speaker) = &cue.speaker
+                    {
+                        spans.push(Span::styled(
+                            format!("{speaker}: "),
+                            Style::default().fg(palette.character).bold(),
+                        ));
+                    }
+                    spans.push(Span::styled(
+                        text.to_string(),
+                        Style::default().fg(palette.quote),
+                    ));
+                    lines.push(Line::from(spans));
+                }
+                ListItem::new(lines)
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+    }
+}
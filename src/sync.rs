@@ -0,0 +1,136 @@
+//! `anime-quotes sync`: keeps the data directory in a git repository and
+//! exchanges it with a remote so every machine sees the same collection.
+
+use color_eyre::eyre::{Result, WrapErr, bail};
+use serde::Deserialize;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const DEFAULT_SYNC_BRANCH: &str = "main";
+const REMOTE_NAME: &str = "origin";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SyncConfig {
+    /// Git URL to pull from and push to; without one, sync only commits.
+    #[serde(default)]
+    remote: Option<String>,
+    #[serde(default = "default_sync_branch")]
+    branch: String,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            remote: None,
+            branch: default_sync_branch(),
+        }
+    }
+}
+
+fn default_sync_branch() -> String {
+    DEFAULT_SYNC_BRANCH.to_string()
+}
+
+/// Commits local changes in `dir`, then pulls (rebasing local commits on top)
+/// and pushes when a remote is configured. Returns a summary for the user.
+pub(crate) fn sync(dir: &Path, config: &SyncConfig) -> Result<Vec<String>> {
+    let mut report = Vec::new();
+    std::fs::create_dir_all(dir).wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+    if !dir.join(".git").exists() {
+        git(
+            dir,
+            &["init", "--quiet", "--initial-branch", &config.branch],
+        )?;
+        report.push(format!("initialized a repository in {}", dir.display()));
+    }
+    if let Some(remote) = &config.remote {
+        match git_output(dir, &["remote", "get-url", REMOTE_NAME])? {
+            Some(current) if current == *remote => {}
+            Some(_) => git(dir, &["remote", "set-url", REMOTE_NAME, remote])?,
+            None => git(dir, &["remote", "add", REMOTE_NAME, remote])?,
+        }
+    }
+
+    git(dir, &["add", "--all"])?;
+    let staged = !run(dir, &["diff", "--cached", "--quiet"])?.status.success();
+    if staged {
+        git(dir, &["commit", "--quiet", "-m", &commit_message()])?;
+        report.push("committed local changes".to_string());
+    }
+
+    if config.remote.is_none() {
+        report.push("no [sync] remote configured, skipping pull and push".to_string());
+        return Ok(report);
+    }
+    let branch = config.branch.as_str();
+    let remote_has_branch = run(
+        dir,
+        &["ls-remote", "--exit-code", "--heads", REMOTE_NAME, branch],
+    )?
+    .status
+    .success();
+    if remote_has_branch {
+        git(dir, &["pull", "--quiet", "--rebase", REMOTE_NAME, branch])?;
+        report.push(format!("pulled {REMOTE_NAME}/{branch}"));
+    }
+    let has_commits = run(dir, &["rev-parse", "--verify", "--quiet", "HEAD"])?
+        .status
+        .success();
+    if has_commits {
+        git(
+            dir,
+            &[
+                "push",
+                "--quiet",
+                "--set-upstream",
+                REMOTE_NAME,
+                &format!("HEAD:{branch}"),
+            ],
+        )?;
+        report.push(format!("pushed to {REMOTE_NAME}/{branch}"));
+    }
+    Ok(report)
+}
+
+fn commit_message() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty());
+    match host {
+        Some(host) => format!("Sync from {host}"),
+        None => "Sync".to_string(),
+    }
+}
+
+fn run(dir: &Path, args: &[&str]) -> Result<Output> {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .wrap_err("failed to run git; is it installed?")
+}
+
+/// Runs git and fails with its stderr when it exits unsuccessfully.
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = run(dir, args)?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Trimmed stdout of a git command, or `None` when it exits unsuccessfully.
+fn git_output(dir: &Path, args: &[&str]) -> Result<Option<String>> {
+    let output = run(dir, args)?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
@@ -0,0 +1,133 @@
+//! A small mustache-style template language for formatting quotes:
+//! `{{field}}` inserts a value, `{{#field}}…{{/field}}` renders only when the
+//! field is present and non-empty, and `{{^field}}…{{/field}}` only when it is not.
+
+use color_eyre::eyre::{Result, bail};
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Field(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parses `source`, rejecting any field not listed in `fields` so typos
+    /// surface immediately instead of rendering as blanks.
+    pub(crate) fn parse(source: &str, fields: &[&str]) -> Result<Self> {
+        let mut stack: Vec<(String, bool, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                current(&mut stack, &mut nodes).push(Node::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                bail!("unclosed `{{{{` in template");
+            };
+            let tag = after[..end].trim();
+            rest = &after[end + 2..];
+
+            if let Some(name) = tag.strip_prefix('#').or_else(|| tag.strip_prefix('^')) {
+                let name = check_field(name.trim(), fields)?;
+                stack.push((name, tag.starts_with('^'), Vec::new()));
+            } else if let Some(name) = tag.strip_prefix('/') {
+                let name = name.trim();
+                match stack.pop() {
+                    Some((open, inverted, children)) if open == name => {
+                        current(&mut stack, &mut nodes).push(Node::Section {
+                            name: open,
+                            inverted,
+                            children,
+                        });
+                    }
+                    Some((open, ..)) => bail!("`{{{{/{name}}}}}` closes `{{{{#{open}}}}}`"),
+                    None => bail!("`{{{{/{name}}}}}` has no matching section"),
+                }
+            } else {
+                let name = check_field(tag, fields)?;
+                current(&mut stack, &mut nodes).push(Node::Field(name));
+            }
+        }
+        if !rest.is_empty() {
+            current(&mut stack, &mut nodes).push(Node::Text(rest.to_string()));
+        }
+        if let Some((open, ..)) = stack.pop() {
+            bail!("section `{{{{#{open}}}}}` is never closed");
+        }
+        Ok(Self { nodes })
+    }
+
+    pub(crate) fn render(&self, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+        self.render_parts(lookup)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect()
+    }
+
+    /// The rendered pieces in order, each with the field it came from, or
+    /// `None` for the template's own text, so they can be styled apart.
+    pub(crate) fn render_parts(
+        &self,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Vec<(String, Option<&str>)> {
+        let mut parts = Vec::new();
+        render_nodes(&self.nodes, lookup, &mut parts);
+        parts
+    }
+}
+
+fn current<'a>(
+    stack: &'a mut [(String, bool, Vec<Node>)],
+    nodes: &'a mut Vec<Node>,
+) -> &'a mut Vec<Node> {
+    match stack.last_mut() {
+        Some((_, _, children)) => children,
+        None => nodes,
+    }
+}
+
+fn check_field(name: &str, fields: &[&str]) -> Result<String> {
+    if fields.contains(&name) {
+        Ok(name.to_string())
+    } else {
+        bail!(
+            "unknown template field `{name}` (available: {})",
+            fields.join(", ")
+        )
+    }
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    lookup: &dyn Fn(&str) -> Option<String>,
+    output: &mut Vec<(String, Option<&'a str>)>,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push((text.clone(), None)),
+            Node::Field(name) => output.push((lookup(name).unwrap_or_default(), Some(name))),
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let present = lookup(name).is_some_and(|value| !value.is_empty());
+                if present != *inverted {
+                    render_nodes(children, lookup, output);
+                }
+            }
+        }
+    }
+}
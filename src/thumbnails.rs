@@ -0,0 +1,86 @@
+//! Downscaled copies of quote images kept in `thumbnails/` in the cache
+//! directory, so launches after the first skip decoding and resizing the
+//! full-resolution files.
+//!
+//! A thumbnail is named after a hash of the image's path, modification time,
+//! crop, and the size it was scaled to, so editing or replacing the image,
+//! or a larger terminal, makes a new one.
+
+use crate::selection::fnv1a;
+use crate::{AnimeQuote, cache_dir};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageResult};
+use std::fs;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+/// Sizes are rounded up to a multiple of this many pixels, so resizing the
+/// terminal a little doesn't make new thumbnails.
+const SIZE_STEP: u32 = 256;
+
+/// The quote's image scaled down to fit in `largest` pixels, from the cache
+/// when it was scaled before, or `None` when the quote has no image.
+pub(crate) fn load(quote: &AnimeQuote, largest: (u32, u32)) -> Option<ImageResult<DynamicImage>> {
+    let largest = rounded(largest);
+    let Some(path) = thumbnail_path(quote, largest) else {
+        return quote.load_image();
+    };
+    if let Ok(thumbnail) = image::open(&path) {
+        return Some(Ok(thumbnail));
+    }
+    let image = match quote.load_image()? {
+        Ok(image) => image,
+        Err(error) => return Some(Err(error)),
+    };
+    // Images that already fit are cheap enough to decode every time.
+    if image.width() <= largest.0 && image.height() <= largest.1 {
+        return Some(Ok(image));
+    }
+    let thumbnail = image.resize(largest.0, largest.1, FilterType::CatmullRom);
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(image::ImageError::IoError)
+        .and_then(|()| thumbnail.save(&path));
+    if let Err(error) = saved {
+        eprintln!("failed to write thumbnail {}: {error}", path.display());
+    }
+    Some(Ok(thumbnail))
+}
+
+/// Whether the quote's image already has a thumbnail for `largest`.
+pub(crate) fn cached(quote: &AnimeQuote, largest: (u32, u32)) -> bool {
+    thumbnail_path(quote, rounded(largest)).is_some_and(|path| path.exists())
+}
+
+fn rounded((width, height): (u32, u32)) -> (u32, u32) {
+    (
+        width.max(1).next_multiple_of(SIZE_STEP),
+        height.max(1).next_multiple_of(SIZE_STEP),
+    )
+}
+
+/// Where the thumbnail of the quote's image at `size` goes, or `None` when
+/// the image's modification time can't be read.
+fn thumbnail_path(quote: &AnimeQuote, (width, height): (u32, u32)) -> Option<PathBuf> {
+    let image = quote.image.as_deref()?;
+    let modified = fs::metadata(image)
+        .and_then(|metadata| metadata.modified())
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?;
+    let crop = quote
+        .crop
+        .map(|crop| format!("{},{},{},{}", crop.x, crop.y, crop.width, crop.height))
+        .unwrap_or_default();
+    let key = format!(
+        "{image}\n{}.{}\n{crop}\n{width}x{height}",
+        modified.as_secs(),
+        modified.subsec_nanos()
+    );
+    Some(
+        cache_dir()
+            .join("thumbnails")
+            .join(format!("{:016x}.png", fnv1a(key.as_bytes()))),
+    )
+}
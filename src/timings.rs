@@ -0,0 +1,69 @@
+//! `--timings`: how long each part of starting the viewer took, printed to
+//! stderr once it exits, for tracking down slow launches.
+
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Total time spent decoding images and how many were decoded, added to by
+/// the loader thread.
+pub(crate) type DecodeTotals = Arc<Mutex<(Duration, usize)>>;
+
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    last: Instant,
+    drawn: bool,
+    pub(crate) decoding: DecodeTotals,
+}
+
+impl Timings {
+    pub(crate) fn start() -> Self {
+        Self {
+            phases: Vec::new(),
+            last: Instant::now(),
+            drawn: false,
+            decoding: DecodeTotals::default(),
+        }
+    }
+
+    /// Records the time since the previous phase ended as `name`.
+    pub(crate) fn phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push((name, now - self.last));
+        self.last = now;
+    }
+
+    /// Records the first frame, after the setup phases; later frames are
+    /// left out.
+    pub(crate) fn frame_drawn(&mut self) {
+        if !self.drawn {
+            self.drawn = true;
+            self.phase("first frame");
+        }
+    }
+
+    pub(crate) fn report(&self) -> String {
+        let mut report = String::from("startup timings:\n");
+        let width = self.phases.iter().map(|(name, _)| name.len()).max();
+        let width = width.unwrap_or_default().max("image decoding".len());
+        for (name, duration) in &self.phases {
+            let _ = writeln!(report, "  {name:<width$}  {}", milliseconds(*duration));
+        }
+        let (decoding, count) = *self
+            .decoding
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let _ = writeln!(
+            report,
+            "  {:<width$}  {} for {count} image{} (in the background)",
+            "image decoding",
+            milliseconds(decoding),
+            if count == 1 { "" } else { "s" },
+        );
+        report
+    }
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
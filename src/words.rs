@@ -0,0 +1,62 @@
+//! Words of the Japanese line for the word cursor, and looking them up.
+//!
+//! There is no dictionary to segment with, so the line is split where the
+//! script changes: a run of kanji takes the kana that follow it (its
+//! okurigana and particles), and katakana and Latin runs stand alone.
+//! Jisho.org recognizes inflected forms, so `戦わなければ` still finds 戦う.
+
+use crate::encode_query_component;
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Script {
+    Kanji,
+    Hiragana,
+    Katakana,
+    Other,
+    /// Hiragana after kanji, in the kanji's word.
+    Okurigana,
+    /// Punctuation and spaces, which end a word and are never one.
+    Break,
+}
+
+fn script(ch: char) -> Script {
+    match ch {
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々' | 'ヶ' => Script::Kanji,
+        '\u{3041}'..='\u{309F}' => Script::Hiragana,
+        // ー lengthens katakana and belongs with it.
+        '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' => Script::Katakana,
+        ch if ch.is_alphanumeric() => Script::Other,
+        _ => Script::Break,
+    }
+}
+
+/// The byte ranges of the words in `text`, in order.
+pub(crate) fn split(text: &str) -> Vec<Range<usize>> {
+    let mut words: Vec<Range<usize>> = Vec::new();
+    let mut previous = Script::Break;
+    for (index, ch) in text.char_indices() {
+        let current = script(ch);
+        let continues = match (previous, current) {
+            (_, Script::Break) => false,
+            (Script::Kanji | Script::Okurigana, Script::Hiragana) => true,
+            (previous, current) => previous == current,
+        };
+        match words.last_mut() {
+            Some(word) if continues => word.end = index + ch.len_utf8(),
+            _ if current != Script::Break => words.push(index..index + ch.len_utf8()),
+            _ => {}
+        }
+        previous = if continues && current == Script::Hiragana && previous != current {
+            Script::Okurigana
+        } else {
+            current
+        };
+    }
+    words
+}
+
+/// The jisho.org search for `word`.
+pub(crate) fn jisho_url(word: &str) -> String {
+    format!("https://jisho.org/search/{}", encode_query_component(word))
+}